@@ -23,7 +23,7 @@ use qiniu_ng::{
     },
     Credential,
 };
-use std::{io::Result as IOResult, mem::transmute, ptr::null_mut};
+use std::{io::Result as IOResult, mem::transmute, ptr::null_mut, time::Duration};
 use tap::TapOps;
 
 /// @brief 批量上传器
@@ -251,6 +251,36 @@ pub extern "C" fn qiniu_ng_batch_uploader_set_max_concurrency(
     let _ = qiniu_ng_batch_uploader_t::from(batch_uploader);
 }
 
+/// @brief 设置上传任务失败后的最大重试次数
+/// @details
+///     默认不重试。仅当上传错误可重试时才会重试，例如在仅能新增模式下对象已经存在（状态码 614）时将不会重试。
+///     此外，由于数据流无法倒回，上传阅读器提供的数据的任务总是不会重试
+/// @param[in] batch_uploader 批量上传器实例
+/// @param[in] max_retries 最大重试次数
+#[no_mangle]
+pub extern "C" fn qiniu_ng_batch_uploader_set_max_retries(
+    batch_uploader: qiniu_ng_batch_uploader_t,
+    max_retries: size_t,
+) {
+    let mut batch_uploader = Option::<Box<BatchUploader>>::from(batch_uploader).unwrap();
+    batch_uploader.max_retries(max_retries);
+    let _ = qiniu_ng_batch_uploader_t::from(batch_uploader);
+}
+
+/// @brief 设置重试上传任务前的退避时长
+/// @details 每次重试前的退避时长都将翻倍，但最长不超过 60 秒。默认为 1 秒
+/// @param[in] batch_uploader 批量上传器实例
+/// @param[in] retry_backoff_ms 首次重试前的退避时长，单位为毫秒
+#[no_mangle]
+pub extern "C" fn qiniu_ng_batch_uploader_set_retry_backoff(
+    batch_uploader: qiniu_ng_batch_uploader_t,
+    retry_backoff_ms: u64,
+) {
+    let mut batch_uploader = Option::<Box<BatchUploader>>::from(batch_uploader).unwrap();
+    batch_uploader.retry_backoff(Duration::from_millis(retry_backoff_ms));
+    let _ = qiniu_ng_batch_uploader_t::from(batch_uploader);
+}
+
 /// @brief 推送上传指定路径的文件的任务
 /// @param[in] batch_uploader 批量上传器实例
 /// @param[in] file_path 文件路径
@@ -11,11 +11,12 @@ use qiniu_http::{
 };
 use qiniu_ng::{
     config::{Config, ConfigBuilder},
-    http::{DomainsManagerBuilder, HTTPAfterAction, HTTPBeforeAction},
+    http::{DomainsManagerBuilder, HTTPAfterAction, HTTPBeforeAction, Proxy, ProxyType},
     storage::{
         recorder::FileSystemRecorder,
         uploader::{UploadLoggerBuilder, UploadLoggerFileLockPolicy, UploadRecorderBuilder},
     },
+    utils::rate_limiter::RateLimiter,
 };
 use std::{fs::OpenOptions, mem::transmute, ptr::null_mut, time::Duration};
 use tap::TapOps;
@@ -37,6 +38,8 @@ struct Builder {
     upload_logger_builder: Option<UploadLoggerBuilder>,
     upload_recorder_builder: UploadRecorderBuilder,
     domains_manager_builder: DomainsManagerBuilder,
+    http_proxy: Option<Proxy>,
+    operation_timeout: Option<Duration>,
 }
 
 impl Default for Builder {
@@ -46,6 +49,8 @@ impl Default for Builder {
             upload_logger_builder: Some(Default::default()),
             upload_recorder_builder: Default::default(),
             domains_manager_builder: Default::default(),
+            http_proxy: None,
+            operation_timeout: None,
         }
     }
 }
@@ -269,7 +274,7 @@ pub extern "C" fn qiniu_ng_config_builder_upload_threshold(builder: qiniu_ng_con
 /// @param[in] upload_block_size 上传分块尺寸，单位为字节
 /// @note 默认为 4 MB
 /// @note 尺寸越小越适合弱网环境
-/// @warning 必须是 4 MB 的倍数
+/// @note 对于分片上传 V2，可以是 1 MB 到 1 GB 之间的任意尺寸；对于分片上传 V1，该配置无效，分块尺寸固定为 4 MB
 #[no_mangle]
 pub extern "C" fn qiniu_ng_config_builder_upload_block_size(
     builder: qiniu_ng_config_builder_t,
@@ -280,6 +285,29 @@ pub extern "C" fn qiniu_ng_config_builder_upload_block_size(
     let _ = qiniu_ng_config_builder_t::from(builder);
 }
 
+/// @brief 指定客户端配置中的上传带宽限制
+/// @details 对 SDK 所有上传请求均有效，所有上传任务共享同一个带宽限制
+/// @param[in] builder 客户端配置生成器实例
+/// @param[in] bytes_per_second 每秒允许上传的字节数
+/// @param[in] burst_size 空闲一段时间后允许瞬间上传的最大字节数，如果传入 `0`，则等于 `bytes_per_second`
+/// @note 默认不限速
+/// @warning `bytes_per_second` 不能为 `0`
+#[no_mangle]
+pub extern "C" fn qiniu_ng_config_builder_upload_rate_limit(
+    builder: qiniu_ng_config_builder_t,
+    bytes_per_second: u64,
+    burst_size: u64,
+) {
+    let mut builder = Option::<Box<Builder>>::from(builder).unwrap();
+    builder.config_builder = builder
+        .config_builder
+        .upload_rate_limiter(RateLimiter::new_with_burst_size(
+            bytes_per_second,
+            if burst_size > 0 { burst_size } else { bytes_per_second },
+        ));
+    let _ = qiniu_ng_config_builder_t::from(builder);
+}
+
 /// @brief 指定客户端配置中的 HTTP 请求连接超时时长
 /// @details 对 SDK 所有发出的 HTTP 请求均有效
 /// @param[in] builder 客户端配置生成器实例
@@ -424,6 +452,83 @@ pub extern "C" fn qiniu_ng_config_builder_http_request_retry_delay(
     let _ = qiniu_ng_config_builder_t::from(builder);
 }
 
+/// @brief 指定客户端配置中的操作超时时长
+/// @details 对 SDK 所有操作均有效
+/// @details
+///     `http_request_timeout` 仅限制单次 HTTP 请求的时长，而一次操作可能会在多个主机甚至多个区域上多次重试
+///     该项限制一次操作包含所有重试在内的总耗时，超时后将返回操作超过截止时间错误
+/// @param[in] builder 客户端配置生成器实例
+/// @param[in] operation_timeout 操作超时时长，单位为秒，传入 0 表示不限制操作的总耗时
+/// @note 默认不限制操作的总耗时
+#[no_mangle]
+pub extern "C" fn qiniu_ng_config_builder_operation_timeout(
+    builder: qiniu_ng_config_builder_t,
+    operation_timeout: u64,
+) {
+    let mut builder = Option::<Box<Builder>>::from(builder).unwrap();
+    builder.operation_timeout = if operation_timeout > 0 {
+        Some(Duration::from_secs(operation_timeout))
+    } else {
+        None
+    };
+    let _ = qiniu_ng_config_builder_t::from(builder);
+}
+
+/// @brief 指定客户端配置中的 HTTP 代理服务器
+/// @details 对 SDK 所有发出的 HTTP 请求均有效
+/// @param[in] builder 客户端配置生成器实例
+/// @param[in] proxy_type 代理服务器类型
+/// @param[in] host 代理服务器主机地址
+/// @param[in] port 代理服务器端口
+/// @param[in] username 代理服务器认证用户名，如果传入 `NULL` 表示代理服务器无需认证
+/// @param[in] password 代理服务器认证密码，仅当 `username` 不为 `NULL` 时有效
+/// @note 默认不使用代理服务器
+/// @note 调用该方法时，输入的字符串将被复制并存储，因此调用完毕后即可释放
+/// @warning 重复调用该方法将覆盖之前设置的代理服务器，包括通过 `qiniu_ng_config_builder_http_no_proxy()` 添加的不使用代理的主机
+#[no_mangle]
+pub extern "C" fn qiniu_ng_config_builder_http_proxy(
+    builder: qiniu_ng_config_builder_t,
+    proxy_type: qiniu_ng_proxy_type_t,
+    host: *const qiniu_ng_char_t,
+    port: u16,
+    username: *const qiniu_ng_char_t,
+    password: *const qiniu_ng_char_t,
+) {
+    let mut builder = Option::<Box<Builder>>::from(builder).unwrap();
+    let mut proxy = Proxy::new(
+        proxy_type.into(),
+        unsafe { ucstr::from_ptr(host) }.to_string().unwrap(),
+        port,
+    );
+    if let Some(username) = unsafe { username.as_ref() } {
+        proxy = proxy.credential(
+            unsafe { ucstr::from_ptr(username) }.to_string().unwrap(),
+            unsafe { password.as_ref() }
+                .map(|password| unsafe { ucstr::from_ptr(password) }.to_string().unwrap())
+                .unwrap_or_default(),
+        );
+    }
+    builder.http_proxy = Some(proxy);
+    let _ = qiniu_ng_config_builder_t::from(builder);
+}
+
+/// @brief 添加不使用 HTTP 代理服务器的主机
+/// @details 主机将匹配其自身及其所有子域名，如果传入 `*`，则所有主机均不使用代理服务器
+/// @param[in] builder 客户端配置生成器实例
+/// @param[in] host 不使用代理服务器的主机
+/// @note 必须在调用 `qiniu_ng_config_builder_http_proxy()` 之后调用该方法，否则将不会产生任何效果
+#[no_mangle]
+pub extern "C" fn qiniu_ng_config_builder_http_no_proxy(
+    builder: qiniu_ng_config_builder_t,
+    host: *const qiniu_ng_char_t,
+) {
+    let mut builder = Option::<Box<Builder>>::from(builder).unwrap();
+    builder.http_proxy = builder
+        .http_proxy
+        .map(|proxy| proxy.no_proxy(unsafe { ucstr::from_ptr(host) }.to_string().unwrap()));
+    let _ = qiniu_ng_config_builder_t::from(builder);
+}
+
 /// @brief 禁用上传日志记录仪
 /// @param[in] builder 客户端配置生成器实例
 /// @note 默认上传日志记录仪将被启用
@@ -1121,8 +1226,14 @@ pub extern "C" fn qiniu_ng_config_build(
     *builder_ptr = qiniu_ng_config_builder_t::default();
 
     let config_builder = {
-        builder
-            .config_builder
+        let mut config_builder = builder.config_builder;
+        if let Some(http_proxy) = builder.http_proxy {
+            config_builder = config_builder.http_proxy(http_proxy);
+        }
+        if let Some(operation_timeout) = builder.operation_timeout {
+            config_builder = config_builder.operation_timeout(operation_timeout);
+        }
+        config_builder
             .upload_logger(
                 match builder
                     .upload_logger_builder
@@ -1411,6 +1522,22 @@ pub extern "C" fn qiniu_ng_config_get_upload_block_size(config: qiniu_ng_config_
     })
 }
 
+/// @brief 获取客户端配置的上传带宽限制
+/// @param[in] config 客户端配置实例
+/// @retval uint64_t 每秒允许上传的字节数，如果返回 `0` 则表示不限速
+#[no_mangle]
+pub extern "C" fn qiniu_ng_config_get_upload_rate_limit(config: qiniu_ng_config_t) -> u64 {
+    let config = Option::<Config>::from(config).unwrap();
+    config
+        .upload_rate_limiter()
+        .as_ref()
+        .map(|rate_limiter| rate_limiter.bytes_per_second())
+        .unwrap_or(0)
+        .tap(|_| {
+            let _ = qiniu_ng_config_t::from(config);
+        })
+}
+
 /// @brief 获取客户端配置的 TCP KeepAlive 空闲时长
 /// @param[in] config 客户端配置实例
 /// @retval uint64_t TCP KeepAlive 空闲时长，单位为秒
@@ -1511,6 +1638,35 @@ pub extern "C" fn qiniu_ng_config_get_http_request_retry_delay(config: qiniu_ng_
     })
 }
 
+/// @brief 获取客户端配置的操作超时时长
+/// @param[in] config 客户端配置实例
+/// @retval uint64_t 操作超时时长，单位为秒，返回 0 表示不限制操作的总耗时
+#[no_mangle]
+pub extern "C" fn qiniu_ng_config_get_operation_timeout(config: qiniu_ng_config_t) -> u64 {
+    let config = Option::<Config>::from(config).unwrap();
+    config
+        .operation_timeout()
+        .map_or(0, |timeout| timeout.as_secs())
+        .tap(|_| {
+            let _ = qiniu_ng_config_t::from(config);
+        })
+}
+
+/// @brief 获取客户端配置的 HTTP 代理服务器 URL
+/// @details 返回的 URL 中不包含代理服务器认证信息
+/// @param[in] config 客户端配置实例
+/// @retval qiniu_ng_str_t HTTP 代理服务器 URL
+/// @note 这里返回的 `qiniu_ng_str_t` 有可能封装的是 `NULL`，表示没有设置代理服务器，请调用 `qiniu_ng_str_is_null()` 进行判断
+/// @warning 当 `qiniu_ng_str_t` 使用完毕后，请务必调用 `qiniu_ng_str_free()` 方法释放内存
+#[no_mangle]
+pub extern "C" fn qiniu_ng_config_get_http_proxy_url(config: qiniu_ng_config_t) -> qiniu_ng_str_t {
+    let config = Option::<Config>::from(config).unwrap();
+    unsafe { qiniu_ng_str_t::from_optional_string_unchecked(config.http_proxy().as_ref().map(|proxy| proxy.url())) }
+        .tap(|_| {
+            let _ = qiniu_ng_config_t::from(config);
+        })
+}
+
 /// @brief 客户端配置是否启用上传日志记录仪
 /// @param[in] config 客户端配置实例
 /// @retval bool 是否启用上传日志记录仪
@@ -1829,3 +1985,40 @@ impl From<UploadLoggerFileLockPolicy> for qiniu_ng_upload_logger_lock_policy_t {
         }
     }
 }
+
+/// @brief HTTP 代理服务器类型
+#[repr(C)]
+#[derive(Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub enum qiniu_ng_proxy_type_t {
+    /// @brief HTTP 代理，对于 HTTPS 请求，将通过 `CONNECT` 方法建立隧道
+    qiniu_ng_proxy_http,
+    /// @brief HTTPS 代理，与 HTTP 代理相同，但与代理服务器之间的连接将使用 TLS 加密
+    qiniu_ng_proxy_https,
+    /// @brief SOCKS5 代理，由客户端解析域名
+    qiniu_ng_proxy_socks5,
+    /// @brief SOCKS5 代理，由代理服务器解析域名
+    qiniu_ng_proxy_socks5_hostname,
+}
+
+impl From<qiniu_ng_proxy_type_t> for ProxyType {
+    fn from(proxy_type: qiniu_ng_proxy_type_t) -> Self {
+        match proxy_type {
+            qiniu_ng_proxy_type_t::qiniu_ng_proxy_http => ProxyType::HTTP,
+            qiniu_ng_proxy_type_t::qiniu_ng_proxy_https => ProxyType::HTTPS,
+            qiniu_ng_proxy_type_t::qiniu_ng_proxy_socks5 => ProxyType::SOCKS5,
+            qiniu_ng_proxy_type_t::qiniu_ng_proxy_socks5_hostname => ProxyType::SOCKS5Hostname,
+        }
+    }
+}
+
+impl From<ProxyType> for qiniu_ng_proxy_type_t {
+    fn from(proxy_type: ProxyType) -> Self {
+        match proxy_type {
+            ProxyType::HTTP => qiniu_ng_proxy_type_t::qiniu_ng_proxy_http,
+            ProxyType::HTTPS => qiniu_ng_proxy_type_t::qiniu_ng_proxy_https,
+            ProxyType::SOCKS5 => qiniu_ng_proxy_type_t::qiniu_ng_proxy_socks5,
+            ProxyType::SOCKS5Hostname => qiniu_ng_proxy_type_t::qiniu_ng_proxy_socks5_hostname,
+        }
+    }
+}
@@ -259,15 +259,17 @@ pub extern "C" fn qiniu_ng_http_request_get_body(
     body_ptr: *mut *const c_void,
     body_size: *mut size_t,
 ) {
-    let request: &Request = request.into();
+    let request: &mut Request = request.into();
+    // 请求体可能由多个片段组成，需要合并为连续的内存后才能返回给调用方
+    let body = request.body_mut().make_contiguous();
     if let Some(body_size) = unsafe { body_size.as_mut() } {
-        *body_size = request.body().len();
+        *body_size = body.len();
     }
     if let Some(body_ptr) = unsafe { body_ptr.as_mut() } {
-        *body_ptr = if request.body().is_empty() {
+        *body_ptr = if body.is_empty() {
             null_mut()
         } else {
-            request.body().as_ref().as_ptr().cast()
+            body.as_ptr().cast()
         };
     }
     let _ = qiniu_ng_http_request_t::from(request);
@@ -286,7 +288,7 @@ pub extern "C" fn qiniu_ng_http_request_set_body(
 ) {
     let request: &mut Request = request.into();
     *request.body_mut() = if body_size == 0 {
-        Cow::Borrowed(&[])
+        Default::default()
     } else {
         let mut buf = Vec::new();
         buf.extend_from_slice(unsafe { from_raw_parts(body_ptr.cast(), body_size) });
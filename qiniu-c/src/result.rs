@@ -60,6 +60,8 @@ pub enum qiniu_ng_curl_error_kind_t {
     qiniu_ng_proxy_error,
     /// SSL 错误
     qiniu_ng_ssl_error,
+    /// SSL / TLS 握手错误
+    qiniu_ng_ssl_handshake_error,
     /// 连接错误
     qiniu_ng_connection_error,
     /// 请求错误
@@ -78,6 +80,7 @@ impl From<qiniu_ng_curl_error_kind_t> for HTTPCallerErrorKind {
             qiniu_ng_curl_error_kind_t::qiniu_ng_resolve_error => Self::ResolveError,
             qiniu_ng_curl_error_kind_t::qiniu_ng_proxy_error => Self::ProxyError,
             qiniu_ng_curl_error_kind_t::qiniu_ng_ssl_error => Self::SSLError,
+            qiniu_ng_curl_error_kind_t::qiniu_ng_ssl_handshake_error => Self::SSLHandshakeError,
             qiniu_ng_curl_error_kind_t::qiniu_ng_connection_error => Self::ConnectionError,
             qiniu_ng_curl_error_kind_t::qiniu_ng_request_error => Self::RequestError,
             qiniu_ng_curl_error_kind_t::qiniu_ng_response_error => Self::ResponseError,
@@ -93,6 +96,7 @@ impl From<HTTPCallerErrorKind> for qiniu_ng_curl_error_kind_t {
             HTTPCallerErrorKind::ResolveError => Self::qiniu_ng_resolve_error,
             HTTPCallerErrorKind::ProxyError => Self::qiniu_ng_proxy_error,
             HTTPCallerErrorKind::SSLError => Self::qiniu_ng_ssl_error,
+            HTTPCallerErrorKind::SSLHandshakeError => Self::qiniu_ng_ssl_handshake_error,
             HTTPCallerErrorKind::ConnectionError => Self::qiniu_ng_connection_error,
             HTTPCallerErrorKind::RequestError => Self::qiniu_ng_request_error,
             HTTPCallerErrorKind::ResponseError => Self::qiniu_ng_response_error,
@@ -150,6 +154,8 @@ pub enum qiniu_ng_err_kind_t {
     qiniu_ng_err_kind_unexpected_redirect_error,
     /// 用户取消
     qiniu_ng_err_kind_user_canceled,
+    /// 操作超过截止时间
+    qiniu_ng_err_kind_deadline_exceeded,
     /// 上传空文件错误
     qiniu_ng_err_kind_empty_file,
     /// JSON 错误
@@ -190,6 +196,7 @@ impl Default for qiniu_ng_err_kind_t {
 ///         - `qiniu_ng_err_unexpected_redirect_error_extract()`
 ///         - `qiniu_ng_err_drop_non_empty_bucket_error_extract()`
 ///         - `qiniu_ng_err_user_canceled_error_extract()`
+///         - `qiniu_ng_err_deadline_exceeded_error_extract()`
 ///         - `qiniu_ng_err_empty_file_error_extract()`
 ///         - `qiniu_ng_err_invalid_upload_token_extract()`
 ///         - `qiniu_ng_err_unknown_error_extract()`
@@ -349,6 +356,20 @@ pub extern "C" fn qiniu_ng_err_user_canceled_error_new() -> qiniu_ng_err_t {
     qiniu_ng_err_t(qiniu_ng_err_kind_t::qiniu_ng_err_kind_user_canceled)
 }
 
+/// @brief 判定错误是否是操作超过截止时间错误，如果是，则释放其内存
+/// @param[in] err SDK 错误实例
+/// @retval bool 当错误确实是操作超过截止时间错误时返回 `true`
+#[no_mangle]
+pub extern "C" fn qiniu_ng_err_deadline_exceeded_error_extract(err: &mut qiniu_ng_err_t) -> bool {
+    match err.0 {
+        qiniu_ng_err_kind_t::qiniu_ng_err_kind_deadline_exceeded => {
+            err.0 = qiniu_ng_err_kind_t::qiniu_ng_err_kind_none;
+            true
+        }
+        _ => false,
+    }
+}
+
 /// @brief 判定错误是否是 JSON 错误，如果是，则释放其内存
 /// @param[in] err SDK 错误实例
 /// @param[in] description 用于返回错误描述，如果传入 `NULL` 表示不获取 `description`，但如果错误确实是 JSON 错误，返回值依然是 `true` 且内存依然会被释放
@@ -725,6 +746,7 @@ impl From<&HTTPError> for qiniu_ng_err_t {
                 qiniu_ng_str_t::from_str_unchecked("Malicious HTTP Response, please try HTTPs protocol")
             })),
             HTTPErrorKind::UserCanceled => Self(qiniu_ng_err_kind_t::qiniu_ng_err_kind_user_canceled),
+            HTTPErrorKind::DeadlineExceeded => Self(qiniu_ng_err_kind_t::qiniu_ng_err_kind_deadline_exceeded),
             HTTPErrorKind::UnknownError(e) => Self(qiniu_ng_err_kind_t::qiniu_ng_err_kind_unknown_error(unsafe {
                 qiniu_ng_str_t::from_string_unchecked(e.to_string())
             })),
@@ -738,6 +760,25 @@ impl From<&UploadError> for qiniu_ng_err_t {
             UploadError::IOError(err) => err.into(),
             UploadError::QiniuError(err) => err.into(),
             UploadError::EmptyFileError => Self(qiniu_ng_err_kind_t::qiniu_ng_err_kind_empty_file),
+            UploadError::EtagMismatchError { .. } => {
+                Self(qiniu_ng_err_kind_t::qiniu_ng_err_kind_unknown_error(unsafe {
+                    qiniu_ng_str_t::from_string_unchecked(err.to_string())
+                }))
+            }
+            UploadError::DeserializeResponseError(e) => {
+                Self(qiniu_ng_err_kind_t::qiniu_ng_err_kind_json_error(unsafe {
+                    qiniu_ng_str_t::from_string_unchecked(e.to_string())
+                }))
+            }
+            UploadError::InvalidUploadTokenError(e) => e.into(),
+            UploadError::UploadTokenExpiredError { .. }
+            | UploadError::KeyMismatchError { .. }
+            | UploadError::FileSizeLimitationError { .. }
+            | UploadError::MimeTypeLimitationError { .. } => {
+                Self(qiniu_ng_err_kind_t::qiniu_ng_err_kind_unknown_error(unsafe {
+                    qiniu_ng_str_t::from_string_unchecked(err.to_string())
+                }))
+            }
         }
     }
 }
@@ -828,6 +869,7 @@ impl From<&qiniu_ng_err_kind_t> for Option<HTTPErrorKind> {
             ))),
             qiniu_ng_err_kind_t::qiniu_ng_err_kind_unexpected_redirect_error => Some(HTTPErrorKind::UnexpectedRedirect),
             qiniu_ng_err_kind_t::qiniu_ng_err_kind_user_canceled => Some(HTTPErrorKind::UserCanceled),
+            qiniu_ng_err_kind_t::qiniu_ng_err_kind_deadline_exceeded => Some(HTTPErrorKind::DeadlineExceeded),
             qiniu_ng_err_kind_t::qiniu_ng_err_kind_json_error(desc) => {
                 Some(HTTPErrorKind::JSONError(clone_string_from_qiniu_ng_str(*desc).into()))
             }
@@ -16,6 +16,10 @@ lazy_static! {
                 {
                     features.push("use-libcurl");
                 }
+                #[cfg(any(feature = "use-rustls"))]
+                {
+                    features.push("use-rustls");
+                }
             })
             .join(",")
             .into_bytes()
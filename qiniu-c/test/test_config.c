@@ -12,6 +12,8 @@ void test_qiniu_ng_config_new_default(void) {
 void test_qiniu_ng_config_new(void) {
     qiniu_ng_config_t config;
     qiniu_ng_config_builder_t builder = qiniu_ng_config_builder_new();
+    qiniu_ng_config_builder_operation_timeout(builder, 60);
+    qiniu_ng_config_builder_operation_timeout(builder, 0);
     TEST_ASSERT_TRUE_MESSAGE(
         qiniu_ng_config_build(&builder, &config, NULL),
         "qiniu_ng_config_build() failed");
@@ -28,6 +30,9 @@ void test_qiniu_ng_config_new(void) {
     TEST_ASSERT_EQUAL_INT_MESSAGE(
         qiniu_ng_config_get_upload_threshold(config), 1 << 22,
         "qiniu_ng_config_get_upload_threshold() returns unexpected value");
+    TEST_ASSERT_EQUAL_INT_MESSAGE(
+        qiniu_ng_config_get_operation_timeout(config), 0,
+        "qiniu_ng_config_get_operation_timeout() returns unexpected value");
 
     qiniu_ng_str_t user_agent = qiniu_ng_config_get_user_agent(config);
     TEST_ASSERT_EQUAL_INT_MESSAGE(
@@ -82,6 +87,12 @@ void test_qiniu_ng_config_new(void) {
         qiniu_ng_config_get_domains_manager_auto_persistent_disabled(config),
         "qiniu_ng_config_get_domains_manager_auto_persistent_disabled() returns unexpected value");
 
+    qiniu_ng_str_t http_proxy_url = qiniu_ng_config_get_http_proxy_url(config);
+    TEST_ASSERT_TRUE_MESSAGE(
+        qiniu_ng_str_is_null(http_proxy_url),
+        "qiniu_ng_str_is_null(http_proxy_url) returns unexpected value");
+    qiniu_ng_str_free(&http_proxy_url);
+
     qiniu_ng_config_free(&config);
 }
 
@@ -91,6 +102,7 @@ void test_qiniu_ng_config_new2(void) {
     qiniu_ng_config_builder_set_appended_user_agent(builder, QINIU_NG_CHARS("test-user-agent"));
     qiniu_ng_config_builder_use_https(builder, false);
     qiniu_ng_config_builder_batch_max_operation_size(builder, 10000);
+    qiniu_ng_config_builder_operation_timeout(builder, 60);
     qiniu_ng_config_builder_upload_threshold(builder, 1 << 23);
     qiniu_ng_config_builder_uc_host(builder, QINIU_NG_CHARS("uc.qiniu.com"));
     qiniu_ng_config_builder_disable_uplog(builder);
@@ -109,6 +121,8 @@ void test_qiniu_ng_config_new2(void) {
     free(temp_file);
     qiniu_ng_config_builder_domains_manager_url_frozen_duration(builder, 60 * 60 * 24);
     qiniu_ng_config_builder_domains_manager_disable_auto_persistent(builder);
+    qiniu_ng_config_builder_http_proxy(builder, qiniu_ng_proxy_socks5_hostname, QINIU_NG_CHARS("127.0.0.1"), 1080, QINIU_NG_CHARS("user"), QINIU_NG_CHARS("password"));
+    qiniu_ng_config_builder_http_no_proxy(builder, QINIU_NG_CHARS("localhost"));
 
     qiniu_ng_config_t config;
     TEST_ASSERT_TRUE_MESSAGE(
@@ -127,6 +141,9 @@ void test_qiniu_ng_config_new2(void) {
     TEST_ASSERT_EQUAL_INT_MESSAGE(
         qiniu_ng_config_get_upload_threshold(config), 1 << 23,
         "qiniu_ng_config_get_upload_threshold() returns unexpected value");
+    TEST_ASSERT_EQUAL_INT_MESSAGE(
+        qiniu_ng_config_get_operation_timeout(config), 60,
+        "qiniu_ng_config_get_operation_timeout() returns unexpected value");
 
     qiniu_ng_str_t user_agent = qiniu_ng_config_get_user_agent(config);
     TEST_ASSERT_EQUAL_INT_MESSAGE(
@@ -186,6 +203,12 @@ void test_qiniu_ng_config_new2(void) {
         qiniu_ng_config_get_domains_manager_auto_persistent_disabled(config),
         "qiniu_ng_config_get_domains_manager_auto_persistent_disabled() returns unexpected value");
 
+    qiniu_ng_str_t http_proxy_url = qiniu_ng_config_get_http_proxy_url(config);
+    TEST_ASSERT_EQUAL_STRING_MESSAGE(
+        qiniu_ng_str_get_ptr(http_proxy_url), QINIU_NG_CHARS("socks5h://127.0.0.1:1080"),
+        "qiniu_ng_str_get_ptr(http_proxy_url) != \"socks5h://127.0.0.1:1080\"");
+    qiniu_ng_str_free(&http_proxy_url);
+
     qiniu_ng_config_free(&config);
 }
 
@@ -61,6 +61,10 @@ pub enum ErrorKind {
     ResponseStatusCodeError(StatusCode, Box<str>),
     /// 用户取消
     UserCanceled,
+    /// 操作超过截止时间
+    ///
+    /// 操作（包含其所有重试）的总耗时超过了设定的操作超时时长
+    DeadlineExceeded,
 }
 
 /// HTTP 调用错误
@@ -96,6 +100,8 @@ pub enum HTTPCallerErrorKind {
     ProxyError,
     /// SSL 错误
     SSLError,
+    /// SSL / TLS 握手错误，包括服务器证书验证失败和固定公钥不匹配
+    SSLHandshakeError,
     /// 连接错误
     ConnectionError,
     /// 请求错误
@@ -302,6 +308,14 @@ impl Error {
         Self::new(RetryKind::UnretryableError, error_kind, false, method, url, request_id)
     }
 
+    /// 修改可重试类型
+    ///
+    /// 通常用于重试策略决定放弃重试，或是切换区域时，修正错误的可重试类型，以便于上层逻辑做出正确的处理
+    pub fn with_retry_kind(mut self, retry_kind: RetryKind) -> Self {
+        self.retry_kind = retry_kind;
+        self
+    }
+
     fn extract_req_id_from_response(response: Option<&Response>) -> Option<RequestID> {
         response.and_then(|resp| {
             resp.headers()
@@ -341,6 +355,7 @@ impl fmt::Display for ErrorKind {
             Self::MaliciousResponse => write!(f, "MaliciousResponse"),
             Self::UnexpectedRedirect => write!(f, "UnexpectedRedirect"),
             Self::UserCanceled => write!(f, "UserCanceled"),
+            Self::DeadlineExceeded => write!(f, "DeadlineExceeded"),
             Self::IOError(err) => write!(f, "IOError({})", err),
             Self::UnknownError(err) => write!(f, "UnknownError({})", err),
             Self::ResponseStatusCodeError(status_code, error_message) => write!(
@@ -399,6 +414,7 @@ impl StdError for Error {
             ErrorKind::MaliciousResponse => "Malicious response",
             ErrorKind::UnexpectedRedirect => "Unexpected redirect",
             ErrorKind::UserCanceled => "User canceled",
+            ErrorKind::DeadlineExceeded => "Deadline exceeded",
             ErrorKind::IOError(err) => err.description(),
             ErrorKind::UnknownError(err) => err.description(),
             ErrorKind::ResponseStatusCodeError(_, error_message) => &error_message,
@@ -28,13 +28,20 @@
 mod error;
 mod header;
 mod method;
+mod proxy;
 mod request;
 mod response;
+mod tls;
 pub use error::{Error, ErrorKind, HTTPCallerError, HTTPCallerErrorKind, Result, RetryKind};
 pub use header::{HeaderName, HeaderNameOwned, HeaderValue, HeaderValueOwned, Headers, HeadersOwned};
 pub use method::Method;
-pub use request::{Body as RequestBody, ProgressCallback, Request, RequestBuilder, URL};
+pub use proxy::{Proxy, ProxyType};
+pub use request::{
+    Body as RequestBody, BodyReader as RequestBodyReader, ProgressCallback, Request, RequestBuilder, URL,
+};
 pub use response::{Body as ResponseBody, Response, ResponseBuilder, StatusCode};
+use std::{future::Future, pin::Pin};
+pub use tls::{PEMSource, TLSOptions, TLSVersion};
 
 /// HTTP 请求处理函数
 ///
@@ -42,3 +49,16 @@ pub use response::{Body as ResponseBody, Response, ResponseBuilder, StatusCode};
 pub trait HTTPCaller: Send + Sync {
     fn call(&self, request: &Request) -> Result<Response>;
 }
+
+/// 异步 HTTP 响应
+pub type AsyncResponse<'a> = Pin<Box<dyn Future<Output = Result<Response>> + Send + 'a>>;
+
+/// 异步 HTTP 请求处理函数
+///
+/// 实现该接口，即可异步处理七牛 SDK 异步接口发送的 HTTP 请求。
+///
+/// 注意，返回的 Future 不能持有 `request` 的引用，实现者应该在返回 Future 前从 `request` 中提取所需的全部数据。
+/// 进度回调闭包只能在调用 `call` 期间使用，因此异步实现可以不支持进度回调
+pub trait AsyncHTTPCaller: Send + Sync {
+    fn call<'a>(&'a self, request: &Request) -> AsyncResponse<'a>;
+}
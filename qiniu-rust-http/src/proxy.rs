@@ -0,0 +1,181 @@
+use getset::{CopyGetters, Getters};
+use std::{borrow::Cow, fmt};
+
+/// 代理服务器类型
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ProxyType {
+    /// HTTP 代理
+    ///
+    /// 对于 HTTP 请求，将直接由代理服务器转发，对于 HTTPS 请求，将通过 `CONNECT` 方法建立隧道
+    HTTP,
+    /// HTTPS 代理
+    ///
+    /// 与 HTTP 代理相同，但与代理服务器之间的连接将使用 TLS 加密。
+    /// 使用 libcurl 时需要 libcurl 启用 HTTPS 代理支持，否则请求将返回 `ProxyError` 错误
+    HTTPS,
+    /// SOCKS5 代理，由客户端解析域名
+    SOCKS5,
+    /// SOCKS5 代理，由代理服务器解析域名
+    SOCKS5Hostname,
+}
+
+impl ProxyType {
+    /// 获取代理服务器类型对应的 URL 协议
+    pub fn scheme(self) -> &'static str {
+        match self {
+            ProxyType::HTTP => "http",
+            ProxyType::HTTPS => "https",
+            ProxyType::SOCKS5 => "socks5",
+            ProxyType::SOCKS5Hostname => "socks5h",
+        }
+    }
+}
+
+/// 代理服务器
+///
+/// 包含代理服务器的类型，地址，认证信息和不使用代理的主机列表
+#[derive(Clone, PartialEq, Eq, Getters, CopyGetters)]
+pub struct Proxy {
+    /// 代理服务器类型
+    #[get_copy = "pub"]
+    proxy_type: ProxyType,
+
+    /// 代理服务器主机地址
+    #[get = "pub"]
+    host: String,
+
+    /// 代理服务器端口
+    #[get_copy = "pub"]
+    port: u16,
+
+    /// 代理服务器认证用户名
+    #[get = "pub"]
+    username: Option<String>,
+
+    /// 代理服务器认证密码
+    #[get = "pub"]
+    password: Option<String>,
+
+    /// 不使用代理的主机列表
+    #[get = "pub"]
+    no_proxy_hosts: Vec<String>,
+}
+
+impl Proxy {
+    /// 创建代理服务器
+    pub fn new(proxy_type: ProxyType, host: impl Into<String>, port: u16) -> Self {
+        Self {
+            proxy_type,
+            host: host.into(),
+            port,
+            username: None,
+            password: None,
+            no_proxy_hosts: Vec::new(),
+        }
+    }
+
+    /// 设置代理服务器认证信息
+    pub fn credential(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.username = Some(username.into());
+        self.password = Some(password.into());
+        self
+    }
+
+    /// 添加不使用代理的主机
+    ///
+    /// 主机将匹配其自身及其所有子域名，以 `.` 开头的主机与不以 `.` 开头的主机效果相同。
+    /// 如果传入 `*`，则所有主机均不使用代理
+    pub fn no_proxy(mut self, host: impl Into<String>) -> Self {
+        self.no_proxy_hosts.push(host.into());
+        self
+    }
+
+    /// 获取代理服务器 URL，不包含认证信息
+    pub fn url(&self) -> String {
+        format!("{}://{}:{}", self.proxy_type.scheme(), self.host_for_url(), self.port)
+    }
+
+    /// 判断访问指定主机时是否应该绕过代理服务器
+    pub fn should_bypass(&self, host: &str) -> bool {
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        self.no_proxy_hosts.iter().any(|no_proxy_host| {
+            let no_proxy_host = no_proxy_host.trim().trim_start_matches('.');
+            if no_proxy_host == "*" {
+                return true;
+            }
+            let no_proxy_host = no_proxy_host.trim_start_matches('[').trim_end_matches(']');
+            if no_proxy_host.is_empty() || host.len() < no_proxy_host.len() {
+                return false;
+            }
+            let (prefix, suffix) = host.split_at(host.len() - no_proxy_host.len());
+            suffix.eq_ignore_ascii_case(no_proxy_host) && (prefix.is_empty() || prefix.ends_with('.'))
+        })
+    }
+
+    fn host_for_url(&self) -> Cow<'_, str> {
+        if self.host.contains(':') && !self.host.starts_with('[') {
+            Cow::Owned(format!("[{}]", self.host))
+        } else {
+            Cow::Borrowed(&self.host)
+        }
+    }
+}
+
+impl fmt::Debug for Proxy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Proxy")
+            .field("proxy_type", &self.proxy_type)
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "[REDACTED]"))
+            .field("no_proxy_hosts", &self.no_proxy_hosts)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{error::Error, result::Result};
+
+    #[test]
+    fn test_proxy_url() -> Result<(), Box<dyn Error>> {
+        assert_eq!(
+            Proxy::new(ProxyType::HTTP, "proxy.example.com", 3128).url(),
+            "http://proxy.example.com:3128"
+        );
+        assert_eq!(
+            Proxy::new(ProxyType::SOCKS5Hostname, "::1", 1080).url(),
+            "socks5h://[::1]:1080"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_proxy_should_bypass() -> Result<(), Box<dyn Error>> {
+        let proxy = Proxy::new(ProxyType::HTTP, "proxy.example.com", 3128)
+            .no_proxy("qiniu.com")
+            .no_proxy(".internal")
+            .no_proxy("192.168.1.1")
+            .no_proxy("::1");
+        assert!(proxy.should_bypass("qiniu.com"));
+        assert!(proxy.should_bypass("up.QINIU.com"));
+        assert!(!proxy.should_bypass("notqiniu.com"));
+        assert!(proxy.should_bypass("uc.internal"));
+        assert!(proxy.should_bypass("192.168.1.1"));
+        assert!(!proxy.should_bypass("192.168.1.10"));
+        assert!(proxy.should_bypass("[::1]"));
+        assert!(!proxy.should_bypass("qbox.me"));
+
+        assert!(Proxy::new(ProxyType::HTTP, "proxy.example.com", 3128)
+            .no_proxy("*")
+            .should_bypass("qbox.me"));
+        assert!(!format!(
+            "{:?}",
+            Proxy::new(ProxyType::HTTP, "proxy.example.com", 3128).credential("user", "secret")
+        )
+        .contains("secret"));
+        Ok(())
+    }
+}
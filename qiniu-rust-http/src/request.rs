@@ -1,12 +1,178 @@
-use super::{HeaderName, HeaderValue, Headers, Method};
+use super::{HeaderName, HeaderValue, Headers, Method, Proxy, TLSOptions};
 use getset::{CopyGetters, Getters, MutGetters};
-use std::{borrow::Cow, ffi::c_void, fmt, net::SocketAddr, ptr::null_mut, time::Duration};
+use std::{
+    borrow::Cow,
+    convert::TryInto,
+    ffi::c_void,
+    fmt,
+    io::{Error as IOError, ErrorKind as IOErrorKind, Read, Result as IOResult, Seek, SeekFrom},
+    net::SocketAddr,
+    ptr::null_mut,
+    time::Duration,
+};
 
 /// 请求 URL
 pub type URL<'b> = Cow<'b, str>;
 
 /// 请求体
-pub type Body<'b> = Cow<'b, [u8]>;
+///
+/// 请求体由一个或多个数据片段依次拼接而成，这样在请求体中嵌入调用方持有的数据时无需复制。
+/// HTTP 请求处理函数应当依次发送所有片段，可以通过 `reader` 方法读取整个请求体
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Body<'b> {
+    segments: Vec<Cow<'b, [u8]>>,
+}
+
+impl<'b> Body<'b> {
+    /// 由多个数据片段组成请求体
+    pub fn from_segments(segments: impl IntoIterator<Item = Cow<'b, [u8]>>) -> Body<'b> {
+        Body {
+            segments: segments.into_iter().filter(|segment| !segment.is_empty()).collect(),
+        }
+    }
+
+    /// 请求体的数据片段
+    pub fn segments(&self) -> &[Cow<'b, [u8]>] {
+        &self.segments
+    }
+
+    /// 请求体长度
+    pub fn len(&self) -> usize {
+        self.segments.iter().map(|segment| segment.len()).sum()
+    }
+
+    /// 请求体是否为空
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// 获取完整的请求体
+    ///
+    /// 仅当请求体由多个片段组成时才会复制数据
+    pub fn to_bytes(&self) -> Cow<[u8]> {
+        match self.segments.as_slice() {
+            [] => Cow::Borrowed(&[]),
+            [segment] => Cow::Borrowed(segment.as_ref()),
+            segments => Cow::Owned(segments.concat()),
+        }
+    }
+
+    /// 将请求体合并为一个片段，并返回完整的请求体
+    ///
+    /// 适用于需要长期持有连续的请求体内存的场景
+    pub fn make_contiguous(&mut self) -> &[u8] {
+        if self.segments.len() > 1 {
+            self.segments = vec![Cow::Owned(self.segments.concat())];
+        }
+        self.segments.first().map_or(&[], |segment| segment.as_ref())
+    }
+
+    /// 借用当前请求体的数据，生成新的请求体
+    pub fn as_borrowed(&self) -> Body {
+        Body {
+            segments: self
+                .segments
+                .iter()
+                .map(|segment| Cow::Borrowed(segment.as_ref()))
+                .collect(),
+        }
+    }
+
+    /// 创建请求体读取器
+    ///
+    /// 读取器支持倒回，便于 HTTP 请求处理函数重新发送请求体
+    pub fn reader(&self) -> BodyReader {
+        BodyReader {
+            segments: &self.segments,
+            position: 0,
+        }
+    }
+}
+
+impl<'b> From<Cow<'b, [u8]>> for Body<'b> {
+    fn from(body: Cow<'b, [u8]>) -> Self {
+        Body::from_segments(Some(body))
+    }
+}
+
+impl<'b> From<&'b [u8]> for Body<'b> {
+    fn from(body: &'b [u8]) -> Self {
+        Cow::Borrowed(body).into()
+    }
+}
+
+impl From<Vec<u8>> for Body<'_> {
+    fn from(body: Vec<u8>) -> Self {
+        Cow::<[u8]>::Owned(body).into()
+    }
+}
+
+impl fmt::Debug for Body<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Body")
+            .field("len", &self.len())
+            .field("segments", &self.segments.len())
+            .finish()
+    }
+}
+
+/// 请求体读取器
+pub struct BodyReader<'r> {
+    segments: &'r [Cow<'r, [u8]>],
+    position: u64,
+}
+
+impl Read for BodyReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> IOResult<usize> {
+        let mut offset = self.position;
+        for segment in self.segments.iter() {
+            let segment_size: u64 = segment.len().try_into().unwrap_or(u64::max_value());
+            if offset < segment_size {
+                let rest = &segment[offset.try_into().unwrap_or(usize::max_value())..];
+                let size = rest.len().min(buf.len());
+                buf[..size].copy_from_slice(&rest[..size]);
+                self.position += size as u64;
+                return Ok(size);
+            }
+            offset -= segment_size;
+        }
+        Ok(0)
+    }
+}
+
+impl Seek for BodyReader<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> IOResult<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(position) => {
+                self.position = position;
+                return Ok(position);
+            }
+            SeekFrom::End(offset) => (
+                self.segments
+                    .iter()
+                    .map(|segment| segment.len().try_into().unwrap_or(u64::max_value()))
+                    .sum(),
+                offset,
+            ),
+            SeekFrom::Current(offset) => (self.position, offset),
+        };
+        let position = if offset >= 0 {
+            base.checked_add(offset as u64)
+        } else {
+            base.checked_sub(offset.wrapping_neg() as u64)
+        };
+        match position {
+            Some(position) => {
+                self.position = position;
+                Ok(position)
+            }
+            None => Err(IOError::new(
+                IOErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+}
 
 /// 进度回调闭包
 #[derive(Copy, Clone)]
@@ -56,6 +222,16 @@ pub struct Request<'b> {
     #[get_mut = "pub"]
     resolved_socket_addrs: Cow<'b, [SocketAddr]>,
 
+    /// 代理服务器
+    #[get_copy = "pub"]
+    #[get_mut = "pub"]
+    proxy: Option<&'b Proxy>,
+
+    /// TLS 选项
+    #[get_copy = "pub"]
+    #[get_mut = "pub"]
+    tls_options: Option<&'b TLSOptions>,
+
     /// 自定义数据指针
     #[get_copy = "pub"]
     #[get_mut = "pub"]
@@ -187,6 +363,18 @@ impl<'r> RequestBuilder<'r> {
         self
     }
 
+    /// 设置代理服务器
+    pub fn proxy(mut self, proxy: &'r Proxy) -> RequestBuilder<'r> {
+        self.request.proxy = Some(proxy);
+        self
+    }
+
+    /// 设置 TLS 选项
+    pub fn tls_options(mut self, tls_options: &'r TLSOptions) -> RequestBuilder<'r> {
+        self.request.tls_options = Some(tls_options);
+        self
+    }
+
     /// 设置上传进度回调
     pub fn on_uploading_progress(mut self, callback: impl Into<ProgressCallback<'r>>) -> RequestBuilder<'r> {
         self.request.on_uploading_progress = Some(callback.into());
@@ -247,10 +435,12 @@ impl Default for Request<'_> {
             url: "http://localhost".into(),
             method: Method::GET,
             headers: Headers::new(),
-            body: Cow::Borrowed(&[]),
+            body: Default::default(),
             user_agent: Cow::Borrowed(""),
             follow_redirection: false,
             resolved_socket_addrs: Cow::Borrowed(&[]),
+            proxy: None,
+            tls_options: None,
             on_uploading_progress: None,
             on_downloading_progress: None,
             custom_data: null_mut(),
@@ -274,6 +464,8 @@ impl fmt::Debug for Request<'_> {
             .field("user_agent", &self.user_agent)
             .field("follow_redirection", &self.follow_redirection)
             .field("resolved_socket_addrs", &self.resolved_socket_addrs)
+            .field("proxy", &self.proxy)
+            .field("tls_options", &self.tls_options)
             .field(
                 "on_uploading_progress",
                 if self.on_uploading_progress.is_some() {
@@ -321,3 +513,45 @@ impl From<fn(u64, u64)> for ProgressCallback<'_> {
         Self(_ProgressCallback::Fn(f))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{error::Error, result::Result};
+
+    #[test]
+    fn test_request_body_segments() -> Result<(), Box<dyn Error>> {
+        let data = b"hello qiniu".to_vec();
+        let mut body = Body::from_segments(vec![
+            Cow::Owned(b"--prefix\r\n".to_vec()),
+            Cow::Borrowed(data.as_slice()),
+            Cow::Borrowed(b"".as_ref()),
+            Cow::Borrowed(b"\r\n--suffix".as_ref()),
+        ]);
+        assert_eq!(body.segments().len(), 3);
+        assert_eq!(body.len(), 31);
+        assert_eq!(
+            body.to_bytes().as_ref(),
+            b"--prefix\r\nhello qiniu\r\n--suffix".as_ref()
+        );
+        assert!(matches!(body.segments()[1], Cow::Borrowed(_)));
+
+        let mut reader = body.reader();
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+        assert_eq!(buf.as_slice(), body.to_bytes().as_ref());
+
+        assert_eq!(reader.seek(SeekFrom::Start(10))?, 10);
+        let mut buf = [0u8; 5];
+        reader.read_exact(&mut buf)?;
+        assert_eq!(&buf, b"hello");
+        assert_eq!(reader.seek(SeekFrom::End(-8))?, 23);
+        assert_eq!(reader.seek(SeekFrom::Current(-23))?, 0);
+        assert!(reader.seek(SeekFrom::Current(-1)).is_err());
+
+        assert_eq!(body.make_contiguous(), b"--prefix\r\nhello qiniu\r\n--suffix".as_ref());
+        assert_eq!(body.segments().len(), 1);
+        assert!(Body::default().is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+use getset::{CopyGetters, Getters};
+use std::{fmt, path::PathBuf};
+
+/// PEM 格式数据来源
+#[derive(Clone, PartialEq, Eq)]
+pub enum PEMSource {
+    /// PEM 文件路径
+    File(PathBuf),
+    /// PEM 格式数据
+    Content(Vec<u8>),
+}
+
+impl fmt::Debug for PEMSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PEMSource::File(path) => f.debug_tuple("File").field(path).finish(),
+            // 私钥数据不应该被输出到日志中，因此这里仅输出数据长度
+            PEMSource::Content(content) => write!(f, "Content([{} bytes])", content.len()),
+        }
+    }
+}
+
+/// TLS 协议版本
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TLSVersion {
+    /// TLS 1.0
+    TLSv1_0,
+    /// TLS 1.1
+    TLSv1_1,
+    /// TLS 1.2
+    TLSv1_2,
+    /// TLS 1.3
+    TLSv1_3,
+}
+
+/// TLS 选项
+///
+/// 用于访问使用私有 CA 签发证书，或要求客户端证书认证的服务器，以及固定服务器公钥
+#[derive(Clone, Debug, Default, PartialEq, Eq, Getters, CopyGetters)]
+pub struct TLSOptions {
+    /// 信任的 CA 证书
+    ///
+    /// 设置后将代替默认信任的 CA 证书，因此如果还需要访问使用公共 CA 签发证书的服务器，则需要包含相应的 CA 证书
+    #[get = "pub"]
+    ca_bundle: Option<PEMSource>,
+
+    /// 客户端证书
+    #[get = "pub"]
+    client_certificate: Option<PEMSource>,
+
+    /// 客户端私钥
+    #[get = "pub"]
+    client_private_key: Option<PEMSource>,
+
+    /// 最低 TLS 协议版本
+    #[get_copy = "pub"]
+    min_version: Option<TLSVersion>,
+
+    /// 固定的服务器公钥列表
+    ///
+    /// 列表中每一项均为服务器证书 SubjectPublicKeyInfo 的 SHA-256 摘要的 Base64 编码。
+    /// 列表非空时，服务器证书的公钥必须与列表中的任意一项匹配，否则 TLS 握手将失败
+    #[get = "pub"]
+    pinned_public_keys: Vec<String>,
+}
+
+impl TLSOptions {
+    /// 设置信任的 CA 证书
+    pub fn set_ca_bundle(mut self, ca_bundle: PEMSource) -> Self {
+        self.ca_bundle = Some(ca_bundle);
+        self
+    }
+
+    /// 设置客户端证书及其私钥
+    pub fn set_client_certificate(mut self, certificate: PEMSource, private_key: PEMSource) -> Self {
+        self.client_certificate = Some(certificate);
+        self.client_private_key = Some(private_key);
+        self
+    }
+
+    /// 设置最低 TLS 协议版本
+    pub fn set_min_version(mut self, min_version: TLSVersion) -> Self {
+        self.min_version = Some(min_version);
+        self
+    }
+
+    /// 添加固定的服务器公钥
+    ///
+    /// 传入服务器证书 SubjectPublicKeyInfo 的 SHA-256 摘要的 Base64 编码
+    pub fn pin_public_key(mut self, sha256_digest: impl Into<String>) -> Self {
+        self.pinned_public_keys.push(sha256_digest.into());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{error::Error, result::Result};
+
+    #[test]
+    fn test_tls_options() -> Result<(), Box<dyn Error>> {
+        let tls_options = TLSOptions::default()
+            .set_ca_bundle(PEMSource::File("/etc/ssl/private-ca.pem".into()))
+            .set_client_certificate(
+                PEMSource::Content(b"certificate".to_vec()),
+                PEMSource::Content(b"private key".to_vec()),
+            )
+            .set_min_version(TLSVersion::TLSv1_2)
+            .pin_public_key("r/mIkG3eEpVdm+u/ko/cwxzOMo1bk4TyHIlByibiA5E=")
+            .pin_public_key("YLh1dUR9y6Kja30RrAn7JKnbQG/uEtLMkBgFF2Fuihg=");
+        assert_eq!(
+            tls_options.ca_bundle(),
+            &Some(PEMSource::File("/etc/ssl/private-ca.pem".into()))
+        );
+        assert_eq!(tls_options.min_version(), Some(TLSVersion::TLSv1_2));
+        assert_eq!(tls_options.pinned_public_keys().len(), 2);
+        assert!(TLSVersion::TLSv1_0 < TLSVersion::TLSv1_3);
+        assert!(!format!("{:?}", tls_options).contains("private key"));
+        Ok(())
+    }
+}
@@ -0,0 +1,381 @@
+use super::http_call_mock::fake_req_id;
+use qiniu_http::{
+    Error as HTTPError, ErrorKind as HTTPErrorKind, HTTPCaller, HeadersOwned, Request, Response, ResponseBody,
+    ResponseBuilder, Result, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{read, write},
+    io::{Error as IOError, ErrorKind as IOErrorKind, Read, Result as IOResult},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use url::Url;
+
+const REDACTED: &str = "[REDACTED]";
+const REDACTED_HEADERS: &[&str] = &["authorization", "proxy-authorization", "cookie", "set-cookie"];
+const REDACTED_QUERY_PAIRS: &[&str] = &["token"];
+
+#[derive(Serialize, Deserialize, Default)]
+struct Cassette {
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct RecordedRequest {
+    method: String,
+    url: String,
+    path: String,
+    headers: BTreeMap<String, String>,
+    body_hash: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct RecordedResponse {
+    status_code: StatusCode,
+    headers: BTreeMap<String, String>,
+    body: String,
+}
+
+impl Cassette {
+    fn load(path: &Path) -> IOResult<Self> {
+        serde_json::from_slice(&read(path)?).map_err(|err| IOError::new(IOErrorKind::InvalidData, err))
+    }
+
+    fn save(&self, path: &Path) -> IOResult<()> {
+        write(path, serde_json::to_vec_pretty(self)?)
+    }
+}
+
+impl RecordedRequest {
+    fn new(request: &Request) -> Self {
+        Self {
+            method: request.method().as_str().to_owned(),
+            url: redact_url(request.url()),
+            path: url_path(request.url()),
+            headers: request
+                .headers()
+                .iter()
+                .map(|(name, value)| (name.as_ref().to_owned(), redact_header(name.as_ref(), value.as_ref())))
+                .collect(),
+            body_hash: body_hash(&request.body().to_bytes()),
+        }
+    }
+
+    fn key(&self, match_body: bool) -> String {
+        if match_body {
+            format!("{} {} {}", self.method, self.path, self.body_hash)
+        } else {
+            format!("{} {}", self.method, self.path)
+        }
+    }
+}
+
+impl RecordedResponse {
+    // 响应体只读取一次，录制后以字节数组的形式放回响应中。
+    // `Response::clone_body` 复制的文件与原文件共享读取位置，读取后原响应体将为空，因此不能使用
+    fn new(response: &mut Response) -> IOResult<Self> {
+        let mut body = Vec::new();
+        match response.take_body() {
+            Some(ResponseBody::Bytes(bytes)) => body = bytes,
+            Some(ResponseBody::File(mut file)) => {
+                file.read_to_end(&mut body)?;
+            }
+            Some(ResponseBody::Reader(mut reader)) => {
+                reader.read_to_end(&mut body)?;
+            }
+            None => {}
+        }
+        if !body.is_empty() {
+            *response.body_mut() = Some(ResponseBody::Bytes(body.to_owned()));
+        }
+        Ok(Self {
+            status_code: response.status_code(),
+            headers: response
+                .headers()
+                .iter()
+                .map(|(name, value)| (name.as_ref().to_owned(), redact_header(name.as_ref(), value)))
+                .collect(),
+            body: base64::encode(&body),
+        })
+    }
+
+    fn to_response(&self) -> IOResult<Response> {
+        let mut headers: HeadersOwned = self
+            .headers
+            .iter()
+            .map(|(name, value)| (name.to_owned().into(), value.to_owned()))
+            .collect();
+        headers.entry("X-Reqid".into()).or_insert_with(fake_req_id);
+        let body = base64::decode(&self.body).map_err(|err| IOError::new(IOErrorKind::InvalidData, err))?;
+        let mut builder = ResponseBuilder::default()
+            .status_code(self.status_code)
+            .headers(headers);
+        if !body.is_empty() {
+            builder = builder.bytes_as_body(body);
+        }
+        Ok(builder.build())
+    }
+}
+
+fn url_path(url: &str) -> String {
+    Url::parse(url).map_or_else(|_| url.to_owned(), |url| url.path().to_owned())
+}
+
+fn redact_url(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(mut parsed) => {
+            if parsed.query().is_none() {
+                return url.to_owned();
+            }
+            let pairs = parsed
+                .query_pairs()
+                .map(|(key, value)| {
+                    if REDACTED_QUERY_PAIRS.contains(&key.as_ref()) {
+                        (key.into_owned(), REDACTED.to_owned())
+                    } else {
+                        (key.into_owned(), value.into_owned())
+                    }
+                })
+                .collect::<Vec<_>>();
+            parsed.query_pairs_mut().clear().extend_pairs(pairs);
+            parsed.to_string()
+        }
+        Err(_) => url.to_owned(),
+    }
+}
+
+fn redact_header(name: &str, value: &str) -> String {
+    if REDACTED_HEADERS.contains(&name.to_lowercase().as_str()) {
+        REDACTED.to_owned()
+    } else {
+        value.to_owned()
+    }
+}
+
+fn body_hash(body: &[u8]) -> String {
+    hex::encode(Sha1::digest(body))
+}
+
+fn replay_error(request: &Request, message: String) -> HTTPError {
+    HTTPError::new_unretryable_error_from_req_resp(
+        HTTPErrorKind::IOError(IOError::new(IOErrorKind::NotFound, message)),
+        request,
+        None,
+    )
+}
+
+/// 录制 HTTP 请求与响应，并保存到磁带文件
+///
+/// 每次调用成功后都会立即重写磁带文件，请求和响应中的认证信息将被脱敏。
+/// HTTP 调用本身发生的错误不会被录制
+pub struct RecordingCaller<T: HTTPCaller> {
+    caller: T,
+    path: PathBuf,
+    cassette: Mutex<Cassette>,
+}
+
+impl<T: HTTPCaller> RecordingCaller<T> {
+    pub fn new(caller: T, path: impl Into<PathBuf>) -> Self {
+        Self {
+            caller,
+            path: path.into(),
+            cassette: Default::default(),
+        }
+    }
+
+    pub fn recorded(&self) -> usize {
+        self.cassette.lock().unwrap().interactions.len()
+    }
+
+    fn record(&self, request: &Request, response: &mut Response) -> IOResult<()> {
+        let interaction = Interaction {
+            request: RecordedRequest::new(request),
+            response: RecordedResponse::new(response)?,
+        };
+        let mut cassette = self.cassette.lock().unwrap();
+        cassette.interactions.push(interaction);
+        cassette.save(&self.path)
+    }
+}
+
+impl<T: HTTPCaller> HTTPCaller for RecordingCaller<T> {
+    fn call(&self, request: &Request) -> Result<Response> {
+        let mut response = self.caller.call(request)?;
+        self.record(request, &mut response).map_err(|err| {
+            HTTPError::new_unretryable_error_from_req_resp(HTTPErrorKind::IOError(err), request, None)
+        })?;
+        Ok(response)
+    }
+}
+
+/// 回放磁带文件中录制的 HTTP 响应
+///
+/// 按照 HTTP 方法，URL 路径和请求体摘要匹配请求，忽略主机名和查询参数，以便于在不同区域的主机上回放。
+/// 对于多次录制的相同请求，按照录制顺序依次回放，全部回放后重复回放最后一次的响应
+pub struct ReplayCaller {
+    recorded: Vec<Interaction>,
+    interactions: HashMap<String, Vec<usize>>,
+    played: Mutex<HashMap<String, usize>>,
+    match_body: bool,
+}
+
+impl ReplayCaller {
+    pub fn new(path: impl AsRef<Path>) -> IOResult<Self> {
+        Ok(Self::from_interactions(
+            Cassette::load(path.as_ref())?.interactions,
+            true,
+        ))
+    }
+
+    /// 不再匹配请求体摘要
+    ///
+    /// 适用于请求体中含有随机内容的请求，例如 multipart 表单的分隔符
+    pub fn ignore_body(self) -> Self {
+        Self::from_interactions(self.recorded, false)
+    }
+
+    fn from_interactions(recorded: Vec<Interaction>, match_body: bool) -> Self {
+        let mut interactions: HashMap<String, Vec<usize>> = HashMap::new();
+        for (index, interaction) in recorded.iter().enumerate() {
+            interactions
+                .entry(interaction.request.key(match_body))
+                .or_default()
+                .push(index);
+        }
+        Self {
+            recorded,
+            interactions,
+            played: Default::default(),
+            match_body,
+        }
+    }
+}
+
+impl HTTPCaller for ReplayCaller {
+    fn call(&self, request: &Request) -> Result<Response> {
+        let key = RecordedRequest::new(request).key(self.match_body);
+        let interactions = self
+            .interactions
+            .get(&key)
+            .ok_or_else(|| replay_error(request, format!("No recorded interaction for {}", key)))?;
+        let index = {
+            let mut played = self.played.lock().unwrap();
+            let played = played.entry(key).or_insert(0);
+            let index = interactions[(*played).min(interactions.len() - 1)];
+            *played += 1;
+            index
+        };
+        self.recorded[index]
+            .response
+            .to_response()
+            .map_err(|err| HTTPError::new_unretryable_error_from_req_resp(HTTPErrorKind::IOError(err), request, None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::http_call_mock::CallHandlers, *};
+    use qiniu_http::{Method, RequestBuilder};
+    use std::{error::Error, io::Write, result::Result};
+    use tempfile::{tempdir, tempfile};
+
+    #[test]
+    fn test_record_and_replay() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        let path = dir.path().join("cassette.json");
+        let recorder = RecordingCaller::new(
+            CallHandlers::new(|_| panic!("Unexpected call")).install(Method::POST, r"/upload", |request, called| {
+                Ok(ResponseBuilder::default()
+                    .status_code(200u16)
+                    .header("Content-Type", "application/json")
+                    .bytes_as_body(format!("{{\"size\":{},\"called\":{}}}", request.body().len(), called))
+                    .build())
+            }),
+            &path,
+        );
+        for body in [b"hello".as_ref(), b"hello", b"world"].iter() {
+            let request = RequestBuilder::default()
+                .method(Method::POST)
+                .url("http://up.qiniup.com/upload?token=secret")
+                .header("Authorization", "UpToken secret")
+                .body(*body)
+                .build();
+            let mut response = recorder.call(&request)?;
+            assert_eq!(response.status_code(), 200);
+            assert!(response.body_len()? > 0);
+        }
+        assert_eq!(recorder.recorded(), 3);
+        let content = String::from_utf8(read(&path)?)?;
+        assert!(!content.contains("secret"));
+
+        let replayer = ReplayCaller::new(&path)?;
+        let request = RequestBuilder::default()
+            .method(Method::POST)
+            .url("http://up-z1.qiniup.com/upload")
+            .body(b"hello".as_ref())
+            .build();
+        for &called in [1, 2, 2].iter() {
+            let mut response = replayer.call(&request)?;
+            assert_eq!(response.status_code(), 200);
+            assert_eq!(
+                read_body(&mut response)?,
+                format!("{{\"size\":5,\"called\":{}}}", called)
+            );
+        }
+        let request = RequestBuilder::default()
+            .method(Method::POST)
+            .url("http://up.qiniup.com/upload")
+            .body(b"unknown".as_ref())
+            .build();
+        assert!(replayer.call(&request).is_err());
+
+        let replayer = ReplayCaller::new(&path)?.ignore_body();
+        assert!(replayer.call(&request).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_file_body() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        let path = dir.path().join("cassette.json");
+        let recorder = RecordingCaller::new(
+            CallHandlers::new(|_| panic!("Unexpected call")).install(Method::GET, r"/download", |_, _| {
+                let mut file = tempfile().unwrap();
+                file.write_all(b"file body").unwrap();
+                Ok(ResponseBuilder::default()
+                    .status_code(200u16)
+                    .header("Content-Type", "application/octet-stream")
+                    .file_as_body(file)
+                    .unwrap()
+                    .build())
+            }),
+            &path,
+        );
+        let request = RequestBuilder::default()
+            .method(Method::GET)
+            .url("http://iovip.qbox.me/download")
+            .build();
+        let mut response = recorder.call(&request)?;
+        assert_eq!(read_body(&mut response)?, "file body");
+
+        let mut response = ReplayCaller::new(&path)?.call(&request)?;
+        assert_eq!(read_body(&mut response)?, "file body");
+        Ok(())
+    }
+
+    fn read_body(response: &mut Response) -> IOResult<String> {
+        match response.take_body() {
+            Some(ResponseBody::Bytes(bytes)) => Ok(String::from_utf8(bytes).unwrap()),
+            _ => Err(IOError::new(IOErrorKind::InvalidData, "Unexpected body")),
+        }
+    }
+}
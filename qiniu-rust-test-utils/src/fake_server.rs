@@ -0,0 +1,846 @@
+use super::http_call_mock::fake_req_id;
+use qiniu_http::{HTTPCaller, Headers, Method, Request, Response, ResponseBuilder, Result, StatusCode};
+use qiniu_ng::{http::DomainsManagerBuilder, storage::uploader::UploadPolicy, utils::etag, ConfigBuilder, Credential};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, HashMap},
+    result::Result as StdResult,
+    sync::{
+        atomic::{AtomicUsize, Ordering::Relaxed},
+        Arc, RwLock,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use url::{form_urlencoded, Url};
+
+pub const FAKE_UP_HOST: &str = "up.fake.qiniu.test";
+pub const FAKE_UPLOAD_HOST: &str = "upload.fake.qiniu.test";
+pub const FAKE_IO_HOST: &str = "iovip.fake.qiniu.test";
+pub const FAKE_RS_HOST: &str = "rs.fake.qiniu.test";
+pub const FAKE_RSF_HOST: &str = "rsf.fake.qiniu.test";
+pub const FAKE_API_HOST: &str = "api.fake.qiniu.test";
+
+const LIST_LIMIT: usize = 1000;
+const MAX_PART_NUMBER: usize = 10000;
+
+type Outcome<T> = StdResult<T, (StatusCode, String)>;
+
+#[derive(Clone)]
+struct StoredObject {
+    data: Arc<Vec<u8>>,
+    hash: String,
+    mime_type: String,
+    put_time: u64,
+}
+
+impl StoredObject {
+    fn new(data: Vec<u8>, mime_type: impl Into<String>) -> Self {
+        Self {
+            hash: etag::from_bytes(&data),
+            data: Arc::new(data),
+            mime_type: mime_type.into(),
+            put_time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| (duration.as_nanos() / 100) as u64),
+        }
+    }
+
+    fn info(&self) -> Value {
+        json!({
+            "fsize": self.data.len(),
+            "hash": self.hash,
+            "mimeType": self.mime_type,
+            "putTime": self.put_time,
+            "type": 0,
+        })
+    }
+}
+
+#[derive(Default)]
+struct StoredBucket {
+    private: bool,
+    objects: BTreeMap<String, StoredObject>,
+}
+
+struct PendingUpload {
+    bucket: String,
+    key: Option<String>,
+    parts: HashMap<usize, (String, Vec<u8>)>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CompletedPart {
+    etag: String,
+    part_number: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CompletedParts {
+    parts: Vec<CompletedPart>,
+    fname: Option<String>,
+    mime_type: Option<String>,
+    #[serde(default)]
+    custom_vars: HashMap<String, String>,
+}
+
+struct FormField {
+    name: String,
+    file_name: Option<String>,
+    content_type: Option<String>,
+    data: Vec<u8>,
+}
+
+struct FakeQiniuServerInner {
+    credential: Credential,
+    buckets: RwLock<HashMap<String, StoredBucket>>,
+    uploads: RwLock<HashMap<String, PendingUpload>>,
+    upload_id_counter: AtomicUsize,
+}
+
+/// 进程内的七牛服务器模拟
+///
+/// 以 HTTP 请求处理函数的形式模拟 uc，up，rs 和 rsf 服务，所有对象均保存在内存中。
+/// 服务器会用 `Credential` 校验上传凭证和管理凭证，但忽略请求的主机名，因此任何区域的域名都会被路由到同一个模拟服务器
+#[derive(Clone)]
+pub struct FakeQiniuServer {
+    inner: Arc<FakeQiniuServerInner>,
+}
+
+impl FakeQiniuServer {
+    pub fn new(credential: Credential) -> Self {
+        Self {
+            inner: Arc::new(FakeQiniuServerInner {
+                credential,
+                buckets: Default::default(),
+                uploads: Default::default(),
+                upload_id_counter: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    pub fn credential(&self) -> &Credential {
+        &self.inner.credential
+    }
+
+    /// 创建使用该模拟服务器的客户端配置构建器
+    ///
+    /// 模拟服务器的域名无法被解析，因此将禁用域名预解析，同时禁用上传日志
+    pub fn config_builder(&self) -> ConfigBuilder {
+        ConfigBuilder::default()
+            .http_request_handler(self.clone())
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .upload_logger(None)
+    }
+
+    pub fn create_bucket(&self, bucket: impl Into<String>, private: bool) -> &Self {
+        self.inner
+            .buckets
+            .write()
+            .unwrap()
+            .entry(bucket.into())
+            .or_default()
+            .private = private;
+        self
+    }
+
+    pub fn put_object(
+        &self,
+        bucket: &str,
+        key: impl Into<String>,
+        data: impl Into<Vec<u8>>,
+        mime_type: impl Into<String>,
+    ) -> &Self {
+        self.inner
+            .buckets
+            .write()
+            .unwrap()
+            .get_mut(bucket)
+            .unwrap_or_else(|| panic!("Bucket {} is not created", bucket))
+            .objects
+            .insert(key.into(), StoredObject::new(data.into(), mime_type));
+        self
+    }
+
+    pub fn get_object(&self, bucket: &str, key: &str) -> Option<Vec<u8>> {
+        self.inner
+            .buckets
+            .read()
+            .unwrap()
+            .get(bucket)
+            .and_then(|bucket| bucket.objects.get(key))
+            .map(|object| object.data.as_ref().to_owned())
+    }
+
+    pub fn object_keys(&self, bucket: &str) -> Vec<String> {
+        self.inner
+            .buckets
+            .read()
+            .unwrap()
+            .get(bucket)
+            .map(|bucket| bucket.objects.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn handle(&self, request: &Request) -> Outcome<Response> {
+        let url = Url::parse(request.url()).map_err(|err| (400, err.to_string()))?;
+        let segments = url
+            .path()
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>();
+        let query = url.query_pairs().into_owned().collect::<HashMap<_, _>>();
+        match (request.method(), segments.as_slice()) {
+            (Method::GET, ["v3", "query"]) => self.query_region(&query),
+            (Method::GET, ["v2", "bucketInfo"]) => {
+                self.verify_management_request(request)?;
+                self.bucket_info(&query)
+            }
+            (Method::GET, ["v6", "domain", "list"]) => {
+                self.verify_management_request(request)?;
+                self.bucket_domains(&query)
+            }
+            (Method::POST, []) => self.form_upload(request),
+            (Method::POST, ["log", ..]) => Ok(json_response(200, &json!({}))),
+            (Method::POST, ["buckets", bucket, "objects", key, "uploads"]) => {
+                self.verify_upload_token(request, None)?;
+                self.init_parts(bucket, key)
+            }
+            (Method::PUT, ["buckets", _, "objects", _, "uploads", upload_id, part_number]) => {
+                self.verify_upload_token(request, None)?;
+                self.upload_part(request, upload_id, part_number)
+            }
+            (Method::POST, ["buckets", _, "objects", _, "uploads", upload_id]) => {
+                self.complete_parts(request, upload_id)
+            }
+            (Method::POST, ["batch"]) => {
+                self.verify_management_request(request)?;
+                self.batch(request)
+            }
+            (Method::GET, ["list"]) | (Method::POST, ["list"]) => {
+                self.verify_management_request(request)?;
+                self.list(&query)
+            }
+            (_, [operation, ..]) if ["stat", "delete", "copy", "move"].contains(operation) => {
+                self.verify_management_request(request)?;
+                let (status_code, data) = self.rs_operation(url.path());
+                Ok(match data {
+                    Some(data) => json_response(status_code, &data),
+                    None => empty_response(status_code),
+                })
+            }
+            _ => Err((
+                404,
+                format!("Unexpected request: {} {}", request.method(), request.url()),
+            )),
+        }
+    }
+
+    fn query_region(&self, query: &HashMap<String, String>) -> Outcome<Response> {
+        if query.get("ak").map(|ak| ak.as_str()) != Some(self.inner.credential.access_key()) {
+            return Err((401, "invalid access key".to_owned()));
+        }
+        self.ensure_bucket(query.get("bucket").map_or("", |bucket| bucket.as_str()))?;
+        Ok(json_response(
+            200,
+            &json!({
+                "hosts": [{
+                    "io": { "src": { "main": [FAKE_IO_HOST] } },
+                    "up": {
+                        "acc": { "main": [FAKE_UPLOAD_HOST] },
+                        "old_acc": { "main": [FAKE_UPLOAD_HOST] },
+                        "old_src": { "main": [FAKE_UP_HOST] },
+                        "src": { "main": [FAKE_UP_HOST] },
+                    },
+                    "rs": { "acc": { "main": [FAKE_RS_HOST] } },
+                    "rsf": { "acc": { "main": [FAKE_RSF_HOST] } },
+                    "api": { "acc": { "main": [FAKE_API_HOST] } },
+                }]
+            }),
+        ))
+    }
+
+    fn bucket_info(&self, query: &HashMap<String, String>) -> Outcome<Response> {
+        let bucket_name = query.get("bucket").map_or("", |bucket| bucket.as_str());
+        let buckets = self.inner.buckets.read().unwrap();
+        let bucket = buckets
+            .get(bucket_name)
+            .ok_or_else(|| (631, "no such bucket".to_owned()))?;
+        Ok(json_response(
+            200,
+            &json!({ "private": if bucket.private { 1 } else { 0 } }),
+        ))
+    }
+
+    fn bucket_domains(&self, query: &HashMap<String, String>) -> Outcome<Response> {
+        let bucket = query.get("tbl").map_or("", |bucket| bucket.as_str());
+        self.ensure_bucket(bucket)?;
+        Ok(json_response(200, &json!([format!("{}.{}", bucket, FAKE_IO_HOST)])))
+    }
+
+    fn form_upload(&self, request: &Request) -> Outcome<Response> {
+        let content_type = request
+            .headers()
+            .get(&"Content-Type".into())
+            .ok_or_else(|| (400, "missing Content-Type".to_owned()))?;
+        let fields = parse_multipart(content_type, &request.body().to_bytes())
+            .ok_or_else(|| (400, "invalid multipart form".to_owned()))?;
+        let field = |name: &str| fields.iter().find(|field| field.name == name);
+        let token = field("token")
+            .map(|field| String::from_utf8_lossy(&field.data).into_owned())
+            .ok_or_else(|| (401, "token not specified".to_owned()))?;
+        let policy = self.parse_upload_token(&token)?;
+        let file = field("file").ok_or_else(|| (400, "file is not specified".to_owned()))?;
+        if let Some(crc32) = field("crc32") {
+            let expected = String::from_utf8_lossy(&crc32.data);
+            if expected != crc::crc32::checksum_ieee(&file.data).to_string() {
+                return Err((406, "crc32 not match".to_owned()));
+            }
+        }
+        let key = field("key").map(|field| String::from_utf8_lossy(&field.data).into_owned());
+        let vars = fields
+            .iter()
+            .filter(|field| field.name.starts_with("x:"))
+            .map(|field| (field.name.to_owned(), String::from_utf8_lossy(&field.data).into_owned()))
+            .collect::<HashMap<_, _>>();
+        self.save_object(
+            &policy,
+            key,
+            file.data.to_owned(),
+            file.content_type.to_owned(),
+            file.file_name.to_owned(),
+            &vars,
+        )
+    }
+
+    fn init_parts(&self, bucket: &str, encoded_key: &str) -> Outcome<Response> {
+        self.ensure_bucket(bucket)?;
+        let key = decode_key(encoded_key)?;
+        let upload_id = format!("fake-upload-{}", self.inner.upload_id_counter.fetch_add(1, Relaxed));
+        self.inner.uploads.write().unwrap().insert(
+            upload_id.to_owned(),
+            PendingUpload {
+                bucket: bucket.to_owned(),
+                key,
+                parts: HashMap::new(),
+            },
+        );
+        let expire_at = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| {
+            (duration + Duration::from_secs(7 * 24 * 60 * 60)).as_secs()
+        });
+        Ok(json_response(
+            200,
+            &json!({ "uploadId": upload_id, "expireAt": expire_at }),
+        ))
+    }
+
+    fn upload_part(&self, request: &Request, upload_id: &str, part_number: &str) -> Outcome<Response> {
+        let part_number: usize = part_number
+            .parse()
+            .ok()
+            .filter(|part_number| (1..=MAX_PART_NUMBER).contains(part_number))
+            .ok_or_else(|| (400, "invalid part number".to_owned()))?;
+        let data = request.body().to_bytes().into_owned();
+        let md5 = format!("{:x}", md5::compute(&data));
+        if let Some(content_md5) = request.headers().get(&"Content-MD5".into()) {
+            if content_md5.as_ref() != md5 {
+                return Err((400, "Content-MD5 not match".to_owned()));
+            }
+        }
+        let etag = etag::from_bytes(&data);
+        self.inner
+            .uploads
+            .write()
+            .unwrap()
+            .get_mut(upload_id)
+            .ok_or_else(|| (612, "no such upload id".to_owned()))?
+            .parts
+            .insert(part_number, (etag.to_owned(), data));
+        Ok(json_response(200, &json!({ "etag": etag, "md5": md5 })))
+    }
+
+    fn complete_parts(&self, request: &Request, upload_id: &str) -> Outcome<Response> {
+        let policy = self.verify_upload_token(request, None)?;
+        let completed: CompletedParts = serde_json::from_slice(&request.body().to_bytes())
+            .map_err(|err| (400, format!("invalid JSON body: {}", err)))?;
+        let upload = self
+            .inner
+            .uploads
+            .write()
+            .unwrap()
+            .remove(upload_id)
+            .ok_or_else(|| (612, "no such upload id".to_owned()))?;
+        let mut data = Vec::new();
+        let mut last_part_number = 0;
+        for part in completed.parts.iter() {
+            if part.part_number <= last_part_number {
+                return Err((400, "parts must be sorted by part number".to_owned()));
+            }
+            last_part_number = part.part_number;
+            match upload.parts.get(&part.part_number) {
+                Some((etag, part_data)) if etag == &part.etag => data.extend_from_slice(part_data),
+                _ => return Err((400, format!("invalid part {}", part.part_number))),
+            }
+        }
+        if data.is_empty() {
+            return Err((400, "empty file".to_owned()));
+        }
+        if policy.bucket() != Some(upload.bucket.as_str()) {
+            return Err((403, "bucket doesn't match with scope".to_owned()));
+        }
+        self.save_object(
+            &policy,
+            upload.key,
+            data,
+            completed.mime_type,
+            completed.fname,
+            &completed.custom_vars,
+        )
+    }
+
+    fn save_object(
+        &self,
+        policy: &UploadPolicy,
+        key: Option<String>,
+        data: Vec<u8>,
+        mime_type: Option<String>,
+        file_name: Option<String>,
+        vars: &HashMap<String, String>,
+    ) -> Outcome<Response> {
+        let bucket_name = policy
+            .bucket()
+            .ok_or_else(|| (400, "scope is not specified".to_owned()))?;
+        let (min_size, max_size) = policy.file_size_limitation();
+        if min_size.map_or(false, |min_size| data.len() < min_size) {
+            return Err((403, "file is too small".to_owned()));
+        }
+        if max_size.map_or(false, |max_size| data.len() > max_size) {
+            return Err((413, "file is too large".to_owned()));
+        }
+        let object = StoredObject::new(
+            data,
+            mime_type
+                .filter(|mime_type| !mime_type.is_empty())
+                .unwrap_or_else(|| "application/octet-stream".to_owned()),
+        );
+        let key = match (key, policy.key()) {
+            (Some(key), Some(scope_key)) if policy.use_prefixal_object_key() && !key.starts_with(scope_key) => {
+                return Err((403, "key doesn't match with scope".to_owned()));
+            }
+            (Some(key), Some(scope_key)) if !policy.use_prefixal_object_key() && key != scope_key => {
+                return Err((403, "key doesn't match with scope".to_owned()));
+            }
+            (Some(key), _) => key,
+            (None, Some(scope_key)) if !policy.use_prefixal_object_key() => scope_key.to_owned(),
+            (None, _) => object.hash.to_owned(),
+        };
+
+        let mut buckets = self.inner.buckets.write().unwrap();
+        let bucket = buckets
+            .get_mut(bucket_name)
+            .ok_or_else(|| (631, "no such bucket".to_owned()))?;
+        if policy.is_insert_only() && bucket.objects.contains_key(&key) {
+            return Err((614, "file exists".to_owned()));
+        }
+        let body = match policy.return_body() {
+            Some(return_body) => {
+                let value = |name: &str| -> Value {
+                    match name {
+                        "bucket" => json!(bucket_name),
+                        "key" => json!(key),
+                        "etag" => json!(object.hash),
+                        "fname" => json!(file_name),
+                        "fsize" => json!(object.data.len()),
+                        "mimeType" => json!(object.mime_type),
+                        _ => vars.get(name).map_or(Value::Null, |value| json!(value)),
+                    }
+                };
+                replace_magic_variables(return_body, value)
+            }
+            None => json!({ "hash": object.hash, "key": key }).to_string(),
+        };
+        bucket.objects.insert(key, object);
+        Ok(raw_json_response(200, body))
+    }
+
+    fn rs_operation(&self, path: &str) -> (StatusCode, Option<Value>) {
+        match self.try_rs_operation(path) {
+            Ok(data) => (200, data),
+            Err((status_code, message)) => (status_code, Some(json!({ "error": message }))),
+        }
+    }
+
+    fn try_rs_operation(&self, path: &str) -> Outcome<Option<Value>> {
+        let segments = path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>();
+        let mut buckets = self.inner.buckets.write().unwrap();
+        match segments.as_slice() {
+            ["stat", entry] => {
+                let (bucket, key) = decode_entry(entry)?;
+                let object = get_object(&buckets, &bucket, &key)?;
+                Ok(Some(object.info()))
+            }
+            ["delete", entry] => {
+                let (bucket, key) = decode_entry(entry)?;
+                buckets
+                    .get_mut(&bucket)
+                    .ok_or_else(|| (631, "no such bucket".to_owned()))?
+                    .objects
+                    .remove(&key)
+                    .ok_or_else(|| (612, "no such file or directory".to_owned()))?;
+                Ok(None)
+            }
+            [operation, src, dest, rest @ ..] if *operation == "copy" || *operation == "move" => {
+                let force = match rest {
+                    [] => false,
+                    ["force", force] => *force == "true",
+                    _ => return Err((400, "invalid arguments".to_owned())),
+                };
+                let (src_bucket, src_key) = decode_entry(src)?;
+                let (dest_bucket, dest_key) = decode_entry(dest)?;
+                let object = get_object(&buckets, &src_bucket, &src_key)?.to_owned();
+                let dest_objects = &mut buckets
+                    .get_mut(&dest_bucket)
+                    .ok_or_else(|| (631, "no such bucket".to_owned()))?
+                    .objects;
+                if !force && dest_objects.contains_key(&dest_key) {
+                    return Err((614, "file exists".to_owned()));
+                }
+                dest_objects.insert(dest_key.to_owned(), object);
+                if *operation == "move" && (&src_bucket, &src_key) != (&dest_bucket, &dest_key) {
+                    if let Some(bucket) = buckets.get_mut(&src_bucket) {
+                        bucket.objects.remove(&src_key);
+                    }
+                }
+                Ok(None)
+            }
+            _ => Err((400, format!("unsupported operation: {}", path))),
+        }
+    }
+
+    fn batch(&self, request: &Request) -> Outcome<Response> {
+        let results = form_urlencoded::parse(&request.body().to_bytes())
+            .filter(|(name, _)| name == "op")
+            .map(|(_, op)| self.rs_operation(&op))
+            .collect::<Vec<_>>();
+        let status_code = if results.iter().all(|(status_code, _)| *status_code == 200) {
+            200
+        } else {
+            298
+        };
+        let results = results
+            .into_iter()
+            .map(|(code, data)| match data {
+                Some(data) => json!({ "code": code, "data": data }),
+                None => json!({ "code": code }),
+            })
+            .collect::<Vec<_>>();
+        Ok(json_response(status_code, &json!(results)))
+    }
+
+    fn list(&self, query: &HashMap<String, String>) -> Outcome<Response> {
+        let bucket_name = query.get("bucket").map_or("", |bucket| bucket.as_str());
+        let prefix = query.get("prefix").map_or("", |prefix| prefix.as_str());
+        let delimiter = query.get("delimiter").map_or("", |delimiter| delimiter.as_str());
+        let limit = query
+            .get("limit")
+            .and_then(|limit| limit.parse().ok())
+            .filter(|&limit| limit > 0 && limit <= LIST_LIMIT)
+            .unwrap_or(LIST_LIMIT);
+        let marker = match query.get("marker").filter(|marker| !marker.is_empty()) {
+            Some(marker) => Some(
+                base64::decode_config(marker, base64::URL_SAFE)
+                    .ok()
+                    .and_then(|marker| String::from_utf8(marker).ok())
+                    .ok_or_else(|| (400, "invalid marker".to_owned()))?,
+            ),
+            None => None,
+        };
+
+        let buckets = self.inner.buckets.read().unwrap();
+        let bucket = buckets
+            .get(bucket_name)
+            .ok_or_else(|| (631, "no such bucket".to_owned()))?;
+        let mut items = Vec::new();
+        let mut common_prefixes: Vec<String> = Vec::new();
+        let mut last_key: Option<&str> = None;
+        let mut has_more = false;
+        for (key, object) in bucket.objects.iter() {
+            if marker.as_ref().map_or(false, |marker| key <= marker) || !key.starts_with(prefix) {
+                continue;
+            }
+            let common_prefix = if delimiter.is_empty() {
+                None
+            } else {
+                key[prefix.len()..]
+                    .find(delimiter)
+                    .map(|index| key[..prefix.len() + index + delimiter.len()].to_owned())
+            };
+            match common_prefix {
+                Some(common_prefix) if common_prefixes.last() == Some(&common_prefix) => {}
+                common_prefix => {
+                    if items.len() + common_prefixes.len() >= limit {
+                        has_more = true;
+                        break;
+                    }
+                    match common_prefix {
+                        Some(common_prefix) => common_prefixes.push(common_prefix),
+                        None => {
+                            let mut item = object.info();
+                            item["key"] = json!(key);
+                            items.push(item);
+                        }
+                    }
+                }
+            }
+            last_key = Some(key);
+        }
+        let marker = match (has_more, last_key) {
+            (true, Some(last_key)) => base64::encode_config(last_key, base64::URL_SAFE),
+            _ => String::new(),
+        };
+        Ok(json_response(
+            200,
+            &json!({ "marker": marker, "items": items, "commonPrefixes": common_prefixes }),
+        ))
+    }
+
+    fn ensure_bucket(&self, bucket: &str) -> Outcome<()> {
+        if self.inner.buckets.read().unwrap().contains_key(bucket) {
+            Ok(())
+        } else {
+            Err((631, "no such bucket".to_owned()))
+        }
+    }
+
+    fn verify_upload_token(&self, request: &Request, token: Option<&str>) -> Outcome<UploadPolicy> {
+        let token = match token {
+            Some(token) => token.to_owned(),
+            None => request
+                .headers()
+                .get(&"Authorization".into())
+                .and_then(|authorization| authorization.strip_prefix("UpToken "))
+                .map(|token| token.to_owned())
+                .ok_or_else(|| (401, "token not specified".to_owned()))?,
+        };
+        self.parse_upload_token(&token)
+    }
+
+    fn parse_upload_token(&self, token: &str) -> Outcome<UploadPolicy> {
+        let bad_token = || (401, "bad token".to_owned());
+        let index = token.rfind(':').ok_or_else(bad_token)?;
+        let (signature, encoded_policy) = (&token[..index], &token[index + 1..]);
+        if self.inner.credential.sign(encoded_policy.as_bytes()) != signature {
+            return Err(bad_token());
+        }
+        let policy = base64::decode_config(encoded_policy, base64::URL_SAFE)
+            .ok()
+            .and_then(|policy| UploadPolicy::from_json(policy).ok())
+            .ok_or_else(bad_token)?;
+        if policy
+            .token_deadline()
+            .map_or(false, |deadline| deadline < SystemTime::now())
+        {
+            return Err((401, "token out of date".to_owned()));
+        }
+        Ok(policy)
+    }
+
+    fn verify_management_request(&self, request: &Request) -> Outcome<()> {
+        let authorization = request
+            .headers()
+            .get(&"Authorization".into())
+            .ok_or_else(|| (401, "bad token".to_owned()))?;
+        let is_valid = if let Some(token) = authorization.strip_prefix("Qiniu ") {
+            self.sign_request_v2(request)
+                .map_or(false, |signature| signature == token)
+        } else if authorization.starts_with("QBox ") {
+            self.inner.credential.is_valid_request(request)
+        } else {
+            false
+        };
+        if is_valid {
+            Ok(())
+        } else {
+            Err((401, "bad token".to_owned()))
+        }
+    }
+
+    fn sign_request_v2(&self, request: &Request) -> Option<String> {
+        let url = Url::parse(request.url()).ok()?;
+        let mut data_to_sign = Vec::with_capacity(1024);
+        data_to_sign.extend_from_slice(request.method().as_bytes());
+        data_to_sign.extend_from_slice(b" ");
+        data_to_sign.extend_from_slice(url.path().as_bytes());
+        if let Some(query) = url.query().filter(|query| !query.is_empty()) {
+            data_to_sign.extend_from_slice(b"?");
+            data_to_sign.extend_from_slice(query.as_bytes());
+        }
+        data_to_sign.extend_from_slice(b"\nHost: ");
+        data_to_sign.extend_from_slice(url.host_str()?.as_bytes());
+        if let Some(port) = url.port() {
+            data_to_sign.extend_from_slice(b":");
+            data_to_sign.extend_from_slice(port.to_string().as_bytes());
+        }
+        data_to_sign.extend_from_slice(b"\n");
+        let content_type = request.headers().get(&"Content-Type".into());
+        if let Some(content_type) = content_type {
+            data_to_sign.extend_from_slice(b"Content-Type: ");
+            data_to_sign.extend_from_slice(content_type.as_bytes());
+            data_to_sign.extend_from_slice(b"\n");
+        }
+        append_x_qiniu_headers(&mut data_to_sign, request.headers());
+        data_to_sign.extend_from_slice(b"\n");
+        if let Some(content_type) = content_type {
+            if !request.body().is_empty() && !content_type.eq_ignore_ascii_case("application/octet-stream") {
+                data_to_sign.extend_from_slice(&request.body().to_bytes());
+            }
+        }
+        Some(self.inner.credential.sign(&data_to_sign))
+    }
+}
+
+impl HTTPCaller for FakeQiniuServer {
+    fn call(&self, request: &Request) -> Result<Response> {
+        Ok(self
+            .handle(request)
+            .unwrap_or_else(|(status_code, message)| json_response(status_code, &json!({ "error": message }))))
+    }
+}
+
+fn append_x_qiniu_headers(data_to_sign: &mut Vec<u8>, headers: &Headers) {
+    let mut x_qiniu_headers = headers
+        .iter()
+        .filter(|(key, _)| key.len() > "X-Qiniu-".len() && key.starts_with("X-Qiniu-"))
+        .collect::<Vec<_>>();
+    x_qiniu_headers.sort_unstable();
+    for (header_key, header_value) in x_qiniu_headers {
+        data_to_sign.extend_from_slice(header_key.as_bytes());
+        data_to_sign.extend_from_slice(b": ");
+        data_to_sign.extend_from_slice(header_value.as_bytes());
+        data_to_sign.extend_from_slice(b"\n");
+    }
+}
+
+fn get_object<'b>(buckets: &'b HashMap<String, StoredBucket>, bucket: &str, key: &str) -> Outcome<&'b StoredObject> {
+    buckets
+        .get(bucket)
+        .ok_or_else(|| (631, "no such bucket".to_owned()))?
+        .objects
+        .get(key)
+        .ok_or_else(|| (612, "no such file or directory".to_owned()))
+}
+
+fn decode_base64_string(encoded: &str) -> Outcome<String> {
+    base64::decode_config(encoded, base64::URL_SAFE)
+        .ok()
+        .and_then(|decoded| String::from_utf8(decoded).ok())
+        .ok_or_else(|| (400, "invalid base64 string".to_owned()))
+}
+
+fn decode_key(encoded_key: &str) -> Outcome<Option<String>> {
+    if encoded_key == "~" {
+        Ok(None)
+    } else {
+        decode_base64_string(encoded_key).map(Some)
+    }
+}
+
+fn decode_entry(encoded_entry: &str) -> Outcome<(String, String)> {
+    let entry = decode_base64_string(encoded_entry)?;
+    let mut iter = entry.splitn(2, ':');
+    let bucket = iter.next().unwrap_or_default().to_owned();
+    let key = iter.next().unwrap_or_default().to_owned();
+    Ok((bucket, key))
+}
+
+fn replace_magic_variables(template: &str, value: impl Fn(&str) -> Value) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("$(") {
+        result.push_str(&rest[..start]);
+        match rest[start + 2..].find(')') {
+            Some(end) => {
+                result.push_str(&value(&rest[start + 2..start + 2 + end]).to_string());
+                rest = &rest[start + 2 + end + 1..];
+            }
+            None => {
+                rest = &rest[start..];
+                break;
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+fn parse_multipart(content_type: &str, body: &[u8]) -> Option<Vec<FormField>> {
+    let boundary = content_type
+        .split(';')
+        .map(|param| param.trim())
+        .find_map(|param| param.strip_prefix("boundary="))?
+        .trim_matches('"');
+    let delimiter = format!("--{}", boundary).into_bytes();
+    let mut fields = Vec::new();
+    let mut rest = &body[find(body, &delimiter)? + delimiter.len()..];
+    loop {
+        if rest.starts_with(b"--") {
+            return Some(fields);
+        }
+        rest = rest.strip_prefix(b"\r\n")?;
+        let end = find(rest, &delimiter)?;
+        let part = rest[..end].strip_suffix(b"\r\n")?;
+        let header_end = find(part, b"\r\n\r\n")?;
+        let mut field = FormField {
+            name: String::new(),
+            file_name: None,
+            content_type: None,
+            data: part[header_end + 4..].to_vec(),
+        };
+        for line in String::from_utf8_lossy(&part[..header_end]).split("\r\n") {
+            let mut iter = line.splitn(2, ':');
+            let (name, value) = (iter.next()?.trim(), iter.next()?.trim());
+            if name.eq_ignore_ascii_case("Content-Type") {
+                field.content_type = Some(value.to_owned());
+            } else if name.eq_ignore_ascii_case("Content-Disposition") {
+                for param in value.split(';').map(|param| param.trim()) {
+                    if let Some(name) = param.strip_prefix("name=") {
+                        field.name = name.trim_matches('"').to_owned();
+                    } else if let Some(file_name) = param.strip_prefix("filename=") {
+                        field.file_name = Some(file_name.trim_matches('"').to_owned());
+                    }
+                }
+            }
+        }
+        fields.push(field);
+        rest = &rest[end + delimiter.len()..];
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+fn raw_json_response(status_code: StatusCode, body: String) -> Response {
+    ResponseBuilder::default()
+        .status_code(status_code)
+        .header("Content-Type", "application/json")
+        .header("X-Reqid", fake_req_id())
+        .bytes_as_body(body)
+        .build()
+}
+
+fn json_response(status_code: StatusCode, body: &Value) -> Response {
+    raw_json_response(status_code, body.to_string())
+}
+
+fn empty_response(status_code: StatusCode) -> Response {
+    ResponseBuilder::default()
+        .status_code(status_code)
+        .header("X-Reqid", fake_req_id())
+        .build()
+}
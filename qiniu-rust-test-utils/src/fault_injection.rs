@@ -0,0 +1,286 @@
+use super::http_call_mock::fake_req_id;
+use qiniu_http::{
+    Error as HTTPError, ErrorKind as HTTPErrorKind, HTTPCaller, HTTPCallerErrorKind, Request, Response, ResponseBody,
+    ResponseBuilder, Result, StatusCode,
+};
+use regex::Regex;
+use serde_json::json;
+use std::{
+    io::{Error as IOError, ErrorKind as IOErrorKind, Read},
+    sync::atomic::{AtomicUsize, Ordering::Relaxed},
+    thread::sleep,
+    time::Duration,
+};
+use url::Url;
+
+/// 注入的故障
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fault {
+    /// 不注入故障，直接调用内部 HTTP 请求处理函数
+    Pass,
+    /// 请求超时，可以在当前主机上重试
+    Timeout,
+    /// DNS 解析失败，需要切换主机重试
+    ResolveError,
+    /// 连接失败，需要切换主机重试
+    ConnectionError,
+    /// 返回指定状态码的错误响应
+    Status(StatusCode),
+    /// 延迟指定时长后再调用内部 HTTP 请求处理函数
+    Latency(Duration),
+    /// 调用内部 HTTP 请求处理函数后，仅保留响应体的前若干字节
+    TruncatedBody(usize),
+}
+
+/// 故障注入规则
+///
+/// 规则按照主机名和 URL 路径的正则表达式匹配请求，被匹配的请求依次按照故障计划注入故障，计划执行完毕后不再注入故障
+pub struct FaultRule {
+    host_regexp: Option<Regex>,
+    path_regexp: Option<Regex>,
+    schedule: Vec<(Fault, usize)>,
+    called: AtomicUsize,
+}
+
+impl Default for FaultRule {
+    fn default() -> Self {
+        Self {
+            host_regexp: None,
+            path_regexp: None,
+            schedule: Vec::new(),
+            called: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl FaultRule {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn host(mut self, host_regexp: impl AsRef<str>) -> Self {
+        self.host_regexp = Some(Regex::new(host_regexp.as_ref()).unwrap());
+        self
+    }
+
+    pub fn path(mut self, path_regexp: impl AsRef<str>) -> Self {
+        self.path_regexp = Some(Regex::new(path_regexp.as_ref()).unwrap());
+        self
+    }
+
+    /// 对接下来匹配的 `times` 次请求注入指定故障
+    pub fn then(mut self, fault: Fault, times: usize) -> Self {
+        self.schedule.push((fault, times));
+        self
+    }
+
+    /// 对之后匹配的全部请求注入指定故障
+    pub fn always(self, fault: Fault) -> Self {
+        self.then(fault, usize::max_value())
+    }
+
+    fn is_match(&self, host: &str, path: &str) -> bool {
+        self.host_regexp.as_ref().map_or(true, |regexp| regexp.is_match(host))
+            && self.path_regexp.as_ref().map_or(true, |regexp| regexp.is_match(path))
+    }
+
+    fn next_fault(&self) -> Fault {
+        let mut called = self.called.fetch_add(1, Relaxed);
+        for (fault, times) in self.schedule.iter() {
+            if called < *times {
+                return fault.to_owned();
+            }
+            called -= times;
+        }
+        Fault::Pass
+    }
+}
+
+/// 按照故障注入规则为 HTTP 请求注入故障
+///
+/// 对于每个请求，仅第一条匹配的规则生效。与 `UploadingProgressErrorMock` 随机注入故障不同，
+/// 故障按照规则中的计划依次注入，因此可以确定地测试域名冻结和多区域重试等行为
+pub struct FaultInjectionCaller<T: HTTPCaller> {
+    caller: T,
+    rules: Vec<FaultRule>,
+}
+
+impl<T: HTTPCaller> FaultInjectionCaller<T> {
+    pub fn new(caller: T) -> Self {
+        Self {
+            caller,
+            rules: Vec::new(),
+        }
+    }
+
+    pub fn rule(mut self, rule: FaultRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    fn next_fault(&self, request: &Request) -> Fault {
+        let (host, path) = match Url::parse(request.url()) {
+            Ok(url) => (url.host_str().unwrap_or_default().to_owned(), url.path().to_owned()),
+            Err(_) => return Fault::Pass,
+        };
+        self.rules
+            .iter()
+            .find(|rule| rule.is_match(&host, &path))
+            .map_or(Fault::Pass, |rule| rule.next_fault())
+    }
+}
+
+impl<T: HTTPCaller> HTTPCaller for FaultInjectionCaller<T> {
+    fn call(&self, request: &Request) -> Result<Response> {
+        match self.next_fault(request) {
+            Fault::Pass => self.caller.call(request),
+            Fault::Timeout => Err(HTTPError::new_retryable_error_from_req_resp(
+                HTTPErrorKind::new_http_caller_error_kind(
+                    HTTPCallerErrorKind::TimeoutError,
+                    IOError::new(IOErrorKind::TimedOut, "Injected timeout"),
+                ),
+                true,
+                request,
+                None,
+            )),
+            Fault::ResolveError => Err(HTTPError::new_host_unretryable_error_from_req_resp(
+                HTTPErrorKind::new_http_caller_error_kind(
+                    HTTPCallerErrorKind::ResolveError,
+                    IOError::new(IOErrorKind::NotFound, "Injected DNS resolution failure"),
+                ),
+                true,
+                request,
+                None,
+            )),
+            Fault::ConnectionError => Err(HTTPError::new_host_unretryable_error_from_req_resp(
+                HTTPErrorKind::new_http_caller_error_kind(
+                    HTTPCallerErrorKind::ConnectionError,
+                    IOError::new(IOErrorKind::ConnectionRefused, "Injected connection failure"),
+                ),
+                true,
+                request,
+                None,
+            )),
+            Fault::Status(status_code) => Ok(ResponseBuilder::default()
+                .status_code(status_code)
+                .header("Content-Type", "application/json")
+                .header("X-Reqid", fake_req_id())
+                .bytes_as_body(json!({ "error": "Injected fault" }).to_string())
+                .build()),
+            Fault::Latency(latency) => {
+                sleep(latency);
+                self.caller.call(request)
+            }
+            Fault::TruncatedBody(size) => {
+                let mut response = self.caller.call(request)?;
+                let mut body = Vec::new();
+                let result = match response.take_body() {
+                    Some(ResponseBody::Bytes(bytes)) => {
+                        body = bytes;
+                        Ok(0)
+                    }
+                    Some(ResponseBody::File(mut file)) => file.read_to_end(&mut body),
+                    Some(ResponseBody::Reader(mut reader)) => reader.read_to_end(&mut body),
+                    None => Ok(0),
+                };
+                result.map_err(|err| {
+                    HTTPError::new_retryable_error_from_req_resp(
+                        HTTPErrorKind::new_http_caller_error_kind(HTTPCallerErrorKind::ResponseError, err),
+                        false,
+                        request,
+                        None,
+                    )
+                })?;
+                body.truncate(size);
+                *response.body_mut() = Some(ResponseBody::Bytes(body));
+                Ok(response)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::http_call_mock::CounterCallMock, *};
+    use qiniu_http::{Method, RequestBuilder, RetryKind};
+    use std::{error::Error, result::Result, time::Instant};
+
+    #[test]
+    fn test_fault_schedule() -> Result<(), Box<dyn Error>> {
+        let inner = CounterCallMock::new(OkCaller);
+        let caller = FaultInjectionCaller::new(inner.to_owned())
+            .rule(
+                FaultRule::new()
+                    .host(r"^up-z0\.")
+                    .path(r"^/buckets/")
+                    .then(Fault::Timeout, 3)
+                    .then(Fault::Status(503), 1),
+            )
+            .rule(FaultRule::new().host(r"^up-z1\.").always(Fault::ResolveError));
+
+        for _ in 0..3 {
+            let err = caller
+                .call(&request("http://up-z0.qiniup.com/buckets/test"))
+                .unwrap_err();
+            assert_eq!(err.retry_kind(), RetryKind::RetryableError);
+        }
+        assert_eq!(
+            caller
+                .call(&request("http://up-z0.qiniup.com/buckets/test"))?
+                .status_code(),
+            503
+        );
+        assert_eq!(inner.call_called(), 0);
+        assert_eq!(
+            caller
+                .call(&request("http://up-z0.qiniup.com/buckets/test"))?
+                .status_code(),
+            200
+        );
+        assert_eq!(caller.call(&request("http://up-z0.qiniup.com/"))?.status_code(), 200);
+        assert_eq!(inner.call_called(), 2);
+
+        for _ in 0..5 {
+            let err = caller.call(&request("http://up-z1.qiniup.com/")).unwrap_err();
+            assert_eq!(err.retry_kind(), RetryKind::HostUnretryableError);
+        }
+        assert_eq!(inner.call_called(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_latency_and_truncated_body() -> Result<(), Box<dyn Error>> {
+        let caller = FaultInjectionCaller::new(OkCaller).rule(
+            FaultRule::new()
+                .then(Fault::Latency(Duration::from_millis(100)), 1)
+                .then(Fault::TruncatedBody(4), 1),
+        );
+        let begin_at = Instant::now();
+        let mut response = caller.call(&request("http://rs.qbox.me/stat/test"))?;
+        assert!(begin_at.elapsed() >= Duration::from_millis(100));
+        assert_eq!(response.body_len()?, 11);
+
+        let mut response = caller.call(&request("http://rs.qbox.me/stat/test"))?;
+        match response.take_body() {
+            Some(ResponseBody::Bytes(bytes)) => assert_eq!(bytes, b"{\"ok".to_vec()),
+            _ => panic!("Unexpected body"),
+        }
+        Ok(())
+    }
+
+    struct OkCaller;
+
+    impl HTTPCaller for OkCaller {
+        fn call(&self, _request: &Request) -> qiniu_http::Result<Response> {
+            Ok(ResponseBuilder::default()
+                .status_code(200u16)
+                .header("Content-Type", "application/json")
+                .bytes_as_body("{\"ok\":true}")
+                .build())
+        }
+    }
+
+    fn request(url: &str) -> Request<'_> {
+        RequestBuilder::default().method(Method::GET).url(url).build()
+    }
+}
@@ -1,3 +1,7 @@
+pub mod cassette;
 pub mod env;
+#[cfg(feature = "fake-server")]
+pub mod fake_server;
+pub mod fault_injection;
 pub mod http_call_mock;
 pub mod temp_file;
@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod tests {
+    use qiniu_ng::{storage::uploader::UploadPolicyBuilder, utils::etag, Client, Config, Credential};
+    use qiniu_test_utils::{
+        fake_server::{FakeQiniuServer, FAKE_UPLOAD_HOST, FAKE_UP_HOST},
+        fault_injection::{Fault, FaultInjectionCaller, FaultRule},
+        http_call_mock::URLRecorderCallMock,
+        temp_file::create_temp_file,
+    };
+    use serde_json::json;
+    use std::{boxed::Box, convert::TryInto, error::Error, fs::read, result::Result};
+
+    const BUCKET_NAME: &str = "fake-bucket";
+
+    #[test]
+    fn test_fake_server_upload_file_and_manage_object() -> Result<(), Box<dyn Error>> {
+        let server = get_server();
+        let config = server.config_builder().build();
+        let temp_path = create_temp_file(1 << 19)?.into_temp_path();
+        let etag = etag::from_file(&temp_path)?;
+        let policy = UploadPolicyBuilder::new_policy_for_object(BUCKET_NAME, "dir/512k", &config)
+            .return_body("{\"hash\":$(etag),\"key\":$(key),\"fsize\":$(fsize),\"var_key1\":$(x:var_key1)}")
+            .build();
+        let client = get_client(&server, config);
+        let result = client
+            .upload()
+            .upload_for_upload_policy(policy, server.credential().to_owned())?
+            .key("dir/512k")
+            .var("var_key1", "var_value1")
+            .upload_file(&temp_path, "512k", Some(mime::IMAGE_PNG))?;
+        assert_eq!(result.key(), Some("dir/512k"));
+        assert_eq!(result.hash(), Some(etag.as_str()));
+        assert_eq!(result.get("fsize"), Some(&json!(1 << 19)));
+        assert_eq!(result.get("var_key1"), Some(&json!("var_value1")));
+        assert_eq!(server.get_object(BUCKET_NAME, "dir/512k"), Some(read(&temp_path)?));
+
+        let bucket = client.storage().bucket(BUCKET_NAME).build();
+        assert!(!bucket.is_private()?);
+        let object = bucket.object("dir/512k");
+        let object_info = object.get_info()?;
+        assert_eq!(object_info.mime_type(), mime::IMAGE_PNG);
+        assert_eq!(object_info.size(), 1 << 19);
+        assert_eq!(object_info.hash(), etag.as_str());
+
+        server.put_object(BUCKET_NAME, "dir/seeded", b"seeded".as_ref(), "text/plain");
+        server.put_object(BUCKET_NAME, "other", b"other".as_ref(), "text/plain");
+        let keys = bucket
+            .list_objects("dir/")
+            .map(|object| object.map(|object| object.key().to_owned()))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(keys, vec!["dir/512k".to_owned(), "dir/seeded".to_owned()]);
+
+        object.delete()?;
+        assert!(object.get_info().is_err());
+        assert_eq!(server.object_keys(BUCKET_NAME), vec!["dir/seeded", "other"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_server_upload_large_file() -> Result<(), Box<dyn Error>> {
+        const FILE_SIZE: u64 = (1 << 23) + (1 << 20);
+        let server = get_server();
+        let config = server.config_builder().build();
+        let temp_path = create_temp_file(FILE_SIZE.try_into().unwrap())?.into_temp_path();
+        let etag = etag::from_file(&temp_path)?;
+        let policy = UploadPolicyBuilder::new_policy_for_bucket(BUCKET_NAME, &config).build();
+        let result = get_client(&server, config)
+            .upload()
+            .upload_for_upload_policy(policy, server.credential().to_owned())?
+            .upload_file(&temp_path, "9m", None)?;
+        assert_eq!(result.key(), Some(etag.as_str()));
+        assert_eq!(result.hash(), Some(etag.as_str()));
+        assert_eq!(server.get_object(BUCKET_NAME, &etag), Some(read(&temp_path)?));
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_server_upload_large_file_with_faults() -> Result<(), Box<dyn Error>> {
+        const FILE_SIZE: u64 = (1 << 23) + (1 << 20);
+        let server = get_server();
+        let recorder = URLRecorderCallMock::new(
+            FaultInjectionCaller::new(server.to_owned())
+                .rule(FaultRule::new().host(FAKE_UPLOAD_HOST).always(Fault::ResolveError))
+                .rule(
+                    FaultRule::new()
+                        .host(FAKE_UP_HOST)
+                        .path("^/buckets/")
+                        .then(Fault::Timeout, 3),
+                ),
+        );
+        let config = server
+            .config_builder()
+            .http_request_handler(recorder.to_owned())
+            .build();
+        let temp_path = create_temp_file(FILE_SIZE.try_into().unwrap())?.into_temp_path();
+        let etag = etag::from_file(&temp_path)?;
+        let policy = UploadPolicyBuilder::new_policy_for_bucket(BUCKET_NAME, &config).build();
+        let result = get_client(&server, config)
+            .upload()
+            .upload_for_upload_policy(policy, server.credential().to_owned())?
+            .upload_file(&temp_path, "9m", None)?;
+        assert_eq!(result.hash(), Some(etag.as_str()));
+        assert_eq!(server.get_object(BUCKET_NAME, &etag), Some(read(&temp_path)?));
+        let urls_called = recorder.urls_called();
+        assert!(urls_called.iter().any(|url| url.contains(FAKE_UPLOAD_HOST)));
+        assert!(urls_called.iter().any(|url| url.contains(FAKE_UP_HOST)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_fake_server_reject_invalid_upload_token() -> Result<(), Box<dyn Error>> {
+        let server = get_server();
+        let config = server.config_builder().build();
+        let policy = UploadPolicyBuilder::new_policy_for_object(BUCKET_NAME, "forged", &config).build();
+        let result = get_client(&server, config)
+            .upload()
+            .upload_for_upload_policy(policy, Credential::new("ak", "forged-secret-key"))?
+            .key("forged")
+            .upload_stream(b"forged".as_ref(), 6, "forged", None);
+        assert!(result.is_err());
+        assert!(server.object_keys(BUCKET_NAME).is_empty());
+        Ok(())
+    }
+
+    fn get_server() -> FakeQiniuServer {
+        let server = FakeQiniuServer::new(Credential::new("fake-access-key", "fake-secret-key"));
+        server.create_bucket(BUCKET_NAME, false);
+        server
+    }
+
+    fn get_client(server: &FakeQiniuServer, config: Config) -> Client {
+        let credential = server.credential();
+        Client::new(
+            credential.access_key().to_owned(),
+            credential.secret_key().to_owned(),
+            config,
+        )
+    }
+}
@@ -1,3 +1,4 @@
+mod fake_server;
 mod handler;
 mod storage;
 mod uploader;
@@ -1,20 +1,20 @@
 use curl::{
-    easy::{Easy2, Handler, List, ReadError, SeekResult, WriteError},
+    easy::{Easy2, Handler, List, ReadError, SeekResult, SslVersion, WriteError},
     Version,
 };
 use derive_builder::Builder;
 use lazy_static::lazy_static;
 use object_pool::Pool;
 use qiniu_http::{
-    Error, ErrorKind, HTTPCaller, HTTPCallerErrorKind, HeadersOwned, Method, ProgressCallback, Request, Response,
-    ResponseBuilder, Result, StatusCode,
+    Error, ErrorKind, HTTPCaller, HTTPCallerErrorKind, HeadersOwned, Method, PEMSource, ProgressCallback, ProxyType,
+    Request, RequestBodyReader, Response, ResponseBuilder, Result, StatusCode, TLSOptions, TLSVersion,
 };
 use std::{
     convert::TryInto,
     default::Default,
     env,
     fs::File,
-    io::{Cursor, Read, Seek, SeekFrom, Write},
+    io::{Error as IOError, ErrorKind as IOErrorKind, Read, Seek, SeekFrom, Write},
     mem::{size_of, transmute, transmute_copy},
     net::IpAddr,
     path::{Path, PathBuf},
@@ -23,10 +23,15 @@ use std::{
 };
 use url::Url;
 
+mod pool;
+use pool::ConnectionPool;
+pub use pool::ConnectionPoolOptions;
+
 static INITIALIZER: Once = Once::new();
 lazy_static! {
     static ref IPV6_SUPPORT: bool = Version::get().feature_ipv6();
     static ref MULTI_IP_ADDRS_SUPPORT: bool = Version::get().version_num() >= 0x07_3b_00;
+    static ref HTTPS_PROXY_SUPPORT: bool = Version::get().feature_https_proxy();
     static ref FULL_USER_AGENT: Box<str> = format!(
         "QiniuRust/qiniu-http-{}/rust-{}/libcurl-{}",
         env!("CARGO_PKG_VERSION"),
@@ -54,6 +59,15 @@ pub struct CurlClient {
 
     #[builder(default, setter(skip))]
     user_agent: Option<String>,
+
+    /// 连接池选项
+    ///
+    /// 设置后，所有线程发送的请求将共享连接缓存，DNS 缓存和 TLS 会话，否则每个 Easy 句柄独立缓存
+    #[builder(default)]
+    connection_pool_options: Option<ConnectionPoolOptions>,
+
+    #[builder(default, setter(skip))]
+    connection_pool: Option<ConnectionPool>,
 }
 
 impl HTTPCaller for CurlClient {
@@ -62,7 +76,19 @@ impl HTTPCaller for CurlClient {
         let mut easy: Box<Easy2<Context>> = r.into();
         self.reset_context(&mut easy);
         self.set_context(easy.get_mut(), request);
-        let result = self.perform(&mut easy, request);
+        let result = if let Some(connection_pool) = &self.connection_pool {
+            // 请求超时时长已被操作截止时间限制，因此等待请求许可的时长同样不会超过截止时间
+            if let Some(_permit) = connection_pool.acquire(Self::host_key(request), request.request_timeout()) {
+                let result = Self::handle_if_err(connection_pool.attach(&mut easy), request)
+                    .and_then(|_| self.perform(&mut easy, request));
+                connection_pool.detach(&mut easy);
+                result
+            } else {
+                Self::handle_if_err(Err(curl::Error::new(curl_sys::CURLE_OPERATION_TIMEDOUT)), request)
+            }
+        } else {
+            self.perform(&mut easy, request)
+        };
         let _: Easy2ContextRef = easy.into();
         result
     }
@@ -83,6 +109,16 @@ impl CurlClient {
         self.build_response(easy.get_mut(), request, status_code, server_ip, server_port)
     }
 
+    fn host_key(request: &Request) -> String {
+        Url::parse(request.url())
+            .ok()
+            .and_then(|url| {
+                url.host_str()
+                    .map(|host| host.to_owned() + ":" + &url.port_or_known_default().unwrap_or(0).to_string())
+            })
+            .unwrap_or_else(|| request.url().to_owned())
+    }
+
     fn build_response(
         &self,
         context: &mut Context,
@@ -131,7 +167,7 @@ impl CurlClient {
         context.download_progress = request.on_downloading_progress();
 
         if !request.body().is_empty() {
-            context.request_body = Some(Cursor::new(request.body()));
+            context.request_body = Some(request.body().reader());
         }
 
         match request.method() {
@@ -196,6 +232,32 @@ impl CurlClient {
                 Self::handle_if_err(easy.resolve(list), request)?;
             }
         }
+        if let Some(proxy) = request.proxy() {
+            if proxy.proxy_type() == ProxyType::HTTPS && !*HTTPS_PROXY_SUPPORT {
+                // 当前 libcurl 不支持 HTTPS 代理，切换主机重试也无法成功，因此直接返回错误
+                return Err(Error::new_unretryable_error_from_req_resp(
+                    ErrorKind::new_http_caller_error_kind(
+                        HTTPCallerErrorKind::ProxyError,
+                        IOError::new(IOErrorKind::Other, "HTTPS proxy is not supported by libcurl"),
+                    ),
+                    request,
+                    None,
+                ));
+            }
+            Self::handle_if_err(easy.proxy(&proxy.url()), request)?;
+            if let Some(username) = proxy.username() {
+                Self::handle_if_err(easy.proxy_username(username), request)?;
+            }
+            if let Some(password) = proxy.password() {
+                Self::handle_if_err(easy.proxy_password(password), request)?;
+            }
+            if !proxy.no_proxy_hosts().is_empty() {
+                Self::handle_if_err(easy.noproxy(&proxy.no_proxy_hosts().join(",")), request)?;
+            }
+        }
+        if let Some(tls_options) = request.tls_options() {
+            self.set_tls_options(easy, tls_options, request)?;
+        }
         Self::handle_if_err(easy.accept_encoding(""), request)?;
         Self::handle_if_err(easy.transfer_encoding(true), request)?;
         Self::handle_if_err(easy.follow_location(request.follow_redirection()), request)?;
@@ -225,6 +287,49 @@ impl CurlClient {
         Ok(())
     }
 
+    fn set_tls_options<T>(&self, easy: &mut Easy2<T>, tls_options: &TLSOptions, request: &Request) -> Result<()> {
+        match tls_options.ca_bundle() {
+            Some(PEMSource::File(path)) => Self::handle_if_err(easy.cainfo(path), request)?,
+            Some(PEMSource::Content(content)) => Self::handle_if_err(easy.ssl_cainfo_blob(content), request)?,
+            None => {}
+        }
+        match tls_options.client_certificate() {
+            Some(PEMSource::File(path)) => Self::handle_if_err(easy.ssl_cert(path), request)?,
+            Some(PEMSource::Content(content)) => Self::handle_if_err(easy.ssl_cert_blob(content), request)?,
+            None => {}
+        }
+        if tls_options.client_certificate().is_some() {
+            Self::handle_if_err(easy.ssl_cert_type("PEM"), request)?;
+        }
+        match tls_options.client_private_key() {
+            Some(PEMSource::File(path)) => Self::handle_if_err(easy.ssl_key(path), request)?,
+            Some(PEMSource::Content(content)) => Self::handle_if_err(easy.ssl_key_blob(content), request)?,
+            None => {}
+        }
+        if tls_options.client_private_key().is_some() {
+            Self::handle_if_err(easy.ssl_key_type("PEM"), request)?;
+        }
+        if let Some(min_version) = tls_options.min_version() {
+            let min_version = match min_version {
+                TLSVersion::TLSv1_0 => SslVersion::Tlsv10,
+                TLSVersion::TLSv1_1 => SslVersion::Tlsv11,
+                TLSVersion::TLSv1_2 => SslVersion::Tlsv12,
+                TLSVersion::TLSv1_3 => SslVersion::Tlsv13,
+            };
+            Self::handle_if_err(easy.ssl_min_max_version(min_version, SslVersion::Default), request)?;
+        }
+        if !tls_options.pinned_public_keys().is_empty() {
+            let pinned_public_keys = tls_options
+                .pinned_public_keys()
+                .iter()
+                .map(|sha256_digest| "sha256//".to_owned() + sha256_digest)
+                .collect::<Vec<_>>()
+                .join(";");
+            Self::handle_if_err(easy.pinned_public_key(&pinned_public_keys), request)?;
+        }
+        Ok(())
+    }
+
     fn handle_if_err<T>(result: result::Result<T, curl::Error>, request: &Request) -> Result<T> {
         match result {
             Ok(result) => Ok(result),
@@ -271,9 +376,10 @@ impl CurlClient {
                         request,
                         None,
                     ))
-                } else if err.is_couldnt_resolve_proxy() {
+                } else if err.is_couldnt_resolve_proxy() || err.code() == 97 {
+                    // 97 即 `CURLE_PROXY`，libcurl 7.73.0 起用于表示代理握手错误，`curl` 库尚未提供对应的判断方法
                     Err(Error::new_host_unretryable_error_from_req_resp(
-                        ErrorKind::new_http_caller_error_kind(HTTPCallerErrorKind::ResolveError, err),
+                        ErrorKind::new_http_caller_error_kind(HTTPCallerErrorKind::ProxyError, err),
                         true,
                         request,
                         None,
@@ -287,16 +393,25 @@ impl CurlClient {
                     ))
                 } else if err.is_ssl_connect_error()
                     || err.is_peer_failed_verification()
-                    || err.is_ssl_engine_notfound()
-                    || err.is_ssl_certproblem()
                     || err.is_ssl_cipher()
                     || err.is_ssl_cacert()
+                    || err.is_ssl_issuer_error()
+                    || err.code() == 90
+                {
+                    // 90 即 `CURLE_SSL_PINNEDPUBKEYNOTMATCH`，`curl` 库尚未提供对应的判断方法
+                    Err(Error::new_host_unretryable_error_from_req_resp(
+                        ErrorKind::new_http_caller_error_kind(HTTPCallerErrorKind::SSLHandshakeError, err),
+                        true,
+                        request,
+                        None,
+                    ))
+                } else if err.is_ssl_engine_notfound()
+                    || err.is_ssl_certproblem()
                     || err.is_use_ssl_failed()
                     || err.is_ssl_engine_initfailed()
                     || err.is_ssl_cacert_badfile()
                     || err.is_ssl_crl_badfile()
                     || err.is_ssl_shutdown_failed()
-                    || err.is_ssl_issuer_error()
                 {
                     Err(Error::new_host_unretryable_error_from_req_resp(
                         ErrorKind::new_http_caller_error_kind(HTTPCallerErrorKind::SSLError, err),
@@ -324,7 +439,7 @@ enum ProgressStatus {
 }
 
 struct Context<'r> {
-    request_body: Option<Cursor<&'r [u8]>>,
+    request_body: Option<RequestBodyReader<'r>>,
     response_body: Option<ResponseBody>,
     response_headers: Option<HeadersOwned>,
     buffer_size: usize,
@@ -467,8 +582,14 @@ impl Default for CurlClient {
 }
 
 impl CurlClientBuilder {
-    fn build(self) -> CurlClient {
-        self.inner_build().unwrap()
+    /// 构建 libcurl 客户端
+    pub fn build(self) -> CurlClient {
+        let mut client = self.inner_build().unwrap();
+        if let Some(options) = &client.connection_pool_options {
+            INITIALIZER.call_once(curl::init);
+            client.connection_pool = Some(ConnectionPool::new(options.to_owned()).unwrap());
+        }
+        client
     }
 }
 
@@ -0,0 +1,416 @@
+//! libcurl 连接池模块
+//!
+//! 通过 libcurl Share 接口，在多个线程的 Easy 句柄之间共享连接缓存，DNS 缓存和 TLS 会话
+
+use curl::{
+    easy::{Easy2, HttpVersion},
+    Error as CurlError, ShareError, Version,
+};
+use curl_sys::{
+    curl_easy_setopt, curl_lock_access, curl_lock_data, curl_share_cleanup, curl_share_init, curl_share_setopt, CURL,
+    CURLE_OK, CURLOPT_SHARE, CURLSH, CURLSHE_OK, CURLSHOPT_LOCKFUNC, CURLSHOPT_SHARE, CURLSHOPT_UNLOCKFUNC,
+    CURLSHOPT_USERDATA, CURL_LOCK_DATA_CONNECT, CURL_LOCK_DATA_DNS, CURL_LOCK_DATA_SSL_SESSION,
+};
+use lazy_static::lazy_static;
+use std::{
+    collections::HashMap,
+    fmt,
+    os::raw::c_void,
+    ptr::null_mut,
+    result,
+    sync::{Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+lazy_static! {
+    static ref HTTP2_SUPPORT: bool = Version::get().feature_http2();
+    static ref CONNECT_SHARE_SUPPORT: bool = Version::get().version_num() >= 0x07_39_00;
+    static ref MAXAGE_CONN_SUPPORT: bool = Version::get().version_num() >= 0x07_41_00;
+}
+
+/// 连接池选项
+#[derive(Debug, Clone)]
+pub struct ConnectionPoolOptions {
+    max_requests_per_host: usize,
+    idle_timeout: Duration,
+    http2: bool,
+}
+
+impl Default for ConnectionPoolOptions {
+    fn default() -> Self {
+        Self {
+            max_requests_per_host: 0,
+            idle_timeout: Duration::from_secs(118),
+            http2: true,
+        }
+    }
+}
+
+impl ConnectionPoolOptions {
+    /// 设置每个主机的最大并发请求数
+    ///
+    /// 超过该数量的请求将阻塞等待，直到同一主机上的其他请求完成，等待时长超过请求超时时长则返回超时错误。默认为 0，表示不限制。
+    /// 注意限制的是请求数而非连接数，由于连接缓存被共享，并发请求可能复用已有的连接，启用 HTTP/2 时还可能在同一连接上多路复用
+    pub fn set_max_requests_per_host(mut self, max_requests_per_host: usize) -> Self {
+        self.max_requests_per_host = max_requests_per_host;
+        self
+    }
+
+    /// 设置空闲连接的超时时长
+    ///
+    /// 空闲时长超过该值的连接将不再被复用。默认为 118 秒，与 libcurl 默认值一致。需要 libcurl 7.65.0 及以上版本
+    pub fn set_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// 设置是否启用 HTTP/2
+    ///
+    /// 启用后，如果 libcurl 支持 HTTP/2，则对 HTTPS 请求协商使用 HTTP/2，并发请求将尽可能在同一连接上多路复用。默认启用
+    pub fn set_http2(mut self, http2: bool) -> Self {
+        self.http2 = http2;
+        self
+    }
+}
+
+/// 连接池
+///
+/// 持有 libcurl Share 句柄，所有使用该连接池的 Easy 句柄共享连接缓存，DNS 缓存和 TLS 会话
+pub(crate) struct ConnectionPool {
+    share: *mut CURLSH,
+    locks: Box<ShareLocks>,
+    request_limiter: HostRequestLimiter,
+    options: ConnectionPoolOptions,
+}
+
+// libcurl Share 句柄的所有访问均由加锁回调函数保护，因此可以在线程间共享
+unsafe impl Send for ConnectionPool {}
+unsafe impl Sync for ConnectionPool {}
+
+impl ConnectionPool {
+    pub(crate) fn new(options: ConnectionPoolOptions) -> result::Result<Self, ShareError> {
+        let share = unsafe { curl_share_init() };
+        if share.is_null() {
+            return Err(ShareError::new(curl_sys::CURLSHE_NOMEM));
+        }
+        let pool = Self {
+            share,
+            locks: Box::new(ShareLocks::default()),
+            request_limiter: HostRequestLimiter::new(options.max_requests_per_host),
+            options,
+        };
+        unsafe {
+            let userdata: *const ShareLocks = pool.locks.as_ref();
+            Self::handle_share_code(curl_share_setopt(share, CURLSHOPT_USERDATA, userdata as *mut c_void))?;
+            Self::handle_share_code(curl_share_setopt(
+                share,
+                CURLSHOPT_LOCKFUNC,
+                lock_callback as extern "C" fn(*mut CURL, curl_lock_data, curl_lock_access, *mut c_void),
+            ))?;
+            Self::handle_share_code(curl_share_setopt(
+                share,
+                CURLSHOPT_UNLOCKFUNC,
+                unlock_callback as extern "C" fn(*mut CURL, curl_lock_data, *mut c_void),
+            ))?;
+            Self::handle_share_code(curl_share_setopt(share, CURLSHOPT_SHARE, CURL_LOCK_DATA_DNS))?;
+            Self::handle_share_code(curl_share_setopt(share, CURLSHOPT_SHARE, CURL_LOCK_DATA_SSL_SESSION))?;
+            // 共享连接缓存需要 libcurl 7.57.0 及以上版本，更低版本将仅共享 DNS 缓存和 TLS 会话
+            if *CONNECT_SHARE_SUPPORT {
+                Self::handle_share_code(curl_share_setopt(share, CURLSHOPT_SHARE, CURL_LOCK_DATA_CONNECT))?;
+            }
+        }
+        Ok(pool)
+    }
+
+    /// 为 Easy 句柄设置连接池，必须在发送请求后调用 `detach` 解除
+    pub(crate) fn attach<T>(&self, easy: &mut Easy2<T>) -> result::Result<(), CurlError> {
+        Self::handle_easy_code(unsafe { curl_easy_setopt(easy.raw(), CURLOPT_SHARE, self.share) })?;
+        if *MAXAGE_CONN_SUPPORT {
+            easy.maxage_conn(self.options.idle_timeout)?;
+        }
+        if self.options.http2 && *HTTP2_SUPPORT {
+            easy.http_version(HttpVersion::V2TLS)?;
+            easy.pipewait(true)?;
+        }
+        Ok(())
+    }
+
+    /// 为 Easy 句柄解除连接池，确保连接池被释放时不再有 Easy 句柄引用它
+    pub(crate) fn detach<T>(&self, easy: &mut Easy2<T>) {
+        unsafe {
+            curl_easy_setopt(easy.raw(), CURLOPT_SHARE, null_mut::<CURLSH>());
+        }
+    }
+
+    /// 获取指定主机的请求许可，在许可被释放前，同一主机上的并发请求数不会超过限制
+    ///
+    /// 最多等待 `timeout` 时长，超时则返回 `None`
+    pub(crate) fn acquire(&self, host: String, timeout: Duration) -> Option<HostRequestPermit<'_>> {
+        self.request_limiter.acquire(host, timeout)
+    }
+
+    fn handle_share_code(code: curl_sys::CURLSHcode) -> result::Result<(), ShareError> {
+        if code == CURLSHE_OK {
+            Ok(())
+        } else {
+            Err(ShareError::new(code))
+        }
+    }
+
+    fn handle_easy_code(code: curl_sys::CURLcode) -> result::Result<(), CurlError> {
+        if code == CURLE_OK {
+            Ok(())
+        } else {
+            Err(CurlError::new(code))
+        }
+    }
+}
+
+impl Drop for ConnectionPool {
+    fn drop(&mut self) {
+        unsafe {
+            curl_share_cleanup(self.share);
+        }
+    }
+}
+
+impl fmt::Debug for ConnectionPool {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ConnectionPool")
+            .field("options", &self.options)
+            .finish()
+    }
+}
+
+/// Share 句柄所需的锁，每种共享数据各自使用一把锁
+#[derive(Default)]
+struct ShareLocks([ShareLock; 8]);
+
+impl ShareLocks {
+    fn get(&self, data: curl_lock_data) -> &ShareLock {
+        self.0.get(data as usize).unwrap_or(&self.0[0])
+    }
+}
+
+/// libcurl 的加锁和解锁由两个独立的回调函数完成，因此无法直接持有 `MutexGuard`，这里用条件变量实现
+#[derive(Default)]
+struct ShareLock {
+    locked: Mutex<bool>,
+    condvar: Condvar,
+}
+
+impl ShareLock {
+    fn lock(&self) {
+        let mut locked = lock_ignore_poison(&self.locked);
+        while *locked {
+            locked = self.condvar.wait(locked).unwrap_or_else(|err| err.into_inner());
+        }
+        *locked = true;
+    }
+
+    fn unlock(&self) {
+        *lock_ignore_poison(&self.locked) = false;
+        self.condvar.notify_one();
+    }
+}
+
+extern "C" fn lock_callback(_: *mut CURL, data: curl_lock_data, _: curl_lock_access, userptr: *mut c_void) {
+    let locks = unsafe { &*(userptr as *const ShareLocks) };
+    locks.get(data).lock();
+}
+
+extern "C" fn unlock_callback(_: *mut CURL, data: curl_lock_data, userptr: *mut c_void) {
+    let locks = unsafe { &*(userptr as *const ShareLocks) };
+    locks.get(data).unlock();
+}
+
+/// 主机并发请求数限制器
+struct HostRequestLimiter {
+    max_requests_per_host: usize,
+    requests: Mutex<HashMap<String, usize>>,
+    condvar: Condvar,
+}
+
+impl HostRequestLimiter {
+    fn new(max_requests_per_host: usize) -> Self {
+        Self {
+            max_requests_per_host,
+            requests: Default::default(),
+            condvar: Condvar::new(),
+        }
+    }
+
+    fn acquire(&self, host: String, timeout: Duration) -> Option<HostRequestPermit<'_>> {
+        if self.max_requests_per_host == 0 {
+            return Some(HostRequestPermit {
+                limiter: self,
+                host: None,
+            });
+        }
+        let deadline = Instant::now() + timeout;
+        let mut requests = lock_ignore_poison(&self.requests);
+        while requests.get(&host).copied().unwrap_or(0) >= self.max_requests_per_host {
+            let now = Instant::now();
+            if now >= deadline {
+                return None;
+            }
+            requests = self
+                .condvar
+                .wait_timeout(requests, deadline - now)
+                .unwrap_or_else(|err| err.into_inner())
+                .0;
+        }
+        *requests.entry(host.clone()).or_insert(0) += 1;
+        Some(HostRequestPermit {
+            limiter: self,
+            host: Some(host),
+        })
+    }
+
+    fn release(&self, host: &str) {
+        let mut requests = lock_ignore_poison(&self.requests);
+        if let Some(count) = requests.get_mut(host) {
+            *count -= 1;
+            if *count == 0 {
+                requests.remove(host);
+            }
+        }
+        self.condvar.notify_all();
+    }
+}
+
+/// 主机请求许可，被释放时归还许可
+pub(crate) struct HostRequestPermit<'p> {
+    limiter: &'p HostRequestLimiter,
+    host: Option<String>,
+}
+
+impl Drop for HostRequestPermit<'_> {
+    fn drop(&mut self) {
+        if let Some(host) = &self.host {
+            self.limiter.release(host);
+        }
+    }
+}
+
+fn lock_ignore_poison<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        error::Error,
+        result::Result,
+        sync::{
+            atomic::{AtomicUsize, Ordering::SeqCst},
+            Arc,
+        },
+        thread,
+    };
+
+    struct NoopHandler;
+
+    impl curl::easy::Handler for NoopHandler {}
+
+    #[test]
+    fn test_host_request_limiter() -> Result<(), Box<dyn Error>> {
+        let limiter = Arc::new(HostRequestLimiter::new(2));
+        let current = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let threads = (0..8)
+            .map(|_| {
+                let limiter = limiter.to_owned();
+                let current = current.to_owned();
+                let peak = peak.to_owned();
+                thread::spawn(move || {
+                    let _permit = limiter.acquire("upload.qiniup.com:443".to_owned(), Duration::from_secs(60));
+                    let _other_permit = limiter.acquire("up.qiniup.com:443".to_owned(), Duration::from_secs(60));
+                    peak.fetch_max(current.fetch_add(1, SeqCst) + 1, SeqCst);
+                    thread::sleep(Duration::from_millis(50));
+                    current.fetch_sub(1, SeqCst);
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(peak.load(SeqCst), 2);
+        assert!(lock_ignore_poison(&limiter.requests).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_host_request_limiter_timeout() -> Result<(), Box<dyn Error>> {
+        let limiter = HostRequestLimiter::new(1);
+        let permit = limiter.acquire("upload.qiniup.com:443".to_owned(), Duration::from_secs(1));
+        assert!(permit.is_some());
+
+        let begin_at = Instant::now();
+        assert!(limiter
+            .acquire("upload.qiniup.com:443".to_owned(), Duration::from_millis(100))
+            .is_none());
+        assert!(begin_at.elapsed() >= Duration::from_millis(100));
+        assert!(limiter
+            .acquire("upload.qiniup.com:443".to_owned(), Duration::from_millis(0))
+            .is_none());
+
+        let other_permit = limiter.acquire("up.qiniup.com:443".to_owned(), Duration::from_millis(0));
+        assert!(other_permit.is_some());
+
+        drop(permit);
+        assert!(limiter
+            .acquire("upload.qiniup.com:443".to_owned(), Duration::from_millis(0))
+            .is_some());
+        drop(other_permit);
+        assert!(lock_ignore_poison(&limiter.requests).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_host_request_limiter_wakes_waiter() -> Result<(), Box<dyn Error>> {
+        let limiter = Arc::new(HostRequestLimiter::new(1));
+        let permit = limiter.acquire("upload.qiniup.com:443".to_owned(), Duration::from_secs(1));
+        assert!(permit.is_some());
+        let waiter = {
+            let limiter = limiter.to_owned();
+            thread::spawn(move || {
+                let begin_at = Instant::now();
+                let acquired = limiter
+                    .acquire("upload.qiniup.com:443".to_owned(), Duration::from_secs(60))
+                    .is_some();
+                (acquired, begin_at.elapsed())
+            })
+        };
+        thread::sleep(Duration::from_millis(100));
+        drop(permit);
+        let (acquired, elapsed) = waiter.join().unwrap();
+        assert!(acquired);
+        assert!(elapsed < Duration::from_secs(30));
+        assert!(lock_ignore_poison(&limiter.requests).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_host_request_limiter_unlimited() -> Result<(), Box<dyn Error>> {
+        let limiter = HostRequestLimiter::new(0);
+        let permits = (0..16)
+            .map(|_| limiter.acquire("upload.qiniup.com:443".to_owned(), Duration::from_millis(0)))
+            .collect::<Vec<_>>();
+        assert!(permits.iter().all(|permit| permit.is_some()));
+        assert!(lock_ignore_poison(&limiter.requests).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_connection_pool() -> Result<(), Box<dyn Error>> {
+        curl::init();
+        let pool = ConnectionPool::new(ConnectionPoolOptions::default().set_max_requests_per_host(4))?;
+        let mut easy = Easy2::new(NoopHandler);
+        pool.attach(&mut easy)?;
+        pool.detach(&mut easy);
+        drop(pool);
+        Ok(())
+    }
+}
@@ -0,0 +1,1420 @@
+//! 七牛 SDK 纯 Rust HTTP 客户端
+//!
+//! 为 `qiniu-rust-http` 所定义的 HTTP 客户端接口提供纯 Rust 实现，基于 reqwest 发送 HTTP 请求，使用 rustls 处理 HTTPS 连接，不依赖任何 C 库。
+//!
+//! 连接池与 Keep-Alive 均由 reqwest 提供，所有请求都在同一个后台 tokio 运行时中执行，调用者线程仅等待请求完成。
+//! 同时实现了 `HTTPCaller` 与 `AsyncHTTPCaller`，可以分别作为七牛 SDK 同步接口与异步接口的 HTTP 请求处理函数。
+
+use bytes::Bytes;
+use derive_builder::Builder;
+use futures::{executor::block_on, future, stream, Stream};
+use hyper::client::connect::dns::Name;
+use lazy_static::lazy_static;
+use qiniu_http::{
+    AsyncResponse, Error, ErrorKind, HTTPCaller, HTTPCallerErrorKind, HeadersOwned, Method, PEMSource, Proxy,
+    ProxyType, Request, Response, ResponseBuilder, Result, RetryKind, TLSOptions, TLSVersion,
+};
+use reqwest::{
+    dns::{Addrs, Resolve, Resolving},
+    header::CONTENT_LENGTH,
+    redirect::Policy,
+    Body, Client,
+};
+use ring::digest::{digest, SHA256};
+use rustls::{
+    client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier},
+    Certificate, ClientConfig, OwnedTrustAnchor, PrivateKey, RootCertStore, ServerName, SupportedProtocolVersion,
+};
+use rustls_pemfile::Item;
+use std::{
+    borrow::Cow,
+    cmp::min,
+    env,
+    error::Error as StdError,
+    fmt,
+    fs::{self, File},
+    future::Future,
+    io::{self, Write},
+    net::{SocketAddr, ToSocketAddrs},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+use tokio::{
+    runtime::{Builder as RuntimeBuilder, Runtime},
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    task::spawn_blocking,
+    time::{interval_at, timeout, Instant as TokioInstant, Interval},
+};
+use url::Url;
+
+const TRANSFER_CHUNK_SIZE: usize = 1 << 16;
+const MAX_CACHED_CLIENTS: usize = 32;
+
+lazy_static! {
+    static ref FULL_USER_AGENT: Box<str> = format!(
+        "QiniuRust/qiniu-http-{}/rust-{}/rustls",
+        env!("CARGO_PKG_VERSION"),
+        rustc_version_runtime::version(),
+    )
+    .into();
+    static ref TEMP_DIR: PathBuf = env::temp_dir();
+    static ref RUNTIME: Runtime = RuntimeBuilder::new_multi_thread()
+        .worker_threads(2)
+        .thread_name("qiniu-rustls")
+        .enable_all()
+        .build()
+        .expect("Failed to create tokio runtime");
+}
+
+/// 基于 rustls 的 HTTP 客户端
+///
+/// 影响连接建立的请求字段（代理，TLS 选项，预解析的服务器套接字地址，连接超时时长，TCP KeepAlive 空闲时长和是否跟踪重定向）均相同的请求将共享同一个连接池。
+/// 由于 reqwest 不支持设置 TCP KeepAlive 探测包的发送间隔，`tcp_keepalive_probe_interval` 将被忽略
+#[derive(Debug, Builder)]
+#[builder(
+    pattern = "owned",
+    setter(into, strip_option),
+    build_fn(name = "inner_build", private)
+)]
+pub struct RustlsClient {
+    /// 响应体内存缓冲区大小，超过该大小的响应体将被写入临时文件
+    #[builder(default = "1 << 22")]
+    buffer_size: usize,
+
+    /// 临时文件目录
+    #[builder(default)]
+    temp_dir: Option<PathBuf>,
+
+    /// 自动跟踪重定向的最大次数
+    #[builder(default = "3")]
+    max_redirections: usize,
+
+    #[builder(setter(skip))]
+    clients: Mutex<Vec<(ClientOptions, Client)>>,
+}
+
+impl HTTPCaller for RustlsClient {
+    fn call(&self, request: &Request) -> Result<Response> {
+        // 仅进入后台运行时的上下文而不在其上阻塞，因此即使调用者本身运行在其他异步运行时中也不会出错
+        let _guard = RUNTIME.enter();
+        let exchange = self.prepare(request)?;
+        block_on(self.perform(exchange, request))
+    }
+}
+
+impl qiniu_http::AsyncHTTPCaller for RustlsClient {
+    /// 异步发送 HTTP 请求
+    ///
+    /// 返回的 Future 可以在任意执行器中运行，每次轮询时都会进入后台运行时的上下文。不支持进度回调
+    fn call<'a>(&'a self, request: &Request) -> AsyncResponse<'a> {
+        let _guard = RUNTIME.enter();
+        let exchange = self.prepare(request);
+        let mut performing = Box::pin(async move { self.perform(exchange?, &NoProgress).await });
+        Box::pin(future::poll_fn(move |cx| {
+            let _guard = RUNTIME.enter();
+            performing.as_mut().poll(cx)
+        }))
+    }
+}
+
+impl RustlsClient {
+    fn prepare(&self, request: &Request) -> Result<Exchange> {
+        let mut context = Context {
+            method: request.method(),
+            url: request.url().into(),
+            proxied: false,
+        };
+        let url = Url::parse(request.url()).map_err(|err| context.error(HTTPCallerErrorKind::UnknownError, err))?;
+        let host = url.host_str().unwrap_or_default().to_owned();
+        let proxy = request.proxy().filter(|proxy| !proxy.should_bypass(&host));
+        context.proxied = proxy.is_some();
+        let client = self.client(
+            &context,
+            ClientOptions {
+                proxy: proxy.cloned(),
+                tls_options: request.tls_options().cloned(),
+                resolved_socket_addrs: if request.resolved_socket_addrs().is_empty() {
+                    None
+                } else {
+                    Some((host, request.resolved_socket_addrs().to_vec()))
+                },
+                follow_redirection: request.follow_redirection(),
+                connect_timeout: request.connect_timeout(),
+                tcp_keepalive_idle_timeout: request.tcp_keepalive_idle_timeout(),
+            },
+        )?;
+
+        let method = reqwest::Method::from_bytes(request.method().as_str().as_bytes())
+            .map_err(|err| context.error(HTTPCallerErrorKind::UnknownError, err))?;
+        let mut builder = client.request(method, url);
+        let mut has_user_agent = false;
+        for (header_name, header_value) in request.headers().iter() {
+            let header_name = header_name.as_ref();
+            if ["Host", "Content-Length", "Connection", "Transfer-Encoding", "Expect"]
+                .iter()
+                .any(|name| header_name.eq_ignore_ascii_case(name))
+            {
+                continue;
+            }
+            has_user_agent = has_user_agent || header_name.eq_ignore_ascii_case("User-Agent");
+            builder = builder.header(header_name, header_value.as_ref());
+        }
+        if !has_user_agent {
+            builder = builder.header(
+                "User-Agent",
+                match request.user_agent() {
+                    Some(user_agent) => format!("{}rustls/", user_agent),
+                    None => FULL_USER_AGENT.to_string(),
+                },
+            );
+        }
+
+        let low_speed = LowSpeedChecker::new(request.low_transfer_speed(), request.low_transfer_speed_timeout());
+        let mut uploading = None;
+        if !request.body().is_empty() || request.method() == Method::POST || request.method() == Method::PUT {
+            let body = Bytes::from(request.body().to_bytes().into_owned());
+            let total = body.len() as u64;
+            builder = builder.header(CONTENT_LENGTH, total);
+            builder = if request.on_uploading_progress().is_some() || low_speed.is_enabled() {
+                let (sender, receiver) = unbounded_channel();
+                uploading = Some(Uploading { total, receiver });
+                builder.body(Body::wrap_stream(uploading_stream(body, sender)))
+            } else {
+                builder.body(body)
+            };
+        }
+        Ok(Exchange {
+            request: builder
+                .build()
+                .map_err(|err| context.error(HTTPCallerErrorKind::UnknownError, err))?,
+            context,
+            client,
+            uploading,
+            low_speed,
+            request_timeout: request.request_timeout(),
+        })
+    }
+
+    // 连接池属于 reqwest 客户端，因此影响连接建立的请求字段均相同的请求总是复用同一个客户端
+    fn client(&self, context: &Context, options: ClientOptions) -> Result<Client> {
+        let mut clients = self.clients.lock().unwrap();
+        if let Some((_, client)) = clients.iter().find(|(cached_options, _)| cached_options == &options) {
+            return Ok(client.to_owned());
+        }
+        let client = self.build_client(context, &options)?;
+        if clients.len() >= MAX_CACHED_CLIENTS {
+            clients.remove(0);
+        }
+        clients.push((options, client.to_owned()));
+        Ok(client)
+    }
+
+    fn build_client(&self, context: &Context, options: &ClientOptions) -> Result<Client> {
+        let tls_config = make_tls_config(options.tls_options.as_ref(), options.proxy.as_ref()).map_err(|err| {
+            context.error_with_retry_kind(
+                RetryKind::UnretryableError,
+                ErrorKind::new_http_caller_error_kind(HTTPCallerErrorKind::SSLError, err),
+            )
+        })?;
+        let mut builder = Client::builder()
+            .use_preconfigured_tls(tls_config)
+            .redirect(if options.follow_redirection {
+                Policy::limited(self.max_redirections)
+            } else {
+                Policy::none()
+            })
+            .connect_timeout(options.connect_timeout)
+            .tcp_keepalive(Some(options.tcp_keepalive_idle_timeout).filter(|idle| *idle > Duration::from_secs(0)))
+            .tcp_nodelay(true)
+            .dns_resolver(Arc::new(Resolver(options.resolved_socket_addrs.to_owned())));
+        builder = match &options.proxy {
+            Some(proxy) => {
+                let mut reqwest_proxy = reqwest::Proxy::all(proxy.url()).map_err(|err| {
+                    context.error_with_retry_kind(
+                        RetryKind::UnretryableError,
+                        ErrorKind::new_http_caller_error_kind(HTTPCallerErrorKind::ProxyError, err),
+                    )
+                })?;
+                if let Some(username) = proxy.username() {
+                    reqwest_proxy = reqwest_proxy.basic_auth(username, proxy.password().as_deref().unwrap_or_default());
+                }
+                builder.proxy(reqwest_proxy)
+            }
+            None => builder.no_proxy(),
+        };
+        builder
+            .build()
+            .map_err(|err| context.error(HTTPCallerErrorKind::UnknownError, err))
+    }
+
+    async fn perform(&self, exchange: Exchange, progress: &(impl Progress + ?Sized)) -> Result<Response> {
+        let Exchange {
+            context,
+            client,
+            request,
+            uploading,
+            mut low_speed,
+            request_timeout,
+        } = exchange;
+        let transfer = async {
+            let is_head = request.method() == reqwest::Method::HEAD;
+            let mut response = send(&context, &client, request, uploading, &mut low_speed, progress).await?;
+            let mut headers = HeadersOwned::with_capacity(response.headers().len());
+            for (header_name, header_value) in response.headers().iter() {
+                headers.insert(
+                    header_name.as_str().into(),
+                    String::from_utf8_lossy(header_value.as_bytes()).into_owned(),
+                );
+            }
+            // `ResponseBuilder` 不能跨线程传递，因此在接收完响应体后再构建响应，使得 Future 可以在多线程执行器中运行
+            let body = if is_head {
+                None
+            } else {
+                Some(self.receive(&context, &mut response, &mut low_speed, progress).await?)
+            };
+            let mut builder = ResponseBuilder::default()
+                .status_code(response.status().as_u16())
+                .headers(headers);
+            if let Some(remote_addr) = response.remote_addr() {
+                builder = builder.server_ip(remote_addr.ip()).server_port(remote_addr.port());
+            }
+            builder = match body {
+                Some(ResponseBody::Bytes(bytes)) => builder.bytes_as_body(bytes),
+                Some(ResponseBody::File(file)) => builder.file_as_body(file).map_err(|err| {
+                    context.error_with_retry_kind(RetryKind::UnretryableError, ErrorKind::IOError(err))
+                })?,
+                None => builder,
+            };
+            Ok(builder.build())
+        };
+        if request_timeout > Duration::from_secs(0) {
+            timeout(request_timeout, transfer).await.unwrap_or_else(|_| {
+                Err(context.error(
+                    HTTPCallerErrorKind::TimeoutError,
+                    io::Error::new(io::ErrorKind::TimedOut, "Request timeout"),
+                ))
+            })
+        } else {
+            transfer.await
+        }
+    }
+
+    async fn receive(
+        &self,
+        context: &Context,
+        response: &mut reqwest::Response,
+        low_speed: &mut LowSpeedChecker,
+        progress: &(impl Progress + ?Sized),
+    ) -> Result<ResponseBody> {
+        let temp_dir = self.temp_dir.as_deref().unwrap_or(&TEMP_DIR);
+        let content_length = response.content_length();
+        let mut body = ResponseBody::Bytes(Vec::with_capacity(min(
+            self.buffer_size,
+            content_length.unwrap_or(0) as usize,
+        )));
+        let mut downloaded = 0u64;
+        low_speed.restart();
+        let mut ticker = low_speed.ticker();
+        loop {
+            tokio::select! {
+                chunk = response.chunk() => match chunk.map_err(|err| context.reqwest_error(err))? {
+                    Some(chunk) => {
+                        body.write(&chunk, self.buffer_size, temp_dir)
+                            .map_err(|err| context.io_error(HTTPCallerErrorKind::ResponseError, err))?;
+                        downloaded += chunk.len() as u64;
+                        progress.downloading(downloaded, content_length.unwrap_or(downloaded));
+                        low_speed
+                            .check(downloaded)
+                            .map_err(|err| context.io_error(HTTPCallerErrorKind::ResponseError, err))?;
+                    }
+                    None => return Ok(body),
+                },
+                _ = tick(&mut ticker) => {
+                    low_speed
+                        .check(downloaded)
+                        .map_err(|err| context.io_error(HTTPCallerErrorKind::ResponseError, err))?;
+                }
+            }
+        }
+    }
+}
+
+impl Default for RustlsClient {
+    fn default() -> Self {
+        RustlsClientBuilder::default().build()
+    }
+}
+
+impl RustlsClientBuilder {
+    /// 生成 HTTP 客户端
+    pub fn build(self) -> RustlsClient {
+        self.inner_build().unwrap()
+    }
+}
+
+async fn send(
+    context: &Context,
+    client: &Client,
+    request: reqwest::Request,
+    uploading: Option<Uploading>,
+    low_speed: &mut LowSpeedChecker,
+    progress: &(impl Progress + ?Sized),
+) -> Result<reqwest::Response> {
+    let sending = client.execute(request);
+    let Uploading { total, mut receiver } = match uploading {
+        Some(uploading) => uploading,
+        None => return sending.await.map_err(|err| context.reqwest_error(err)),
+    };
+    tokio::pin!(sending);
+    let mut uploaded = 0u64;
+    let mut ticker = low_speed.ticker();
+    loop {
+        tokio::select! {
+            result = &mut sending => return result.map_err(|err| context.reqwest_error(err)),
+            Some(have_uploaded) = receiver.recv() => {
+                uploaded = have_uploaded;
+                progress.uploading(uploaded, total);
+                low_speed
+                    .check(uploaded)
+                    .map_err(|err| context.io_error(HTTPCallerErrorKind::RequestError, err))?;
+            }
+            // 请求体发送完毕后，等待服务器处理请求的时间不计入传输速度
+            _ = tick(&mut ticker), if uploaded < total => {
+                low_speed
+                    .check(uploaded)
+                    .map_err(|err| context.io_error(HTTPCallerErrorKind::RequestError, err))?;
+            }
+        }
+    }
+}
+
+// 将请求体切分为多个数据块发送，每当一个数据块被取走时，通过 `sender` 通知已经上传的数据量
+fn uploading_stream(
+    body: Bytes,
+    sender: UnboundedSender<u64>,
+) -> impl Stream<Item = io::Result<Bytes>> + Send + Sync + 'static {
+    stream::iter((0..body.len()).step_by(TRANSFER_CHUNK_SIZE).map(move |offset| {
+        let end = min(offset + TRANSFER_CHUNK_SIZE, body.len());
+        sender.send(end as u64).ok();
+        Ok(body.slice(offset..end))
+    }))
+}
+
+trait Progress {
+    fn uploading(&self, uploaded: u64, total: u64);
+    fn downloading(&self, downloaded: u64, total: u64);
+}
+
+struct NoProgress;
+
+impl Progress for NoProgress {
+    fn uploading(&self, _uploaded: u64, _total: u64) {}
+    fn downloading(&self, _downloaded: u64, _total: u64) {}
+}
+
+impl Progress for Request<'_> {
+    fn uploading(&self, uploaded: u64, total: u64) {
+        if let Some(on_uploading_progress) = self.on_uploading_progress() {
+            on_uploading_progress.call(uploaded, total);
+        }
+    }
+
+    fn downloading(&self, downloaded: u64, total: u64) {
+        if let Some(on_downloading_progress) = self.on_downloading_progress() {
+            on_downloading_progress.call(downloaded, total);
+        }
+    }
+}
+
+// 发送请求所需的全部数据，在构建时已从 `Request` 中提取，因此不再持有 `Request` 的引用
+struct Exchange {
+    context: Context,
+    client: Client,
+    request: reqwest::Request,
+    uploading: Option<Uploading>,
+    low_speed: LowSpeedChecker,
+    request_timeout: Duration,
+}
+
+struct Uploading {
+    total: u64,
+    receiver: UnboundedReceiver<u64>,
+}
+
+struct Context {
+    method: Method,
+    url: Box<str>,
+    proxied: bool,
+}
+
+impl Context {
+    fn error(&self, kind: HTTPCallerErrorKind, err: impl StdError + Send + 'static) -> Error {
+        let error_kind = ErrorKind::new_http_caller_error_kind(kind, err);
+        match kind {
+            HTTPCallerErrorKind::ResolveError
+            | HTTPCallerErrorKind::ProxyError
+            | HTTPCallerErrorKind::SSLError
+            | HTTPCallerErrorKind::SSLHandshakeError
+            | HTTPCallerErrorKind::ConnectionError => {
+                self.error_with_retry_kind(RetryKind::HostUnretryableError, error_kind)
+            }
+            HTTPCallerErrorKind::RequestError | HTTPCallerErrorKind::TimeoutError => {
+                self.error_with_retry_kind(RetryKind::RetryableError, error_kind)
+            }
+            HTTPCallerErrorKind::ResponseError => Error::new(
+                RetryKind::RetryableError,
+                error_kind,
+                false,
+                Some(self.method),
+                Some(self.url.to_owned()),
+                None,
+            ),
+            HTTPCallerErrorKind::UnknownError => self.error_with_retry_kind(RetryKind::UnretryableError, error_kind),
+        }
+    }
+
+    fn error_with_retry_kind(&self, retry_kind: RetryKind, error_kind: ErrorKind) -> Error {
+        Error::new(
+            retry_kind,
+            error_kind,
+            retry_kind != RetryKind::UnretryableError,
+            Some(self.method),
+            Some(self.url.to_owned()),
+            None,
+        )
+    }
+
+    // 超时错误总是被视为 `TimeoutError`，其他 IO 错误则根据出错时所处的阶段决定错误类型
+    fn io_error(&self, kind: HTTPCallerErrorKind, err: io::Error) -> Error {
+        match err.kind() {
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => self.error(HTTPCallerErrorKind::TimeoutError, err),
+            _ => self.error(kind, err),
+        }
+    }
+
+    fn reqwest_error(&self, err: reqwest::Error) -> Error {
+        let kind = if err.is_timeout()
+            || find_cause::<io::Error>(&err).is_some_and(|err| err.kind() == io::ErrorKind::TimedOut)
+        {
+            HTTPCallerErrorKind::TimeoutError
+        } else if find_cause::<ResolveError>(&err).is_some() {
+            HTTPCallerErrorKind::ResolveError
+        } else if find_cause::<rustls::Error>(&err).is_some() {
+            HTTPCallerErrorKind::SSLHandshakeError
+        } else if err.is_connect() {
+            // 通过代理访问时，连接错误均发生在与代理服务器建立连接或隧道的过程中
+            if self.proxied {
+                HTTPCallerErrorKind::ProxyError
+            } else {
+                HTTPCallerErrorKind::ConnectionError
+            }
+        } else if err.is_builder() || err.is_redirect() {
+            HTTPCallerErrorKind::UnknownError
+        } else if err.is_body() || err.is_decode() {
+            HTTPCallerErrorKind::ResponseError
+        } else {
+            HTTPCallerErrorKind::RequestError
+        };
+        self.error(kind, err)
+    }
+}
+
+// 在错误链中查找指定类型的错误，包括被包装在 `io::Error` 中的错误
+fn find_cause<'e, E: StdError + 'static>(err: &'e (dyn StdError + 'static)) -> Option<&'e E> {
+    let mut cause = Some(err);
+    while let Some(err) = cause {
+        if let Some(err) = err.downcast_ref::<E>() {
+            return Some(err);
+        }
+        // `io::Error::source()` 会跳过被包装的错误本身，因此需要直接取出被包装的错误
+        cause = match err.downcast_ref::<io::Error>().and_then(|err| err.get_ref()) {
+            Some(inner) => Some(inner as &(dyn StdError + 'static)),
+            None => err.source(),
+        };
+    }
+    None
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ClientOptions {
+    proxy: Option<Proxy>,
+    tls_options: Option<TLSOptions>,
+    resolved_socket_addrs: Option<(String, Vec<SocketAddr>)>,
+    follow_redirection: bool,
+    connect_timeout: Duration,
+    tcp_keepalive_idle_timeout: Duration,
+}
+
+// 预解析的服务器套接字地址仅用于请求的原始主机，其他主机（例如重定向的目标主机）依然通过系统 DNS 解析
+struct Resolver(Option<(String, Vec<SocketAddr>)>);
+
+impl Resolve for Resolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        if let Some((host, socket_addrs)) = &self.0 {
+            if name.as_str().eq_ignore_ascii_case(host) {
+                let addrs: Addrs = Box::new(socket_addrs.clone().into_iter());
+                return Box::pin(future::ready(Ok(addrs)));
+            }
+        }
+        let host = name.as_str().to_owned();
+        Box::pin(async move {
+            let socket_addrs = spawn_blocking(move || {
+                (host.as_str(), 0)
+                    .to_socket_addrs()
+                    .map(|socket_addrs| socket_addrs.collect::<Vec<_>>())
+            })
+            .await
+            .unwrap_or_else(|err| Err(io::Error::other(err)))
+            .map_err(ResolveError)?;
+            let addrs: Addrs = Box::new(socket_addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+#[derive(Debug)]
+struct ResolveError(io::Error);
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Failed to resolve host: {}", self.0)
+    }
+}
+
+impl StdError for ResolveError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(&self.0)
+    }
+}
+
+struct LowSpeedChecker {
+    low_transfer_speed: u64,
+    low_transfer_speed_timeout: Duration,
+    started_at: Instant,
+    started_with: u64,
+}
+
+impl LowSpeedChecker {
+    fn new(low_transfer_speed: u32, low_transfer_speed_timeout: Duration) -> Self {
+        Self {
+            low_transfer_speed: low_transfer_speed.into(),
+            low_transfer_speed_timeout,
+            started_at: Instant::now(),
+            started_with: 0,
+        }
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.low_transfer_speed > 0 && self.low_transfer_speed_timeout > Duration::from_secs(0)
+    }
+
+    fn restart(&mut self) {
+        self.started_at = Instant::now();
+        self.started_with = 0;
+    }
+
+    // 即使没有任何数据传输，也需要定期检查传输速度，否则连接停滞时将无法及时发现
+    fn ticker(&self) -> Option<Interval> {
+        if self.is_enabled() {
+            let period = self.low_transfer_speed_timeout;
+            Some(interval_at(TokioInstant::now() + period, period))
+        } else {
+            None
+        }
+    }
+
+    // 以 `low_transfer_speed_timeout` 为时间窗口统计传输速度，如果一个时间窗口内的平均传输速度低于 `low_transfer_speed`，则返回错误
+    fn check(&mut self, transferred: u64) -> io::Result<()> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        let elapsed = self.started_at.elapsed();
+        if elapsed >= self.low_transfer_speed_timeout {
+            let window_transferred = transferred.saturating_sub(self.started_with);
+            if (window_transferred as f64) < self.low_transfer_speed as f64 * elapsed.as_secs_f64() {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "Transfer speed is too low"));
+            }
+            self.started_at = Instant::now();
+            self.started_with = transferred;
+        }
+        Ok(())
+    }
+}
+
+async fn tick(ticker: &mut Option<Interval>) {
+    match ticker {
+        Some(ticker) => {
+            ticker.tick().await;
+        }
+        None => future::pending().await,
+    }
+}
+
+enum ResponseBody {
+    Bytes(Vec<u8>),
+    File(File),
+}
+
+impl ResponseBody {
+    fn write(&mut self, data: &[u8], buffer_size: usize, temp_dir: &Path) -> io::Result<()> {
+        match self {
+            ResponseBody::Bytes(bytes) => {
+                if bytes.len() + data.len() > buffer_size {
+                    let mut tmpfile = tempfile::tempfile_in(temp_dir)?;
+                    tmpfile.write_all(bytes)?;
+                    tmpfile.write_all(data)?;
+                    *self = ResponseBody::File(tmpfile);
+                } else {
+                    bytes.extend_from_slice(data);
+                }
+            }
+            ResponseBody::File(file) => {
+                file.write_all(data)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn default_root_store() -> RootCertStore {
+    let mut root_store = RootCertStore::empty();
+    root_store.add_trust_anchors(
+        webpki_roots::TLS_SERVER_ROOTS
+            .iter()
+            .map(|ta| OwnedTrustAnchor::from_subject_spki_name_constraints(ta.subject, ta.spki, ta.name_constraints)),
+    );
+    root_store
+}
+
+// 使用 HTTPS 代理时，与代理服务器之间的 TLS 连接同样使用该配置，因此代理服务器的证书也必须被信任
+fn make_tls_config(tls_options: Option<&TLSOptions>, proxy: Option<&Proxy>) -> io::Result<ClientConfig> {
+    let tls_options = match tls_options {
+        Some(tls_options) => tls_options,
+        None => {
+            return Ok(ClientConfig::builder()
+                .with_safe_defaults()
+                .with_root_certificates(default_root_store())
+                .with_no_client_auth())
+        }
+    };
+    let root_store = match tls_options.ca_bundle() {
+        Some(ca_bundle) => {
+            let mut root_store = RootCertStore::empty();
+            let (added, _) =
+                root_store.add_parsable_certificates(&rustls_pemfile::certs(&mut read_pem(ca_bundle)?.as_ref())?);
+            if added == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "No valid CA certificate is found in CA bundle",
+                ));
+            }
+            root_store
+        }
+        None => default_root_store(),
+    };
+    // rustls 仅支持 TLS 1.2 和 TLS 1.3，因此最低版本低于 TLS 1.3 时均使用默认版本
+    let protocol_versions: &[&SupportedProtocolVersion] = match tls_options.min_version() {
+        Some(TLSVersion::TLSv1_3) => &[&rustls::version::TLS13],
+        _ => rustls::DEFAULT_VERSIONS,
+    };
+    let builder = ClientConfig::builder()
+        .with_safe_default_cipher_suites()
+        .with_safe_default_kx_groups()
+        .with_protocol_versions(protocol_versions)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    let builder = builder.with_custom_certificate_verifier(Arc::new(PinnedPublicKeyVerifier {
+        inner: WebPkiVerifier::new(root_store, None),
+        pinned_public_keys: tls_options.pinned_public_keys().to_owned(),
+        proxy_host: proxy
+            .filter(|proxy| proxy.proxy_type() == ProxyType::HTTPS)
+            .map(|proxy| proxy.host().to_owned()),
+    }));
+    match (tls_options.client_certificate(), tls_options.client_private_key()) {
+        (Some(certificate), Some(private_key)) => {
+            let certificates = rustls_pemfile::certs(&mut read_pem(certificate)?.as_ref())?
+                .into_iter()
+                .map(Certificate)
+                .collect();
+            builder
+                .with_client_auth_cert(certificates, read_private_key(private_key)?)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+        }
+        _ => Ok(builder.with_no_client_auth()),
+    }
+}
+
+fn read_pem(source: &PEMSource) -> io::Result<Cow<'_, [u8]>> {
+    match source {
+        PEMSource::File(path) => fs::read(path).map(Cow::Owned),
+        PEMSource::Content(content) => Ok(Cow::Borrowed(content)),
+    }
+}
+
+fn read_private_key(source: &PEMSource) -> io::Result<PrivateKey> {
+    rustls_pemfile::read_all(&mut read_pem(source)?.as_ref())?
+        .into_iter()
+        .find_map(|item| match item {
+            Item::RSAKey(key) | Item::PKCS8Key(key) | Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "No valid private key is found"))
+}
+
+// 在验证证书链之后，如果设置了固定的服务器公钥，再验证服务器公钥是否与其中任意一个匹配。固定的公钥仅用于七牛服务器，不用于 HTTPS 代理服务器
+struct PinnedPublicKeyVerifier {
+    inner: WebPkiVerifier,
+    pinned_public_keys: Vec<String>,
+    proxy_host: Option<String>,
+}
+
+impl ServerCertVerifier for PinnedPublicKeyVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        let verified =
+            self.inner
+                .verify_server_cert(end_entity, intermediates, server_name, scts, ocsp_response, now)?;
+        let is_proxy = match (server_name, &self.proxy_host) {
+            (ServerName::DnsName(dns_name), Some(proxy_host)) => dns_name.as_ref().eq_ignore_ascii_case(proxy_host),
+            (ServerName::IpAddress(ip), Some(proxy_host)) => {
+                proxy_host.trim_start_matches('[').trim_end_matches(']').parse() == Ok(*ip)
+            }
+            _ => false,
+        };
+        if !self.pinned_public_keys.is_empty() && !is_proxy {
+            verify_pinned_public_keys(end_entity, &self.pinned_public_keys)
+                .map_err(|err| rustls::Error::General(err.to_string()))?;
+        }
+        Ok(verified)
+    }
+}
+
+// 参考 RFC 7469，计算服务器证书 SubjectPublicKeyInfo 的 SHA-256 摘要，并与固定的服务器公钥列表比较
+fn verify_pinned_public_keys(certificate: &Certificate, pinned_public_keys: &[String]) -> io::Result<()> {
+    let public_key = subject_public_key_info(&certificate.0)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Invalid server certificate"))?;
+    let sha256_digest = base64::encode(digest(&SHA256, public_key).as_ref());
+    if pinned_public_keys.iter().any(|pinned| pinned == &sha256_digest) {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "Server public key does not match any pinned public key",
+        ))
+    }
+}
+
+// 从 DER 编码的 X.509 证书中提取 SubjectPublicKeyInfo，参考 RFC 5280 第 4.1 节
+fn subject_public_key_info(certificate: &[u8]) -> Option<&[u8]> {
+    let (_, certificate, _) = read_der(certificate)?;
+    let (_, mut tbs_certificate, _) = read_der(certificate)?;
+    // 跳过可选的 version 字段
+    if tbs_certificate.first() == Some(&0xa0) {
+        tbs_certificate = read_der(tbs_certificate)?.2;
+    }
+    // 跳过 serialNumber，signature，issuer，validity，subject 字段
+    for _ in 0..5 {
+        tbs_certificate = read_der(tbs_certificate)?.2;
+    }
+    read_der(tbs_certificate).map(|(element, _, _)| element)
+}
+
+// 读取一个 DER 元素，返回该元素本身，元素的内容和之后剩余的数据
+fn read_der(data: &[u8]) -> Option<(&[u8], &[u8], &[u8])> {
+    let len_byte = *data.get(1)?;
+    let (header_len, content_len) = if len_byte < 0x80 {
+        (2, usize::from(len_byte))
+    } else {
+        let len_len = usize::from(len_byte & 0x7f);
+        if len_len == 0 || len_len > 4 {
+            return None;
+        }
+        let content_len = data
+            .get(2..2 + len_len)?
+            .iter()
+            .fold(0usize, |content_len, &byte| content_len << 8 | usize::from(byte));
+        (2 + len_len, content_len)
+    };
+    let element = data.get(..header_len.checked_add(content_len)?)?;
+    Some((element, &element[header_len..], &data[element.len()..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qiniu_http::{RequestBuilder, ResponseBody as HTTPResponseBody};
+    use rcgen::generate_simple_self_signed;
+    use rustls::{ServerConfig, ServerConnection, StreamOwned};
+    use std::{
+        cell::Cell,
+        error::Error,
+        io::Read,
+        net::{Shutdown, TcpListener, TcpStream},
+        result::Result,
+        sync::atomic::{AtomicUsize, Ordering::Relaxed},
+        thread::{sleep, spawn},
+    };
+
+    #[test]
+    fn test_chunked_response_body() -> Result<(), Box<dyn Error>> {
+        let (addr, _) = serve(|stream| {
+            read_request(stream)?;
+            stream.write_all(
+                b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nX-Reqid: fake-req-id\r\n\r\n\
+                  5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n",
+            )
+        })?;
+        let downloaded = Cell::new(0);
+        let on_downloading_progress = |have_downloaded: u64, _total: u64| downloaded.set(have_downloaded);
+        let url = format!("http://{}/chunked", addr);
+        let mut response = RustlsClient::default().call(
+            &RequestBuilder::default()
+                .url(&url)
+                .on_downloading_progress(&on_downloading_progress as &dyn Fn(u64, u64))
+                .build(),
+        )?;
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(response.header("X-Reqid").map(|v| v.as_str()), Some("fake-req-id"));
+        assert_eq!(response.server_ip(), Some(addr.ip()));
+        assert_eq!(response.server_port(), addr.port());
+        assert_eq!(read_body(&mut response)?, b"hello world");
+        assert_eq!(downloaded.get(), 11);
+        Ok(())
+    }
+
+    #[test]
+    fn test_keep_alive() -> Result<(), Box<dyn Error>> {
+        let (addr, connections) = serve(|stream| {
+            while let Some((head, _)) = read_request(stream)? {
+                assert!(!head.to_ascii_lowercase().contains("connection: close"));
+                stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok")?;
+            }
+            Ok(())
+        })?;
+        let client = RustlsClient::default();
+        let url = format!("http://{}/keep-alive", addr);
+        for _ in 0..3 {
+            let mut response = client.call(&RequestBuilder::default().url(&url).build())?;
+            assert_eq!(read_body(&mut response)?, b"ok");
+        }
+        assert_eq!(connections.load(Relaxed), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_async_call() -> Result<(), Box<dyn Error>> {
+        let (addr, connections) = serve(|stream| {
+            while let Some((head, body)) = read_request(stream)? {
+                assert!(head.starts_with("POST /async "));
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nX-Reqid: fake-req-id\r\n\r\n",
+                    body.len()
+                )?;
+                stream.write_all(&body)?;
+            }
+            Ok(())
+        })?;
+        let client = RustlsClient::default();
+        let url = format!("http://{}/async", addr);
+        block_on(future::try_join_all((0..3).map(|i| {
+            let body = format!("async body {}", i).into_bytes();
+            let request = RequestBuilder::default()
+                .method(Method::POST)
+                .url(&url)
+                .body(body.to_owned())
+                .build();
+            let responding = qiniu_http::AsyncHTTPCaller::call(&client, &request);
+            async move {
+                let mut response = responding.await?;
+                assert_eq!(response.status_code(), 200);
+                assert_eq!(response.header("X-Reqid").map(|v| v.as_str()), Some("fake-req-id"));
+                assert_eq!(read_body(&mut response)?, body);
+                Ok::<_, Box<dyn Error>>(())
+            }
+        })))?;
+        assert!(connections.load(Relaxed) >= 1);
+
+        let err = block_on(qiniu_http::AsyncHTTPCaller::call(
+            &client,
+            &RequestBuilder::default().url("http://127.0.0.1:1/unreachable").build(),
+        ))
+        .unwrap_err();
+        assert!(matches!(
+            caller_error_kind(&err),
+            Some(HTTPCallerErrorKind::ConnectionError)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_upload_body_with_progress() -> Result<(), Box<dyn Error>> {
+        const BODY_SIZE: usize = 1 << 20;
+        let (addr, _) = serve(|stream| {
+            if let Some((head, body)) = read_request(stream)? {
+                assert!(head.starts_with("POST /upload "));
+                let body_len = body.len().to_string();
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                    body_len.len(),
+                    body_len
+                )?;
+            }
+            Ok(())
+        })?;
+        let progress = Cell::new((0, 0));
+        let on_uploading_progress = |uploaded: u64, total: u64| progress.set((uploaded, total));
+        let url = format!("http://{}/upload", addr);
+        let body = vec![b'x'; BODY_SIZE];
+        let mut response = RustlsClient::default().call(
+            &RequestBuilder::default()
+                .method(Method::POST)
+                .url(&url)
+                .body(body.as_slice())
+                .on_uploading_progress(&on_uploading_progress as &dyn Fn(u64, u64))
+                .build(),
+        )?;
+        assert_eq!(read_body(&mut response)?, BODY_SIZE.to_string().as_bytes());
+        assert_eq!(progress.get(), (BODY_SIZE as u64, BODY_SIZE as u64));
+        Ok(())
+    }
+
+    #[test]
+    fn test_redirection() -> Result<(), Box<dyn Error>> {
+        let (addr, _) = serve(|stream| {
+            while let Some((head, _)) = read_request(stream)? {
+                if head.starts_with("GET /from ") {
+                    stream.write_all(b"HTTP/1.1 302 Found\r\nLocation: /to\r\nContent-Length: 0\r\n\r\n")?;
+                } else {
+                    assert!(head.starts_with("GET /to "));
+                    stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nto")?;
+                }
+            }
+            Ok(())
+        })?;
+        let client = RustlsClient::default();
+        let url = format!("http://{}/from", addr);
+        let mut response = client.call(&RequestBuilder::default().url(&url).follow_redirection(true).build())?;
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(read_body(&mut response)?, b"to");
+
+        let response = client.call(&RequestBuilder::default().url(&url).follow_redirection(false).build())?;
+        assert_eq!(response.status_code(), 302);
+        assert_eq!(response.header("Location").map(|v| v.as_str()), Some("/to"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_request_timeout() -> Result<(), Box<dyn Error>> {
+        let (addr, _) = serve(|stream| {
+            read_request(stream)?;
+            sleep(Duration::from_secs(3));
+            Ok(())
+        })?;
+        let url = format!("http://{}/timeout", addr);
+        let begin_at = Instant::now();
+        let err = RustlsClient::default()
+            .call(
+                &RequestBuilder::default()
+                    .url(&url)
+                    .request_timeout(Duration::from_millis(500))
+                    .build(),
+            )
+            .unwrap_err();
+        assert!(begin_at.elapsed() < Duration::from_secs(2));
+        assert!(matches!(
+            caller_error_kind(&err),
+            Some(HTTPCallerErrorKind::TimeoutError)
+        ));
+        assert_eq!(err.retry_kind(), RetryKind::RetryableError);
+        Ok(())
+    }
+
+    #[test]
+    fn test_low_transfer_speed() -> Result<(), Box<dyn Error>> {
+        let (addr, _) = serve(|stream| {
+            if let Some((head, _)) = read_request(stream)? {
+                stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\n")?;
+                // 慢速传输时每 100 毫秒仅发送一个字节，停滞时则不再发送任何数据
+                if head.starts_with("GET /slow ") {
+                    for _ in 0..100 {
+                        stream.write_all(b"x")?;
+                        sleep(Duration::from_millis(100));
+                    }
+                } else {
+                    sleep(Duration::from_secs(5));
+                }
+            }
+            Ok(())
+        })?;
+        let client = RustlsClient::default();
+        for path in ["slow", "stalled"].iter() {
+            let url = format!("http://{}/{}", addr, path);
+            let begin_at = Instant::now();
+            let err = client
+                .call(
+                    &RequestBuilder::default()
+                        .url(&url)
+                        .low_transfer_speed(1000)
+                        .low_transfer_speed_timeout(Duration::from_millis(300))
+                        .request_timeout(Duration::from_secs(30))
+                        .build(),
+                )
+                .unwrap_err();
+            assert!(begin_at.elapsed() < Duration::from_secs(3));
+            assert!(matches!(
+                caller_error_kind(&err),
+                Some(HTTPCallerErrorKind::TimeoutError)
+            ));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolved_socket_addrs() -> Result<(), Box<dyn Error>> {
+        let (addr, _) = serve(|stream| {
+            if let Some((head, _)) = read_request(stream)? {
+                assert!(head.contains("fake-host.qiniu.test"));
+                stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 8\r\n\r\nresolved")?;
+            }
+            Ok(())
+        })?;
+        let url = format!("http://fake-host.qiniu.test:{}/", addr.port());
+        let socket_addrs = [SocketAddr::new(addr.ip(), 0)];
+        let mut response = RustlsClient::default().call(
+            &RequestBuilder::default()
+                .url(&url)
+                .resolved_socket_addrs(&socket_addrs[..])
+                .build(),
+        )?;
+        assert_eq!(read_body(&mut response)?, b"resolved");
+        Ok(())
+    }
+
+    #[test]
+    fn test_large_response_body_into_file() -> Result<(), Box<dyn Error>> {
+        let (addr, _) = serve(|stream| {
+            read_request(stream)?;
+            stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 26\r\n\r\nabcdefghijklmnopqrstuvwxyz")
+        })?;
+        let url = format!("http://{}/large", addr);
+        let mut response = RustlsClientBuilder::default()
+            .buffer_size(8usize)
+            .build()
+            .call(&RequestBuilder::default().url(&url).build())?;
+        match response.take_body() {
+            Some(HTTPResponseBody::File(mut file)) => {
+                let mut body = Vec::new();
+                file.read_to_end(&mut body)?;
+                assert_eq!(body, b"abcdefghijklmnopqrstuvwxyz");
+            }
+            _ => panic!("Response body should be stored in temporary file"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_tls_options() -> Result<(), Box<dyn Error>> {
+        let certificate = generate_simple_self_signed(vec!["localhost".to_owned()])?;
+        let addr = serve_tls(&certificate, |stream| {
+            read_request(stream)?;
+            stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 6\r\n\r\nsecure")
+        })?;
+        let url = format!("https://localhost:{}/", addr.port());
+        let socket_addrs = [addr];
+        let public_key_digest =
+            base64::encode(digest(&SHA256, subject_public_key_info(&certificate.serialize_der()?).unwrap()).as_ref());
+        let ca_bundle = PEMSource::Content(certificate.serialize_pem()?.into_bytes());
+        let client = RustlsClient::default();
+
+        let tls_options = TLSOptions::default()
+            .set_ca_bundle(ca_bundle.to_owned())
+            .pin_public_key(public_key_digest);
+        let mut response = client.call(
+            &RequestBuilder::default()
+                .url(&url)
+                .resolved_socket_addrs(&socket_addrs[..])
+                .tls_options(&tls_options)
+                .build(),
+        )?;
+        assert_eq!(read_body(&mut response)?, b"secure");
+
+        let tls_options = TLSOptions::default()
+            .set_ca_bundle(ca_bundle)
+            .pin_public_key("r/mIkG3eEpVdm+u/ko/cwxzOMo1bk4TyHIlByibiA5E=");
+        let err = client
+            .call(
+                &RequestBuilder::default()
+                    .url(&url)
+                    .resolved_socket_addrs(&socket_addrs[..])
+                    .tls_options(&tls_options)
+                    .build(),
+            )
+            .unwrap_err();
+        assert!(matches!(
+            caller_error_kind(&err),
+            Some(HTTPCallerErrorKind::SSLHandshakeError)
+        ));
+        assert_eq!(err.retry_kind(), RetryKind::HostUnretryableError);
+
+        let err = client
+            .call(
+                &RequestBuilder::default()
+                    .url(&url)
+                    .resolved_socket_addrs(&socket_addrs[..])
+                    .build(),
+            )
+            .unwrap_err();
+        assert!(matches!(
+            caller_error_kind(&err),
+            Some(HTTPCallerErrorKind::SSLHandshakeError)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_http_proxy_with_connect_tunnel() -> Result<(), Box<dyn Error>> {
+        let certificate = generate_simple_self_signed(vec!["localhost".to_owned()])?;
+        let target_addr = serve_tls(&certificate, |stream| {
+            if let Some((head, _)) = read_request(stream)? {
+                assert!(head.starts_with("GET /tunnel "));
+                assert_eq!(header_value(&head, "Proxy-Authorization"), None);
+                stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 8\r\n\r\ntunneled")?;
+            }
+            Ok(())
+        })?;
+        let (proxy_addr, _) = serve_tcp(move |mut stream| {
+            if let Some((head, _)) = read_request(&mut stream)? {
+                assert!(head.starts_with(&format!("CONNECT localhost:{} HTTP/1.1\r\n", target_addr.port())));
+                assert_eq!(
+                    header_value(&head, "Proxy-Authorization"),
+                    Some(format!("Basic {}", base64::encode("user:password")).as_str())
+                );
+                stream.write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")?;
+                relay(stream, TcpStream::connect(target_addr)?)?;
+            }
+            Ok(())
+        })?;
+        let proxy = Proxy::new(ProxyType::HTTP, "127.0.0.1", proxy_addr.port()).credential("user", "password");
+        let tls_options =
+            TLSOptions::default().set_ca_bundle(PEMSource::Content(certificate.serialize_pem()?.into_bytes()));
+        let url = format!("https://localhost:{}/tunnel", target_addr.port());
+        let mut response = RustlsClient::default().call(
+            &RequestBuilder::default()
+                .url(&url)
+                .proxy(&proxy)
+                .tls_options(&tls_options)
+                .build(),
+        )?;
+        assert_eq!(read_body(&mut response)?, b"tunneled");
+        Ok(())
+    }
+
+    #[test]
+    fn test_socks5_proxy() -> Result<(), Box<dyn Error>> {
+        let (target_addr, _) = serve(|stream| {
+            if let Some((head, _)) = read_request(stream)? {
+                assert!(head.starts_with("GET /socks5 "));
+                stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 6\r\n\r\nsocks5")?;
+            }
+            Ok(())
+        })?;
+        let (proxy_addr, _) = serve_tcp(move |mut stream| {
+            // 协商认证方式，仅接受用户名密码认证
+            let mut buf = [0u8; 2];
+            stream.read_exact(&mut buf)?;
+            assert_eq!(buf[0], 5);
+            let mut methods = vec![0u8; buf[1].into()];
+            stream.read_exact(&mut methods)?;
+            assert!(methods.contains(&2));
+            stream.write_all(&[5, 2])?;
+
+            stream.read_exact(&mut buf)?;
+            assert_eq!(buf[0], 1);
+            let mut username = vec![0u8; buf[1].into()];
+            stream.read_exact(&mut username)?;
+            stream.read_exact(&mut buf[..1])?;
+            let mut password = vec![0u8; buf[0].into()];
+            stream.read_exact(&mut password)?;
+            assert_eq!(
+                (username.as_slice(), password.as_slice()),
+                (&b"user"[..], &b"password"[..])
+            );
+            stream.write_all(&[1, 0])?;
+
+            // 由代理服务器解析域名，因此请求中携带的是域名而非 IP 地址
+            let mut buf = [0u8; 5];
+            stream.read_exact(&mut buf)?;
+            assert_eq!(&buf[..4], &[5, 1, 0, 3]);
+            let mut host = vec![0u8; usize::from(buf[4]) + 2];
+            stream.read_exact(&mut host)?;
+            let port = u16::from_be_bytes([host[host.len() - 2], host[host.len() - 1]]);
+            host.truncate(host.len() - 2);
+            assert_eq!(host, b"fake-host.qiniu.test");
+            assert_eq!(port, target_addr.port());
+            stream.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0])?;
+            relay(stream, TcpStream::connect(target_addr)?)
+        })?;
+        let proxy =
+            Proxy::new(ProxyType::SOCKS5Hostname, "127.0.0.1", proxy_addr.port()).credential("user", "password");
+        let url = format!("http://fake-host.qiniu.test:{}/socks5", target_addr.port());
+        let mut response = RustlsClient::default().call(&RequestBuilder::default().url(&url).proxy(&proxy).build())?;
+        assert_eq!(read_body(&mut response)?, b"socks5");
+        Ok(())
+    }
+
+    #[test]
+    fn test_https_proxy() -> Result<(), Box<dyn Error>> {
+        let certificate = generate_simple_self_signed(vec!["localhost".to_owned()])?;
+        let proxy_addr = serve_tls(&certificate, |stream| {
+            if let Some((head, _)) = read_request(stream)? {
+                assert!(head.starts_with("GET http://fake-host.qiniu.test/https-proxy HTTP/1.1\r\n"));
+                assert_eq!(
+                    header_value(&head, "Proxy-Authorization"),
+                    Some(format!("Basic {}", base64::encode("user:password")).as_str())
+                );
+                stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 7\r\n\r\nproxied")?;
+            }
+            Ok(())
+        })?;
+        let proxy = Proxy::new(ProxyType::HTTPS, "localhost", proxy_addr.port()).credential("user", "password");
+        // 固定的公钥仅用于七牛服务器，不会用于验证 HTTPS 代理服务器
+        let tls_options = TLSOptions::default()
+            .set_ca_bundle(PEMSource::Content(certificate.serialize_pem()?.into_bytes()))
+            .pin_public_key("r/mIkG3eEpVdm+u/ko/cwxzOMo1bk4TyHIlByibiA5E=");
+        let mut response = RustlsClient::default().call(
+            &RequestBuilder::default()
+                .url("http://fake-host.qiniu.test/https-proxy")
+                .proxy(&proxy)
+                .tls_options(&tls_options)
+                .build(),
+        )?;
+        assert_eq!(read_body(&mut response)?, b"proxied");
+        Ok(())
+    }
+
+    trait Stream: Read + Write {}
+
+    impl<T: Read + Write> Stream for T {}
+
+    // 在本地端口上启动 HTTP 服务器，每个连接都在独立的线程中处理，返回服务器地址和已经接受的连接数
+    fn serve(
+        handler: impl Fn(&mut dyn Stream) -> io::Result<()> + Send + Sync + 'static,
+    ) -> io::Result<(SocketAddr, Arc<AtomicUsize>)> {
+        serve_tcp(move |mut stream| handler(&mut stream))
+    }
+
+    fn serve_tcp(
+        handler: impl Fn(TcpStream) -> io::Result<()> + Send + Sync + 'static,
+    ) -> io::Result<(SocketAddr, Arc<AtomicUsize>)> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let connections = Arc::new(AtomicUsize::new(0));
+        let handler = Arc::new(handler);
+        {
+            let connections = connections.to_owned();
+            spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    connections.fetch_add(1, Relaxed);
+                    let handler = handler.to_owned();
+                    spawn(move || handler(stream));
+                }
+            });
+        }
+        Ok((addr, connections))
+    }
+
+    fn serve_tls(
+        certificate: &rcgen::Certificate,
+        handler: impl Fn(&mut dyn Stream) -> io::Result<()> + Send + Sync + 'static,
+    ) -> Result<SocketAddr, Box<dyn Error>> {
+        let server_config = Arc::new(
+            ServerConfig::builder()
+                .with_safe_defaults()
+                .with_no_client_auth()
+                .with_single_cert(
+                    vec![Certificate(certificate.serialize_der()?)],
+                    PrivateKey(certificate.serialize_private_key_der()),
+                )?,
+        );
+        let (addr, _) = serve(move |stream| {
+            let connection = ServerConnection::new(server_config.to_owned())
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            handler(&mut StreamOwned::new(connection, stream))
+        })?;
+        Ok(addr)
+    }
+
+    // 在客户端与目标服务器之间双向转发数据，直到任意一方关闭连接
+    fn relay(client: TcpStream, target: TcpStream) -> io::Result<()> {
+        let (mut client_reader, mut target_writer) = (client.try_clone()?, target.try_clone()?);
+        let forwarding = spawn(move || {
+            let _ = io::copy(&mut client_reader, &mut target_writer);
+            target_writer.shutdown(Shutdown::Write)
+        });
+        let (mut target_reader, mut client_writer) = (target, client);
+        let _ = io::copy(&mut target_reader, &mut client_writer);
+        client_writer.shutdown(Shutdown::Write)?;
+        let _ = forwarding.join();
+        Ok(())
+    }
+
+    // 读取一个 HTTP 请求，返回请求头和请求体，如果连接已经关闭则返回 `None`
+    fn read_request(stream: &mut dyn Stream) -> io::Result<Option<(String, Vec<u8>)>> {
+        let mut head = Vec::new();
+        let mut byte = [0u8; 1];
+        while !head.ends_with(b"\r\n\r\n") {
+            match stream.read(&mut byte) {
+                Ok(0) if head.is_empty() => return Ok(None),
+                Ok(0) => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Incomplete request")),
+                Ok(_) => head.push(byte[0]),
+                Err(err) if head.is_empty() && err.kind() == io::ErrorKind::ConnectionReset => return Ok(None),
+                Err(err) => return Err(err),
+            }
+        }
+        let head = String::from_utf8(head).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let content_length = header_value(&head, "Content-Length")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0);
+        let mut body = vec![0u8; content_length];
+        stream.read_exact(&mut body)?;
+        Ok(Some((head, body)))
+    }
+
+    fn header_value<'h>(head: &'h str, header_name: &str) -> Option<&'h str> {
+        head.lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case(header_name))
+            .map(|(_, value)| value.trim())
+    }
+
+    fn read_body(response: &mut Response) -> io::Result<Vec<u8>> {
+        let mut body = Vec::new();
+        match response.take_body() {
+            Some(HTTPResponseBody::Bytes(bytes)) => body = bytes,
+            Some(HTTPResponseBody::File(mut file)) => {
+                file.read_to_end(&mut body)?;
+            }
+            Some(HTTPResponseBody::Reader(mut reader)) => {
+                reader.read_to_end(&mut body)?;
+            }
+            None => {}
+        }
+        Ok(body)
+    }
+
+    fn caller_error_kind(err: &qiniu_http::Error) -> Option<HTTPCallerErrorKind> {
+        match err.error_kind() {
+            ErrorKind::HTTPCallerError(err) => Some(err.kind()),
+            _ => None,
+        }
+    }
+}
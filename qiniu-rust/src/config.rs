@@ -1,7 +1,12 @@
 //! 七牛客户端配置模块
 use crate::{
-    http::{DomainsManager, HTTPAfterAction, HTTPBeforeAction, HTTPCaller},
-    storage::uploader::{UploadLogger, UploadLoggerBuilder, UploadRecorder},
+    http::{
+        AsyncHTTPCaller, DomainsManager, HTTPAfterAction, HTTPBeforeAction, HTTPCaller, Proxy, RetryPolicy, TLSOptions,
+    },
+    storage::uploader::{
+        ResumableUploadVersion, UploadLogger, UploadLoggerBuilder, UploadPartSizePolicy, UploadRecorder,
+    },
+    utils::rate_limiter::RateLimiter,
 };
 use assert_impl::assert_impl;
 use derive_builder::Builder;
@@ -113,13 +118,41 @@ pub struct ConfigInner {
     #[builder(default = "default::upload_threshold()")]
     upload_threshold: u32,
 
-    /// 上传分块尺寸，尺寸越小越适合弱网环境，必须是 4 MB 的倍数
+    /// 上传分块尺寸，尺寸越小越适合弱网环境
+    ///
+    /// 对于分片上传 V2，可以是 1 MB 到 1 GB 之间的任意尺寸，超出范围的值将被自动调整。
+    /// 对于分片上传 V1，该配置无效，分块尺寸固定为 4 MB
     ///
     /// 单位为字节，默认为 4 MB
     #[get_copy = "pub"]
     #[builder(default = "default::upload_block_size()")]
     upload_block_size: u32,
 
+    /// 上传分块中每个片的尺寸
+    ///
+    /// 仅对分片上传 V1 有效。每个块的第一个片通过 `mkblk` 接口上传，其余的片通过 `bput` 接口上传，
+    /// 上传失败时只需重传当前的片，断点续传时也可以从块内最后一个已上传的片之后继续上传。
+    /// 为 0 或超过块尺寸时，每个块将作为一个片上传
+    ///
+    /// 单位为字节，默认为 2 MB
+    #[get_copy = "pub"]
+    #[builder(default = "default::upload_chunk_size()")]
+    upload_chunk_size: u32,
+
+    /// 上传分片尺寸策略
+    ///
+    /// 仅对分片上传 V2 有效，默认为固定分片尺寸
+    #[get_copy = "pub"]
+    #[builder(default)]
+    upload_part_size_policy: UploadPartSizePolicy,
+
+    /// 分片上传协议版本
+    ///
+    /// 默认为分片上传 V2，对于尚不支持分片上传 V2 的私有云部署，可以改为分片上传 V1
+    #[get_copy = "pub"]
+    #[builder(default)]
+    resumable_upload_version: ResumableUploadVersion,
+
     /// 上传日志记录仪
     ///
     /// 默认情况下，七牛 Rust SDK 会收集文件上传相关日志信息，并自动以异步的形式上传到 Uplog 服务器，并由七牛工作人员进行统计或定位问题
@@ -136,6 +169,33 @@ pub struct ConfigInner {
     #[builder(default)]
     upload_recorder: UploadRecorder,
 
+    /// 上传带宽限速器
+    ///
+    /// 对七牛 Rust SDK 所有上传请求均有效，包括表单上传的请求体和分片上传的每个分片，所有上传任务共享同一个令牌桶
+    ///
+    /// 默认不限速
+    #[get = "pub"]
+    #[builder(default, setter(strip_option))]
+    upload_rate_limiter: Option<RateLimiter>,
+
+    /// HTTP 代理服务器
+    ///
+    /// 对七牛 Rust SDK 所有发出的 HTTP 请求均有效，但访问代理服务器的不使用代理的主机列表中的主机时除外
+    ///
+    /// 默认不使用代理
+    #[get = "pub"]
+    #[builder(default, setter(strip_option))]
+    http_proxy: Option<Proxy>,
+
+    /// HTTPS 请求的 TLS 选项
+    ///
+    /// 对七牛 Rust SDK 所有发出的 HTTPS 请求均有效，可用于私有云部署时信任私有 CA，提供客户端证书，或固定服务器公钥
+    ///
+    /// 默认使用 HTTP 客户端的默认 TLS 选项
+    #[get = "pub"]
+    #[builder(default, setter(strip_option))]
+    tls_options: Option<TLSOptions>,
+
     /// HTTP 请求连接超时时长
     ///
     /// 对七牛 Rust SDK 所有发出的 HTTP 请求均有效
@@ -220,6 +280,27 @@ pub struct ConfigInner {
     #[builder(default = "default::http_request_retry_delay()")]
     http_request_retry_delay: Duration,
 
+    /// HTTP 请求重试策略
+    ///
+    /// 当 SDK 发送 HTTP 请求时发生错误，由重试策略决定在当前主机上重试，切换主机，切换区域还是放弃重试，以及重试前的等待时长。
+    ///
+    /// 如果没有设置，将使用基于 `http_request_retries` 和 `http_request_retry_delay` 的默认重试策略 [`DefaultRetryPolicy`](../http/struct.DefaultRetryPolicy.html)
+    #[get = "pub"]
+    #[builder(setter(name = "boxed_http_request_retry_policy"), private, default)]
+    http_request_retry_policy: Option<Box<dyn RetryPolicy>>,
+
+    /// 操作超时时长
+    ///
+    /// `http_request_timeout` 仅限制单次 HTTP 请求的时长，而一次操作可能会在多个主机甚至多个区域上多次重试。
+    /// 该项限制一次操作（例如获取对象信息，或上传一个文件）包含所有重试在内的总耗时，超时后将返回 `DeadlineExceeded` 错误。
+    ///
+    /// 对七牛 Rust SDK 所有操作均有效，对象上传器和对象操作可以单独指定操作超时时长以覆盖该项。
+    ///
+    /// 默认不限制操作的总耗时
+    #[get_copy = "pub"]
+    #[builder(default, setter(strip_option))]
+    operation_timeout: Option<Duration>,
+
     /// HTTP 请求前回调函数
     ///
     /// 在每次发送 HTTP 请求前将逐一回调列表中所有函数
@@ -249,6 +330,7 @@ pub struct ConfigInner {
     /// 七牛 Rust SDK 本身并不直接包含 HTTP 请求处理逻辑，您需要为 SDK 提供一个 HTTP 请求处理逻辑实现。
     ///
     /// 对于开启了 `use-libcurl` 功能的七牛 Rust SDK，Config 会默认使用 [qiniu-with-libcurl](https://crates.io/crates/qiniu-with-libcurl) 提供的 `HTTPCaller` 来处理 HTTP 请求。
+    /// 对于仅开启了 `use-rustls` 功能的七牛 Rust SDK，Config 会默认使用 [qiniu-with-rustls](https://crates.io/crates/qiniu-with-rustls) 提供的 `HTTPCaller` 来处理 HTTP 请求。
     ///
     /// 对七牛 Rust SDK 所有发出的 HTTP 请求均有效
     #[get = "pub"]
@@ -259,6 +341,14 @@ pub struct ConfigInner {
     )]
     http_request_handler: Box<dyn HTTPCaller>,
 
+    /// 异步 HTTP 请求处理函数
+    ///
+    /// 仅对七牛 Rust SDK 的异步接口有效（需要开启 `async` 功能）。
+    /// 如果没有设置，异步接口将在当前线程内直接调用 `http_request_handler` 处理 HTTP 请求
+    #[get = "pub"]
+    #[builder(setter(name = "boxed_async_http_request_handler"), private, default)]
+    async_http_request_handler: Option<Box<dyn AsyncHTTPCaller>>,
+
     #[doc(hidden)]
     #[get = "pub"]
     #[builder(default, setter(strip_option))]
@@ -322,6 +412,11 @@ mod default {
         1 << 22
     }
 
+    #[inline]
+    pub const fn upload_chunk_size() -> u32 {
+        1 << 21
+    }
+
     #[inline]
     pub fn upload_logger() -> Option<UploadLogger> {
         UploadLoggerBuilder::default().build().map(Some).unwrap_or(None)
@@ -373,7 +468,11 @@ mod default {
         {
             Box::new(qiniu_with_libcurl::CurlClient::default())
         }
-        #[cfg(not(feature = "use-libcurl"))]
+        #[cfg(all(feature = "use-rustls", not(feature = "use-libcurl")))]
+        {
+            Box::new(qiniu_with_rustls::RustlsClient::default())
+        }
+        #[cfg(not(any(feature = "use-libcurl", feature = "use-rustls")))]
         {
             use crate::http::PanickedHTTPCaller;
             Box::new(PanickedHTTPCaller("Must define config.http_request_call"))
@@ -393,10 +492,17 @@ impl fmt::Debug for ConfigInner {
             .field("batch_max_operation_size", &self.batch_max_operation_size)
             .field("upload_threshold", &self.upload_threshold)
             .field("upload_block_size", &self.upload_block_size)
+            .field("upload_chunk_size", &self.upload_chunk_size)
+            .field("upload_part_size_policy", &self.upload_part_size_policy)
+            .field("resumable_upload_version", &self.resumable_upload_version)
             .field("upload_recorder", &self.upload_recorder)
             .field("upload_logger", &self.upload_logger)
+            .field("upload_rate_limiter", &self.upload_rate_limiter)
+            .field("http_proxy", &self.http_proxy)
+            .field("tls_options", &self.tls_options)
             .field("http_request_retries", &self.http_request_retries)
             .field("http_request_retry_delay", &self.http_request_retry_delay)
+            .field("operation_timeout", &self.operation_timeout)
             .field("domains_manager", &self.domains_manager)
             .finish()
     }
@@ -497,12 +603,30 @@ impl ConfigBuilder {
     /// 七牛 Rust SDK 本身并不直接包含 HTTP 请求处理逻辑，您需要为 SDK 提供一个 HTTP 请求处理逻辑实现。
     ///
     /// 对于开启了 `use-libcurl` 功能的七牛 Rust SDK，Config 会默认使用 [qiniu-with-libcurl](https://crates.io/crates/qiniu-with-libcurl) 提供的 `HTTPCaller` 来处理 HTTP 请求。
+    /// 对于仅开启了 `use-rustls` 功能的七牛 Rust SDK，Config 会默认使用 [qiniu-with-rustls](https://crates.io/crates/qiniu-with-rustls) 提供的 `HTTPCaller` 来处理 HTTP 请求。
     ///
     /// 对七牛 Rust SDK 所有发出的 HTTP 请求均有效
     pub fn http_request_handler(self, handler: impl HTTPCaller + 'static) -> Self {
         self.boxed_http_request_handler(Box::new(handler))
     }
 
+    /// 设置 HTTP 请求重试策略
+    ///
+    /// 当 SDK 发送 HTTP 请求时发生错误，由重试策略决定在当前主机上重试，切换主机，切换区域还是放弃重试，以及重试前的等待时长。
+    /// 设置后，`http_request_retries` 和 `http_request_retry_delay` 将不再生效
+    pub fn http_request_retry_policy(self, policy: impl RetryPolicy + 'static) -> Self {
+        self.boxed_http_request_retry_policy(Some(Box::new(policy)))
+    }
+
+    /// 设置异步 HTTP 请求处理函数
+    ///
+    /// 仅对七牛 Rust SDK 的异步接口有效（需要开启 `async` 功能）。
+    /// 如果没有设置，异步接口将在当前线程内直接调用 `http_request_handler` 处理 HTTP 请求。
+    /// 开启 `use-rustls` 功能后，可以使用 `qiniu_with_rustls::RustlsClient` 作为异步 HTTP 请求处理函数
+    pub fn async_http_request_handler(self, handler: impl AsyncHTTPCaller + 'static) -> Self {
+        self.boxed_async_http_request_handler(Some(Box::new(handler)))
+    }
+
     /// 追加 HTTP 请求前回调函数
     ///
     /// 您可以利用该特性输出 HTTP 日志或对 HTTP 请求内容进行修改。
@@ -631,7 +755,10 @@ impl Config {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use qiniu_http::{HTTPCaller, Request, RequestBuilder, Response, ResponseBody, ResponseBuilder, Result};
+    use qiniu_http::{
+        HTTPCaller, PEMSource, ProxyType, Request, RequestBuilder, Response, ResponseBody, ResponseBuilder, Result,
+        TLSVersion,
+    };
     use regex::Regex;
     use std::{error::Error, result::Result as StdResult};
 
@@ -685,10 +812,32 @@ mod tests {
         let config = ConfigBuilder::default()
             .http_request_retries(5)
             .http_request_retry_delay(Duration::from_secs(1))
+            .operation_timeout(Duration::from_secs(30))
             .http_request_handler(FakeHTTPRequester)
             .build();
         assert_eq!(config.http_request_retries(), 5);
         assert_eq!(config.http_request_retry_delay(), Duration::from_secs(1));
+        assert_eq!(config.operation_timeout(), Some(Duration::from_secs(30)));
+        assert!(config.http_proxy().is_none());
+
+        let config = ConfigBuilder::default()
+            .http_proxy(Proxy::new(ProxyType::SOCKS5Hostname, "127.0.0.1", 1080).credential("user", "password"))
+            .build();
+        let proxy = config.http_proxy().as_ref().unwrap();
+        assert_eq!(proxy.url(), "socks5h://127.0.0.1:1080");
+        assert_eq!(proxy.username().as_deref(), Some("user"));
+        assert!(config.tls_options().is_none());
+
+        let config = ConfigBuilder::default()
+            .tls_options(
+                TLSOptions::default()
+                    .set_ca_bundle(PEMSource::File("/etc/ssl/private-ca.pem".into()))
+                    .set_min_version(TLSVersion::TLSv1_2),
+            )
+            .build();
+        let tls_options = config.tls_options().as_ref().unwrap();
+        assert_eq!(tls_options.min_version(), Some(TLSVersion::TLSv1_2));
+        assert!(tls_options.pinned_public_keys().is_empty());
         Ok(())
     }
 }
@@ -199,7 +199,7 @@ impl Credential {
                 == &self.authorization_v1_for_request(
                     req.url(),
                     req.headers().get(&"Content-Type".into()).unwrap_or(&"".into()),
-                    req.body().as_ref(),
+                    &req.body().to_bytes(),
                 )?)
         } else {
             Ok(false)
@@ -3,8 +3,9 @@
 //! 负责对整个 SDK 的 HTTP 逻辑进行处理，包含 HTTP 请求的重试逻辑，HTTP 请求中间件和域名管理等。
 
 pub use qiniu_http::{
-    Error, ErrorKind, HTTPCaller, HTTPCallerErrorKind, HeaderName, HeaderNameOwned, HeaderValue, HeaderValueOwned,
-    Headers, HeadersOwned, Method, Result, RetryKind, StatusCode,
+    AsyncHTTPCaller, AsyncResponse, Error, ErrorKind, HTTPCaller, HTTPCallerErrorKind, HeaderName, HeaderNameOwned,
+    HeaderValue, HeaderValueOwned, Headers, HeadersOwned, Method, PEMSource, Proxy, ProxyType, Result, RetryKind,
+    StatusCode, TLSOptions, TLSVersion,
 };
 mod client;
 pub(crate) use client::Client;
@@ -13,7 +14,7 @@ pub mod domains_manager;
 pub use domains_manager::{Choice, DomainsManager, DomainsManagerBuilder};
 
 mod handler;
-#[cfg(not(feature = "use-libcurl"))]
+#[cfg(any(test, not(any(feature = "use-libcurl", feature = "use-rustls"))))]
 pub(crate) use handler::PanickedHTTPCaller;
 
 mod middleware;
@@ -23,5 +24,8 @@ pub(crate) mod request;
 mod response;
 pub(crate) use response::Response;
 
+pub mod retry_policy;
+pub use retry_policy::{DefaultRetryPolicy, ExponentialBackoffRetryPolicy, RetryContext, RetryDecision, RetryPolicy};
+
 mod token;
 pub(crate) use token::Version as TokenVersion;
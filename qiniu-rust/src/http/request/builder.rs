@@ -6,8 +6,12 @@ use super::{
     HTTPError, HTTPResult, HeaderName, HeaderValue, Headers, Inner, Method, Request,
 };
 use crate::{utils::mime, Config, Credential};
+use qiniu_http::RequestBody;
 use serde::Serialize;
-use std::{borrow::Cow, time::Duration};
+use std::{
+    borrow::Cow,
+    time::{Duration, Instant},
+};
 use url::Url;
 
 pub(crate) struct Builder<'a>(Inner<'a>);
@@ -27,6 +31,7 @@ impl<'a> Builder<'a> {
             read_body: false,
             idempotent: false,
             follow_redirection: false,
+            deadline: None,
             on_url_constructed: None,
             on_uploading_progress: None,
             on_downloading_progress: None,
@@ -65,6 +70,11 @@ impl<'a> Builder<'a> {
         self
     }
 
+    pub(crate) fn deadline(mut self, deadline: Option<Instant>) -> Builder<'a> {
+        self.0.deadline = deadline;
+        self
+    }
+
     pub(crate) fn on_url_constructed(mut self, callback: &'a dyn Fn(&mut Url)) -> Builder<'a> {
         self.0.on_url_constructed = Some(callback);
         self
@@ -104,7 +114,7 @@ impl<'a> Builder<'a> {
         self.build()
     }
 
-    pub(crate) fn raw_body(mut self, content_type: HeaderValue<'a>, body: Cow<'a, [u8]>) -> Request<'a> {
+    pub(crate) fn raw_body(mut self, content_type: HeaderValue<'a>, body: RequestBody<'a>) -> Request<'a> {
         self = self.header("Content-Type".into(), content_type);
         self.0.body = body;
         self.build()
@@ -113,7 +123,7 @@ impl<'a> Builder<'a> {
     pub(crate) fn json_body(mut self, body: &impl Serialize) -> serde_json::Result<Request<'a>> {
         let serialized_body = serde_json::to_vec(body)?;
         self = self.header("Content-Type".into(), mime::JSON_MIME.into());
-        self.0.body = Cow::Owned(serialized_body);
+        self.0.body = serialized_body.into();
         Ok(self.build())
     }
 
@@ -3,7 +3,12 @@ use super::{
     HTTPError, HTTPResult, Headers, Method,
 };
 use crate::config::Config;
-use std::{borrow::Cow, fmt, time::Duration};
+use qiniu_http::RequestBody;
+use std::{
+    borrow::Cow,
+    fmt,
+    time::{Duration, Instant},
+};
 use url::Url;
 
 pub(super) struct Inner<'a> {
@@ -13,12 +18,13 @@ pub(super) struct Inner<'a> {
     pub(super) fop: Cow<'a, str>,
     pub(super) query: Vec<(Cow<'a, str>, Cow<'a, str>)>,
     pub(super) headers: Headers<'a>,
-    pub(super) body: Cow<'a, [u8]>,
+    pub(super) body: RequestBody<'a>,
     pub(super) config: Config,
     pub(super) token: Option<Token<'a>>,
     pub(super) read_body: bool,
     pub(super) idempotent: bool,
     pub(super) follow_redirection: bool,
+    pub(super) deadline: Option<Instant>,
     pub(super) on_url_constructed: Option<&'a dyn Fn(&mut Url)>,
     pub(super) on_uploading_progress: Option<&'a dyn Fn(u64, u64)>,
     pub(super) on_downloading_progress: Option<&'a dyn Fn(u64, u64)>,
@@ -41,6 +47,7 @@ impl fmt::Debug for Inner<'_> {
             .field("read_body", &self.read_body)
             .field("idempotent", &self.idempotent)
             .field("follow_redirection", &self.follow_redirection)
+            .field("deadline", &self.deadline)
             .field(
                 "on_url_constructed",
                 if self.on_url_constructed.is_some() {
@@ -3,15 +3,20 @@ mod inner;
 
 pub(crate) use builder::Builder;
 
-use super::{response::Response, Choice};
+use super::{
+    response::Response,
+    retry_policy::{DefaultRetryPolicy, RetryContext, RetryDecision, RetryPolicy},
+    Choice,
+};
 use crate::utils::mime;
+#[cfg(feature = "async")]
+use futures_timer::Delay;
 use inner::Inner;
 use qiniu_http::{
     Error as HTTPError, ErrorKind as HTTPErrorKind, HeaderName, HeaderValue, Headers, Method, Request as HTTPRequest,
     RequestBuilder, Response as HTTPResponse, ResponseBody as HTTPResponseBody, Result as HTTPResult,
     RetryKind as HTTPRetryKind, StatusCode,
 };
-use rand::{thread_rng, Rng};
 use serde::Deserialize;
 use std::{
     fmt,
@@ -29,52 +34,219 @@ pub(super) struct RequestErrorResponse {
 
 pub(crate) struct Request<'a>(Inner<'a>);
 
+enum ChoiceError {
+    SwitchHost(HTTPError),
+    Exit(HTTPError),
+}
+
+#[derive(Copy, Clone)]
+struct Timing {
+    started_at: Instant,
+    deadline: Option<Instant>,
+}
+
 impl<'a> Request<'a> {
     pub(crate) fn send(&self) -> HTTPResult<Response> {
+        let timing = self.start_timing();
         let mut prev_err: Option<HTTPError> = None;
-        let choices = self
-            .0
-            .config
-            .domains_manager()
-            .choose(self.0.base_urls)
-            .map_err(|err| {
-                HTTPError::new_host_unretryable_error(
-                    HTTPErrorKind::UnknownError(Box::new(err)),
-                    true,
-                    Some(self.0.method.to_owned()),
-                    None,
-                    None,
-                )
-            })?;
-        for choice in choices {
+        for choice in self.choices()? {
             let base_url = choice.base_url;
             let timer = Instant::now();
-            match self.try_choice(choice) {
+            match self.try_choice(choice, timing) {
                 Ok(resp) => {
                     return Ok(resp);
                 }
-                Err(err) => match err.retry_kind() {
-                    HTTPRetryKind::RetryableError | HTTPRetryKind::HostUnretryableError if self.is_retry_safe(&err) => {
-                        self.0.config.domains_manager().freeze_url(base_url).unwrap();
-                        if let Some(on_error) = &self.0.on_error {
-                            (on_error)(Some(base_url), &err, timer.elapsed());
-                        }
-                        prev_err = Some(err);
-                        continue;
+                Err(ChoiceError::SwitchHost(err)) => {
+                    self.0.config.domains_manager().freeze_url(base_url).unwrap();
+                    self.call_on_error(base_url, &err, timer);
+                    prev_err = Some(err);
+                }
+                Err(ChoiceError::Exit(err)) => {
+                    self.call_on_error(base_url, &err, timer);
+                    return Err(err);
+                }
+            }
+        }
+        Err(prev_err.unwrap())
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) async fn send_async(&self) -> HTTPResult<Response<'a>> {
+        let timing = self.start_timing();
+        let mut prev_err: Option<HTTPError> = None;
+        for choice in self.choices()? {
+            let base_url = choice.base_url;
+            let timer = Instant::now();
+            match self.try_choice_async(choice, timing).await {
+                Ok(resp) => {
+                    return Ok(resp);
+                }
+                Err(ChoiceError::SwitchHost(err)) => {
+                    self.0.config.domains_manager().freeze_url(base_url).unwrap();
+                    self.call_on_error(base_url, &err, timer);
+                    prev_err = Some(err);
+                }
+                Err(ChoiceError::Exit(err)) => {
+                    self.call_on_error(base_url, &err, timer);
+                    return Err(err);
+                }
+            }
+        }
+        Err(prev_err.unwrap())
+    }
+
+    fn choices(&self) -> HTTPResult<Vec<Choice<'a>>> {
+        self.0.config.domains_manager().choose(self.0.base_urls).map_err(|err| {
+            HTTPError::new_host_unretryable_error(
+                HTTPErrorKind::UnknownError(Box::new(err)),
+                true,
+                Some(self.0.method.to_owned()),
+                None,
+                None,
+            )
+        })
+    }
+
+    fn start_timing(&self) -> Timing {
+        let started_at = Instant::now();
+        Timing {
+            started_at,
+            deadline: self
+                .0
+                .deadline
+                .or_else(|| self.0.config.operation_timeout().map(|timeout| started_at + timeout)),
+        }
+    }
+
+    fn try_choice(&self, choice: Choice<'a>, timing: Timing) -> Result<Response<'a>, ChoiceError> {
+        let mut request = self.make_request(&choice).map_err(ChoiceError::Exit)?;
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            self.limit_timeout_by_deadline(&mut request, timing.deadline)
+                .map_err(ChoiceError::Exit)?;
+            let timer = Instant::now();
+            let result = self.do_request(&mut request);
+            match self.handle_response(result, &request, choice.base_url, timer) {
+                Ok(response) => {
+                    return Ok(response);
+                }
+                Err(err) => {
+                    let delay = self.handle_error(choice.base_url, err, attempts, timer, timing)?;
+                    if delay > Duration::from_nanos(0) {
+                        sleep(delay);
                     }
-                    _ => {
-                        if let Some(on_error) = &self.0.on_error {
-                            (on_error)(Some(base_url), &err, timer.elapsed());
-                        }
-                        return Err(err);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "async")]
+    async fn try_choice_async(&self, choice: Choice<'a>, timing: Timing) -> Result<Response<'a>, ChoiceError> {
+        let mut request = self.make_request(&choice).map_err(ChoiceError::Exit)?;
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            self.limit_timeout_by_deadline(&mut request, timing.deadline)
+                .map_err(ChoiceError::Exit)?;
+            let timer = Instant::now();
+            let result = self.do_request_async(&mut request).await;
+            match self.handle_response(result, &request, choice.base_url, timer) {
+                Ok(response) => {
+                    return Ok(response);
+                }
+                Err(err) => {
+                    let delay = self.handle_error(choice.base_url, err, attempts, timer, timing)?;
+                    if delay > Duration::from_nanos(0) {
+                        Delay::new(delay).await;
                     }
-                },
+                }
             }
         }
-        Err(prev_err.unwrap())
     }
 
-    fn try_choice(&self, choice: Choice<'a>) -> HTTPResult<Response<'a>> {
+    // 根据重试策略决定下一步操作。返回 `Ok` 表示在等待指定时长后重试当前主机。
+    // 对于可以安全重试的错误，每次请求失败都会回调 `on_error`，其他错误仅在放弃当前主机时回调一次
+    fn handle_error(
+        &self,
+        base_url: &str,
+        err: HTTPError,
+        attempts: usize,
+        timer: Instant,
+        timing: Timing,
+    ) -> Result<Duration, ChoiceError> {
+        let is_retry_safe = self.is_retry_safe(&err);
+        if err.retry_kind() == HTTPRetryKind::RetryableError && is_retry_safe {
+            self.call_on_error(base_url, &err, timer);
+        }
+        let context = RetryContext::new(&err, is_retry_safe, attempts, timing.started_at.elapsed());
+        let decision = if let Some(policy) = self.0.config.http_request_retry_policy() {
+            policy.decide(&context)
+        } else {
+            DefaultRetryPolicy::new(self.retries(), self.0.config.http_request_retry_delay()).decide(&context)
+        };
+        match decision {
+            RetryDecision::RetrySameHost(delay) if is_retry_safe => {
+                // 如果等待后已经超过截止时间，则无需等待，直接返回错误
+                if timing
+                    .deadline
+                    .map_or(false, |deadline| Instant::now() + delay >= deadline)
+                {
+                    Err(ChoiceError::Exit(self.deadline_exceeded_error(&err)))
+                } else {
+                    Ok(delay)
+                }
+            }
+            RetryDecision::SwitchHost if is_retry_safe => Err(ChoiceError::SwitchHost(err)),
+            RetryDecision::GiveUp => Err(ChoiceError::Exit(err.with_retry_kind(HTTPRetryKind::UnretryableError))),
+            _ => match err.retry_kind() {
+                HTTPRetryKind::UnretryableError => Err(ChoiceError::Exit(
+                    err.with_retry_kind(HTTPRetryKind::ZoneUnretryableError),
+                )),
+                _ => Err(ChoiceError::Exit(err)),
+            },
+        }
+    }
+
+    // 确保单次请求不会超过截止时间，如果已经超过截止时间，则直接返回错误
+    fn limit_timeout_by_deadline(&self, request: &mut HTTPRequest, deadline: Option<Instant>) -> HTTPResult<()> {
+        if let Some(deadline) = deadline {
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(HTTPError::new_unretryable_error(
+                    HTTPErrorKind::DeadlineExceeded,
+                    Some(self.0.method),
+                    Some(request.url().into()),
+                    None,
+                ));
+            }
+            let remaining = deadline - now;
+            if request.request_timeout() > remaining {
+                *request.request_timeout_mut() = remaining;
+            }
+            if request.connect_timeout() > remaining {
+                *request.connect_timeout_mut() = remaining;
+            }
+        }
+        Ok(())
+    }
+
+    fn deadline_exceeded_error(&self, err: &HTTPError) -> HTTPError {
+        HTTPError::new_unretryable_error(
+            HTTPErrorKind::DeadlineExceeded,
+            Some(self.0.method),
+            err.url().to_owned(),
+            err.request_id().to_owned(),
+        )
+    }
+
+    fn call_on_error(&self, base_url: &str, err: &HTTPError, timer: Instant) {
+        if let Some(on_error) = &self.0.on_error {
+            (on_error)(Some(base_url), err, timer.elapsed());
+        }
+    }
+
+    fn make_request<'r>(&'r self, choice: &'r Choice<'a>) -> HTTPResult<HTTPRequest<'r>> {
         let mut request = {
             let mut builder = RequestBuilder::default()
                 .method(self.0.method)
@@ -88,10 +260,16 @@ impl<'a> Request<'a> {
                 .low_transfer_speed_timeout(self.0.config.http_low_transfer_speed_timeout())
                 .follow_redirection(self.0.follow_redirection)
                 .headers(self.0.headers.to_owned())
-                .body(self.0.body.as_ref());
+                .body(self.0.body.as_borrowed());
             if !choice.socket_addrs.is_empty() {
                 builder = builder.resolved_socket_addrs(choice.socket_addrs.as_ref());
             }
+            if let Some(proxy) = self.0.config.http_proxy() {
+                builder = builder.proxy(proxy);
+            }
+            if let Some(tls_options) = self.0.config.tls_options() {
+                builder = builder.tls_options(tls_options);
+            }
             if let Some(on_uploading_progress) = self.0.on_uploading_progress {
                 builder = builder.on_uploading_progress(on_uploading_progress);
             }
@@ -103,66 +281,73 @@ impl<'a> Request<'a> {
         if let Some(token) = &self.0.token {
             token.sign(&mut request);
         }
+        Ok(request)
+    }
 
-        let mut prev_err: Option<HTTPError> = None;
+    fn retries(&self) -> usize {
         let retries = self.0.config.http_request_retries();
         assert!(retries > 0);
-        for _ in 0..=retries {
-            let timer = Instant::now();
-            match self
-                .do_request(&mut request)
-                .and_then(|response| Self::check_response(response, &request))
-                .and_then(|response| self.fulfill_body_if_needed(response, &request))
-                .map(|response| Response {
-                    inner: response,
-                    method: self.0.method,
-                    base_url: choice.base_url,
-                    path: self.0.path,
-                })
-                .and_then(|mut response| {
-                    if let Some(on_response) = &self.0.on_response {
-                        (on_response)(&mut response, timer.elapsed())?;
-                    }
-                    Ok(response)
-                }) {
-                Ok(response) => {
-                    return Ok(response);
+        retries
+    }
+
+    fn handle_response(
+        &self,
+        result: HTTPResult<HTTPResponse>,
+        request: &HTTPRequest,
+        base_url: &'a str,
+        timer: Instant,
+    ) -> HTTPResult<Response<'a>> {
+        result
+            .and_then(|response| Self::check_response(response, request))
+            .and_then(|response| self.fulfill_body_if_needed(response, request))
+            .map(|response| Response {
+                inner: response,
+                method: self.0.method,
+                base_url,
+                path: self.0.path,
+            })
+            .and_then(|mut response| {
+                if let Some(on_response) = &self.0.on_response {
+                    (on_response)(&mut response, timer.elapsed())?;
                 }
-                Err(err) => match err.retry_kind() {
-                    HTTPRetryKind::RetryableError if self.is_retry_safe(&err) => {
-                        if let Some(on_error) = &self.0.on_error {
-                            (on_error)(Some(&choice.base_url), &err, timer.elapsed());
-                        }
-                        prev_err = Some(err);
-                        let delay_nanos = self.0.config.http_request_retry_delay().as_nanos() as u64;
-                        if delay_nanos > 0 {
-                            sleep(Duration::from_nanos(
-                                thread_rng().gen_range(delay_nanos / 2, delay_nanos),
-                            ));
-                        }
-                        continue;
-                    }
-                    _ => {
-                        return Err(err);
-                    }
-                },
-            }
-        }
-        Err(prev_err.unwrap())
+                Ok(response)
+            })
     }
 
     fn do_request(&self, request: &mut HTTPRequest) -> HTTPResult<HTTPResponse> {
+        self.call_before_actions(request)?;
+        let mut response = self.0.config.http_request_handler().call(&request)?;
+        self.call_after_actions(request, &mut response)?;
+        Ok(response)
+    }
+
+    #[cfg(feature = "async")]
+    async fn do_request_async(&self, request: &mut HTTPRequest<'_>) -> HTTPResult<HTTPResponse> {
+        self.call_before_actions(request)?;
+        let mut response = if let Some(handler) = self.0.config.async_http_request_handler() {
+            handler.call(&request).await?
+        } else {
+            self.0.config.http_request_handler().call(&request)?
+        };
+        self.call_after_actions(request, &mut response)?;
+        Ok(response)
+    }
+
+    fn call_before_actions(&self, request: &mut HTTPRequest) -> HTTPResult<()> {
         for handler in self.0.config.http_request_before_action_handlers().iter() {
             handler.before_call(request)?;
         }
-        let mut response = self.0.config.http_request_handler().call(&request)?;
+        Ok(())
+    }
+
+    fn call_after_actions(&self, request: &mut HTTPRequest, response: &mut HTTPResponse) -> HTTPResult<()> {
         for handler in self.0.config.http_request_after_action_handlers().iter() {
-            handler.after_call(request, &mut response)?;
+            handler.after_call(request, response)?;
         }
         if let Some(handler) = self.0.config.http_request_final_handler() {
-            handler.after_call(request, &mut response)?;
+            handler.after_call(request, response)?;
         }
-        Ok(response)
+        Ok(())
     }
 
     fn make_url(&self, base_url: &str) -> HTTPResult<String> {
@@ -340,16 +525,23 @@ impl fmt::Debug for Request<'_> {
 
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "async")]
+    use super::super::{AsyncHTTPCaller, AsyncResponse};
     use super::{
         super::{
             super::{
                 config::{Config, ConfigBuilder},
                 credential::Credential,
             },
-            DomainsManagerBuilder, HTTPAfterAction, HTTPBeforeAction, HTTPCaller, TokenVersion,
+            DomainsManagerBuilder, ExponentialBackoffRetryPolicy, HTTPAfterAction, HTTPBeforeAction, HTTPCaller,
+            TokenVersion,
         },
         Builder, *,
     };
+    #[cfg(feature = "async")]
+    use futures::executor::block_on;
+    #[cfg(feature = "async")]
+    use qiniu_http::ResponseBuilder;
     use qiniu_test_utils::http_call_mock::{CounterCallMock, ErrorResponseMock};
     use std::{
         boxed::Box,
@@ -835,6 +1027,244 @@ mod tests {
         Ok(())
     }
 
+    struct FixedRetryPolicy(RetryDecision);
+
+    impl RetryPolicy for FixedRetryPolicy {
+        fn decide(&self, _context: &RetryContext) -> RetryDecision {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_retry_policy_give_up() -> StdResult<(), Box<dyn StdError>> {
+        let mock = CounterCallMock::new(HTTPRetryer {
+            retry_kind: HTTPRetryKind::RetryableError,
+            is_retry_safe: true,
+        });
+        let config = ConfigBuilder::default()
+            .http_request_retry_policy(FixedRetryPolicy(RetryDecision::GiveUp))
+            .http_request_handler(mock.clone())
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let err = Builder::new(
+            config.clone(),
+            Method::GET,
+            "/test_call",
+            &["http://z1h1.com:1111", "http://z1h2.com:2222"],
+        )
+        .token(TokenVersion::V2, get_credential().into())
+        .no_body()
+        .send()
+        .unwrap_err();
+        assert_eq!(err.retry_kind(), HTTPRetryKind::UnretryableError);
+        assert_eq!(mock.call_called(), 1);
+        assert!(!config.domains_manager().is_frozen_url("http://z1h1.com:1111")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_retry_policy_switch_region() -> StdResult<(), Box<dyn StdError>> {
+        let mock = CounterCallMock::new(HTTPRetryer {
+            retry_kind: HTTPRetryKind::UnretryableError,
+            is_retry_safe: true,
+        });
+        let config = ConfigBuilder::default()
+            .http_request_retry_policy(FixedRetryPolicy(RetryDecision::SwitchRegion))
+            .http_request_handler(mock.clone())
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let err = Builder::new(
+            config,
+            Method::GET,
+            "/test_call",
+            &["http://z1h1.com:1111", "http://z1h2.com:2222"],
+        )
+        .token(TokenVersion::V2, get_credential().into())
+        .no_body()
+        .send()
+        .unwrap_err();
+        assert_eq!(err.retry_kind(), HTTPRetryKind::ZoneUnretryableError);
+        assert_eq!(mock.call_called(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_retry_policy_with_unsafe_error() -> StdResult<(), Box<dyn StdError>> {
+        let mock = CounterCallMock::new(HTTPRetryer {
+            retry_kind: HTTPRetryKind::RetryableError,
+            is_retry_safe: false,
+        });
+        let config = ConfigBuilder::default()
+            .http_request_retry_policy(FixedRetryPolicy(RetryDecision::RetrySameHost(Duration::from_millis(1))))
+            .http_request_handler(mock.clone())
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let err = Builder::new(
+            config,
+            Method::POST,
+            "/test_call",
+            &["http://z1h1.com:1111", "http://z1h2.com:2222"],
+        )
+        .token(TokenVersion::V2, get_credential().into())
+        .raw_body(mime::JSON_MIME.into(), b"{\"test\":123}".as_ref().into())
+        .send()
+        .unwrap_err();
+        assert_eq!(err.retry_kind(), HTTPRetryKind::RetryableError);
+        assert_eq!(mock.call_called(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_exponential_backoff_retry_policy_with_budget() -> StdResult<(), Box<dyn StdError>> {
+        let mock = CounterCallMock::new(HTTPRetryer {
+            retry_kind: HTTPRetryKind::RetryableError,
+            is_retry_safe: true,
+        });
+        let config = ConfigBuilder::default()
+            .http_request_retry_policy(
+                ExponentialBackoffRetryPolicy::new(
+                    usize::max_value(),
+                    Duration::from_millis(1),
+                    Duration::from_millis(5),
+                )
+                .set_total_budget(Duration::from_millis(100)),
+            )
+            .http_request_handler(mock.clone())
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let timer = Instant::now();
+        let err = Builder::new(
+            config.clone(),
+            Method::GET,
+            "/test_call",
+            &["http://z1h1.com:1111", "http://z1h2.com:2222"],
+        )
+        .token(TokenVersion::V2, get_credential().into())
+        .no_body()
+        .send()
+        .unwrap_err();
+        assert!(timer.elapsed() < Duration::from_secs(1));
+        assert_eq!(err.retry_kind(), HTTPRetryKind::UnretryableError);
+        assert!(mock.call_called() > 1);
+        assert!(!config.domains_manager().is_frozen_url("http://z1h1.com:1111")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_operation_timeout() -> StdResult<(), Box<dyn StdError>> {
+        let mock = CounterCallMock::new(HTTPRetryer {
+            retry_kind: HTTPRetryKind::RetryableError,
+            is_retry_safe: true,
+        });
+        let config = ConfigBuilder::default()
+            .http_request_retries(RETRIES)
+            .http_request_retry_delay(Duration::from_millis(100))
+            .operation_timeout(Duration::from_millis(200))
+            .http_request_handler(mock.clone())
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let timer = Instant::now();
+        let err = Builder::new(
+            config,
+            Method::GET,
+            "/test_call",
+            &["http://z1h1.com:1111", "http://z1h2.com:2222"],
+        )
+        .token(TokenVersion::V2, get_credential().into())
+        .no_body()
+        .send()
+        .unwrap_err();
+        assert!(timer.elapsed() < Duration::from_millis(500));
+        assert!(matches!(err.error_kind(), HTTPErrorKind::DeadlineExceeded));
+        assert_eq!(err.retry_kind(), HTTPRetryKind::UnretryableError);
+        assert!(mock.call_called() < RETRIES + 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_request_deadline() -> StdResult<(), Box<dyn StdError>> {
+        let mock = CounterCallMock::new(HTTPRetryer {
+            retry_kind: HTTPRetryKind::RetryableError,
+            is_retry_safe: true,
+        });
+        let config = ConfigBuilder::default()
+            .operation_timeout(Duration::from_secs(60))
+            .http_request_handler(mock.clone())
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let err = Builder::new(config, Method::GET, "/test_call", &["http://z1h1.com:1111"])
+            .token(TokenVersion::V2, get_credential().into())
+            .deadline(Some(Instant::now()))
+            .no_body()
+            .send()
+            .unwrap_err();
+        assert!(matches!(err.error_kind(), HTTPErrorKind::DeadlineExceeded));
+        assert_eq!(mock.call_called(), 0);
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    struct AsyncHTTPRetryer {
+        called: Arc<AtomicUsize>,
+    }
+
+    #[cfg(feature = "async")]
+    impl AsyncHTTPCaller for AsyncHTTPRetryer {
+        fn call<'a>(&'a self, request: &HTTPRequest) -> AsyncResponse<'a> {
+            assert!(request.headers().contains_key(&"authorization".into()));
+            Box::pin(async move {
+                if self.called.fetch_add(1, Relaxed) == 0 {
+                    Err(HTTPError::new(
+                        HTTPRetryKind::RetryableError,
+                        HTTPErrorKind::IOError(io::Error::new(io::ErrorKind::Other, "Test Error")),
+                        true,
+                        None,
+                        None,
+                        None,
+                    ))
+                } else {
+                    Ok(ResponseBuilder::default()
+                        .status_code(200u16)
+                        .bytes_as_body(b"{}".to_vec())
+                        .build())
+                }
+            })
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_send_async_with_retryable_error() -> StdResult<(), Box<dyn StdError>> {
+        let mock = CounterCallMock::new(HTTPRetryer {
+            retry_kind: HTTPRetryKind::UnretryableError,
+            is_retry_safe: false,
+        });
+        let called = Arc::new(AtomicUsize::new(0));
+        let config = ConfigBuilder::default()
+            .http_request_retries(RETRIES)
+            .http_request_retry_delay(Duration::from_millis(1))
+            .http_request_handler(mock.clone())
+            .async_http_request_handler(AsyncHTTPRetryer { called: called.clone() })
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let on_error_called = AtomicUsize::new(0);
+        let on_error = |_: Option<&str>, _: &HTTPError, _: Duration| {
+            on_error_called.fetch_add(1, Relaxed);
+        };
+        let request = Builder::new(config.clone(), Method::GET, "/test_call", &["http://z1h1.com:1111"])
+            .token(TokenVersion::V2, get_credential().into())
+            .on_error(&on_error)
+            .accept_json()
+            .no_body();
+        let response = block_on(request.send_async())?;
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(called.load(Relaxed), 2);
+        assert_eq!(on_error_called.load(Relaxed), 1);
+        assert_eq!(mock.call_called(), 0);
+        assert!(!config.domains_manager().is_frozen_url("http://z1h1.com:1111")?);
+        Ok(())
+    }
+
     fn get_credential() -> Credential {
         Credential::new("abcdefghklmnopq", "1234567890")
     }
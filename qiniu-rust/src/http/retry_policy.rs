@@ -0,0 +1,287 @@
+//! HTTP 重试策略模块
+//!
+//! 决定 HTTP 请求发生错误后，是在当前主机上重试，切换到下一个主机重试，切换区域重试，还是放弃重试
+
+use getset::CopyGetters;
+use qiniu_http::{Error as HTTPError, RetryKind};
+use rand::{thread_rng, Rng};
+use std::{cmp::min, time::Duration};
+
+/// 重试决策
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RetryDecision {
+    /// 等待指定时长后，在当前主机上重试
+    RetrySameHost(Duration),
+
+    /// 冻结当前主机，切换到下一个主机重试
+    ///
+    /// 如果已经没有可用的主机，则返回当前错误
+    SwitchHost,
+
+    /// 放弃当前区域，返回当前错误，由上层逻辑（例如上传）切换到下一个区域重试
+    ///
+    /// 如果错误原本不可重试，则会被修正为区域不可重试错误
+    SwitchRegion,
+
+    /// 放弃重试，返回当前错误
+    ///
+    /// 错误将被修正为不可重试错误，上层逻辑也不会再重试
+    GiveUp,
+}
+
+/// 重试上下文
+///
+/// 提供给重试策略用于做出决策的信息
+#[derive(CopyGetters)]
+pub struct RetryContext<'a> {
+    /// HTTP 请求错误
+    #[get_copy = "pub"]
+    error: &'a HTTPError,
+
+    /// 是否重试安全
+    ///
+    /// 综合考虑了 HTTP 请求方法，请求是否幂等和错误本身是否重试安全。
+    /// 对于重试不安全的错误，无论重试策略做出何种决策，都不会在当前区域内重试
+    #[get_copy = "pub"]
+    is_retry_safe: bool,
+
+    /// 在当前主机上已经尝试的次数，包含本次尝试，从 1 开始计数
+    #[get_copy = "pub"]
+    attempts: usize,
+
+    /// 从开始发送请求到现在所经过的时长，包含在所有主机上的尝试耗时
+    #[get_copy = "pub"]
+    elapsed: Duration,
+}
+
+impl<'a> RetryContext<'a> {
+    pub(crate) fn new(error: &'a HTTPError, is_retry_safe: bool, attempts: usize, elapsed: Duration) -> Self {
+        Self {
+            error,
+            is_retry_safe,
+            attempts,
+            elapsed,
+        }
+    }
+
+    /// 错误的可重试类型
+    pub fn retry_kind(&self) -> RetryKind {
+        self.error.retry_kind()
+    }
+}
+
+/// HTTP 重试策略
+///
+/// 实现该接口，即可自定义七牛 Rust SDK 发送 HTTP 请求时的重试策略
+pub trait RetryPolicy: Send + Sync {
+    /// 根据重试上下文做出重试决策
+    fn decide(&self, context: &RetryContext) -> RetryDecision;
+}
+
+/// 默认重试策略
+///
+/// 对于可重试错误，在当前主机上重试 `retries` 次，每次等待 `retry_delay` 的 50% - 100% 之间的随机时长，然后切换到下一个主机。
+/// 对于主机不可重试错误，直接切换到下一个主机。
+/// 对于区域不可重试错误，切换区域，而不可重试错误则放弃重试
+#[derive(Copy, Clone, Debug)]
+pub struct DefaultRetryPolicy {
+    retries: usize,
+    retry_delay: Duration,
+}
+
+impl DefaultRetryPolicy {
+    /// 创建默认重试策略
+    pub fn new(retries: usize, retry_delay: Duration) -> Self {
+        Self { retries, retry_delay }
+    }
+}
+
+impl RetryPolicy for DefaultRetryPolicy {
+    fn decide(&self, context: &RetryContext) -> RetryDecision {
+        match context.retry_kind() {
+            RetryKind::RetryableError if context.is_retry_safe() && context.attempts() <= self.retries => {
+                let delay_nanos = self.retry_delay.as_nanos() as u64;
+                if delay_nanos > 0 {
+                    RetryDecision::RetrySameHost(Duration::from_nanos(
+                        thread_rng().gen_range(delay_nanos / 2, delay_nanos),
+                    ))
+                } else {
+                    RetryDecision::RetrySameHost(Duration::from_nanos(0))
+                }
+            }
+            RetryKind::RetryableError | RetryKind::HostUnretryableError if context.is_retry_safe() => {
+                RetryDecision::SwitchHost
+            }
+            RetryKind::UnretryableError => RetryDecision::GiveUp,
+            _ => RetryDecision::SwitchRegion,
+        }
+    }
+}
+
+/// 指数退避重试策略
+///
+/// 对于可重试错误，在当前主机上最多重试 `retries` 次，第 N 次重试前等待 0 至 `min(max_delay, base_delay * 2 ^ (N - 1))` 之间的随机时长（即 Full Jitter 算法），
+/// 然后切换到下一个主机。
+///
+/// 如果设置了总时长预算，则当请求总耗时加上下次等待时长超过预算时，放弃重试
+#[derive(Copy, Clone, Debug)]
+pub struct ExponentialBackoffRetryPolicy {
+    retries: usize,
+    base_delay: Duration,
+    max_delay: Duration,
+    total_budget: Option<Duration>,
+}
+
+impl ExponentialBackoffRetryPolicy {
+    /// 创建指数退避重试策略
+    pub fn new(retries: usize, base_delay: Duration, max_delay: Duration) -> Self {
+        Self {
+            retries,
+            base_delay,
+            max_delay,
+            total_budget: None,
+        }
+    }
+
+    /// 设置总时长预算
+    pub fn set_total_budget(mut self, total_budget: Duration) -> Self {
+        self.total_budget = Some(total_budget);
+        self
+    }
+
+    fn backoff(&self, attempts: usize) -> Duration {
+        let exponent = min(attempts.saturating_sub(1), 31) as u32;
+        let cap = self
+            .base_delay
+            .checked_mul(1 << exponent)
+            .map_or(self.max_delay, |delay| min(delay, self.max_delay));
+        let cap_nanos = cap.as_nanos() as u64;
+        if cap_nanos > 0 {
+            Duration::from_nanos(thread_rng().gen_range(0, cap_nanos + 1))
+        } else {
+            Duration::from_nanos(0)
+        }
+    }
+
+    fn is_over_budget(&self, elapsed: Duration) -> bool {
+        self.total_budget.map_or(false, |budget| elapsed >= budget)
+    }
+}
+
+impl RetryPolicy for ExponentialBackoffRetryPolicy {
+    fn decide(&self, context: &RetryContext) -> RetryDecision {
+        if self.is_over_budget(context.elapsed()) {
+            return RetryDecision::GiveUp;
+        }
+        match context.retry_kind() {
+            RetryKind::RetryableError if context.is_retry_safe() && context.attempts() <= self.retries => {
+                let delay = self.backoff(context.attempts());
+                if self.is_over_budget(context.elapsed() + delay) {
+                    RetryDecision::GiveUp
+                } else {
+                    RetryDecision::RetrySameHost(delay)
+                }
+            }
+            RetryKind::RetryableError | RetryKind::HostUnretryableError if context.is_retry_safe() => {
+                RetryDecision::SwitchHost
+            }
+            RetryKind::UnretryableError => RetryDecision::GiveUp,
+            _ => RetryDecision::SwitchRegion,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qiniu_http::ErrorKind as HTTPErrorKind;
+    use std::{error::Error, io, result::Result};
+
+    fn make_error(retry_kind: RetryKind) -> HTTPError {
+        HTTPError::new(
+            retry_kind,
+            HTTPErrorKind::IOError(io::Error::new(io::ErrorKind::Other, "Test Error")),
+            true,
+            None,
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_default_retry_policy() -> Result<(), Box<dyn Error>> {
+        let policy = DefaultRetryPolicy::new(2, Duration::from_millis(100));
+        let err = make_error(RetryKind::RetryableError);
+        for attempts in 1..=2 {
+            match policy.decide(&RetryContext::new(&err, true, attempts, Duration::from_secs(0))) {
+                RetryDecision::RetrySameHost(delay) => {
+                    assert!(delay >= Duration::from_millis(50));
+                    assert!(delay < Duration::from_millis(100));
+                }
+                decision => panic!("Unexpected decision: {:?}", decision),
+            }
+        }
+        assert_eq!(
+            policy.decide(&RetryContext::new(&err, true, 3, Duration::from_secs(0))),
+            RetryDecision::SwitchHost
+        );
+        assert_eq!(
+            policy.decide(&RetryContext::new(&err, false, 1, Duration::from_secs(0))),
+            RetryDecision::SwitchRegion
+        );
+
+        let err = make_error(RetryKind::HostUnretryableError);
+        assert_eq!(
+            policy.decide(&RetryContext::new(&err, true, 1, Duration::from_secs(0))),
+            RetryDecision::SwitchHost
+        );
+        let err = make_error(RetryKind::ZoneUnretryableError);
+        assert_eq!(
+            policy.decide(&RetryContext::new(&err, true, 1, Duration::from_secs(0))),
+            RetryDecision::SwitchRegion
+        );
+        let err = make_error(RetryKind::UnretryableError);
+        assert_eq!(
+            policy.decide(&RetryContext::new(&err, true, 1, Duration::from_secs(0))),
+            RetryDecision::GiveUp
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_exponential_backoff_retry_policy() -> Result<(), Box<dyn Error>> {
+        let policy = ExponentialBackoffRetryPolicy::new(10, Duration::from_millis(100), Duration::from_secs(1))
+            .set_total_budget(Duration::from_secs(10));
+        let err = make_error(RetryKind::RetryableError);
+        for attempts in 1..=10 {
+            let max_delay = min(Duration::from_millis(100 << (attempts - 1)), Duration::from_secs(1));
+            match policy.decide(&RetryContext::new(&err, true, attempts, Duration::from_secs(0))) {
+                RetryDecision::RetrySameHost(delay) => assert!(delay <= max_delay),
+                decision => panic!("Unexpected decision: {:?}", decision),
+            }
+        }
+        assert_eq!(
+            policy.decide(&RetryContext::new(&err, true, 11, Duration::from_secs(0))),
+            RetryDecision::SwitchHost
+        );
+        assert_eq!(
+            policy.decide(&RetryContext::new(&err, true, 1, Duration::from_secs(10))),
+            RetryDecision::GiveUp
+        );
+        assert_eq!(
+            policy.decide(&RetryContext::new(&err, true, 100, Duration::from_secs(10))),
+            RetryDecision::GiveUp
+        );
+
+        let err = make_error(RetryKind::HostUnretryableError);
+        assert_eq!(
+            policy.decide(&RetryContext::new(&err, true, 1, Duration::from_secs(1))),
+            RetryDecision::SwitchHost
+        );
+        assert_eq!(
+            policy.decide(&RetryContext::new(&err, false, 1, Duration::from_secs(1))),
+            RetryDecision::SwitchRegion
+        );
+        Ok(())
+    }
+}
@@ -28,7 +28,7 @@ impl<'t> Token<'t> {
                 if let Ok(authorization) = self.credential.authorization_v1_for_request(
                     &url,
                     req.headers().get(&"Content-Type".into()).unwrap_or(&"".into()),
-                    req.body().as_ref(),
+                    &req.body().to_bytes(),
                 ) {
                     req.headers_mut().insert("Authorization".into(), authorization.into());
                 }
@@ -36,7 +36,7 @@ impl<'t> Token<'t> {
             Version::V2 => {
                 if let Ok(authorization) =
                     self.credential
-                        .authorization_v2_for_request(method, &url, req.headers(), req.body().as_ref())
+                        .authorization_v2_for_request(method, &url, req.headers(), &req.body().to_bytes())
                 {
                     req.headers_mut().insert("Authorization".into(), authorization.into());
                 }
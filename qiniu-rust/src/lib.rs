@@ -479,7 +479,7 @@
 //!
 //! ### 文件上传策略
 //!
-//! 默认情况下，对于尺寸大于 4 MB 的文件，SDK 默认自动使用分片上传的方式来上传，分片上传通过将一个文件切割为标准的块（默认的固定大小为 4 MB，可以通过修改配置增加尺寸，但必须是 4 MB 的倍数），然后通过上传块的方式来进行文件的上传。一个块中的片和另外一个块中的片是可以并发的。分片上传不等于断点续传，但是分片上传可以支持断点续传。
+//! 默认情况下，对于尺寸大于 4 MB 的文件，SDK 默认自动使用分片上传的方式来上传，分片上传通过将一个文件切割为标准的块（默认的固定大小为 4 MB，可以通过修改配置调整为 1 MB 到 1 GB 之间的任意尺寸，也可以启用自适应分片尺寸），然后通过上传块的方式来进行文件的上传。一个块中的片和另外一个块中的片是可以并发的。分片上传不等于断点续传，但是分片上传可以支持断点续传。
 //!
 //! 断点续传是将每个块上传完毕的返回的 `context` 保存到本地的文件中持久化，如果本次上传被中断，下次可以从这个进度文件中读取每个块上传的状态，然后继续上传完毕没有完成的块，最后完成文件的拼接。
 //!
@@ -583,6 +583,12 @@
 //! 该实现使用 `request` 的 `custom_data` 字段，该字段是瘦指针类型，支持从请求前回调函数传输数据到响应后回调函数，由于传输的数据比较简单，可以直接填入 `custom_data` 字段。
 //! 如果传输的数据比较复杂，则必须使用 `Box` 将数据封装为指针。
 //!
+//! ## 异步接口
+//!
+//! 开启 `async` 功能后，SDK 将提供部分接口的异步版本，例如 `Object::get_info_async`，`Bucket::is_private_async`，以及 `ObjectUploader::upload_file_async`。
+//! 异步接口与同步接口共享相同的重试与域名切换逻辑，并通过 `ConfigBuilder::async_http_request_handler` 设置的 [`AsyncHTTPCaller`](http/trait.AsyncHTTPCaller.html) 发送 HTTP 请求。
+//! 由于请求中包含不可跨线程的回调函数，异步接口返回的 Future 没有实现 `Send`，因此需要在当前线程内等待其完成。
+//!
 //! ## 私有云配置
 //!
 //! 默认情况下，Rust SDK 内置了七牛公有云存储的配置。如果需要使用七牛私有云，则需要对 `Config` 中的配置作出必要的调整，这里给出一个例子：
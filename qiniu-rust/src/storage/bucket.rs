@@ -1,7 +1,10 @@
 //! 存储空间模块
 
 use super::{
+    directory_syncer::{self, SyncDirectoryOptions, SyncDirectoryReport, SyncDirectoryResult},
+    lister::ListIter,
     object::Object,
+    prefix_downloader::{self, DownloadPrefixReport, DownloadPrefixResult},
     region::{Region, RegionId},
     uploader::{BatchUploader, ObjectUploader, UploadManager, UploadToken},
 };
@@ -18,6 +21,7 @@ use std::{
     borrow::{Borrow, Cow},
     ffi::c_void,
     iter::Iterator,
+    path::Path,
     result::Result,
     sync::Arc,
 };
@@ -38,6 +42,7 @@ struct BucketInner {
     backup_regions: OnceCell<Box<[Cow<'static, Region>]>>,
     domains: OnceCell<Box<[Cow<'static, str>]>>,
     rs_urls: OnceCell<Box<[String]>>,
+    rsf_urls: OnceCell<Box<[String]>>,
     http_client: Client,
     bucket_info: OnceCell<BucketInfo>,
 }
@@ -229,6 +234,7 @@ impl BucketBuilder {
             backup_regions,
             domains,
             rs_urls: OnceCell::new(),
+            rsf_urls: OnceCell::new(),
             bucket_info: OnceCell::new(),
         }))
     }
@@ -300,6 +306,57 @@ impl Bucket {
         Ok(domains.iter().map(|domain| domain.as_ref()).collect())
     }
 
+    /// 异步获取存储空间区域
+    ///
+    /// 需要开启 `async` 功能。
+    /// 如果区域在存储空间生成前未指定，则该方法可能会连接七牛服务器查询当前存储空间所在区域
+    #[cfg(feature = "async")]
+    pub async fn region_async(&self) -> HTTPResult<&Region> {
+        if let Some(region) = self.0.region.get() {
+            return Ok(region.as_ref());
+        }
+        let mut regions: Vec<Region> =
+            Region::query_async(self.name(), self.credential().access_key(), self.config().clone())
+                .await?
+                .into();
+        let first_region = Cow::Owned(regions.swap_remove(0));
+        self.0
+            .backup_regions
+            .get_or_init(|| regions.into_iter().map(Cow::Owned).collect());
+        Ok(self.0.region.get_or_init(|| first_region).as_ref())
+    }
+
+    /// 异步获取存储空间区域迭代器
+    ///
+    /// 需要开启 `async` 功能。
+    /// 如果区域在存储空间生成前未指定，则该方法可能会连接七牛服务器查询当前存储空间所在区域和备用区域
+    #[cfg(feature = "async")]
+    pub async fn regions_async<'a>(&'a self) -> HTTPResult<BucketRegionIter<'a>> {
+        self.region_async().await?;
+        Ok(BucketRegionIter {
+            bucket: self,
+            itered: 0,
+        })
+    }
+
+    /// 异步获取存储空间下载域名列表
+    ///
+    /// 需要开启 `async` 功能。
+    /// 如果下载域名在存储空间生成前未指定，则该方法可能会连接七牛服务器查询当前存储空间下载域名列表
+    #[cfg(feature = "async")]
+    pub async fn domains_async(&self) -> HTTPResult<Vec<&str>> {
+        let domains = match self.0.domains.get() {
+            Some(domains) => domains,
+            None => {
+                let domains = domain::query_async(&self.0.http_client, &self.0.credential, self.name()).await?;
+                self.0
+                    .domains
+                    .get_or_init(|| domains.into_iter().map(Cow::Owned).collect())
+            }
+        };
+        Ok(domains.iter().map(|domain| domain.as_ref()).collect())
+    }
+
     pub(super) fn get_domain_and_backup_domains(&self) -> DomainsResult<(&str, Vec<&str>)> {
         let mut domains = self.domains()?;
         match domains.pop() {
@@ -322,6 +379,60 @@ impl Bucket {
             )
     }
 
+    /// 异步创建面向该存储区域的对象上传器
+    ///
+    /// 需要开启 `async` 功能。
+    /// 与 `uploader` 不同，查询存储空间所在区域时不会阻塞当前线程
+    #[cfg(feature = "async")]
+    pub async fn uploader_async(&self) -> ObjectUploader<'_> {
+        let regions = self.regions_async().await.ok();
+        self.upload_manager()
+            .upload_for_internal_generated_upload_token_with_regions(
+                self.0.name.to_owned(),
+                UploadToken::new_from_bucket(self.0.name.to_owned(), self.0.credential.to_owned(), self.config())
+                    .into(),
+                regions,
+            )
+    }
+
+    /// 列举存储空间中指定前缀的对象
+    ///
+    /// 返回的迭代器将按需分批向七牛服务器请求对象列表，`prefix` 为空时将列举存储空间中的全部对象
+    pub fn list_objects<'b>(&'b self, prefix: impl Into<Cow<'b, str>>) -> ListIter<'b> {
+        ListIter::new(self, prefix.into())
+    }
+
+    /// 将本地目录单向同步到存储空间
+    ///
+    /// 本地目录中的文件将以 `prefix` 加上文件相对路径作为对象名称上传，路径总是以 `/` 分隔。
+    /// 对于存储空间中已经存在且 Hash 值与本地文件 Etag 一致的对象，将跳过上传。
+    /// 需要注意的是，使用分片上传 V2 且分片尺寸不为 4 MB 上传的对象，其 Hash 值并非标准的 Etag，这些对象总是会被重新上传。
+    ///
+    /// 单个对象上传或删除失败，或本地文件或子目录无法读取，都不会中止同步，错误将记录在返回的同步报告中
+    pub fn sync_directory(
+        &self,
+        local_dir: impl AsRef<Path>,
+        prefix: impl AsRef<str>,
+        options: SyncDirectoryOptions,
+    ) -> SyncDirectoryResult<SyncDirectoryReport> {
+        directory_syncer::sync_directory(self, local_dir.as_ref(), prefix.as_ref(), options)
+    }
+
+    /// 将存储空间中指定前缀的对象下载到本地目录
+    ///
+    /// 对象将以去掉 `prefix` 后的名称作为相对路径保存在 `local_dir` 中，并使用线程池并行下载。
+    /// 每个对象下载完毕后都将校验 Etag，因此使用分片上传 V2 且分片尺寸不为 4 MB 上传的对象将会下载失败。
+    ///
+    /// 下载状态将保存在本地目录的状态文件中，重新执行时，本地文件已经是最新的对象将被跳过。
+    /// 单个对象下载失败不会中止下载，错误将记录在返回的下载报告中
+    pub fn download_prefix(
+        &self,
+        prefix: impl AsRef<str>,
+        local_dir: impl AsRef<Path>,
+    ) -> DownloadPrefixResult<DownloadPrefixReport> {
+        prefix_downloader::download_prefix(self, prefix.as_ref(), local_dir.as_ref())
+    }
+
     /// 创建面向该存储区域的批量上传器
     pub fn batch_uploader(&self) -> BatchUploader {
         BatchUploader::new_for_bucket(self.to_owned())
@@ -332,22 +443,20 @@ impl Bucket {
         self.get_bucket_info().map(|info| info.private != 0)
     }
 
+    /// 异步判断存储空间是否是私有的
+    ///
+    /// 需要开启 `async` 功能
+    #[cfg(feature = "async")]
+    pub async fn is_private_async(&self) -> HTTPResult<bool> {
+        self.get_bucket_info_async().await.map(|info| info.private != 0)
+    }
+
     fn get_bucket_info(&self) -> HTTPResult<&BucketInfo> {
         self.0.bucket_info.get_or_try_init(|| {
             let bucket_info: BucketInfo = self
                 .0
                 .http_client
-                .get(
-                    "/v2/bucketInfo",
-                    &self
-                        .0
-                        .http_client
-                        .config()
-                        .uc_urls()
-                        .iter()
-                        .map(|url| url.as_ref())
-                        .collect::<Vec<_>>(),
-                )
+                .get("/v2/bucketInfo", &self.uc_urls())
                 .query("bucket".into(), self.name().into())
                 .token(TokenVersion::V2, self.0.credential.borrow().into())
                 .no_body()
@@ -357,6 +466,34 @@ impl Bucket {
         })
     }
 
+    #[cfg(feature = "async")]
+    async fn get_bucket_info_async(&self) -> HTTPResult<&BucketInfo> {
+        if let Some(bucket_info) = self.0.bucket_info.get() {
+            return Ok(bucket_info);
+        }
+        let bucket_info: BucketInfo = self
+            .0
+            .http_client
+            .get("/v2/bucketInfo", &self.uc_urls())
+            .query("bucket".into(), self.name().into())
+            .token(TokenVersion::V2, self.0.credential.borrow().into())
+            .no_body()
+            .send_async()
+            .await?
+            .parse_json()?;
+        Ok(self.0.bucket_info.get_or_init(|| bucket_info))
+    }
+
+    fn uc_urls(&self) -> Vec<&str> {
+        self.0
+            .http_client
+            .config()
+            .uc_urls()
+            .iter()
+            .map(|url| url.as_ref())
+            .collect()
+    }
+
     pub(super) fn rs_urls(&self) -> Vec<&str> {
         self.0
             .rs_urls
@@ -382,6 +519,26 @@ impl Bucket {
             .collect()
     }
 
+    pub(super) fn rsf_urls(&self) -> Vec<&str> {
+        self.0
+            .rsf_urls
+            .get_or_init(|| {
+                self.region()
+                    .map(|region| {
+                        region
+                            .rsf_urls_ref(self.config().use_https())
+                            .into_iter()
+                            .map(|url| url.to_owned())
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default()
+                    .into_boxed_slice()
+            })
+            .iter()
+            .map(|url| url.as_str())
+            .collect()
+    }
+
     #[inline]
     pub(crate) fn upload_manager(&self) -> &UploadManager {
         &self.0.upload_manager
@@ -489,6 +646,41 @@ mod domain {
         Ok(domains)
     }
 
+    /// 与 `query` 共享同一份缓存
+    #[cfg(feature = "async")]
+    pub(super) async fn query_async(
+        http_client: &Client,
+        credential: &Credential,
+        bucket_name: &str,
+    ) -> Result<Vec<String>> {
+        let cache_key = QueryCacheKey::new(credential, bucket_name);
+        if let Some(cache_entry) = QUERY_CACHE.get(&cache_key) {
+            return Ok(cache_entry.data().to_owned());
+        }
+        let domains: Vec<String> = http_client
+            .get(
+                "/v6/domain/list",
+                &http_client
+                    .config()
+                    .api_urls()
+                    .iter()
+                    .map(|url| url.as_ref())
+                    .collect::<Vec<_>>(),
+            )
+            .query("tbl".into(), bucket_name.into())
+            .token(TokenVersion::V2, credential.borrow().into())
+            .no_body()
+            .send_async()
+            .await?
+            .parse_json()?;
+        QUERY_CACHE.insert(
+            cache_key,
+            domains.to_owned(),
+            SystemTime::now() + Duration::from_secs(24 * 60 * 60),
+        );
+        Ok(domains)
+    }
+
     #[cfg(test)]
     pub(super) fn clear_query_cache() {
         QUERY_CACHE.clear();
@@ -530,6 +722,8 @@ mod tests {
         credential::Credential,
         http::{DomainsManagerBuilder, HeadersOwned, PanickedHTTPCaller},
     };
+    #[cfg(feature = "async")]
+    use futures::executor::block_on;
     use qiniu_test_utils::http_call_mock::{CounterCallMock, JSONCallMock};
     use serde_json::json;
     use std::{boxed::Box, error::Error, result::Result, sync::Arc, thread};
@@ -774,6 +968,105 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_storage_bucket_query_region_async() -> Result<(), Box<dyn Error>> {
+        clear_query_cache();
+
+        let mock = CounterCallMock::new(JSONCallMock::new(
+            200,
+            HeadersOwned::new(),
+            json!({
+                "hosts": [{
+                    "io": { "src": { "main": [ "iovip.qbox.me" ] } },
+                    "up": {
+                        "acc": { "backup": [ "upload-jjh.qiniup.com", "upload-xs.qiniup.com" ], "main": [ "upload.qiniup.com" ] },
+                        "old_acc": { "info": "compatible to non-SNI device", "main": [ "upload.qbox.me" ] },
+                        "old_src": { "info": "compatible to non-SNI device", "main": [ "up.qbox.me" ] },
+                        "src": { "backup": [ "up-jjh.qiniup.com", "up-xs.qiniup.com" ], "main": [ "up.qiniup.com" ] }
+                    },
+                    "rs": { "acc": { "main": [ "rs.qbox.me" ] } },
+                    "rsf": { "acc": { "main": [ "rsf.qbox.me" ] } },
+                    "api": { "acc": { "main": [ "api.qiniu.com" ] } }
+                },{
+                    "io": { "src": { "main": [ "iovip-z2.qbox.me" ] } },
+                    "up": {
+                        "acc": { "backup": [ "upload-jjh-z2.qiniup.com", "upload-xs-z2.qiniup.com" ], "main": [ "upload-z2.qiniup.com" ] },
+                        "old_acc": { "info": "compatible to non-SNI device", "main": [ "upload-z2.qbox.me" ] },
+                        "old_src": { "info": "compatible to non-SNI device", "main": [ "up-z2.qbox.me" ] },
+                        "src": { "backup": [ "up-jjh-z2.qiniup.com", "up-xs-z2.qiniup.com" ], "main": [ "up-z2.qiniup.com" ] }
+                    },
+                    "rs": { "acc": { "main": [ "rs-z2.qbox.me" ] } },
+                    "rsf": { "acc": { "main": [ "rsf-z2.qbox.me" ] } },
+                    "api": { "acc": { "main": [ "api-z2.qiniu.com" ] } }
+                }]
+            }),
+        ));
+        let bucket = BucketBuilder::new(
+            "test-bucket".into(),
+            get_credential(),
+            UploadManager::new(
+                ConfigBuilder::default()
+                    .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+                    .http_request_handler(mock.clone())
+                    .build(),
+            ),
+        )
+        .build();
+        assert_eq!(mock.call_called(), 0);
+
+        assert!(block_on(bucket.region_async())?
+            .up_urls_ref(true)
+            .contains(&"https://up-jjh.qiniup.com"));
+        let regions = block_on(bucket.regions_async())?.collect::<Vec<_>>();
+        assert_eq!(regions.len(), 2);
+        assert!(regions
+            .get(1)
+            .unwrap()
+            .up_urls_ref(true)
+            .contains(&"https://up-jjh-z2.qiniup.com"));
+        assert!(bucket.region()?.up_urls_ref(true).contains(&"https://up.qiniup.com"));
+        assert_eq!(mock.call_called(), 1);
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_storage_bucket_query_domain_async() -> Result<(), Box<dyn Error>> {
+        clear_query_cache();
+
+        let mock = CounterCallMock::new(JSONCallMock::new(
+            200,
+            HeadersOwned::new(),
+            json!(["abc.com", "def.com"]),
+        ));
+        let new_bucket = || {
+            BucketBuilder::new(
+                "test-bucket".into(),
+                get_credential(),
+                UploadManager::new(
+                    ConfigBuilder::default()
+                        .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+                        .http_request_handler(mock.clone())
+                        .build(),
+                ),
+            )
+            .build()
+        };
+        let bucket = new_bucket();
+        assert_eq!(mock.call_called(), 0);
+        assert!(block_on(bucket.domains_async())?.contains(&"abc.com"));
+        assert!(block_on(bucket.domains_async())?.contains(&"def.com"));
+        assert_eq!(mock.call_called(), 1);
+
+        // 不同的存储空间实例之间共享查询缓存
+        let bucket = new_bucket();
+        assert!(bucket.domains()?.contains(&"abc.com"));
+        assert!(block_on(bucket.domains_async())?.contains(&"def.com"));
+        assert_eq!(mock.call_called(), 1);
+        Ok(())
+    }
+
     #[test]
     fn test_storage_bucket_set_domain() -> Result<(), Box<dyn Error>> {
         clear_query_cache();
@@ -940,6 +1233,25 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_storage_bucket_is_private_async() -> Result<(), Box<dyn Error>> {
+        let bucket = BucketBuilder::new(
+            "test-bucket".into(),
+            get_credential(),
+            UploadManager::new(
+                ConfigBuilder::default()
+                    .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+                    .http_request_handler(JSONCallMock::new(200, HeadersOwned::new(), json!({"private": 0})))
+                    .build(),
+            ),
+        )
+        .build();
+        assert!(!block_on(bucket.is_private_async())?);
+        assert!(!bucket.is_private()?);
+        Ok(())
+    }
+
     fn get_credential() -> Credential {
         Credential::new("abcdefghklmnopq", "1234567890")
     }
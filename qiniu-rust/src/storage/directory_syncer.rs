@@ -0,0 +1,620 @@
+//! 目录同步模块
+//!
+//! 将本地目录单向同步到存储空间，仅上传发生变化的文件，并可以删除存储空间中多余的对象
+
+use super::{
+    bucket::Bucket,
+    uploader::{BatchUploadJobBuilder, UploadError, UploadPolicyBuilder, UploadResult, UploadToken},
+};
+use crate::{
+    http::{Error as HTTPError, ErrorKind as HTTPErrorKind},
+    utils::etag,
+};
+use matches::matches;
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{metadata, read_dir},
+    io::{Error as IOError, ErrorKind as IOErrorKind, Result as IOResult},
+    mem::take,
+    path::{Path, PathBuf},
+    result::Result,
+    sync::{Arc, Mutex},
+};
+use thiserror::Error;
+
+/// 每批上传的文件数量，批量上传任务在创建时就会打开文件，因此需要限制同时打开的文件数量
+const UPLOAD_BATCH_SIZE: usize = 256;
+
+/// 目录同步选项
+#[derive(Debug, Clone, Default)]
+pub struct SyncDirectoryOptions {
+    include_patterns: Vec<String>,
+    exclude_patterns: Vec<String>,
+    delete_remote_extras: bool,
+    dry_run: bool,
+}
+
+impl SyncDirectoryOptions {
+    /// 仅同步匹配指定模式的文件
+    ///
+    /// 模式将与文件相对于本地目录的路径（总是以 `/` 分隔）进行匹配。
+    /// 支持 `*` 匹配除 `/` 以外的任意字符，`?` 匹配除 `/` 以外的单个字符，`**` 匹配包括 `/` 在内的任意字符，
+    /// 例如 `**/*.html` 将匹配任意层级下的 HTML 文件。
+    ///
+    /// 可以多次调用以指定多个模式，文件只要匹配其中之一即可。如果没有指定任何模式，则同步所有文件
+    pub fn include(mut self, pattern: impl Into<String>) -> Self {
+        self.include_patterns.push(pattern.into());
+        self
+    }
+
+    /// 不同步匹配指定模式的文件
+    ///
+    /// 模式语法与 `include` 相同，被排除的文件对应的对象也不会因为 `delete_remote_extras` 而被删除
+    pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.exclude_patterns.push(pattern.into());
+        self
+    }
+
+    /// 是否删除存储空间中存在，但本地目录中不存在的对象
+    ///
+    /// 仅会删除指定前缀下的对象，默认不删除
+    pub fn delete_remote_extras(mut self, delete_remote_extras: bool) -> Self {
+        self.delete_remote_extras = delete_remote_extras;
+        self
+    }
+
+    /// 仅计算需要上传和删除的对象，而不实际执行
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    fn is_matched(&self, relative_path: &str) -> bool {
+        let path: Vec<char> = relative_path.chars().collect();
+        let matches = |pattern: &String| glob_match(&pattern.chars().collect::<Vec<_>>(), &path);
+        (self.include_patterns.is_empty() || self.include_patterns.iter().any(matches))
+            && !self.exclude_patterns.iter().any(matches)
+    }
+}
+
+/// 目录同步报告
+#[derive(Debug, Default)]
+pub struct SyncDirectoryReport {
+    uploaded: Vec<String>,
+    skipped: Vec<String>,
+    deleted: Vec<String>,
+    upload_failures: Vec<(String, UploadError)>,
+    delete_failures: Vec<(String, HTTPError)>,
+    local_failures: Vec<(String, IOError)>,
+    dry_run: bool,
+}
+
+impl SyncDirectoryReport {
+    /// 上传成功的对象名称列表
+    ///
+    /// 对于试运行，则为需要上传的对象名称列表
+    pub fn uploaded(&self) -> &[String] {
+        &self.uploaded
+    }
+
+    /// 由于内容没有变化而跳过上传的对象名称列表
+    pub fn skipped(&self) -> &[String] {
+        &self.skipped
+    }
+
+    /// 删除成功的对象名称列表
+    ///
+    /// 对于试运行，则为需要删除的对象名称列表
+    pub fn deleted(&self) -> &[String] {
+        &self.deleted
+    }
+
+    /// 上传失败的对象名称及错误
+    pub fn upload_failures(&self) -> &[(String, UploadError)] {
+        &self.upload_failures
+    }
+
+    /// 删除失败的对象名称及错误
+    pub fn delete_failures(&self) -> &[(String, HTTPError)] {
+        &self.delete_failures
+    }
+
+    /// 无法读取的本地文件或目录对应的对象名称及错误
+    ///
+    /// 目录对应的名称以 `/` 结尾，这些文件或目录将被跳过，其对应的对象也不会因为 `delete_remote_extras` 而被删除
+    pub fn local_failures(&self) -> &[(String, IOError)] {
+        &self.local_failures
+    }
+
+    /// 是否是试运行的报告
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// 是否全部操作均执行成功
+    pub fn is_success(&self) -> bool {
+        self.upload_failures.is_empty() && self.delete_failures.is_empty() && self.local_failures.is_empty()
+    }
+}
+
+/// 目录同步错误
+///
+/// 单个对象上传或删除失败，或本地文件或子目录无法读取，都不会导致同步中止，其错误将记录在同步报告中
+#[derive(Error, Debug)]
+pub enum SyncDirectoryError {
+    /// 无法读取要同步的本地目录
+    #[error("Failed to do local io operation during syncing: {0}")]
+    IOError(#[from] IOError),
+    /// 查询存储空间中的对象时发生错误
+    #[error("Qiniu API call error: {0}")]
+    QiniuError(#[from] HTTPError),
+}
+
+/// 目录同步结果
+pub type SyncDirectoryResult<T> = Result<T, SyncDirectoryError>;
+
+struct LocalFile {
+    key: String,
+    path: PathBuf,
+}
+
+pub(super) fn sync_directory(
+    bucket: &Bucket,
+    local_dir: &Path,
+    prefix: &str,
+    options: SyncDirectoryOptions,
+) -> SyncDirectoryResult<SyncDirectoryReport> {
+    let mut report = SyncDirectoryReport {
+        dry_run: options.dry_run,
+        ..Default::default()
+    };
+    let mut local_files = Vec::new();
+    walk_directory(
+        local_dir,
+        "",
+        &options,
+        prefix,
+        &mut local_files,
+        &mut report.local_failures,
+    )?;
+    local_files.sort_by(|a, b| a.key.cmp(&b.key));
+    report.local_failures.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut files_to_upload = Vec::new();
+    let mut keys_to_delete = Vec::new();
+
+    if options.delete_remote_extras {
+        // 需要删除多余对象时，必须列举前缀下的全部对象，因此直接使用列举结果比对 Hash 值
+        let mut remote_hashes = HashMap::new();
+        for object in bucket.list_objects(prefix) {
+            let object = object?;
+            remote_hashes.insert(object.key().to_owned(), object.info().hash().to_owned());
+        }
+        let local_keys: HashSet<&str> = local_files.iter().map(|file| file.key.as_str()).collect();
+        keys_to_delete = remote_hashes
+            .keys()
+            .filter(|key| !local_keys.contains(key.as_str()))
+            .filter(|key| options.is_matched(&key[prefix.len()..]))
+            .filter(|key| !is_unreadable(key, prefix, &report.local_failures))
+            .cloned()
+            .collect();
+        keys_to_delete.sort();
+        for file in local_files.into_iter() {
+            let unchanged = match remote_hashes.get(&file.key) {
+                Some(hash) => match etag::from_file(&file.path) {
+                    Ok(etag) => *hash == etag,
+                    Err(err) => {
+                        report.upload_failures.push((file.key, err.into()));
+                        continue;
+                    }
+                },
+                None => false,
+            };
+            if unchanged {
+                report.skipped.push(file.key);
+            } else {
+                files_to_upload.push(file);
+            }
+        }
+    } else {
+        for file in local_files.into_iter() {
+            let unchanged = match bucket.object(file.key.to_owned()).get_info() {
+                Ok(info) => match etag::from_file(&file.path) {
+                    Ok(etag) => info.hash() == etag,
+                    Err(err) => {
+                        report.upload_failures.push((file.key, err.into()));
+                        continue;
+                    }
+                },
+                Err(ref err) if is_not_found(err) => false,
+                Err(err) => return Err(err.into()),
+            };
+            if unchanged {
+                report.skipped.push(file.key);
+            } else {
+                files_to_upload.push(file);
+            }
+        }
+    }
+
+    if options.dry_run {
+        report.uploaded = files_to_upload.into_iter().map(|file| file.key).collect();
+        report.deleted = keys_to_delete;
+        return Ok(report);
+    }
+
+    upload_files(bucket, files_to_upload, &mut report)?;
+    for key in keys_to_delete.into_iter() {
+        match bucket.object(key.to_owned()).delete() {
+            Ok(_) => report.deleted.push(key),
+            Err(err) => report.delete_failures.push((key, err)),
+        }
+    }
+    Ok(report)
+}
+
+fn upload_files(bucket: &Bucket, files: Vec<LocalFile>, report: &mut SyncDirectoryReport) -> SyncDirectoryResult<()> {
+    let results: Arc<Mutex<Vec<(String, UploadResult)>>> = Arc::new(Mutex::new(Vec::with_capacity(files.len())));
+    let mut batch_uploader = bucket.batch_uploader();
+    for files in files.chunks(UPLOAD_BATCH_SIZE) {
+        batch_uploader.expected_jobs_count(files.len());
+        for file in files.iter() {
+            let key = file.key.to_owned();
+            // 同步时需要覆盖已经存在的对象，因此为每个对象单独生成上传凭证
+            let upload_token = UploadToken::new(
+                UploadPolicyBuilder::new_policy_for_object(bucket.name(), &key, bucket.config()).build(),
+                bucket.credential().to_owned(),
+            );
+            let job = BatchUploadJobBuilder::default()
+                .key(key.to_owned())
+                .upload_token(upload_token)
+                .unwrap()
+                .on_completed({
+                    let results = results.to_owned();
+                    move |result| results.lock().unwrap().push((key.to_owned(), result))
+                })
+                .upload_file(&file.path, "", None);
+            // 文件无法打开时，记录为上传失败并继续同步其他文件
+            match job {
+                Ok(job) => {
+                    batch_uploader.push_job(job);
+                }
+                Err(err) => results
+                    .lock()
+                    .unwrap()
+                    .push((file.key.to_owned(), Err(UploadError::IOError(err)))),
+            }
+        }
+        batch_uploader.start();
+    }
+    let mut results = take(&mut *results.lock().unwrap());
+    results.sort_by(|a, b| a.0.cmp(&b.0));
+    for (key, result) in results.into_iter() {
+        match result {
+            Ok(_) => report.uploaded.push(key),
+            Err(err) => report.upload_failures.push((key, err)),
+        }
+    }
+    Ok(())
+}
+
+// 仅当无法读取 `dir` 本身时返回错误，其中的文件或子目录无法读取时，将错误记录在 `local_failures` 中并继续遍历
+fn walk_directory(
+    dir: &Path,
+    relative_dir: &str,
+    options: &SyncDirectoryOptions,
+    prefix: &str,
+    local_files: &mut Vec<LocalFile>,
+    local_failures: &mut Vec<(String, IOError)>,
+) -> IOResult<()> {
+    for entry in read_dir(dir)? {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                // 无法继续列举该目录，其余的文件均视为无法读取
+                local_failures.push((prefix.to_owned() + relative_dir, err));
+                break;
+            }
+        };
+        let file_name = match entry.file_name().into_string() {
+            Ok(file_name) => file_name,
+            Err(file_name) => {
+                local_failures.push((
+                    prefix.to_owned() + relative_dir + &file_name.to_string_lossy(),
+                    IOError::new(
+                        IOErrorKind::InvalidData,
+                        format!("File name {:?} is not valid UTF-8", file_name),
+                    ),
+                ));
+                continue;
+            }
+        };
+        let relative_path = relative_dir.to_owned() + &file_name;
+        let file_type = match entry.file_type() {
+            Ok(file_type) => file_type,
+            Err(err) => {
+                local_failures.push((prefix.to_owned() + &relative_path, err));
+                continue;
+            }
+        };
+        if file_type.is_dir() {
+            let relative_path = relative_path + "/";
+            if let Err(err) = walk_directory(
+                &entry.path(),
+                &relative_path,
+                options,
+                prefix,
+                local_files,
+                local_failures,
+            ) {
+                local_failures.push((prefix.to_owned() + &relative_path, err));
+            }
+        } else if options.is_matched(&relative_path) {
+            // 不跟随指向目录的符号链接，以避免循环遍历
+            let is_file = file_type.is_file()
+                || (file_type.is_symlink()
+                    && match metadata(entry.path()) {
+                        Ok(metadata) => metadata.is_file(),
+                        Err(err) => {
+                            // 符号链接指向的文件不存在或无法访问
+                            local_failures.push((prefix.to_owned() + &relative_path, err));
+                            false
+                        }
+                    });
+            if is_file {
+                local_files.push(LocalFile {
+                    key: prefix.to_owned() + &relative_path,
+                    path: entry.path(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+// 对象对应的本地文件或其所在的目录无法读取，无法确定对象是否多余
+fn is_unreadable(key: &str, prefix: &str, local_failures: &[(String, IOError)]) -> bool {
+    local_failures
+        .iter()
+        .any(|(path, _)| key == path || ((path == prefix || path.ends_with('/')) && key.starts_with(path.as_str())))
+}
+
+fn is_not_found(err: &HTTPError) -> bool {
+    const NOT_FOUND: u16 = 612;
+    matches!(err.error_kind(), HTTPErrorKind::ResponseStatusCodeError(NOT_FOUND, _))
+}
+
+fn glob_match(pattern: &[char], path: &[char]) -> bool {
+    match pattern.first() {
+        None => path.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            let rest = &pattern[2..];
+            if rest.first() == Some(&'/') {
+                // `**/` 可以匹配零个或多个目录
+                let rest = &rest[1..];
+                (0..=path.len()).any(|i| (i == 0 || path[i - 1] == '/') && glob_match(rest, &path[i..]))
+            } else {
+                (0..=path.len()).any(|i| glob_match(rest, &path[i..]))
+            }
+        }
+        Some('*') => (0..=path.len())
+            .take_while(|&i| i == 0 || path[i - 1] != '/')
+            .any(|i| glob_match(&pattern[1..], &path[i..])),
+        Some('?') => path.first().map_or(false, |&c| c != '/') && glob_match(&pattern[1..], &path[1..]),
+        Some(c) => path.first() == Some(c) && glob_match(&pattern[1..], &path[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{bucket::BucketBuilder, region::Region, uploader::UploadManager},
+        *,
+    };
+    use crate::{
+        config::ConfigBuilder,
+        credential::Credential,
+        http::{DomainsManagerBuilder, HeadersOwned, Method},
+        utils::mime,
+    };
+    use qiniu_http::ResponseBuilder;
+    use qiniu_test_utils::http_call_mock::{fake_req_id, CallHandlers};
+    use serde_json::json;
+    use std::{
+        boxed::Box,
+        error::Error,
+        fs::{create_dir_all, write},
+    };
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_storage_directory_syncer_glob_match() -> Result<(), Box<dyn Error>> {
+        let is_matched = |pattern: &str, path: &str| {
+            glob_match(&pattern.chars().collect::<Vec<_>>(), &path.chars().collect::<Vec<_>>())
+        };
+        assert!(is_matched("*.html", "index.html"));
+        assert!(!is_matched("*.html", "blog/index.html"));
+        assert!(is_matched("**/*.html", "index.html"));
+        assert!(is_matched("**/*.html", "blog/2020/index.html"));
+        assert!(is_matched("blog/**", "blog/2020/index.html"));
+        assert!(!is_matched("blog/**", "blogs/index.html"));
+        assert!(is_matched("img/?.png", "img/a.png"));
+        assert!(!is_matched("img/?.png", "img/ab.png"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_storage_directory_syncer_dry_run() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        write(dir.path().join("index.html"), b"<html></html>")?;
+        write(dir.path().join("debug.log"), b"log")?;
+        create_dir_all(dir.path().join("css"))?;
+        write(dir.path().join("css").join("main.css"), b"body {}")?;
+        let index_etag = etag::from_bytes(b"<html></html>");
+
+        let bucket = BucketBuilder::new(
+            "test-bucket".into(),
+            get_credential(),
+            UploadManager::new(
+                ConfigBuilder::default()
+                    .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+                    .http_request_handler(
+                        CallHandlers::new(|request| {
+                            panic!("Unexpected Request: {} {}", request.method(), request.url());
+                        })
+                        .install(Method::GET, "/list\\?", move |_, _| {
+                            let mut headers = HeadersOwned::new();
+                            headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                            headers.insert("X-Reqid".into(), fake_req_id());
+                            Ok(ResponseBuilder::default()
+                                .status_code(200u16)
+                                .headers(headers)
+                                .bytes_as_body(
+                                    json!({
+                                        "items": [
+                                            {"key": "site/index.html", "hash": index_etag, "fsize": 13u64, "mimeType": "text/html", "putTime": 15_000_000_000_000_000u64},
+                                            {"key": "site/old.html", "hash": "old_hash", "fsize": 1u64, "mimeType": "text/html", "putTime": 15_000_000_000_000_000u64},
+                                            {"key": "site/keep.log", "hash": "log_hash", "fsize": 1u64, "mimeType": "text/plain", "putTime": 15_000_000_000_000_000u64}
+                                        ]
+                                    })
+                                    .to_string(),
+                                )
+                                .build())
+                        }),
+                    )
+                    .build(),
+            ),
+        )
+        .region(Region::z0())
+        .build();
+
+        let report = bucket.sync_directory(
+            dir.path(),
+            "site/",
+            SyncDirectoryOptions::default()
+                .exclude("**/*.log")
+                .delete_remote_extras(true)
+                .dry_run(true),
+        )?;
+        assert!(report.is_dry_run());
+        assert!(report.is_success());
+        assert_eq!(report.uploaded(), &["site/css/main.css".to_owned()]);
+        assert_eq!(report.skipped(), &["site/index.html".to_owned()]);
+        assert_eq!(report.deleted(), &["site/old.html".to_owned()]);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_storage_directory_syncer_record_dangling_symlinks() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        write(dir.path().join("index.html"), b"<html></html>")?;
+        std::os::unix::fs::symlink(dir.path().join("missing.html"), dir.path().join("broken.html"))?;
+        let index_etag = etag::from_bytes(b"<html></html>");
+
+        let bucket = BucketBuilder::new(
+            "test-bucket".into(),
+            get_credential(),
+            UploadManager::new(
+                ConfigBuilder::default()
+                    .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+                    .http_request_handler(
+                        CallHandlers::new(|request| {
+                            panic!("Unexpected Request: {} {}", request.method(), request.url());
+                        })
+                        .install(Method::GET, "/list\\?", move |_, _| {
+                            let mut headers = HeadersOwned::new();
+                            headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                            headers.insert("X-Reqid".into(), fake_req_id());
+                            Ok(ResponseBuilder::default()
+                                .status_code(200u16)
+                                .headers(headers)
+                                .bytes_as_body(
+                                    json!({
+                                        "items": [
+                                            {"key": "site/index.html", "hash": index_etag, "fsize": 13u64, "mimeType": "text/html", "putTime": 15_000_000_000_000_000u64},
+                                            {"key": "site/broken.html", "hash": "broken_hash", "fsize": 1u64, "mimeType": "text/html", "putTime": 15_000_000_000_000_000u64},
+                                            {"key": "site/old.html", "hash": "old_hash", "fsize": 1u64, "mimeType": "text/html", "putTime": 15_000_000_000_000_000u64}
+                                        ]
+                                    })
+                                    .to_string(),
+                                )
+                                .build())
+                        }),
+                    )
+                    .build(),
+            ),
+        )
+        .region(Region::z0())
+        .build();
+
+        let report = bucket.sync_directory(
+            dir.path(),
+            "site/",
+            SyncDirectoryOptions::default().delete_remote_extras(true).dry_run(true),
+        )?;
+        assert!(!report.is_success());
+        assert!(report.uploaded().is_empty());
+        assert_eq!(report.skipped(), &["site/index.html".to_owned()]);
+        assert_eq!(report.deleted(), &["site/old.html".to_owned()]);
+        assert_eq!(
+            report
+                .local_failures()
+                .iter()
+                .map(|(key, err)| (key.as_str(), err.kind()))
+                .collect::<Vec<_>>(),
+            vec![("site/broken.html", IOErrorKind::NotFound)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_storage_directory_syncer_record_open_failures() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        let bucket = BucketBuilder::new(
+            "test-bucket".into(),
+            get_credential(),
+            UploadManager::new(
+                ConfigBuilder::default()
+                    .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+                    .http_request_handler(CallHandlers::new(|request| {
+                        panic!("Unexpected Request: {} {}", request.method(), request.url());
+                    }))
+                    .build(),
+            ),
+        )
+        .region(Region::z0())
+        .build();
+
+        let mut report = SyncDirectoryReport::default();
+        upload_files(
+            &bucket,
+            vec![
+                LocalFile {
+                    key: "site/b.html".into(),
+                    path: dir.path().join("b.html"),
+                },
+                LocalFile {
+                    key: "site/a.html".into(),
+                    path: dir.path().join("a.html"),
+                },
+            ],
+            &mut report,
+        )?;
+        assert!(!report.is_success());
+        assert!(report.uploaded().is_empty());
+        assert_eq!(
+            report
+                .upload_failures()
+                .iter()
+                .map(|(key, err)| (key.as_str(), ::matches::matches!(err, UploadError::IOError(_))))
+                .collect::<Vec<_>>(),
+            vec![("site/a.html", true), ("site/b.html", true)]
+        );
+        Ok(())
+    }
+
+    fn get_credential() -> Credential {
+        Credential::new("abcdefghklmnopq", "1234567890")
+    }
+}
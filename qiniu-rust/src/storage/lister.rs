@@ -0,0 +1,191 @@
+//! 对象列举模块
+//!
+//! 列举存储空间中指定前缀的对象
+
+use super::{bucket::Bucket, object::ObjectInfo};
+use crate::http::{Result as HTTPResult, TokenVersion};
+use serde::Deserialize;
+use std::{borrow::Cow, collections::VecDeque, fmt};
+
+const LIST_LIMIT: usize = 1000;
+
+/// 列举出的对象
+#[derive(Deserialize)]
+pub struct ListedObject {
+    key: String,
+
+    #[serde(flatten)]
+    info: ObjectInfo,
+}
+
+impl ListedObject {
+    /// 获取对象名称
+    #[inline]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 获取对象详细信息
+    #[inline]
+    pub fn info(&self) -> &ObjectInfo {
+        &self.info
+    }
+}
+
+impl fmt::Debug for ListedObject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ListedObject")
+            .field("key", &self.key)
+            .field("info", &self.info)
+            .finish()
+    }
+}
+
+#[derive(Deserialize)]
+struct ListResponse {
+    #[serde(default)]
+    marker: String,
+    #[serde(default)]
+    items: Vec<ListedObject>,
+}
+
+/// 对象列举迭代器
+///
+/// 每次向七牛服务器请求最多 1000 个对象，迭代完毕后再自动请求下一批，直到列举完毕或发生错误为止。
+/// 发生错误后，迭代器将不再返回任何对象
+pub struct ListIter<'b> {
+    bucket: &'b Bucket,
+    prefix: Cow<'b, str>,
+    marker: String,
+    buffer: VecDeque<ListedObject>,
+    done: bool,
+}
+
+impl<'b> ListIter<'b> {
+    pub(super) fn new(bucket: &'b Bucket, prefix: Cow<'b, str>) -> Self {
+        Self {
+            bucket,
+            prefix,
+            marker: String::new(),
+            buffer: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    fn fetch(&mut self) -> HTTPResult<()> {
+        let limit = LIST_LIMIT.to_string();
+        let rsf_urls = self.bucket.rsf_urls();
+        let mut request = self
+            .bucket
+            .http_client()
+            .get("/list", &rsf_urls)
+            .idempotent()
+            .query("bucket".into(), self.bucket.name().into())
+            .query("limit".into(), limit.as_str().into())
+            .token(TokenVersion::V2, self.bucket.credential().into())
+            .accept_json();
+        if !self.prefix.is_empty() {
+            request = request.query("prefix".into(), self.prefix.as_ref().into());
+        }
+        if !self.marker.is_empty() {
+            request = request.query("marker".into(), self.marker.as_str().into());
+        }
+        let response: ListResponse = request.no_body().send()?.parse_json()?;
+        self.done = response.marker.is_empty();
+        self.marker = response.marker;
+        self.buffer.extend(response.items);
+        Ok(())
+    }
+}
+
+impl Iterator for ListIter<'_> {
+    type Item = HTTPResult<ListedObject>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(object) = self.buffer.pop_front() {
+                return Some(Ok(object));
+            }
+            if self.done {
+                return None;
+            }
+            if let Err(err) = self.fetch() {
+                self.done = true;
+                return Some(Err(err));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{bucket::BucketBuilder, region::Region, uploader::UploadManager};
+    use crate::{
+        config::ConfigBuilder,
+        credential::Credential,
+        http::{DomainsManagerBuilder, HeadersOwned, Method},
+        utils::mime,
+    };
+    use qiniu_http::ResponseBuilder;
+    use qiniu_test_utils::http_call_mock::{fake_req_id, CallHandlers};
+    use serde_json::json;
+    use std::{boxed::Box, error::Error, result::Result};
+
+    #[test]
+    fn test_storage_lister_list_objects() -> Result<(), Box<dyn Error>> {
+        let bucket = BucketBuilder::new(
+            "test-bucket".into(),
+            get_credential(),
+            UploadManager::new(
+                ConfigBuilder::default()
+                    .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+                    .http_request_handler(
+                        CallHandlers::new(|request| {
+                            panic!("Unexpected Request: {} {}", request.method(), request.url());
+                        })
+                        .install(Method::GET, "/list\\?", |request, called| {
+                            assert!(request.url().contains("prefix=dir%2F"));
+                            let body = match called {
+                                1 => {
+                                    assert!(!request.url().contains("marker="));
+                                    json!({
+                                        "marker": "next_marker",
+                                        "items": [{"key": "dir/a", "hash": "hash_a", "fsize": 1u64, "mimeType": "text/plain", "putTime": 15_000_000_000_000_000u64}]
+                                    })
+                                }
+                                2 => {
+                                    assert!(request.url().contains("marker=next_marker"));
+                                    json!({
+                                        "items": [{"key": "dir/b", "hash": "hash_b", "fsize": 2u64, "mimeType": "text/plain", "putTime": 15_000_000_000_000_000u64}]
+                                    })
+                                }
+                                _ => panic!("Unexpected call `GET {}` for {} times", request.url(), called),
+                            };
+                            let mut headers = HeadersOwned::new();
+                            headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                            headers.insert("X-Reqid".into(), fake_req_id());
+                            Ok(ResponseBuilder::default()
+                                .status_code(200u16)
+                                .headers(headers)
+                                .bytes_as_body(body.to_string())
+                                .build())
+                        }),
+                    )
+                    .build(),
+            ),
+        )
+        .region(Region::z0())
+        .build();
+        let objects = bucket.list_objects("dir/").collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(objects.len(), 2);
+        assert_eq!(objects[0].key(), "dir/a");
+        assert_eq!(objects[0].info().hash(), "hash_a");
+        assert_eq!(objects[1].key(), "dir/b");
+        assert_eq!(objects[1].info().size(), 2);
+        Ok(())
+    }
+
+    fn get_credential() -> Credential {
+        Credential::new("abcdefghklmnopq", "1234567890")
+    }
+}
@@ -3,8 +3,11 @@
 //! 负责对整个 SDK 存储方面的逻辑进行处理
 
 pub mod bucket;
+pub mod directory_syncer;
+pub mod lister;
 pub mod manager;
 pub mod object;
+pub mod prefix_downloader;
 pub mod recorder;
 pub mod region;
 mod resource;
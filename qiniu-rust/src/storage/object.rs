@@ -15,7 +15,8 @@ use serde::Deserialize;
 use std::{
     borrow::Cow,
     fmt,
-    time::{Duration, SystemTime},
+    io::Write,
+    time::{Duration, Instant, SystemTime},
 };
 
 /// 对象
@@ -25,6 +26,7 @@ pub struct Object {
     key: Cow<'static, str>,
     bucket: Bucket,
     encoded_entry_uri: OnceCell<String>,
+    operation_timeout: Option<Duration>,
 }
 
 impl Object {
@@ -33,9 +35,21 @@ impl Object {
             bucket,
             key,
             encoded_entry_uri: OnceCell::new(),
+            operation_timeout: None,
         }
     }
 
+    /// 指定操作超时时长
+    ///
+    /// 限制对该对象的每次操作包含所有重试在内的总耗时，超时后将返回 `DeadlineExceeded` 错误。
+    /// 将覆盖客户端配置中的操作超时时长。
+    ///
+    /// 注意，存储空间懒加载查询所在区域的耗时不受该项限制，该查询仅受客户端配置中的操作超时时长限制
+    pub fn operation_timeout(mut self, timeout: Duration) -> Self {
+        self.operation_timeout = Some(timeout);
+        self
+    }
+
     /// 获取对象所在存储空间信息
     pub fn bucket(&self) -> &Bucket {
         &self.bucket
@@ -53,6 +67,7 @@ impl Object {
             .get(&Stat::new(self).to_uri(), &self.bucket.rs_urls())
             .idempotent()
             .token(TokenVersion::V2, self.bucket.credential().into())
+            .deadline(self.deadline())
             .accept_json()
             .no_body()
             .send()?
@@ -66,12 +81,53 @@ impl Object {
             .post(&Delete::new(self).to_uri(), &self.bucket.rs_urls())
             .idempotent()
             .token(TokenVersion::V2, self.bucket.credential().into())
+            .deadline(self.deadline())
             .no_body()
             .send()?
             .ignore_body();
         Ok(())
     }
 
+    /// 异步获取对象详细信息
+    ///
+    /// 需要开启 `async` 功能
+    #[cfg(feature = "async")]
+    pub async fn get_info_async(&self) -> HTTPResult<ObjectInfo> {
+        self.bucket
+            .http_client()
+            .get(&Stat::new(self).to_uri(), &self.bucket.rs_urls())
+            .idempotent()
+            .token(TokenVersion::V2, self.bucket.credential().into())
+            .deadline(self.deadline())
+            .accept_json()
+            .no_body()
+            .send_async()
+            .await?
+            .parse_json()
+    }
+
+    /// 异步删除对象
+    ///
+    /// 需要开启 `async` 功能
+    #[cfg(feature = "async")]
+    pub async fn delete_async(&self) -> HTTPResult<()> {
+        self.bucket
+            .http_client()
+            .post(&Delete::new(self).to_uri(), &self.bucket.rs_urls())
+            .idempotent()
+            .token(TokenVersion::V2, self.bucket.credential().into())
+            .deadline(self.deadline())
+            .no_body()
+            .send_async()
+            .await?
+            .ignore_body();
+        Ok(())
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        self.operation_timeout.map(|timeout| Instant::now() + timeout)
+    }
+
     pub(super) fn encoded_entry_uri(&self) -> &str {
         self.encoded_entry_uri.get_or_init(|| {
             let entry_uri = self.bucket.name().to_owned() + ":" + self.key.as_ref();
@@ -81,7 +137,8 @@ impl Object {
 
     /// 创建面向该对象的对象上传器
     pub fn uploader(&self) -> ObjectUploader {
-        self.bucket
+        let uploader = self
+            .bucket
             .upload_manager()
             .upload_for_internal_generated_upload_token_with_regions(
                 self.bucket.name().into(),
@@ -94,7 +151,12 @@ impl Object {
                 .into(),
                 self.bucket.regions().ok(),
             )
-            .key(Cow::Borrowed(self.key.as_ref()))
+            .key(Cow::Borrowed(self.key.as_ref()));
+        if let Some(timeout) = self.operation_timeout {
+            uploader.operation_timeout(timeout)
+        } else {
+            uploader
+        }
     }
 
     /// 获取下载 URL 元信息
@@ -103,6 +165,16 @@ impl Object {
         Ok(header_info)
     }
 
+    /// 下载对象内容，并写入指定的输出流
+    ///
+    /// 该 API 将会根据当前存储空间是否私有，来决定使用的下载 URL，返回写入的字节数
+    pub fn download_to(&self, writer: &mut dyn Write) -> DomainsResult<u64> {
+        let size = self
+            .url(Duration::from_secs(3600))?
+            .download_to(self.bucket().http_client(), writer)?;
+        Ok(size)
+    }
+
     /// 获取下载 URL
     ///
     /// 该 API 将会根据当前存储空间是否私有，来决定生成的 URL
@@ -218,16 +290,26 @@ impl fmt::Debug for ObjectInfo {
 
 #[cfg(test)]
 mod tests {
-    use super::super::{bucket::BucketBuilder, uploader::UploadManager};
+    use super::super::{bucket::BucketBuilder, region::Region, uploader::UploadManager};
     use crate::{
         config::ConfigBuilder,
         credential::Credential,
         http::{DomainsManagerBuilder, ErrorKind as HTTPErrorKind, HeadersOwned},
     };
     use chrono::{offset::Utc, DateTime};
-    use qiniu_test_utils::http_call_mock::JSONCallMock;
+    #[cfg(feature = "async")]
+    use futures::executor::block_on;
+    use qiniu_test_utils::{
+        fault_injection::{Fault, FaultInjectionCaller, FaultRule},
+        http_call_mock::JSONCallMock,
+    };
     use serde_json::json;
-    use std::{boxed::Box, error::Error, result::Result};
+    use std::{
+        boxed::Box,
+        error::Error,
+        result::Result,
+        time::{Duration, Instant},
+    };
 
     #[test]
     fn test_storage_object_stat() -> Result<(), Box<dyn Error>> {
@@ -305,6 +387,70 @@ mod tests {
         panic!("Should not reach here");
     }
 
+    #[test]
+    fn test_storage_object_operation_timeout() -> Result<(), Box<dyn Error>> {
+        let bucket = BucketBuilder::new(
+            "test-bucket".into(),
+            get_credential(),
+            UploadManager::new(
+                ConfigBuilder::default()
+                    .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+                    .http_request_handler(
+                        FaultInjectionCaller::new(JSONCallMock::new(200, HeadersOwned::new(), json!({})))
+                            .rule(FaultRule::new().always(Fault::Timeout)),
+                    )
+                    .http_request_retries(100)
+                    .http_request_retry_delay(Duration::from_millis(100))
+                    .operation_timeout(Duration::from_secs(60))
+                    .build(),
+            ),
+        )
+        .region(Region::z0())
+        .build();
+        let object = bucket
+            .object("test-object")
+            .operation_timeout(Duration::from_millis(300));
+        for _ in 0..2 {
+            let timer = Instant::now();
+            let err = object.delete().unwrap_err();
+            assert!(timer.elapsed() < Duration::from_secs(2));
+            assert!(matches!(err.error_kind(), HTTPErrorKind::DeadlineExceeded));
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_storage_object_stat_async() -> Result<(), Box<dyn Error>> {
+        let bucket = BucketBuilder::new(
+            "test-bucket".into(),
+            get_credential(),
+            UploadManager::new(
+                ConfigBuilder::default()
+                    .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+                    .http_request_handler(JSONCallMock::new(
+                        200,
+                        HeadersOwned::new(),
+                        json!({
+                            "fsize":        5_122_935u64,
+                            "hash":         "ljfockr0lOil_bZfyaI2ZY78HWoH",
+                            "mimeType":     "application/octet-stream",
+                            "putTime":      13_603_956_734_587_420u64,
+                            "md5":          "e41714a18899cf59c200a9bddfa78b95"
+                        }),
+                    ))
+                    .build(),
+            ),
+        )
+        .build();
+        let object = bucket.object("test-object");
+        let object_info = block_on(object.get_info_async())?;
+        assert_eq!(object_info.size(), 5_122_935);
+        assert_eq!(object_info.hash(), "ljfockr0lOil_bZfyaI2ZY78HWoH");
+        block_on(object.delete_async())?;
+        Ok(())
+    }
+
     fn get_credential() -> Credential {
         Credential::new("abcdefghklmnopq", "1234567890")
     }
@@ -0,0 +1,471 @@
+//! 前缀下载模块
+//!
+//! 将存储空间中指定前缀的对象增量下载到本地目录
+
+use super::bucket::{Bucket, DomainsError};
+use crate::{
+    http::Error as HTTPError,
+    utils::{etag, ron::Ron},
+};
+use matches::matches;
+use rayon::ThreadPoolBuilder;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::{create_dir_all, metadata, remove_file, rename, File, OpenOptions},
+    io::{BufReader, BufWriter, Error as IOError, ErrorKind as IOErrorKind, Result as IOResult, Write},
+    path::{Component, Path, PathBuf},
+    result::Result,
+    sync::Mutex,
+};
+use thiserror::Error;
+
+/// 下载状态文件名称，保存在本地目录中，用于在重新执行时跳过已经下载过的对象
+pub const DOWNLOAD_STATE_FILE_NAME: &str = ".qiniu_ng_download_state.json";
+
+const DOWNLOADING_FILE_SUFFIX: &str = ".qiniu_ng_downloading";
+
+/// 前缀下载报告
+#[derive(Debug, Default)]
+pub struct DownloadPrefixReport {
+    downloaded: Vec<String>,
+    skipped: Vec<String>,
+    failures: Vec<(String, DownloadPrefixError)>,
+}
+
+impl DownloadPrefixReport {
+    /// 下载成功的对象名称列表
+    pub fn downloaded(&self) -> &[String] {
+        &self.downloaded
+    }
+
+    /// 由于本地文件已经是最新而跳过下载的对象名称列表
+    pub fn skipped(&self) -> &[String] {
+        &self.skipped
+    }
+
+    /// 下载失败的对象名称及错误
+    pub fn failures(&self) -> &[(String, DownloadPrefixError)] {
+        &self.failures
+    }
+
+    /// 是否全部对象均下载成功
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// 前缀下载错误
+#[derive(Error, Debug)]
+pub enum DownloadPrefixError {
+    /// 读写本地文件时发生 IO 错误
+    #[error("Failed to do local io operation during downloading: {0}")]
+    IOError(#[from] IOError),
+    /// 列举或下载对象时发生错误
+    #[error("Qiniu API call error: {0}")]
+    QiniuError(#[from] HTTPError),
+    /// 获取存储空间下载域名时发生错误
+    #[error("Get domains error: {0}")]
+    DomainsError(#[from] DomainsError),
+    /// 下载完毕后，本地计算的 Etag 与对象的 Hash 值不一致
+    #[error("Etag mismatch, local etag is {local_etag}, but remote hash is {remote_hash}")]
+    EtagMismatchError {
+        /// 本地计算的 Etag
+        local_etag: String,
+        /// 对象的 Hash 值
+        remote_hash: String,
+    },
+    /// 下载完毕后，本地文件尺寸与对象尺寸不一致
+    ///
+    /// 仅当对象的 Hash 值不是标准 Etag 时，才会以文件尺寸校验下载结果
+    #[error("Size mismatch, local size is {local_size}, but remote size is {remote_size}")]
+    SizeMismatchError {
+        /// 本地文件尺寸
+        local_size: u64,
+        /// 对象尺寸
+        remote_size: u64,
+    },
+    /// 对象名称无法映射为本地目录内的安全路径，例如包含 `..`
+    #[error("Key can not be mapped to local path: {0}")]
+    InvalidKeyError(String),
+}
+
+/// 前缀下载结果
+pub type DownloadPrefixResult<T> = Result<T, DownloadPrefixError>;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct DownloadState {
+    hash: String,
+    size: u64,
+}
+
+struct DownloadTask {
+    key: String,
+    hash: String,
+    size: u64,
+    path: PathBuf,
+}
+
+pub(super) fn download_prefix(
+    bucket: &Bucket,
+    prefix: &str,
+    local_dir: &Path,
+) -> DownloadPrefixResult<DownloadPrefixReport> {
+    create_dir_all(local_dir)?;
+    let state_path = local_dir.join(DOWNLOAD_STATE_FILE_NAME);
+    let mut states = load_states(&state_path)?;
+    let mut report = DownloadPrefixReport::default();
+    let mut tasks = Vec::new();
+
+    for object in bucket.list_objects(prefix) {
+        let object = object?;
+        let key = object.key().to_owned();
+        let path = match key_to_path(local_dir, &key[prefix.len()..]) {
+            Some(path) => path,
+            None => {
+                // 以 `/` 结尾的对象通常是目录占位符，无需下载
+                if !key.ends_with('/') {
+                    report
+                        .failures
+                        .push((key.to_owned(), DownloadPrefixError::InvalidKeyError(key)));
+                }
+                continue;
+            }
+        };
+        let task = DownloadTask {
+            key,
+            hash: object.info().hash().to_owned(),
+            size: object.info().size(),
+            path,
+        };
+        if is_up_to_date(&task, states.get(&task.key))? {
+            states.insert(
+                task.key.to_owned(),
+                DownloadState {
+                    hash: task.hash,
+                    size: task.size,
+                },
+            );
+            report.skipped.push(task.key);
+        } else {
+            tasks.push(task);
+        }
+    }
+
+    let results = Mutex::new(Vec::with_capacity(tasks.len()));
+    let thread_pool = bucket
+        .thread_pool()
+        .map(|pool| Ron::Referenced(pool.as_ref()))
+        .unwrap_or_else(|| {
+            Ron::Owned(
+                ThreadPoolBuilder::new()
+                    .thread_name(|index| format!("qiniu_ng_prefix_downloader_worker_{}", index))
+                    .build()
+                    .unwrap(),
+            )
+        });
+    thread_pool.scope(|s| {
+        for task in tasks.into_iter() {
+            let results = &results;
+            s.spawn(move |_| {
+                let result = download_object(bucket, &task);
+                results.lock().unwrap().push((task, result));
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by(|a, b| a.0.key.cmp(&b.0.key));
+    for (task, result) in results.into_iter() {
+        match result {
+            Ok(_) => {
+                states.insert(
+                    task.key.to_owned(),
+                    DownloadState {
+                        hash: task.hash,
+                        size: task.size,
+                    },
+                );
+                report.downloaded.push(task.key);
+            }
+            Err(err) => report.failures.push((task.key, err)),
+        }
+    }
+    save_states(&state_path, &states)?;
+    Ok(report)
+}
+
+fn download_object(bucket: &Bucket, task: &DownloadTask) -> DownloadPrefixResult<()> {
+    if let Some(parent) = task.path.parent() {
+        create_dir_all(parent)?;
+    }
+    let mut downloading_path = task.path.to_owned().into_os_string();
+    downloading_path.push(DOWNLOADING_FILE_SUFFIX);
+    let downloading_path = PathBuf::from(downloading_path);
+
+    let result = (|| -> DownloadPrefixResult<()> {
+        let mut file = BufWriter::new(File::create(&downloading_path)?);
+        bucket.object(task.key.to_owned()).download_to(&mut file)?;
+        file.flush()?;
+        drop(file);
+        if etag::is_etag(&task.hash) {
+            let local_etag = etag::from_file(&downloading_path)?;
+            if local_etag != task.hash {
+                return Err(DownloadPrefixError::EtagMismatchError {
+                    local_etag,
+                    remote_hash: task.hash.to_owned(),
+                });
+            }
+        } else {
+            // 对象的 Hash 值不是标准 Etag，无法与本地计算的 Etag 比较，只能校验文件尺寸
+            let local_size = metadata(&downloading_path)?.len();
+            if local_size != task.size {
+                return Err(DownloadPrefixError::SizeMismatchError {
+                    local_size,
+                    remote_size: task.size,
+                });
+            }
+        }
+        rename(&downloading_path, &task.path)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = remove_file(&downloading_path);
+    }
+    result
+}
+
+fn is_up_to_date(task: &DownloadTask, state: Option<&DownloadState>) -> IOResult<bool> {
+    let file_size = match metadata(&task.path) {
+        Ok(metadata) => metadata.len(),
+        Err(ref err) if err.kind() == IOErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err),
+    };
+    if file_size != task.size {
+        return Ok(false);
+    }
+    if let Some(state) = state {
+        if state.hash == task.hash && state.size == task.size {
+            return Ok(true);
+        }
+    }
+    // 状态文件中没有记录，但本地文件内容可能已经与对象一致。
+    // 对象的 Hash 值不是标准 Etag 时无法确认，需要重新下载
+    Ok(etag::is_etag(&task.hash) && etag::from_file(&task.path)? == task.hash)
+}
+
+fn key_to_path(local_dir: &Path, relative_key: &str) -> Option<PathBuf> {
+    if relative_key.is_empty() || relative_key.ends_with('/') || relative_key == DOWNLOAD_STATE_FILE_NAME {
+        return None;
+    }
+    let relative_path = Path::new(relative_key);
+    if relative_key
+        .split('/')
+        .any(|segment| segment.is_empty() || segment == "." || segment == "..")
+        || !relative_path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return None;
+    }
+    Some(local_dir.join(relative_path))
+}
+
+fn load_states(state_path: &Path) -> IOResult<HashMap<String, DownloadState>> {
+    match File::open(state_path) {
+        Ok(file) => Ok(serde_json::from_reader(BufReader::new(file)).unwrap_or_default()),
+        Err(ref err) if err.kind() == IOErrorKind::NotFound => Ok(HashMap::new()),
+        Err(err) => Err(err),
+    }
+}
+
+fn save_states(state_path: &Path, states: &HashMap<String, DownloadState>) -> IOResult<()> {
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(state_path)?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer(&mut writer, states)?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{bucket::BucketBuilder, region::Region, uploader::UploadManager},
+        *,
+    };
+    use crate::{
+        config::ConfigBuilder,
+        credential::Credential,
+        http::{DomainsManagerBuilder, HeadersOwned, Method},
+        utils::mime,
+    };
+    use matches::matches;
+    use qiniu_http::ResponseBuilder;
+    use qiniu_test_utils::http_call_mock::{fake_req_id, CallHandlers};
+    use serde_json::json;
+    use std::{
+        boxed::Box,
+        error::Error,
+        fs::{read, write},
+    };
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_storage_prefix_downloader_download_prefix() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        write(dir.path().join("a.txt"), b"aaa")?;
+        let (hash_a, hash_b) = (etag::from_bytes(b"aaa"), etag::from_bytes(b"bbb"));
+
+        let mut builder = BucketBuilder::new(
+            "test-bucket".into(),
+            get_credential(),
+            UploadManager::new(
+                ConfigBuilder::default()
+                    .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+                    .http_request_handler(
+                        CallHandlers::new(|request| {
+                            panic!("Unexpected Request: {} {}", request.method(), request.url());
+                        })
+                        .install(Method::GET, "/v2/bucketInfo", |_, _| {
+                            let mut headers = HeadersOwned::new();
+                            headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                            Ok(ResponseBuilder::default()
+                                .status_code(200u16)
+                                .headers(headers)
+                                .bytes_as_body(json!({"private": 0}).to_string())
+                                .build())
+                        })
+                        .install(Method::GET, "/list\\?", move |_, _| {
+                            let mut headers = HeadersOwned::new();
+                            headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                            headers.insert("X-Reqid".into(), fake_req_id());
+                            Ok(ResponseBuilder::default()
+                                .status_code(200u16)
+                                .headers(headers)
+                                .bytes_as_body(
+                                    json!({
+                                        "items": [
+                                            {"key": "p/a.txt", "hash": hash_a, "fsize": 3u64, "mimeType": "text/plain", "putTime": 15_000_000_000_000_000u64},
+                                            {"key": "p/dir/b.txt", "hash": hash_b, "fsize": 3u64, "mimeType": "text/plain", "putTime": 15_000_000_000_000_000u64},
+                                            {"key": "p/../evil.txt", "hash": "evil_hash", "fsize": 1u64, "mimeType": "text/plain", "putTime": 15_000_000_000_000_000u64}
+                                        ]
+                                    })
+                                    .to_string(),
+                                )
+                                .build())
+                        })
+                        .install(Method::GET, "^https?://z1h1\\.com/p/dir/b\\.txt", |request, called| {
+                            if called > 1 {
+                                panic!("Unexpected call `GET {}` for {} times", request.url(), called);
+                            }
+                            let mut headers = HeadersOwned::new();
+                            headers.insert("Content-Type".into(), "text/plain".into());
+                            Ok(ResponseBuilder::default()
+                                .status_code(200u16)
+                                .headers(headers)
+                                .bytes_as_body(b"bbb".to_vec())
+                                .build())
+                        }),
+                    )
+                    .build(),
+            ),
+        );
+        builder.region(Region::z0()).prepend_domain("z1h1.com")?;
+        let bucket = builder.build();
+
+        let report = bucket.download_prefix("p/", dir.path())?;
+        assert_eq!(report.skipped(), &["p/a.txt".to_owned()]);
+        assert_eq!(report.downloaded(), &["p/dir/b.txt".to_owned()]);
+        assert_eq!(report.failures().len(), 1);
+        assert!(matches!(
+            &report.failures()[0].1,
+            DownloadPrefixError::InvalidKeyError(key) if key == "p/../evil.txt"
+        ));
+        assert_eq!(read(dir.path().join("dir").join("b.txt"))?, b"bbb");
+
+        let report = bucket.download_prefix("p/", dir.path())?;
+        assert_eq!(report.skipped(), &["p/a.txt".to_owned(), "p/dir/b.txt".to_owned()]);
+        assert!(report.downloaded().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_storage_prefix_downloader_download_objects_with_non_etag_hash() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        write(dir.path().join("c.txt"), b"ccc")?;
+
+        let mut builder = BucketBuilder::new(
+            "test-bucket".into(),
+            get_credential(),
+            UploadManager::new(
+                ConfigBuilder::default()
+                    .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+                    .http_request_handler(
+                        CallHandlers::new(|request| {
+                            panic!("Unexpected Request: {} {}", request.method(), request.url());
+                        })
+                        .install(Method::GET, "/v2/bucketInfo", |_, _| {
+                            let mut headers = HeadersOwned::new();
+                            headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                            Ok(ResponseBuilder::default()
+                                .status_code(200u16)
+                                .headers(headers)
+                                .bytes_as_body(json!({"private": 0}).to_string())
+                                .build())
+                        })
+                        .install(Method::GET, "/list\\?", |_, _| {
+                            let mut headers = HeadersOwned::new();
+                            headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                            headers.insert("X-Reqid".into(), fake_req_id());
+                            Ok(ResponseBuilder::default()
+                                .status_code(200u16)
+                                .headers(headers)
+                                .bytes_as_body(
+                                    json!({
+                                        "items": [
+                                            {"key": "p/c.txt", "hash": "3d1b6b3e3b9f3c1e8e5b0a6f6c2d7e41", "fsize": 3u64, "mimeType": "text/plain", "putTime": 15_000_000_000_000_000u64}
+                                        ]
+                                    })
+                                    .to_string(),
+                                )
+                                .build())
+                        })
+                        .install(Method::GET, "^https?://z1h1\\.com/p/c\\.txt", |request, called| {
+                            if called > 1 {
+                                panic!("Unexpected call `GET {}` for {} times", request.url(), called);
+                            }
+                            let mut headers = HeadersOwned::new();
+                            headers.insert("Content-Type".into(), "text/plain".into());
+                            Ok(ResponseBuilder::default()
+                                .status_code(200u16)
+                                .headers(headers)
+                                .bytes_as_body(b"CCC".to_vec())
+                                .build())
+                        }),
+                    )
+                    .build(),
+            ),
+        );
+        builder.region(Region::z0()).prepend_domain("z1h1.com")?;
+        let bucket = builder.build();
+
+        // 尺寸相同的本地文件无法通过 Etag 确认是否一致，需要重新下载，下载结果仅校验尺寸
+        let report = bucket.download_prefix("p/", dir.path())?;
+        assert!(report.is_success());
+        assert!(report.skipped().is_empty());
+        assert_eq!(report.downloaded(), &["p/c.txt".to_owned()]);
+        assert_eq!(read(dir.path().join("c.txt"))?, b"CCC");
+
+        let report = bucket.download_prefix("p/", dir.path())?;
+        assert_eq!(report.skipped(), &["p/c.txt".to_owned()]);
+        assert!(report.downloaded().is_empty());
+        Ok(())
+    }
+
+    fn get_credential() -> Credential {
+        Credential::new("abcdefghklmnopq", "1234567890")
+    }
+}
@@ -433,6 +433,40 @@ impl Region {
         Ok(regions)
     }
 
+    /// 异步查询七牛服务器，根据存储空间名称获取区域列表
+    ///
+    /// 需要开启 `async` 功能，与 `query` 共享同一份缓存
+    #[cfg(feature = "async")]
+    pub async fn query_async<'a>(
+        bucket: impl Into<Cow<'a, str>>,
+        access_key: impl Into<Cow<'a, str>>,
+        config: Config,
+    ) -> Result<Box<[Region]>> {
+        let bucket = bucket.into();
+        let access_key = access_key.into();
+        let cache_key = QueryCacheKey::new(&access_key, &bucket);
+        if let Some(cache_entry) = QUERY_CACHE.get(&cache_key) {
+            return Ok(cache_entry.data().to_owned());
+        }
+        let uc_urls = config.uc_urls().to_owned();
+        let result: RegionQueryResults = Client::new(config)
+            .get("/v3/query", &uc_urls.iter().map(|url| url.as_ref()).collect::<Vec<_>>())
+            .query("ak".into(), access_key)
+            .query("bucket".into(), bucket)
+            .accept_json()
+            .no_body()
+            .send_async()
+            .await?
+            .parse_json()?;
+        let regions = result.into_regions();
+        QUERY_CACHE.insert(
+            cache_key,
+            regions.to_owned(),
+            SystemTime::now() + Duration::from_secs(24 * 60 * 60),
+        );
+        Ok(regions)
+    }
+
     #[doc(hidden)]
     pub fn into_raw(self) -> *const c_void {
         Arc::into_raw(self.0).cast()
@@ -1,26 +1,40 @@
 use super::{
-    super::bucket::Bucket, object_uploader::ResumablePolicy, CreateUploaderError, CreateUploaderResult, UploadManager,
-    UploadPolicy, UploadResult, UploadToken,
+    super::bucket::Bucket, object_uploader::ResumablePolicy, ChecksumPolicy, CreateUploaderError, CreateUploaderResult,
+    ObjectUploader, UploadManager, UploadPolicy, UploadResult, UploadToken,
 };
-use crate::{utils::ron::Ron, Config, Credential};
+use crate::{
+    utils::{rate_limiter::RateLimiter, ron::Ron},
+    Config, Credential,
+};
+use matches::matches;
 use mime::Mime;
 use rayon::{ThreadPool, ThreadPoolBuilder};
 use std::{
     borrow::Cow,
     collections::HashMap,
     fs::File,
-    io::{Read, Result},
+    io::{Read, Result, Seek, SeekFrom},
     mem::replace,
     path::Path,
-    sync::Arc,
+    result::Result as StdResult,
+    sync::{
+        mpsc::{channel, sync_channel, Receiver, Sender, SyncSender},
+        Arc, Condvar, Mutex, RwLock,
+    },
+    thread::{sleep, Builder as ThreadBuilder, JoinHandle},
+    time::{Duration, Instant},
 };
 
 type OnUploadingProgressCallback = Box<dyn Fn(u64, Option<u64>) + Send + Sync>;
 type OnCompletedCallback = Box<dyn Fn(UploadResult) + Send + Sync>;
 
+const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+
 enum BatchUploadTarget {
     File(File),
     Stream(Box<dyn Read + Send>),
+    Bytes(Vec<u8>),
 }
 
 /// 批量上传任务，包装一个上传任务供批量上传器负责上传
@@ -30,7 +44,7 @@ pub struct BatchUploadJob {
     upload_token: Option<UploadToken>,
     vars: HashMap<String, String>,
     metadata: HashMap<String, String>,
-    checksum_enabled: bool,
+    checksum_policy: ChecksumPolicy,
     resumable_policy: Option<ResumablePolicy>,
     file_name: String,
     mime: Option<Mime>,
@@ -38,6 +52,7 @@ pub struct BatchUploadJob {
     on_completed: Option<OnCompletedCallback>,
     target: BatchUploadTarget,
     expected_data_size: u64,
+    rate_limiter: Option<RateLimiter>,
 }
 
 /// 批量上传任务生成器，提供上传数据所需的多个参数
@@ -46,10 +61,11 @@ pub struct BatchUploadJobBuilder {
     upload_token: Option<UploadToken>,
     vars: HashMap<String, String>,
     metadata: HashMap<String, String>,
-    checksum_enabled: bool,
+    checksum_policy: ChecksumPolicy,
     on_uploading_progress: Option<OnUploadingProgressCallback>,
     on_completed: Option<OnCompletedCallback>,
     resumable_policy: Option<ResumablePolicy>,
+    rate_limiter: Option<RateLimiter>,
 }
 
 enum BatchUploaderCore {
@@ -64,6 +80,9 @@ struct BatchUploaderContext {
     core: BatchUploaderCore,
     max_concurrency: usize,
     thread_pool_size: usize,
+    upload_rate_limit_per_job: Option<u64>,
+    max_retries: usize,
+    retry_backoff: Duration,
 }
 
 /// 批量上传器，上传之前所有提交的任务
@@ -89,6 +108,9 @@ impl BatchUploader {
                 },
                 max_concurrency: 0,
                 thread_pool_size: 0,
+                upload_rate_limit_per_job: None,
+                max_retries: 0,
+                retry_backoff: DEFAULT_RETRY_BACKOFF,
             },
         })
     }
@@ -100,6 +122,9 @@ impl BatchUploader {
                 core: BatchUploaderCore::Bucket(bucket),
                 max_concurrency: 0,
                 thread_pool_size: 0,
+                upload_rate_limit_per_job: None,
+                max_retries: 0,
+                retry_backoff: DEFAULT_RETRY_BACKOFF,
             },
         }
     }
@@ -129,6 +154,35 @@ impl BatchUploader {
         self
     }
 
+    /// 限制每个上传任务的带宽
+    ///
+    /// 单位为字节每秒，每个上传任务都将使用各自独立的限速器，除非上传任务自行指定了限速器。
+    /// 该限制将与客户端配置中的全局限速器同时生效，如果需要限制批量上传的总带宽，请使用全局限速器。
+    /// 传入 `0` 表示不限速
+    pub fn upload_rate_limit_per_job(&mut self, bytes_per_second: u64) -> &mut Self {
+        self.context.upload_rate_limit_per_job =
+            Some(bytes_per_second).filter(|&bytes_per_second| bytes_per_second > 0);
+        self
+    }
+
+    /// 上传任务失败后的最大重试次数
+    ///
+    /// 默认不重试。仅当上传错误可重试（参见 `UploadError::is_retryable`）时才会重试，
+    /// 例如在仅能新增模式下对象已经存在（状态码 614），或上传凭证错误时将不会重试。
+    /// 此外，由于数据流无法倒回，上传数据流的任务总是不会重试
+    pub fn max_retries(&mut self, max_retries: usize) -> &mut Self {
+        self.context.max_retries = max_retries;
+        self
+    }
+
+    /// 重试上传任务前的退避时长
+    ///
+    /// 每次重试前的退避时长都将翻倍，但最长不超过 60 秒。默认为 1 秒
+    pub fn retry_backoff(&mut self, retry_backoff: Duration) -> &mut Self {
+        self.context.retry_backoff = retry_backoff;
+        self
+    }
+
     /// 提交上传任务
     pub fn push_job(&mut self, job: BatchUploadJob) -> &mut Self {
         self.jobs.push(job);
@@ -148,12 +202,336 @@ impl BatchUploader {
 
         thread_pool.scope(|s| {
             while let Some(job) = jobs.pop() {
-                s.spawn(|_| handle_job(context, job, &thread_pool))
+                s.spawn(|_| {
+                    let (outcome, on_completed) = handle_job(context, job, &thread_pool);
+                    if let Some(on_completed) = on_completed.as_ref() {
+                        on_completed(outcome.result);
+                    }
+                })
             }
         });
 
         self.jobs = jobs;
     }
+
+    /// 开始执行上传任务，并收集上传结果
+    ///
+    /// 与 `start` 相同，该方法会持续阻塞直到上传任务全部执行完毕（不保证执行顺序）。
+    /// 但上传结果将按照任务提交的顺序收集在返回的批量上传报告中，不会再回调上传任务内定义的 `on_completed`。
+    ///
+    /// 方法返回后，当前批量上传器的上传任务将被清空，但其他参数都将保留，可以重新添加任务并复用
+    pub fn start_and_collect(&mut self) -> BatchUploadReport {
+        let thread_pool = build_thread_pool(&self.context);
+        let context = &self.context;
+        let mut jobs = replace(&mut self.jobs, Vec::new());
+        let outcomes = Mutex::new(Vec::with_capacity(jobs.len()));
+
+        thread_pool.scope(|s| {
+            for (index, job) in jobs.drain(..).enumerate() {
+                let (thread_pool, outcomes) = (&thread_pool, &outcomes);
+                s.spawn(move |_| {
+                    let (outcome, _) = handle_job(context, job, thread_pool);
+                    outcomes.lock().unwrap().push((index, outcome));
+                })
+            }
+        });
+
+        self.jobs = jobs;
+
+        let mut outcomes = outcomes.into_inner().unwrap();
+        outcomes.sort_by_key(|(index, _)| *index);
+        BatchUploadReport {
+            outcomes: outcomes.into_iter().map(|(_, outcome)| outcome).collect(),
+        }
+    }
+
+    /// 在后台开始执行上传任务
+    ///
+    /// 与 `start` 不同，该方法不会阻塞，而是返回后台批量上传器，可以在上传期间持续提交新的上传任务。
+    /// 后台批量上传器通过容量为 `channel_capacity` 的有界通道接受上传任务，
+    /// 当正在执行的上传任务数量已经达到线程池大小，且通道已满时，提交上传任务将会阻塞，直到有上传任务执行完毕为止。
+    ///
+    /// 当前批量上传器中已经提交的任务将最先执行。
+    /// 上传结果将通过后台批量上传器返回，不会再回调上传任务内定义的 `on_completed`
+    pub fn start_in_background(self, channel_capacity: usize) -> BackgroundBatchUploader {
+        BackgroundBatchUploader::start(self.context, self.jobs, channel_capacity)
+    }
+}
+
+/// 后台批量上传器
+///
+/// 在后台执行上传任务，可以在上传期间持续提交新的上传任务，并同时获取已经完成的上传任务的执行结果。
+/// 所有任务提交完毕后，调用 `close` 停止接受新的上传任务，再调用 `join` 等待上传任务全部执行完毕。
+///
+/// 后台批量上传器被释放时，将停止接受新的上传任务，并等待已经提交的上传任务全部开始执行，但不会等待它们执行完毕。
+/// 停止接受新的上传任务后，即使还有未释放的后台批量上传任务发送器，也不会阻塞 `join` 或释放操作
+pub struct BackgroundBatchUploader {
+    job_sender: BatchUploadJobSender,
+    outcome_receiver: Receiver<BatchUploadJobOutcome>,
+    dispatcher: Option<JoinHandle<()>>,
+}
+
+/// 后台批量上传任务发送器
+///
+/// 可以克隆后发送到其他线程，以便在多个线程中向同一个后台批量上传器提交上传任务。
+/// 后台批量上传器停止接受新的上传任务后，通过发送器提交的上传任务也将被原样返回
+#[derive(Clone)]
+pub struct BatchUploadJobSender {
+    // 通道中的 `None` 表示停止接受新的上传任务，分派线程收到后即退出，无需等待所有发送器都被释放
+    sender: SyncSender<Option<BatchUploadJob>>,
+    closed: Arc<RwLock<bool>>,
+}
+
+impl BatchUploadJobSender {
+    /// 提交上传任务
+    ///
+    /// 如果通道已满，将会阻塞直到通道有空余为止。
+    /// 如果后台批量上传器已经停止接受新的上传任务，则将上传任务原样返回
+    pub fn submit(&self, job: BatchUploadJob) -> StdResult<(), BatchUploadJob> {
+        // 持有读锁直到发送完毕，确保停止接受新的上传任务前提交的任务都排在 `None` 之前
+        let closed = self.closed.read().unwrap();
+        if *closed {
+            return Err(job);
+        }
+        self.sender.send(Some(job)).map_err(|err| err.0.unwrap())
+    }
+
+    fn is_closed(&self) -> bool {
+        *self.closed.read().unwrap()
+    }
+
+    fn close(&self) {
+        let mut closed = self.closed.write().unwrap();
+        if !*closed {
+            *closed = true;
+            drop(closed);
+            let _ = self.sender.send(None);
+        }
+    }
+}
+
+impl BackgroundBatchUploader {
+    fn start(context: BatchUploaderContext, jobs: Vec<BatchUploadJob>, channel_capacity: usize) -> Self {
+        let (job_sender, job_receiver) = sync_channel(channel_capacity);
+        let (outcome_sender, outcome_receiver) = channel();
+        let dispatcher = ThreadBuilder::new()
+            .name("qiniu_ng_batch_uploader_dispatcher".into())
+            .spawn(move || dispatch_jobs(Arc::new(context), jobs, job_receiver, outcome_sender))
+            .unwrap();
+        Self {
+            job_sender: BatchUploadJobSender {
+                sender: job_sender,
+                closed: Arc::new(RwLock::new(false)),
+            },
+            outcome_receiver,
+            dispatcher: Some(dispatcher),
+        }
+    }
+
+    /// 提交上传任务
+    ///
+    /// 如果通道已满，将会阻塞直到通道有空余为止。
+    /// 如果已经调用过 `close`，则将上传任务原样返回
+    pub fn submit(&self, job: BatchUploadJob) -> StdResult<(), BatchUploadJob> {
+        self.job_sender.submit(job)
+    }
+
+    /// 创建后台批量上传任务发送器
+    ///
+    /// 如果已经调用过 `close`，则返回 `None`
+    pub fn job_sender(&self) -> Option<BatchUploadJobSender> {
+        if self.job_sender.is_closed() {
+            None
+        } else {
+            Some(self.job_sender.to_owned())
+        }
+    }
+
+    /// 获取一个已经完成的上传任务的执行结果，如果当前没有已经完成的上传任务，则立即返回 `None`
+    pub fn try_recv(&self) -> Option<BatchUploadJobOutcome> {
+        self.outcome_receiver.try_recv().ok()
+    }
+
+    /// 获取一个已经完成的上传任务的执行结果
+    ///
+    /// 如果当前没有已经完成的上传任务，将会阻塞直到有上传任务执行完毕为止。
+    /// 如果已经停止接受新的上传任务，且所有上传任务的执行结果均已经获取，则返回 `None`
+    pub fn recv(&self) -> Option<BatchUploadJobOutcome> {
+        self.outcome_receiver.recv().ok()
+    }
+
+    /// 迭代上传任务的执行结果，顺序与上传任务完成的顺序一致
+    ///
+    /// 迭代器将会阻塞等待上传任务执行完毕，直到停止接受新的上传任务，且所有上传任务的执行结果均已经获取为止
+    pub fn outcomes(&self) -> impl Iterator<Item = BatchUploadJobOutcome> + '_ {
+        self.outcome_receiver.iter()
+    }
+
+    /// 停止接受新的上传任务
+    ///
+    /// 已经提交的上传任务将继续执行，此后通过任何后台批量上传任务发送器提交的上传任务都将被原样返回。
+    /// 如果通道已满，将会阻塞直到通道有空余为止
+    pub fn close(&mut self) {
+        self.job_sender.close();
+    }
+
+    /// 停止接受新的上传任务，并等待所有上传任务执行完毕
+    ///
+    /// 尚未获取的上传任务执行结果将按照完成的顺序收集在返回的批量上传报告中
+    pub fn join(mut self) -> BatchUploadReport {
+        self.close();
+        if let Some(dispatcher) = self.dispatcher.take() {
+            dispatcher.join().unwrap();
+        }
+        BatchUploadReport {
+            outcomes: self.outcome_receiver.iter().collect(),
+        }
+    }
+}
+
+impl Drop for BackgroundBatchUploader {
+    fn drop(&mut self) {
+        self.close();
+        if let Some(dispatcher) = self.dispatcher.take() {
+            let _ = dispatcher.join();
+        }
+    }
+}
+
+/// 向线程池分派上传任务
+///
+/// 正在执行的上传任务数量不会超过线程池大小，从而在线程池繁忙时阻塞通道，令提交上传任务的一方等待
+fn dispatch_jobs(
+    context: Arc<BatchUploaderContext>,
+    jobs: Vec<BatchUploadJob>,
+    job_receiver: Receiver<Option<BatchUploadJob>>,
+    outcome_sender: Sender<BatchUploadJobOutcome>,
+) {
+    let thread_pool = build_shared_thread_pool(&context);
+    let max_running_jobs = thread_pool.current_num_threads().max(1);
+    let running_jobs = Arc::new((Mutex::new(0usize), Condvar::new()));
+
+    for job in jobs
+        .into_iter()
+        .chain(job_receiver.iter().take_while(Option::is_some).flatten())
+    {
+        {
+            let (count, condvar) = running_jobs.as_ref();
+            let mut count = count.lock().unwrap();
+            while *count >= max_running_jobs {
+                count = condvar.wait(count).unwrap();
+            }
+            *count += 1;
+        }
+        let context = context.to_owned();
+        let worker_thread_pool = thread_pool.to_owned();
+        let running_jobs = running_jobs.to_owned();
+        let outcome_sender = outcome_sender.to_owned();
+        thread_pool.spawn(move || {
+            let (outcome, _) = handle_job(&context, job, &worker_thread_pool);
+            let _ = outcome_sender.send(outcome);
+            let (count, condvar) = running_jobs.as_ref();
+            *count.lock().unwrap() -= 1;
+            condvar.notify_one();
+        });
+    }
+}
+
+/// 批量上传任务执行结果
+#[derive(Debug)]
+pub struct BatchUploadJobOutcome {
+    key: Option<String>,
+    file_name: String,
+    result: UploadResult,
+    attempts: usize,
+    duration: Duration,
+    data_size: u64,
+}
+
+impl BatchUploadJobOutcome {
+    /// 上传任务指定的对象名称
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
+    /// 上传任务指定的文件名称
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    /// 上传结果，如果上传任务经过重试，则为最后一次上传的结果
+    pub fn result(&self) -> &UploadResult {
+        &self.result
+    }
+
+    /// 获取上传结果的所有权
+    pub fn into_result(self) -> UploadResult {
+        self.result
+    }
+
+    /// 上传是否成功
+    pub fn is_success(&self) -> bool {
+        self.result.is_ok()
+    }
+
+    /// 上传次数，包含首次上传和所有重试
+    pub fn attempts(&self) -> usize {
+        self.attempts
+    }
+
+    /// 上传任务耗时，包含所有重试及重试前的退避时长
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// 上传数据尺寸，单位为字节
+    ///
+    /// 对于数据尺寸不可预知的数据流，该值为 `0`
+    pub fn data_size(&self) -> u64 {
+        self.data_size
+    }
+}
+
+/// 批量上传报告
+#[derive(Debug, Default)]
+pub struct BatchUploadReport {
+    outcomes: Vec<BatchUploadJobOutcome>,
+}
+
+impl BatchUploadReport {
+    /// 所有上传任务的执行结果，顺序与任务提交的顺序一致
+    pub fn outcomes(&self) -> &[BatchUploadJobOutcome] {
+        &self.outcomes
+    }
+
+    /// 获取所有上传任务执行结果的所有权
+    pub fn into_outcomes(self) -> Vec<BatchUploadJobOutcome> {
+        self.outcomes
+    }
+
+    /// 上传成功的任务数量
+    pub fn succeeded_count(&self) -> usize {
+        self.outcomes.iter().filter(|outcome| outcome.is_success()).count()
+    }
+
+    /// 上传失败的任务数量
+    pub fn failed_count(&self) -> usize {
+        self.outcomes.len() - self.succeeded_count()
+    }
+
+    /// 是否全部任务均上传成功
+    pub fn is_success(&self) -> bool {
+        self.outcomes.iter().all(|outcome| outcome.is_success())
+    }
+
+    /// 上传成功的任务的数据尺寸总和，单位为字节
+    pub fn uploaded_bytes(&self) -> u64 {
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.is_success())
+            .map(|outcome| outcome.data_size)
+            .sum()
+    }
 }
 
 /// 构建线程池
@@ -168,88 +546,160 @@ fn build_thread_pool(context: &BatchUploaderContext) -> Ron<'_, ThreadPool> {
         .thread_pool()
         .filter(|pool| pool.current_num_threads() > 1)
         .map(|pool| Ron::Referenced(pool.as_ref()))
-        .unwrap_or_else(|| {
-            let mut builder = ThreadPoolBuilder::new();
-            if context.thread_pool_size > 0 {
-                builder = builder.num_threads(context.thread_pool_size);
-            }
-            Ron::Owned(
-                builder
-                    .thread_name(|index| format!("qiniu_ng_batch_uploader_worker_{}", index))
-                    .build()
-                    .unwrap(),
-            )
-        })
+        .unwrap_or_else(|| Ron::Owned(new_thread_pool(context)))
+}
+
+/// 构建可在后台批量上传器的各个线程间共享的线程池，策略与 `build_thread_pool` 一致
+fn build_shared_thread_pool(context: &BatchUploaderContext) -> Arc<ThreadPool> {
+    context
+        .core
+        .thread_pool()
+        .filter(|pool| pool.current_num_threads() > 1)
+        .cloned()
+        .unwrap_or_else(|| Arc::new(new_thread_pool(context)))
 }
 
-fn handle_job(context: &BatchUploaderContext, job: BatchUploadJob, thread_pool: &ThreadPool) {
+fn new_thread_pool(context: &BatchUploaderContext) -> ThreadPool {
+    let mut builder = ThreadPoolBuilder::new();
+    if context.thread_pool_size > 0 {
+        builder = builder.num_threads(context.thread_pool_size);
+    }
+    builder
+        .thread_name(|index| format!("qiniu_ng_batch_uploader_worker_{}", index))
+        .build()
+        .unwrap()
+}
+
+fn handle_job(
+    context: &BatchUploaderContext,
+    job: BatchUploadJob,
+    thread_pool: &ThreadPool,
+) -> (BatchUploadJobOutcome, Option<OnCompletedCallback>) {
     let BatchUploadJob {
         key,
         upload_token,
         vars,
         metadata,
-        checksum_enabled,
+        checksum_policy,
         resumable_policy,
         file_name,
         mime,
-        target,
+        mut target,
         expected_data_size,
         on_uploading_progress,
         on_completed,
+        rate_limiter,
     } = job;
+    let rate_limiter = rate_limiter.or_else(|| context.upload_rate_limit_per_job.map(RateLimiter::new));
+    let begin_at = Instant::now();
+    let mut attempts = 0;
 
-    let mut object_uploader = match &context.core {
+    let result = loop {
+        attempts += 1;
+        let mut object_uploader = new_object_uploader(context, upload_token.as_ref(), thread_pool);
+        if let Some(key) = key.as_ref() {
+            object_uploader = object_uploader.key(key.as_str());
+        }
+        for (var_name, var_value) in vars.iter() {
+            object_uploader = object_uploader.var(var_name.as_str(), var_value.as_str());
+        }
+        for (metadata_name, metadata_value) in metadata.iter() {
+            object_uploader = object_uploader.metadata(metadata_name.as_str(), metadata_value.as_str());
+        }
+        object_uploader = object_uploader.checksum_policy(checksum_policy);
+        if let Some(on_uploading_progress) = on_uploading_progress.as_deref() {
+            object_uploader = object_uploader.on_progress_ref(on_uploading_progress);
+        }
+        if let Some(rate_limiter) = rate_limiter.as_ref() {
+            object_uploader = object_uploader.rate_limiter(rate_limiter.to_owned());
+        }
+        if let Some(resumable_policy) = resumable_policy.as_ref() {
+            match resumable_policy {
+                ResumablePolicy::Threshold(threshold) => {
+                    object_uploader = object_uploader.upload_threshold(*threshold);
+                }
+                ResumablePolicy::Never => {
+                    object_uploader = object_uploader.never_be_resumable();
+                }
+                ResumablePolicy::Always => {
+                    object_uploader = object_uploader.always_be_resumable();
+                }
+            }
+        }
+        let upload_result = match &mut target {
+            BatchUploadTarget::File(file) => match file.seek(SeekFrom::Start(0)) {
+                Ok(_) => object_uploader.upload_stream(file, expected_data_size, file_name.as_str(), mime.to_owned()),
+                Err(err) => Err(err.into()),
+            },
+            BatchUploadTarget::Stream(reader) => {
+                object_uploader.upload_stream(reader, expected_data_size, file_name.as_str(), mime.to_owned())
+            }
+            BatchUploadTarget::Bytes(bytes) => {
+                object_uploader.upload_bytes(bytes.as_slice(), file_name.as_str(), mime.to_owned())
+            }
+        };
+        match upload_result {
+            Err(err) if attempts <= context.max_retries && target.is_rewindable() && err.is_retryable() => {
+                sleep(retry_backoff(context.retry_backoff, attempts));
+            }
+            upload_result => break upload_result,
+        }
+    };
+
+    (
+        BatchUploadJobOutcome {
+            key,
+            file_name,
+            result,
+            attempts,
+            duration: begin_at.elapsed(),
+            data_size: expected_data_size,
+        },
+        on_completed,
+    )
+}
+
+fn new_object_uploader<'a>(
+    context: &'a BatchUploaderContext,
+    upload_token: Option<&'a UploadToken>,
+    thread_pool: &'a ThreadPool,
+) -> ObjectUploader<'a> {
+    let object_uploader = match &context.core {
         BatchUploaderCore::UploadManager {
             upload_manager,
             upload_token: context_upload_token,
         } => upload_manager
-            .upload_for_upload_token(
-                upload_token
-                    .map(Cow::Owned)
-                    .unwrap_or_else(|| Cow::Borrowed(context_upload_token)),
-            )
+            .upload_for_upload_token(Cow::Borrowed(upload_token.unwrap_or(context_upload_token)))
             .unwrap(),
-        BatchUploaderCore::Bucket(bucket) => bucket.uploader(),
+        BatchUploaderCore::Bucket(bucket) => match upload_token {
+            Some(upload_token) => bucket
+                .upload_manager()
+                .upload_for_internal_generated_upload_token_with_regions(
+                    bucket.name().into(),
+                    Cow::Borrowed(upload_token),
+                    bucket.regions().ok(),
+                ),
+            None => bucket.uploader(),
+        },
     };
-    object_uploader = object_uploader
+    object_uploader
         .thread_pool(thread_pool)
-        .max_concurrency(context.max_concurrency);
-    if let Some(key) = key {
-        object_uploader = object_uploader.key(key);
-    }
-    for (var_name, var_value) in vars.into_iter() {
-        object_uploader = object_uploader.var(var_name, var_value);
-    }
-    for (metadata_name, metadata_value) in metadata.into_iter() {
-        object_uploader = object_uploader.metadata(metadata_name, metadata_value);
-    }
-    if checksum_enabled {
-        object_uploader = object_uploader.enable_checksum();
-    } else {
-        object_uploader = object_uploader.disable_checksum();
-    }
-    if let Some(on_uploading_progress) = on_uploading_progress {
-        object_uploader = object_uploader.on_progress(on_uploading_progress);
-    }
-    if let Some(resumable_policy) = resumable_policy {
-        match resumable_policy {
-            ResumablePolicy::Threshold(threshold) => {
-                object_uploader = object_uploader.upload_threshold(threshold);
-            }
-            ResumablePolicy::Never => {
-                object_uploader = object_uploader.never_be_resumable();
-            }
-            ResumablePolicy::Always => {
-                object_uploader = object_uploader.always_be_resumable();
-            }
-        }
-    }
-    let upload_result = match target {
-        BatchUploadTarget::File(file) => object_uploader.upload_stream(file, expected_data_size, file_name, mime),
-        BatchUploadTarget::Stream(reader) => object_uploader.upload_stream(reader, expected_data_size, file_name, mime),
-    };
-    if let Some(on_completed) = on_completed.as_ref() {
-        on_completed(upload_result);
+        .max_concurrency(context.max_concurrency)
+}
+
+/// 计算第 `attempts` 次上传失败后的退避时长，每次翻倍，但不超过 `MAX_RETRY_BACKOFF`
+fn retry_backoff(base: Duration, attempts: usize) -> Duration {
+    let factor = 1u32
+        .checked_shl(attempts.saturating_sub(1) as u32)
+        .unwrap_or(u32::max_value());
+    base.checked_mul(factor)
+        .map(|backoff| backoff.min(MAX_RETRY_BACKOFF))
+        .unwrap_or(MAX_RETRY_BACKOFF)
+}
+
+impl BatchUploadTarget {
+    fn is_rewindable(&self) -> bool {
+        !matches!(self, Self::Stream(_))
     }
 }
 
@@ -260,10 +710,11 @@ impl Default for BatchUploadJobBuilder {
             upload_token: None,
             vars: HashMap::new(),
             metadata: HashMap::new(),
-            checksum_enabled: true,
+            checksum_policy: Default::default(),
             on_uploading_progress: None,
             on_completed: None,
             resumable_policy: None,
+            rate_limiter: None,
         }
     }
 }
@@ -334,7 +785,7 @@ impl BatchUploadJobBuilder {
     ///
     /// 在任何场景下都不推荐禁用上传数据校验
     pub fn disable_checksum(mut self) -> Self {
-        self.checksum_enabled = false;
+        self.checksum_policy = ChecksumPolicy::disabled();
         self
     }
 
@@ -342,7 +793,15 @@ impl BatchUploadJobBuilder {
     ///
     /// 默认总是启用，在任何场景下都不推荐禁用上传数据校验
     pub fn enable_checksum(mut self) -> Self {
-        self.checksum_enabled = true;
+        self.checksum_policy = Default::default();
+        self
+    }
+
+    /// 指定上传数据校验策略
+    ///
+    /// 由于批量上传器总是以数据流的方式上传文件，Etag 校验仅对上传内存数据的任务有效
+    pub fn checksum_policy(mut self, checksum_policy: ChecksumPolicy) -> Self {
+        self.checksum_policy = checksum_policy;
         self
     }
 
@@ -373,6 +832,24 @@ impl BatchUploadJobBuilder {
         self
     }
 
+    /// 为当前上传任务指定带宽限速器
+    ///
+    /// 该限速器将与客户端配置中的全局限速器同时生效
+    pub fn rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// 限制当前上传任务的带宽
+    ///
+    /// 单位为字节每秒，该限制将与客户端配置中的全局限速器同时生效。传入 `0` 表示不限速
+    pub fn upload_rate_limit(mut self, bytes_per_second: u64) -> Self {
+        self.rate_limiter = Some(bytes_per_second)
+            .filter(|&bytes_per_second| bytes_per_second > 0)
+            .map(RateLimiter::new);
+        self
+    }
+
     /// 上传进度回调
     ///
     /// 将在上传期间反复回调指定的闭包，以获取上传进度。
@@ -407,10 +884,11 @@ impl BatchUploadJobBuilder {
             upload_token: self.upload_token,
             vars: self.vars,
             metadata: self.metadata,
-            checksum_enabled: self.checksum_enabled,
+            checksum_policy: self.checksum_policy,
             resumable_policy: self.resumable_policy,
             on_uploading_progress: self.on_uploading_progress,
             on_completed: self.on_completed,
+            rate_limiter: self.rate_limiter,
             file_name: file_name.into(),
             mime,
             expected_data_size: file.metadata()?.len(),
@@ -434,16 +912,44 @@ impl BatchUploadJobBuilder {
             upload_token: self.upload_token,
             vars: self.vars,
             metadata: self.metadata,
-            checksum_enabled: self.checksum_enabled,
+            checksum_policy: self.checksum_policy,
             resumable_policy: self.resumable_policy,
             on_uploading_progress: self.on_uploading_progress,
             on_completed: self.on_completed,
+            rate_limiter: self.rate_limiter,
             file_name: file_name.into(),
             mime,
             expected_data_size: size,
             target: BatchUploadTarget::Stream(Box::new(stream)),
         }
     }
+
+    /// 上传内存数据
+    ///
+    /// 该方法用于生成批量上传任务，用于上传内存中的数据
+    pub fn upload_bytes(
+        self,
+        bytes: impl Into<Vec<u8>>,
+        file_name: impl Into<String>,
+        mime: Option<Mime>,
+    ) -> BatchUploadJob {
+        let bytes = bytes.into();
+        BatchUploadJob {
+            key: self.key,
+            upload_token: self.upload_token,
+            vars: self.vars,
+            metadata: self.metadata,
+            checksum_policy: self.checksum_policy,
+            resumable_policy: self.resumable_policy,
+            on_uploading_progress: self.on_uploading_progress,
+            on_completed: self.on_completed,
+            rate_limiter: self.rate_limiter,
+            file_name: file_name.into(),
+            mime,
+            expected_data_size: bytes.len() as u64,
+            target: BatchUploadTarget::Bytes(bytes),
+        }
+    }
 }
 
 impl BatchUploaderCore {
@@ -454,3 +960,223 @@ impl BatchUploaderCore {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{
+            super::{bucket::BucketBuilder, region::Region},
+            UploadError,
+        },
+        *,
+    };
+    use crate::{
+        config::ConfigBuilder,
+        http::{DomainsManagerBuilder, ErrorKind as HTTPErrorKind, HeadersOwned, Method},
+        utils::{etag, mime},
+    };
+    use matches::matches;
+    use qiniu_http::ResponseBuilder;
+    use qiniu_test_utils::http_call_mock::{fake_req_id, CallHandlers};
+    use serde_json::json;
+    use std::{
+        error::Error,
+        result::Result,
+        sync::atomic::{AtomicUsize, Ordering::Relaxed},
+    };
+
+    #[test]
+    fn test_storage_uploader_batch_uploader_start_and_collect_with_retries() -> Result<(), Box<dyn Error>> {
+        let data = b"hello qiniu".to_vec();
+        let expected_etag = etag::from_bytes(&data);
+        let retried_calls = AtomicUsize::new(0);
+        let config = ConfigBuilder::default()
+            .http_request_handler(
+                CallHandlers::new(|request| {
+                    panic!("Unexpected Request: {} {}", request.method(), request.url());
+                })
+                .install(Method::POST, "^https?://[^/]+/?$", move |request, _| {
+                    let body = String::from_utf8_lossy(&request.body().to_bytes()).into_owned();
+                    let mut headers = HeadersOwned::new();
+                    headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                    headers.insert("X-Reqid".into(), fake_req_id());
+                    if body.contains("existed-key") {
+                        return Ok(ResponseBuilder::default()
+                            .status_code(614u16)
+                            .headers(headers)
+                            .bytes_as_body(json!({"error": "file exists"}).to_string())
+                            .build());
+                    }
+                    let hash = if retried_calls.fetch_add(1, Relaxed) == 0 {
+                        "wrong_hash".to_owned()
+                    } else {
+                        expected_etag.to_owned()
+                    };
+                    Ok(ResponseBuilder::default()
+                        .status_code(200u16)
+                        .headers(headers)
+                        .bytes_as_body(json!({"hash": hash, "key": "retried-key"}).to_string())
+                        .build())
+                }),
+            )
+            .upload_logger(None)
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let bucket = BucketBuilder::new(
+            "test-bucket".into(),
+            Credential::new("abcdefghklmnopq", "1234567890"),
+            UploadManager::new(config),
+        )
+        .region(Region::z0())
+        .build();
+
+        let mut batch_uploader = bucket.batch_uploader();
+        batch_uploader
+            .max_retries(3)
+            .retry_backoff(Duration::from_millis(1))
+            .push_job(
+                BatchUploadJobBuilder::default()
+                    .key("retried-key")
+                    .checksum_policy(ChecksumPolicy::default().etag_verification(true))
+                    .upload_bytes(data.to_owned(), "retried", None),
+            )
+            .push_job(
+                BatchUploadJobBuilder::default()
+                    .key("existed-key")
+                    .upload_bytes(data, "existed", None),
+            );
+        let report = batch_uploader.start_and_collect();
+
+        assert_eq!(report.outcomes().len(), 2);
+        assert_eq!(report.succeeded_count(), 1);
+        assert_eq!(report.failed_count(), 1);
+        assert!(!report.is_success());
+        assert_eq!(report.uploaded_bytes(), 11);
+
+        let retried = &report.outcomes()[0];
+        assert_eq!(retried.key(), Some("retried-key"));
+        assert_eq!(retried.attempts(), 2);
+        assert!(retried.is_success());
+
+        let existed = &report.outcomes()[1];
+        assert_eq!(existed.key(), Some("existed-key"));
+        assert_eq!(existed.attempts(), 1);
+        match existed.result() {
+            Err(UploadError::QiniuError(err)) => {
+                assert!(matches!(
+                    err.error_kind(),
+                    HTTPErrorKind::ResponseStatusCodeError(614, _)
+                ));
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_storage_uploader_batch_uploader_start_in_background() -> Result<(), Box<dyn Error>> {
+        let config = ConfigBuilder::default()
+            .http_request_handler(
+                CallHandlers::new(|request| {
+                    panic!("Unexpected Request: {} {}", request.method(), request.url());
+                })
+                .install(Method::POST, "^https?://[^/]+/?$", |request, called| {
+                    if called > 5 {
+                        panic!("Unexpected call `POST {}` for {} times", request.url(), called);
+                    }
+                    let mut headers = HeadersOwned::new();
+                    headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                    headers.insert("X-Reqid".into(), fake_req_id());
+                    Ok(ResponseBuilder::default()
+                        .status_code(200u16)
+                        .headers(headers)
+                        .bytes_as_body(json!({"hash": "form_hash", "key": "test-key"}).to_string())
+                        .build())
+                }),
+            )
+            .upload_logger(None)
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let bucket = BucketBuilder::new(
+            "test-bucket".into(),
+            Credential::new("abcdefghklmnopq", "1234567890"),
+            UploadManager::new(config),
+        )
+        .region(Region::z0())
+        .build();
+
+        let mut batch_uploader = bucket.batch_uploader();
+        batch_uploader
+            .thread_pool_size(2)
+            .push_job(
+                BatchUploadJobBuilder::default()
+                    .key("key-0")
+                    .upload_bytes(b"0".to_vec(), "", None),
+            );
+        let mut background_uploader = batch_uploader.start_in_background(1);
+        let job_sender = background_uploader.job_sender().unwrap();
+        let idle_job_sender = background_uploader.job_sender().unwrap();
+        let producer = std::thread::spawn(move || {
+            for i in 1..4 {
+                let job = BatchUploadJobBuilder::default().key(format!("key-{}", i)).upload_bytes(
+                    i.to_string().into_bytes(),
+                    "",
+                    None,
+                );
+                assert!(job_sender.submit(job).is_ok());
+            }
+        });
+        assert!(background_uploader
+            .submit(
+                BatchUploadJobBuilder::default()
+                    .key("key-4")
+                    .upload_bytes(b"4".to_vec(), "", None)
+            )
+            .is_ok());
+        let first_outcome = background_uploader.recv().unwrap();
+        assert!(first_outcome.is_success());
+        producer.join().unwrap();
+
+        background_uploader.close();
+        assert!(background_uploader
+            .submit(
+                BatchUploadJobBuilder::default()
+                    .key("key-5")
+                    .upload_bytes(b"5".to_vec(), "", None)
+            )
+            .is_err());
+        assert!(background_uploader.job_sender().is_none());
+        // 停止接受新的上传任务后，未释放的发送器既不能再提交任务，也不会阻塞 `join`
+        assert!(idle_job_sender
+            .submit(
+                BatchUploadJobBuilder::default()
+                    .key("key-6")
+                    .upload_bytes(b"6".to_vec(), "", None)
+            )
+            .is_err());
+        let report = background_uploader.join();
+        drop(idle_job_sender);
+        assert_eq!(report.outcomes().len(), 4);
+        assert!(report.is_success());
+
+        let mut keys = report
+            .outcomes()
+            .iter()
+            .chain(std::iter::once(&first_outcome))
+            .map(|outcome| outcome.key().unwrap().to_owned())
+            .collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(keys, vec!["key-0", "key-1", "key-2", "key-3", "key-4"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_storage_uploader_batch_uploader_retry_backoff() {
+        let base = Duration::from_secs(1);
+        assert_eq!(retry_backoff(base, 1), Duration::from_secs(1));
+        assert_eq!(retry_backoff(base, 2), Duration::from_secs(2));
+        assert_eq!(retry_backoff(base, 4), Duration::from_secs(8));
+        assert_eq!(retry_backoff(base, 7), MAX_RETRY_BACKOFF);
+        assert_eq!(retry_backoff(base, 100), MAX_RETRY_BACKOFF);
+    }
+}
@@ -0,0 +1,103 @@
+/// 分片数据校验算法
+///
+/// 分片上传接口各自仅支持一种校验算法，如果指定的算法不被当前使用的接口支持，将改用接口支持的算法
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PartChecksumAlgorithm {
+    /// 不校验分片数据
+    None,
+
+    /// 使用 MD5 校验分片数据
+    ///
+    /// 对于分片上传 V2，将通过 `Content-MD5` 请求头将分片的 MD5 值交由七牛服务器校验。
+    /// 由于分片上传 V1 的接口不支持 MD5 校验，此时将改用 CRC32 校验
+    Md5,
+
+    /// 使用 CRC32 校验分片数据
+    ///
+    /// 对于分片上传 V1，将比对七牛服务器返回的每个片的 CRC32 值与本地计算的 CRC32 值，如果不一致将重新上传该片。
+    /// 由于分片上传 V2 的接口不支持 CRC32 校验，此时将改用 MD5 校验
+    Crc32,
+}
+
+/// 上传数据校验策略
+///
+/// 分别控制表单上传的 CRC32 校验，分片上传的分片数据校验，以及上传完毕后的整体 Etag 校验
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ChecksumPolicy {
+    form_crc32: bool,
+    part_checksum: PartChecksumAlgorithm,
+    etag_verification: bool,
+}
+
+impl Default for ChecksumPolicy {
+    /// 默认策略
+    ///
+    /// 启用表单上传的 CRC32 校验和分片数据的 MD5 校验，不启用 Etag 校验
+    fn default() -> Self {
+        ChecksumPolicy {
+            form_crc32: true,
+            part_checksum: PartChecksumAlgorithm::Md5,
+            etag_verification: false,
+        }
+    }
+}
+
+impl ChecksumPolicy {
+    /// 禁用全部校验
+    ///
+    /// 在任何场景下都不推荐禁用上传数据校验
+    pub fn disabled() -> Self {
+        ChecksumPolicy {
+            form_crc32: false,
+            part_checksum: PartChecksumAlgorithm::None,
+            etag_verification: false,
+        }
+    }
+
+    /// 是否在表单上传时附带 CRC32 校验值，交由七牛服务器校验
+    ///
+    /// 仅对上传文件和上传内存数据有效，对于无法倒回的数据流，将不会计算 CRC32 校验值
+    pub fn form_crc32(mut self, enabled: bool) -> Self {
+        self.form_crc32 = enabled;
+        self
+    }
+
+    /// 指定分片数据校验算法
+    pub fn part_checksum(mut self, algorithm: PartChecksumAlgorithm) -> Self {
+        self.part_checksum = algorithm;
+        self
+    }
+
+    /// 是否在上传完毕后比对七牛服务器返回的文件 Hash 值与本地计算的 Etag
+    ///
+    /// 如果两者不一致，上传将返回 `UploadError::EtagMismatchError`。
+    /// 该校验需要额外读取一次全部数据，因此仅对上传文件和上传内存数据有效。
+    /// 此外，以下情况将跳过校验：
+    ///
+    /// * 上传策略中自定义的 `returnBody` 中不包含 `hash` 字段
+    /// * 使用分片上传 V2 且分片尺寸不为 4 MB，此时七牛服务器返回的 Hash 值并非标准的 Etag
+    pub fn etag_verification(mut self, enabled: bool) -> Self {
+        self.etag_verification = enabled;
+        self
+    }
+
+    /// 表单上传时是否附带 CRC32 校验值
+    pub fn is_form_crc32_enabled(&self) -> bool {
+        self.form_crc32
+    }
+
+    /// 分片数据校验算法
+    pub fn part_checksum_algorithm(&self) -> PartChecksumAlgorithm {
+        self.part_checksum
+    }
+
+    /// 是否校验分片数据
+    pub fn is_part_checksum_enabled(&self) -> bool {
+        self.part_checksum != PartChecksumAlgorithm::None
+    }
+
+    /// 上传完毕后是否校验 Etag
+    pub fn is_etag_verification_enabled(&self) -> bool {
+        self.etag_verification
+    }
+}
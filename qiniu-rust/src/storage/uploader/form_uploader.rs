@@ -1,27 +1,31 @@
 use super::{
-    upload_response_callback, TokenizedUploadLogger, UpType, UploadError, UploadLoggerRecordBuilder, UploadManager,
-    UploadResponse, UploadToken,
+    upload_request::{parse_upload_response, UploadRequest, UploadingProgress},
+    TokenizedUploadLogger, UpType, UploadError, UploadManager, UploadResponse, UploadThrottle, UploadToken,
 };
 use crate::{
     http::{Error as HTTPError, Result as HTTPResult, RetryKind},
-    utils::crc32,
+    utils::{crc32, rate_limiter::RateLimiter},
 };
+use matches::matches;
 use mime::Mime;
+use qiniu_http::RequestBody;
 use qiniu_multipart::client::lazy::Multipart;
-use serde_json::Value;
 use std::{
     borrow::Cow,
     convert::TryInto,
-    io::{Read, Seek, SeekFrom},
+    io::{empty, Read, Seek, SeekFrom},
     result::Result,
+    time::Instant,
 };
 
 pub(super) struct FormUploaderBuilder<'u> {
     upload_manager: &'u UploadManager,
     up_urls_list: &'u [Box<[Box<str>]>],
     multipart: Multipart<'u, 'u>,
-    on_uploading_progress: Option<&'u dyn Fn(u64, Option<u64>)>,
+    on_uploading_progress: Option<&'u (dyn Fn(u64, Option<u64>) + Send + Sync)>,
     upload_logger: Option<TokenizedUploadLogger>,
+    throttle: UploadThrottle<'u>,
+    deadline: Option<Instant>,
 }
 
 #[must_use]
@@ -29,9 +33,11 @@ pub(super) struct FormUploader<'u> {
     upload_manager: &'u UploadManager,
     up_urls_list: &'u [Box<[Box<str>]>],
     content_type: String,
-    body: Vec<u8>,
-    on_uploading_progress: Option<&'u dyn Fn(u64, Option<u64>)>,
+    body: RequestBody<'u>,
+    uploading_progress: Option<UploadingProgress<'u>>,
     upload_logger: Option<TokenizedUploadLogger>,
+    throttle: UploadThrottle<'u>,
+    deadline: Option<Instant>,
 }
 
 impl<'u> FormUploaderBuilder<'u> {
@@ -46,6 +52,11 @@ impl<'u> FormUploaderBuilder<'u> {
             up_urls_list,
             multipart: Multipart::new(),
             on_uploading_progress: None,
+            throttle: UploadThrottle::new(upload_manager),
+            deadline: upload_manager
+                .config()
+                .operation_timeout()
+                .map(|timeout| Instant::now() + timeout),
             upload_logger: upload_manager.config().upload_logger().as_ref().map(|upload_logger| {
                 upload_logger.tokenize(upload_token.to_owned().into(), upload_manager.http_client().to_owned())
             }),
@@ -72,11 +83,24 @@ impl<'u> FormUploaderBuilder<'u> {
         self
     }
 
-    pub(super) fn on_uploading_progress(mut self, callback: &'u dyn Fn(u64, Option<u64>)) -> FormUploaderBuilder<'u> {
+    pub(super) fn on_uploading_progress(
+        mut self,
+        callback: &'u (dyn Fn(u64, Option<u64>) + Send + Sync),
+    ) -> FormUploaderBuilder<'u> {
         self.on_uploading_progress = Some(callback);
         self
     }
 
+    pub(super) fn rate_limiter(mut self, rate_limiter: &'u RateLimiter) -> FormUploaderBuilder<'u> {
+        self.throttle = self.throttle.job_rate_limiter(rate_limiter);
+        self
+    }
+
+    pub(super) fn deadline(mut self, deadline: Instant) -> FormUploaderBuilder<'u> {
+        self.deadline = Some(deadline);
+        self
+    }
+
     pub(super) fn seekable_stream(
         mut self,
         mut stream: impl Read + Seek + 'u,
@@ -94,7 +118,8 @@ impl<'u> FormUploaderBuilder<'u> {
         if let Some(crc32) = crc32 {
             self.multipart.add_text("crc32", crc32.to_string());
         }
-        self.upload_multipart()
+        let capacity = self.default_body_capacity();
+        self.upload_multipart(capacity)
     }
 
     pub(super) fn stream(
@@ -109,27 +134,67 @@ impl<'u> FormUploaderBuilder<'u> {
         if let Some(crc32) = crc32 {
             self.multipart.add_text("crc32", crc32.to_string());
         }
-        self.upload_multipart()
+        let capacity = self.default_body_capacity();
+        self.upload_multipart(capacity)
+    }
+
+    pub(super) fn bytes(
+        mut self,
+        bytes: &'u [u8],
+        file_name: Cow<'u, str>,
+        mime: Option<Mime>,
+        checksum_enabled: bool,
+    ) -> Result<FormUploader<'u>, UploadError> {
+        let file_name = if file_name.is_empty() { None } else { Some(file_name) };
+        // 文件字段的数据以空数据流占位，仅序列化其他表单字段，内存数据将作为独立的片段嵌入请求体，无需复制
+        self.multipart.add_stream("file", empty(), file_name, mime);
+        if checksum_enabled {
+            self.multipart.add_text("crc32", crc32::from_bytes(bytes).to_string());
+        }
+        let (boundary, mut prefix) = self.read_multipart(1 << 12)?;
+        // 序列化后的表单依次为文本字段，文件字段和结束分隔符，因此文件数据应当插入到结束分隔符之前
+        let end_boundary_size = "\r\n--".len() + boundary.len() + "--".len();
+        debug_assert!(prefix.ends_with(format!("\r\n--{}--", boundary).as_bytes()));
+        let suffix = prefix.split_off(prefix.len().saturating_sub(end_boundary_size));
+        let body = RequestBody::from_segments(vec![Cow::Owned(prefix), Cow::Borrowed(bytes), Cow::Owned(suffix)]);
+        Ok(self.form_uploader(&boundary, body))
     }
 
-    fn upload_multipart(mut self) -> Result<FormUploader<'u>, UploadError> {
+    fn default_body_capacity(&self) -> usize {
+        self.upload_manager
+            .config()
+            .upload_threshold()
+            .try_into()
+            .unwrap_or(1 << 22)
+    }
+
+    fn upload_multipart(mut self, capacity: usize) -> Result<FormUploader<'u>, UploadError> {
+        let (boundary, body) = self.read_multipart(capacity)?;
+        Ok(self.form_uploader(&boundary, body.into()))
+    }
+
+    // 序列化全部表单字段，返回分隔符和序列化后的表单
+    fn read_multipart(&mut self, capacity: usize) -> Result<(String, Vec<u8>), UploadError> {
         let mut fields = self.multipart.prepare().map_err(|err| err.error)?;
-        let mut body = Vec::with_capacity(
-            self.upload_manager
-                .config()
-                .upload_threshold()
-                .try_into()
-                .unwrap_or(1 << 22),
-        );
+        let mut body = Vec::with_capacity(capacity);
         fields.read_to_end(&mut body)?;
-        Ok(FormUploader {
+        Ok((fields.boundary().to_owned(), body))
+    }
+
+    fn form_uploader(self, boundary: &str, body: RequestBody<'u>) -> FormUploader<'u> {
+        let body_size = body.len().try_into().unwrap_or(u64::max_value());
+        FormUploader {
             upload_manager: self.upload_manager,
             up_urls_list: self.up_urls_list,
-            content_type: "multipart/form-data; boundary=".to_owned() + fields.boundary(),
+            content_type: "multipart/form-data; boundary=".to_owned() + boundary,
             body,
-            on_uploading_progress: self.on_uploading_progress,
+            uploading_progress: self
+                .on_uploading_progress
+                .map(|callback| UploadingProgress::new(callback, Some(body_size))),
             upload_logger: self.upload_logger,
-        })
+            throttle: self.throttle,
+            deadline: self.deadline,
+        }
     }
 }
 
@@ -137,75 +202,57 @@ impl<'u> FormUploader<'u> {
     pub(super) fn send(&self) -> HTTPResult<UploadResponse> {
         let mut prev_err: Option<HTTPError> = None;
         for up_urls in self.up_urls_list.iter() {
-            match self.send_form_request(&up_urls.iter().map(|url| url.as_ref()).collect::<Box<[&str]>>()) {
+            let up_urls = up_urls.iter().map(|url| url.as_ref()).collect::<Box<[&str]>>();
+            match self.form_request(&up_urls).send(parse_upload_response) {
                 Ok(value) => {
                     return Ok(value);
                 }
-                Err(err) => match err.retry_kind() {
-                    RetryKind::RetryableError | RetryKind::HostUnretryableError | RetryKind::ZoneUnretryableError => {
-                        prev_err = Some(err);
-                    }
-                    _ => {
+                Err(err) => {
+                    if !Self::should_try_next_zone(&err) {
                         return Err(err);
                     }
-                },
+                    prev_err = Some(err);
+                }
             }
         }
 
         Err(prev_err.expect("FormUploader::send() should try at lease once, but not"))
     }
 
-    fn send_form_request(&self, up_urls: &[&str]) -> HTTPResult<UploadResponse> {
-        let upload_result = self
-            .upload_manager
-            .http_client()
-            .post("/", up_urls)
-            .idempotent()
-            .on_uploading_progress(&|uploaded, total| {
-                if let Some(on_uploading_progress) = &self.on_uploading_progress {
-                    (on_uploading_progress)(uploaded, Some(total));
+    #[cfg(feature = "async")]
+    pub(super) async fn send_async(&self) -> HTTPResult<UploadResponse> {
+        let mut prev_err: Option<HTTPError> = None;
+        for up_urls in self.up_urls_list.iter() {
+            let up_urls = up_urls.iter().map(|url| url.as_ref()).collect::<Box<[&str]>>();
+            match self.form_request(&up_urls).send_async(parse_upload_response).await {
+                Ok(value) => {
+                    return Ok(value);
                 }
-            })
-            .on_response(&|response, duration| {
-                let result = upload_response_callback(response);
-                if result.is_ok() {
-                    if let Some(upload_logger) = &self.upload_logger {
-                        let _ = upload_logger.log(
-                            UploadLoggerRecordBuilder::default()
-                                .response(response)
-                                .duration(duration)
-                                .up_type(UpType::Form)
-                                .sent(self.body.len().try_into().unwrap_or(u64::max_value()))
-                                .total_size(self.body.len().try_into().unwrap_or(u64::max_value()))
-                                .build(),
-                        );
+                Err(err) => {
+                    if !Self::should_try_next_zone(&err) {
+                        return Err(err);
                     }
+                    prev_err = Some(err);
                 }
-                result
-            })
-            .on_error(&|base_url, err, duration| {
-                if let Some(upload_logger) = &self.upload_logger {
-                    let _ = upload_logger.log({
-                        let mut builder = UploadLoggerRecordBuilder::default()
-                            .duration(duration)
-                            .up_type(UpType::Form)
-                            .http_error(err)
-                            .total_size(self.body.len().try_into().unwrap_or(u64::max_value()));
-                        if let Some(base_url) = base_url {
-                            builder = builder.host(base_url);
-                        }
-                        builder.build()
-                    });
-                }
-            })
-            .accept_json()
-            .raw_body(Cow::Borrowed(&self.content_type), Cow::Borrowed(&self.body))
-            .send()?
-            .try_parse_json::<Value>()?;
-        match upload_result {
-            Ok(value) => Ok(value.into()),
-            Err(bytes) => Ok(bytes.into()),
+            }
         }
+
+        Err(prev_err.expect("FormUploader::send_async() should try at lease once, but not"))
+    }
+
+    fn should_try_next_zone(err: &HTTPError) -> bool {
+        matches!(
+            err.retry_kind(),
+            RetryKind::RetryableError | RetryKind::HostUnretryableError | RetryKind::ZoneUnretryableError
+        )
+    }
+
+    fn form_request<'r>(&'r self, up_urls: &'r [&'r str]) -> UploadRequest<'r> {
+        UploadRequest::post(self.upload_manager.http_client(), "/", up_urls, UpType::Form)
+            .body(self.content_type.as_str(), self.body.as_borrowed())
+            .deadline(self.deadline)
+            .upload_logger(self.upload_logger.as_ref())
+            .transfer(self.throttle, self.uploading_progress.as_ref())
     }
 }
 
@@ -220,12 +267,18 @@ mod tests {
         credential::Credential,
         http::{DomainsManagerBuilder, HeadersOwned},
     };
+    use qiniu_http::ResponseBuilder;
     use qiniu_test_utils::{
-        http_call_mock::{CounterCallMock, ErrorResponseMock, JSONCallMock},
+        http_call_mock::{fake_req_id, CallHandlers, CounterCallMock, ErrorResponseMock, JSONCallMock},
         temp_file::create_temp_file,
     };
     use serde_json::json;
-    use std::{boxed::Box, error::Error, result::Result};
+    use std::{
+        boxed::Box,
+        error::Error,
+        result::Result,
+        time::{Duration, Instant},
+    };
 
     #[test]
     fn test_storage_uploader_form_uploader_upload_seekable_stream() -> Result<(), Box<dyn Error>> {
@@ -257,6 +310,37 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_storage_uploader_form_uploader_upload_seekable_stream_with_rate_limiter() -> Result<(), Box<dyn Error>> {
+        let mock = CounterCallMock::new(JSONCallMock::new(
+            200,
+            HeadersOwned::new(),
+            json!({"key": "abc", "hash": "def"}),
+        ));
+        let config = ConfigBuilder::default()
+            .http_request_handler(mock.clone())
+            .upload_logger(None)
+            .upload_rate_limiter(RateLimiter::new_with_burst_size(1 << 12, 0))
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let policy = UploadPolicyBuilder::new_policy_for_bucket("test-bucket", &config).build();
+        let job_rate_limiter = RateLimiter::new(1 << 20);
+        let begin_at = Instant::now();
+        let result = FormUploaderBuilder::new(
+            &UploadManager::new(config),
+            &UploadToken::new(policy, get_credential()),
+            &[vec![Box::from("http://z1h1.com")].into()],
+        )
+        .key("test:file".into())
+        .rate_limiter(&job_rate_limiter)
+        .seekable_stream(create_temp_file(1 << 11)?, "".into(), None, true)?
+        .send()?;
+        assert_eq!(result.key(), Some("abc"));
+        assert!(begin_at.elapsed() >= Duration::from_millis(500));
+        assert_eq!(mock.call_called(), 1);
+        Ok(())
+    }
+
     #[test]
     fn test_storage_uploader_form_uploader_upload_seekable_stream_with_500_error() -> Result<(), Box<dyn Error>> {
         let mock = CounterCallMock::new(ErrorResponseMock::new(500, "test error"));
@@ -387,6 +471,46 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_storage_uploader_form_uploader_upload_bytes_without_copying() -> Result<(), Box<dyn Error>> {
+        let data = vec![b'x'; 1 << 10];
+        let data_address = data.as_ptr() as usize;
+        let config = ConfigBuilder::default()
+            .http_request_handler(CallHandlers::new(move |request| {
+                // 内存数据直接作为请求体的一个片段，没有被复制
+                let segments = request.body().segments();
+                assert_eq!(segments.len(), 3);
+                assert_eq!(segments[1].as_ptr() as usize, data_address);
+                assert_eq!(segments[1].len(), 1 << 10);
+                let body = String::from_utf8(request.body().to_bytes().into_owned()).unwrap();
+                assert!(body.contains("name=\"crc32\""));
+                assert!(body.contains(&"x".repeat(1 << 10)));
+                assert!(body.ends_with("--"));
+                let mut headers = HeadersOwned::new();
+                headers.insert("Content-Type".into(), "application/json".into());
+                headers.insert("X-Reqid".into(), fake_req_id().into());
+                Ok(ResponseBuilder::default()
+                    .status_code(200u16)
+                    .headers(headers)
+                    .bytes_as_body(json!({"key": "abc", "hash": "def"}).to_string())
+                    .build())
+            }))
+            .upload_logger(None)
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let policy = UploadPolicyBuilder::new_policy_for_bucket("test-bucket", &config).build();
+        let upload_token = UploadToken::new(policy, get_credential());
+        let up_urls_list = [vec![Box::from("http://z1h1.com")].into()];
+        let upload_manager = UploadManager::new(config);
+        let result = FormUploaderBuilder::new(&upload_manager, &upload_token, &up_urls_list)
+            .key("test:file".into())
+            .bytes(&data, "".into(), None, true)?
+            .send()?;
+        assert_eq!(result.key(), Some("abc"));
+        assert_eq!(result.hash(), Some("def"));
+        Ok(())
+    }
+
     fn get_credential() -> Credential {
         Credential::new("abcdefghklmnopq", "1234567890")
     }
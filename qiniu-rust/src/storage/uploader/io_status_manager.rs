@@ -1,10 +1,13 @@
+use super::part_sizer::PartSizer;
 use crate::http::Error as HTTPError;
 use assert_impl::assert_impl;
 use std::{
-    collections::HashSet,
+    borrow::Cow,
+    collections::BTreeMap,
     convert::TryInto,
     io::{Error as IOError, ErrorKind as IOErrorKind, Read, Seek, SeekFrom},
     sync::Mutex,
+    time::Duration,
 };
 
 pub(super) enum Result {
@@ -13,101 +16,162 @@ pub(super) enum Result {
     HTTPError(HTTPError),
 }
 
-enum Status<R: Read + Seek + Send> {
+/// 已经上传完毕的分片
+pub(super) struct CompletedPart {
+    pub(super) part_number: usize,
+    pub(super) offset: u64,
+    pub(super) size: u64,
+}
+
+/// 分片数据来源
+pub(super) enum PartSource<'s, R: Read + Seek + Send> {
+    /// 从数据流中读取分片
+    Reader(R),
+    /// 直接从内存中截取分片，无需复制数据
+    Bytes(&'s [u8]),
+}
+
+enum Status<'s, R: Read + Seek + Send> {
     Uploading {
-        reader: R,
-        block_size: u32,
+        source: PartSource<'s, R>,
+        offset: u64,
+        part_sizer: PartSizer,
         current_part_number: usize,
-        uploaded_part_numbers: HashSet<usize>,
+        completed_parts: BTreeMap<usize, (u64, u64)>,
     },
     IOError(IOError),
     HTTPError(HTTPError),
     Success,
 }
 
-pub(super) struct IOStatusManager<R: Read + Seek + Send> {
-    inner: Mutex<Status<R>>,
+pub(super) struct IOStatusManager<'s, R: Read + Seek + Send> {
+    inner: Mutex<Status<'s, R>>,
 }
 
-pub(super) struct PartData {
-    pub(super) data: Vec<u8>,
+pub(super) struct PartData<'s> {
+    pub(super) data: Cow<'s, [u8]>,
     pub(super) part_number: usize,
+    pub(super) offset: u64,
 }
 
-impl<R: Read + Seek + Send> IOStatusManager<R> {
-    pub(super) fn new(io: R, block_size: u32, uploaded_part_numbers: &[usize]) -> IOStatusManager<R> {
+impl<'s, R: Read + Seek + Send> IOStatusManager<'s, R> {
+    pub(super) fn new(
+        source: PartSource<'s, R>,
+        part_sizer: PartSizer,
+        completed_parts: &[CompletedPart],
+    ) -> IOStatusManager<'s, R> {
         IOStatusManager {
             inner: Mutex::new(Status::Uploading {
-                reader: io,
+                source,
+                offset: 0,
+                part_sizer,
                 current_part_number: 0,
-                block_size,
-                uploaded_part_numbers: {
-                    let mut set = HashSet::new();
-                    for &part_number in uploaded_part_numbers {
-                        set.insert(part_number);
-                    }
-                    set
-                },
+                completed_parts: completed_parts
+                    .iter()
+                    .map(|part| (part.part_number, (part.offset, part.size)))
+                    .collect(),
             }),
         }
     }
 
-    pub(super) fn read(&self) -> Option<PartData> {
+    pub(super) fn read(&self) -> Option<PartData<'s>> {
         let mut lock = self.inner.lock().unwrap();
         match &mut *lock {
             Status::Uploading {
-                reader,
-                block_size,
+                source,
+                offset,
+                part_sizer,
                 current_part_number,
-                uploaded_part_numbers,
+                completed_parts,
             } => {
-                let mut have_read = 0;
-                let mut buf = vec![0; block_size.to_owned().try_into().unwrap_or(usize::max_value())];
                 let new_part_number = {
                     let mut new_part_number = *current_part_number + 1;
-                    let mut skip_bytes = 0i64;
-                    while uploaded_part_numbers.get(&new_part_number).is_some() {
+                    let mut new_offset = *offset;
+                    while let Some(&(part_offset, part_size)) = completed_parts.get(&new_part_number) {
                         new_part_number += 1;
-                        skip_bytes += *block_size as i64;
+                        new_offset = part_offset + part_size;
                     }
-                    if skip_bytes > 0 {
-                        if let Err(err) = reader.seek(SeekFrom::Current(skip_bytes)) {
-                            *lock = Status::IOError(err);
-                            return None;
+                    if new_offset > *offset {
+                        if let PartSource::Reader(reader) = source {
+                            if let Err(err) = reader.seek(SeekFrom::Current((new_offset - *offset) as i64)) {
+                                *lock = Status::IOError(err);
+                                return None;
+                            }
                         }
+                        *offset = new_offset;
                     }
                     new_part_number
                 };
-                loop {
-                    match reader.read(&mut buf[have_read..]) {
-                        Ok(0) => {
+                let part_size = match completed_parts.range(new_part_number..).next() {
+                    // 已上传分片之间的空隙，需要按照原有的分片数量重新划分，确保之后的分片偏移量不变
+                    Some((&next_part_number, &(next_offset, _))) if next_offset > *offset => {
+                        let rest_parts_count = (next_part_number - new_part_number) as u64;
+                        let rest_size = next_offset - *offset;
+                        (rest_size + rest_parts_count - 1) / rest_parts_count
+                    }
+                    _ => part_sizer.next_part_size(new_part_number, *offset),
+                };
+                let part_size: usize = part_size.try_into().unwrap_or(usize::max_value());
+                let part_offset = *offset;
+                match source {
+                    PartSource::Bytes(bytes) => {
+                        let bytes: &'s [u8] = *bytes;
+                        let begin: usize = part_offset.try_into().unwrap_or(usize::max_value());
+                        if begin >= bytes.len() {
                             *lock = Status::Success;
-                            if have_read > 0 {
-                                buf.resize_with(have_read, Default::default);
-                                return Some(PartData {
-                                    data: buf,
-                                    part_number: new_part_number,
-                                });
-                            } else {
-                                return None;
-                            }
-                        }
-                        Ok(n) => {
-                            have_read += n;
-                            if have_read == buf.len() {
-                                *current_part_number = new_part_number;
-                                return Some(PartData {
-                                    data: buf,
-                                    part_number: new_part_number,
-                                });
-                            }
+                            return None;
                         }
-                        Err(ref err) if err.kind() == IOErrorKind::Interrupted => {
-                            continue;
+                        let end = begin.saturating_add(part_size).min(bytes.len());
+                        if end == bytes.len() {
+                            *lock = Status::Success;
+                        } else {
+                            *current_part_number = new_part_number;
+                            *offset = end as u64;
                         }
-                        Err(err) => {
-                            *lock = Status::IOError(err);
-                            return None;
+                        Some(PartData {
+                            data: Cow::Borrowed(&bytes[begin..end]),
+                            part_number: new_part_number,
+                            offset: part_offset,
+                        })
+                    }
+                    PartSource::Reader(reader) => {
+                        let mut have_read = 0;
+                        let mut buf = vec![0; part_size];
+                        loop {
+                            match reader.read(&mut buf[have_read..]) {
+                                Ok(0) => {
+                                    *lock = Status::Success;
+                                    if have_read > 0 {
+                                        buf.resize_with(have_read, Default::default);
+                                        return Some(PartData {
+                                            data: Cow::Owned(buf),
+                                            part_number: new_part_number,
+                                            offset: part_offset,
+                                        });
+                                    } else {
+                                        return None;
+                                    }
+                                }
+                                Ok(n) => {
+                                    have_read += n;
+                                    if have_read == buf.len() {
+                                        *current_part_number = new_part_number;
+                                        *offset += have_read as u64;
+                                        return Some(PartData {
+                                            data: Cow::Owned(buf),
+                                            part_number: new_part_number,
+                                            offset: part_offset,
+                                        });
+                                    }
+                                }
+                                Err(ref err) if err.kind() == IOErrorKind::Interrupted => {
+                                    continue;
+                                }
+                                Err(err) => {
+                                    *lock = Status::IOError(err);
+                                    return None;
+                                }
+                            }
                         }
                     }
                 }
@@ -116,6 +180,13 @@ impl<R: Read + Seek + Send> IOStatusManager<R> {
         }
     }
 
+    /// 反馈分片上传耗时，用于调整后续分片的尺寸
+    pub(super) fn feedback(&self, part_size: u64, elapsed: Duration) {
+        if let Status::Uploading { part_sizer, .. } = &mut *self.inner.lock().unwrap() {
+            part_sizer.feedback(part_size, elapsed);
+        }
+    }
+
     pub(super) fn error(&self, err: HTTPError) {
         *self.inner.lock().unwrap() = Status::HTTPError(err);
     }
@@ -138,7 +209,7 @@ impl<R: Read + Seek + Send> IOStatusManager<R> {
     }
 }
 
-impl<R: Read + Seek + Send> Status<R> {
+impl<R: Read + Seek + Send> Status<'_, R> {
     #[allow(dead_code)]
     fn ignore() {
         assert_impl!(Send: Self);
@@ -0,0 +1,367 @@
+//! 魔法变量模块
+//!
+//! 提供类型安全的[魔法变量](https://developer.qiniu.com/kodo/manual/1235/vars#magicvar)与[自定义变量](https://developer.qiniu.com/kodo/manual/1235/vars#xvar)，
+//! 用于生成上传策略中的 `returnBody` 和 `callbackBody`
+
+use crate::utils::mime;
+use std::{fmt, str::FromStr};
+use thiserror::Error;
+use url::form_urlencoded::byte_serialize;
+
+/// 图片基本信息字段
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ImageInfoField {
+    /// 图片宽度
+    Width,
+    /// 图片高度
+    Height,
+    /// 图片格式
+    Format,
+    /// 图片颜色模型
+    ColorModel,
+}
+
+impl ImageInfoField {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Width => "width",
+            Self::Height => "height",
+            Self::Format => "format",
+            Self::ColorModel => "colorModel",
+        }
+    }
+}
+
+/// 魔法变量
+///
+/// 除七牛定义的魔法变量外，也可以通过 `Custom` 引用上传时指定的自定义变量
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MagicVariable {
+    /// 获得上传的目标存储空间名，即 `$(bucket)`
+    Bucket,
+    /// 获得对象保存在存储空间中的名称，即 `$(key)`
+    Key,
+    /// 对象内容的 Etag，即 `$(etag)`
+    Etag,
+    /// 上传的原始文件名，即 `$(fname)`
+    FileName,
+    /// 对象大小，单位为字节，即 `$(fsize)`
+    FileSize,
+    /// 对象的 MIME 类型，即 `$(mimeType)`
+    MimeType,
+    /// 上传策略中指定的终端用户 ID，即 `$(endUser)`
+    EndUser,
+    /// 音视频转码持久化的进度查询 ID，即 `$(persistentId)`
+    PersistentID,
+    /// 图片的 EXIF 信息，即 `$(exif)`，如果指定了字段名称，则为 `$(exif.<field>)`
+    Exif(Option<String>),
+    /// 图片的基本信息，即 `$(imageInfo)`，如果指定了字段，则为 `$(imageInfo.<field>)`
+    ImageInfo(Option<ImageInfoField>),
+    /// 自定义变量，即 `$(x:<name>)`，变量名称无需包含 `x:` 前缀
+    Custom(String),
+}
+
+impl MagicVariable {
+    /// 校验变量是否合法
+    ///
+    /// EXIF 字段名称与自定义变量名称必须不能为空，且仅能包含字母，数字，`_` 和 `-`
+    pub fn validate(&self) -> MagicVariableResult<()> {
+        match self {
+            Self::Exif(Some(field)) if !is_valid_name(field) => {
+                Err(MagicVariableError::InvalidExifField(field.to_owned()))
+            }
+            Self::Custom(name) if !is_valid_name(name) => {
+                Err(MagicVariableError::InvalidCustomVariableName(name.to_owned()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+pub(super) fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+impl fmt::Display for MagicVariable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Bucket => f.write_str("$(bucket)"),
+            Self::Key => f.write_str("$(key)"),
+            Self::Etag => f.write_str("$(etag)"),
+            Self::FileName => f.write_str("$(fname)"),
+            Self::FileSize => f.write_str("$(fsize)"),
+            Self::MimeType => f.write_str("$(mimeType)"),
+            Self::EndUser => f.write_str("$(endUser)"),
+            Self::PersistentID => f.write_str("$(persistentId)"),
+            Self::Exif(None) => f.write_str("$(exif)"),
+            Self::Exif(Some(field)) => write!(f, "$(exif.{})", field),
+            Self::ImageInfo(None) => f.write_str("$(imageInfo)"),
+            Self::ImageInfo(Some(field)) => write!(f, "$(imageInfo.{})", field.as_str()),
+            Self::Custom(name) => write!(f, "$(x:{})", name),
+        }
+    }
+}
+
+impl FromStr for MagicVariable {
+    type Err = MagicVariableError;
+
+    /// 解析魔法变量，可以是 `key` 这样的变量名称，也可以是 `$(key)` 这样的完整形式
+    fn from_str(s: &str) -> MagicVariableResult<Self> {
+        let name = s.strip_prefix("$(").and_then(|s| s.strip_suffix(')')).unwrap_or(s);
+        let variable = match name {
+            "bucket" => Self::Bucket,
+            "key" => Self::Key,
+            "etag" => Self::Etag,
+            "fname" => Self::FileName,
+            "fsize" => Self::FileSize,
+            "mimeType" => Self::MimeType,
+            "endUser" => Self::EndUser,
+            "persistentId" => Self::PersistentID,
+            "exif" => Self::Exif(None),
+            "imageInfo" => Self::ImageInfo(None),
+            "imageInfo.width" => Self::ImageInfo(Some(ImageInfoField::Width)),
+            "imageInfo.height" => Self::ImageInfo(Some(ImageInfoField::Height)),
+            "imageInfo.format" => Self::ImageInfo(Some(ImageInfoField::Format)),
+            "imageInfo.colorModel" => Self::ImageInfo(Some(ImageInfoField::ColorModel)),
+            _ => {
+                if let Some(field) = name.strip_prefix("exif.") {
+                    Self::Exif(Some(field.to_owned()))
+                } else if let Some(custom) = name.strip_prefix("x:") {
+                    Self::Custom(custom.to_owned())
+                } else {
+                    return Err(MagicVariableError::UnknownVariable(s.to_owned()));
+                }
+            }
+        };
+        variable.validate()?;
+        Ok(variable)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum BodyValue {
+    Variable(MagicVariable),
+    Literal(String),
+}
+
+/// 魔法变量请求体
+///
+/// 由多个字段组成，每个字段的值可以是魔法变量，自定义变量或是常量字符串，
+/// 可以渲染为 JSON 格式或 `application/x-www-form-urlencoded` 格式
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MagicVariableBody {
+    fields: Vec<(String, BodyValue)>,
+}
+
+impl MagicVariableBody {
+    /// 渲染为 JSON 格式，魔法变量将由七牛服务器替换为相应的 JSON 值
+    ///
+    /// 例如 `{"key":$(key),"fsize":$(fsize)}`
+    pub fn to_json(&self) -> String {
+        let mut body = String::from("{");
+        for (index, (name, value)) in self.fields.iter().enumerate() {
+            if index > 0 {
+                body.push(',');
+            }
+            body.push_str(&serde_json::to_string(name).unwrap());
+            body.push(':');
+            match value {
+                BodyValue::Variable(variable) => body.push_str(&variable.to_string()),
+                BodyValue::Literal(literal) => body.push_str(&serde_json::to_string(literal).unwrap()),
+            }
+        }
+        body.push('}');
+        body
+    }
+
+    /// 渲染为 `application/x-www-form-urlencoded` 格式
+    ///
+    /// 例如 `key=$(key)&fsize=$(fsize)`
+    pub fn to_form(&self) -> String {
+        self.fields
+            .iter()
+            .map(|(name, value)| {
+                let name = byte_serialize(name.as_bytes()).collect::<String>();
+                match value {
+                    BodyValue::Variable(variable) => format!("{}={}", name, variable),
+                    BodyValue::Literal(literal) => {
+                        format!("{}={}", name, byte_serialize(literal.as_bytes()).collect::<String>())
+                    }
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    /// 按照回调请求体的 `Content-Type` 渲染
+    ///
+    /// `body_type` 为空时视为 `application/x-www-form-urlencoded`，
+    /// 仅支持 `application/x-www-form-urlencoded` 与 `application/json` 两种类型
+    pub fn render_for_body_type(&self, body_type: &str) -> MagicVariableResult<String> {
+        match body_type.split(';').next().unwrap_or_default().trim() {
+            "" | mime::FORM_MIME => Ok(self.to_form()),
+            mime::JSON_MIME => Ok(self.to_json()),
+            _ => Err(MagicVariableError::UnsupportedBodyType(body_type.to_owned())),
+        }
+    }
+}
+
+/// 魔法变量请求体生成器
+#[derive(Default, Debug)]
+pub struct MagicVariableBodyBuilder {
+    fields: Vec<(String, BodyValue)>,
+}
+
+impl MagicVariableBodyBuilder {
+    /// 添加值为魔法变量或自定义变量的字段
+    pub fn variable(&mut self, name: impl Into<String>, variable: MagicVariable) -> &mut Self {
+        self.fields.push((name.into(), BodyValue::Variable(variable)));
+        self
+    }
+
+    /// 添加值为自定义变量的字段，变量名称无需包含 `x:` 前缀
+    pub fn custom_variable(&mut self, name: impl Into<String>, variable_name: impl Into<String>) -> &mut Self {
+        self.variable(name, MagicVariable::Custom(variable_name.into()))
+    }
+
+    /// 添加值为常量字符串的字段
+    pub fn literal(&mut self, name: impl Into<String>, value: impl Into<String>) -> &mut Self {
+        self.fields.push((name.into(), BodyValue::Literal(value.into())));
+        self
+    }
+
+    /// 生成魔法变量请求体
+    ///
+    /// 将校验所有字段，字段名称不能为空，也不能重复
+    pub fn build(&self) -> MagicVariableResult<MagicVariableBody> {
+        for (index, (name, value)) in self.fields.iter().enumerate() {
+            if name.is_empty() {
+                return Err(MagicVariableError::EmptyFieldName);
+            }
+            if self.fields[..index].iter().any(|(n, _)| n == name) {
+                return Err(MagicVariableError::DuplicateField(name.to_owned()));
+            }
+            if let BodyValue::Variable(variable) = value {
+                variable.validate()?;
+            }
+        }
+        Ok(MagicVariableBody {
+            fields: self.fields.to_owned(),
+        })
+    }
+}
+
+/// 魔法变量错误
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum MagicVariableError {
+    /// 未知的魔法变量
+    #[error("Unknown magic variable: {0}")]
+    UnknownVariable(String),
+    /// 非法的 EXIF 字段名称
+    #[error("Invalid exif field: {0}")]
+    InvalidExifField(String),
+    /// 非法的自定义变量名称
+    #[error("Invalid custom variable name: {0}")]
+    InvalidCustomVariableName(String),
+    /// 字段名称为空
+    #[error("Field name must not be empty")]
+    EmptyFieldName,
+    /// 字段名称重复
+    #[error("Duplicate field: {0}")]
+    DuplicateField(String),
+    /// 不支持的请求体类型
+    #[error("Unsupported body type: {0}")]
+    UnsupportedBodyType(String),
+}
+
+/// 魔法变量结果
+pub type MagicVariableResult<T> = Result<T, MagicVariableError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use std::{boxed::Box, error::Error, result::Result};
+
+    #[test]
+    fn test_storage_uploader_magic_variable_parse() -> Result<(), Box<dyn Error>> {
+        assert_eq!("key".parse::<MagicVariable>()?, MagicVariable::Key);
+        assert_eq!("$(fname)".parse::<MagicVariable>()?, MagicVariable::FileName);
+        assert_eq!(
+            "$(imageInfo.width)".parse::<MagicVariable>()?,
+            MagicVariable::ImageInfo(Some(ImageInfoField::Width))
+        );
+        assert_eq!(
+            "exif.DateTime".parse::<MagicVariable>()?,
+            MagicVariable::Exif(Some("DateTime".into()))
+        );
+        assert_eq!("x:user".parse::<MagicVariable>()?, MagicVariable::Custom("user".into()));
+        assert_eq!(
+            "$(fnmae)".parse::<MagicVariable>(),
+            Err(MagicVariableError::UnknownVariable("$(fnmae)".into()))
+        );
+        assert_eq!(
+            "x:".parse::<MagicVariable>(),
+            Err(MagicVariableError::InvalidCustomVariableName("".into()))
+        );
+
+        for variable in [
+            MagicVariable::PersistentID,
+            MagicVariable::Exif(Some("Model".into())),
+            MagicVariable::ImageInfo(Some(ImageInfoField::ColorModel)),
+            MagicVariable::Custom("user".into()),
+        ]
+        .iter()
+        {
+            assert_eq!(&variable.to_string().parse::<MagicVariable>()?, variable);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_storage_uploader_magic_variable_body() -> Result<(), Box<dyn Error>> {
+        let body = MagicVariableBodyBuilder::default()
+            .variable("key", MagicVariable::Key)
+            .variable("size", MagicVariable::FileSize)
+            .variable("w", MagicVariable::ImageInfo(Some(ImageInfoField::Width)))
+            .custom_variable("user", "user")
+            .literal("from", "a&b")
+            .build()?;
+        assert_eq!(
+            body.to_json(),
+            r#"{"key":$(key),"size":$(fsize),"w":$(imageInfo.width),"user":$(x:user),"from":"a&b"}"#
+        );
+        assert_eq!(
+            body.to_form(),
+            "key=$(key)&size=$(fsize)&w=$(imageInfo.width)&user=$(x:user)&from=a%26b"
+        );
+        assert_eq!(body.render_for_body_type("")?, body.to_form());
+        assert_eq!(
+            body.render_for_body_type("application/json; charset=utf-8")?,
+            body.to_json()
+        );
+        assert_eq!(
+            body.render_for_body_type("text/plain"),
+            Err(MagicVariableError::UnsupportedBodyType("text/plain".into()))
+        );
+
+        let literal_body = MagicVariableBodyBuilder::default().literal("k", "v\"").build()?;
+        let v: Value = serde_json::from_str(&literal_body.to_json())?;
+        assert_eq!(v, json!({"k": "v\""}));
+
+        assert_eq!(
+            MagicVariableBodyBuilder::default()
+                .variable("key", MagicVariable::Key)
+                .variable("key", MagicVariable::Etag)
+                .build(),
+            Err(MagicVariableError::DuplicateField("key".into()))
+        );
+        assert_eq!(
+            MagicVariableBodyBuilder::default()
+                .custom_variable("user", "x y")
+                .build(),
+            Err(MagicVariableError::InvalidCustomVariableName("x y".into()))
+        );
+        Ok(())
+    }
+}
@@ -4,24 +4,43 @@
 
 mod batch_uploader;
 mod callback;
+mod checksum_policy;
 mod form_uploader;
 mod io_status_manager;
+mod magic_variable;
 mod object_uploader;
+mod part_sizer;
 mod resumable_uploader;
+mod save_key;
+mod throttle;
 mod upload_logger;
 mod upload_manager;
 mod upload_policy;
 mod upload_recorder;
+mod upload_request;
 mod upload_response;
 mod upload_token;
+mod upload_token_provider;
 
-pub use batch_uploader::{BatchUploadJob, BatchUploadJobBuilder, BatchUploader};
+pub use batch_uploader::{
+    BackgroundBatchUploader, BatchUploadJob, BatchUploadJobBuilder, BatchUploadJobOutcome, BatchUploadJobSender,
+    BatchUploadReport, BatchUploader,
+};
 use callback::upload_response_callback;
+pub use checksum_policy::{ChecksumPolicy, PartChecksumAlgorithm};
+pub use magic_variable::{
+    ImageInfoField, MagicVariable, MagicVariableBody, MagicVariableBodyBuilder, MagicVariableError, MagicVariableResult,
+};
 pub use object_uploader::{ObjectUploader, UploadError, UploadResult};
+pub use part_sizer::UploadPartSizePolicy;
+pub use resumable_uploader::ResumableUploadVersion;
+pub use save_key::{SaveKeyContext, SaveKeyError, SaveKeyPart, SaveKeyResult, SaveKeyTemplate, SaveKeyTemplateBuilder};
+use throttle::UploadThrottle;
 pub use upload_logger::{LockPolicy as UploadLoggerFileLockPolicy, UploadLogger, UploadLoggerBuilder};
 use upload_logger::{TokenizedUploadLogger, UpType, UploadLoggerRecordBuilder};
 pub use upload_manager::{CreateUploaderError, CreateUploaderResult, UploadManager};
 pub use upload_policy::{UploadPolicy, UploadPolicyBuilder};
 pub use upload_recorder::{UploadRecorder, UploadRecorderBuilder};
-pub use upload_response::UploadResponse;
+pub use upload_response::{FromUploadResponse, UploadResponse};
 pub use upload_token::{UploadToken, UploadTokenParseError, UploadTokenParseResult};
+pub use upload_token_provider::{CredentialUploadTokenProvider, UploadTokenProvider, UploadTokenProviderResult};
@@ -1,23 +1,45 @@
 use super::{
-    form_uploader::FormUploaderBuilder,
-    resumable_uploader::{ResumableUploader, ResumableUploaderBuilder},
+    super::{
+        bucket::BucketBuilder,
+        object::{Object, ObjectInfo},
+    },
+    checksum_policy::ChecksumPolicy,
+    form_uploader::{FormUploader, FormUploaderBuilder},
+    part_sizer::{UploadPartSizePolicy, MAX_PARTS_COUNT},
+    resumable_uploader::{ResumableUploadVersion, ResumableUploader, ResumableUploaderBuilder},
     upload_manager::UploadManager,
     upload_recorder::UploadRecorder,
-    upload_token::UploadToken,
+    upload_response::FromUploadResponse,
+    upload_token::{UploadToken, UploadTokenParseError},
+    upload_token_provider::UploadTokenProvider,
     UploadResponse,
 };
-use crate::utils::{rob::Rob, ron::Ron};
+#[cfg(feature = "async")]
+use crate::utils::thread_pool::run_blocking;
+use crate::{
+    http::{Error as HTTPError, ErrorKind as HTTPErrorKind, RetryKind as HTTPRetryKind},
+    utils::{etag, rate_limiter::RateLimiter, rob::Rob, ron::Ron, seek_adapter::SeekAdapter},
+    Credential,
+};
+use matches::matches;
 use mime::Mime;
 use rayon::{ThreadPool, ThreadPoolBuilder};
+use serde_json::json;
 use std::{
     borrow::Cow,
     collections::HashMap,
     fs::File,
-    io::{Error as IOError, Read, Result as IOResult},
+    io::{Empty, Error as IOError, ErrorKind as IOErrorKind, Read, Result as IOResult},
+    marker::PhantomData,
     path::Path,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
 };
 use thiserror::Error;
 
+const ETAG_BLOCK_SIZE: u32 = 1 << 22;
+const DEFAULT_UPLOAD_TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
 pub(super) enum ResumablePolicy {
     Threshold(u32),
     Never,
@@ -26,22 +48,33 @@ pub(super) enum ResumablePolicy {
 
 /// 对象上传器
 ///
-/// 为指定的文件上传准备数据，不能跨线程使用，不能反复使用
+/// 为指定的文件上传准备数据，不能跨线程使用，不能反复使用。
+///
+/// 上传完毕后默认返回 `UploadResponse`，可以调用 `response_type` 方法指定其他上传响应类型
 #[must_use]
-pub struct ObjectUploader<'b> {
+pub struct ObjectUploader<'b, T = UploadResponse> {
     upload_manager: &'b UploadManager,
     bucket_name: Cow<'b, str>,
     up_urls_list: Box<[Box<[Box<str>]>]>,
     upload_token: Cow<'b, UploadToken>,
+    upload_token_provider: Option<Arc<dyn UploadTokenProvider>>,
     key: Option<Cow<'b, str>>,
     vars: HashMap<Cow<'b, str>, Cow<'b, str>>,
     metadata: HashMap<Cow<'b, str>, Cow<'b, str>>,
-    checksum_enabled: bool,
+    checksum_policy: ChecksumPolicy,
     resumable_policy: ResumablePolicy,
+    resumable_upload_version: Option<ResumableUploadVersion>,
+    part_size_policy: Option<UploadPartSizePolicy>,
     #[allow(clippy::type_complexity)]
     on_uploading_progress: Option<Rob<'b, dyn Fn(u64, Option<u64>) + Send + Sync>>,
     thread_pool: Option<Ron<'b, ThreadPool>>,
     max_concurrency: usize,
+    rate_limiter: Option<RateLimiter>,
+    upload_token_expiry_margin: Option<Duration>,
+    skip_if_same: Option<Credential>,
+    operation_timeout: Option<Duration>,
+    deadline: Option<Instant>,
+    response_type: PhantomData<fn() -> T>,
 }
 
 impl<'b> ObjectUploader<'b> {
@@ -56,14 +89,57 @@ impl<'b> ObjectUploader<'b> {
             upload_token,
             bucket_name,
             up_urls_list,
+            upload_token_provider: None,
             key: None,
             vars: HashMap::new(),
             metadata: HashMap::new(),
-            checksum_enabled: true,
+            checksum_policy: Default::default(),
             on_uploading_progress: None,
             thread_pool: None,
             max_concurrency: 0,
+            rate_limiter: None,
+            resumable_upload_version: None,
+            part_size_policy: None,
             resumable_policy: ResumablePolicy::Threshold(upload_manager.config().upload_threshold()),
+            upload_token_expiry_margin: Some(DEFAULT_UPLOAD_TOKEN_EXPIRY_MARGIN),
+            skip_if_same: None,
+            operation_timeout: None,
+            deadline: None,
+            response_type: PhantomData,
+        }
+    }
+}
+
+impl<'b, T: FromUploadResponse> ObjectUploader<'b, T> {
+    /// 指定上传响应类型
+    ///
+    /// 上传完毕后，上传响应体将被转换为指定的类型，任何实现了 `serde::de::DeserializeOwned` 的类型均可使用。
+    /// 适用于在上传策略中自定义了 `returnBody` 或回调请求体的情况，
+    /// 例如将 `$(fsize)`，`$(imageInfo)` 或 `$(x:user)` 等魔法变量与自定义变量直接反序列化为自定义的结构体。
+    /// 如果反序列化失败，上传将返回 `UploadError::DeserializeResponseError`
+    pub fn response_type<R: FromUploadResponse>(self) -> ObjectUploader<'b, R> {
+        ObjectUploader {
+            upload_manager: self.upload_manager,
+            bucket_name: self.bucket_name,
+            up_urls_list: self.up_urls_list,
+            upload_token: self.upload_token,
+            upload_token_provider: self.upload_token_provider,
+            key: self.key,
+            vars: self.vars,
+            metadata: self.metadata,
+            checksum_policy: self.checksum_policy,
+            resumable_policy: self.resumable_policy,
+            resumable_upload_version: self.resumable_upload_version,
+            part_size_policy: self.part_size_policy,
+            on_uploading_progress: self.on_uploading_progress,
+            thread_pool: self.thread_pool,
+            max_concurrency: self.max_concurrency,
+            rate_limiter: self.rate_limiter,
+            upload_token_expiry_margin: self.upload_token_expiry_margin,
+            skip_if_same: self.skip_if_same,
+            operation_timeout: self.operation_timeout,
+            deadline: self.deadline,
+            response_type: PhantomData,
         }
     }
 
@@ -93,6 +169,25 @@ impl<'b> ObjectUploader<'b> {
         self
     }
 
+    /// 为当前上传任务指定带宽限速器
+    ///
+    /// 该限速器将与客户端配置中的全局限速器同时生效。
+    /// 可以将同一个限速器克隆后指定给多个上传任务，以限制它们的总带宽
+    pub fn rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// 限制当前上传任务的带宽
+    ///
+    /// 单位为字节每秒，该限制将与客户端配置中的全局限速器同时生效。传入 `0` 表示不限速
+    pub fn upload_rate_limit(mut self, bytes_per_second: u64) -> Self {
+        self.rate_limiter = Some(bytes_per_second)
+            .filter(|&bytes_per_second| bytes_per_second > 0)
+            .map(RateLimiter::new);
+        self
+    }
+
     /// 指定上传对象的名称
     pub fn key(mut self, key: impl Into<Cow<'b, str>>) -> Self {
         self.key = Some(key.into());
@@ -119,15 +214,24 @@ impl<'b> ObjectUploader<'b> {
     ///
     /// 在任何场景下都不推荐禁用上传数据校验
     pub fn disable_checksum(mut self) -> Self {
-        self.checksum_enabled = false;
+        self.checksum_policy = ChecksumPolicy::disabled();
         self
     }
 
     /// 启用上传数据校验
     ///
-    /// 默认总是启用，在任何场景下都不推荐禁用上传数据校验
+    /// 默认总是启用，在任何场景下都不推荐禁用上传数据校验。
+    /// 调用该方法将恢复默认的上传数据校验策略
     pub fn enable_checksum(mut self) -> Self {
-        self.checksum_enabled = true;
+        self.checksum_policy = Default::default();
+        self
+    }
+
+    /// 指定上传数据校验策略
+    ///
+    /// 可以分别控制表单上传的 CRC32 校验，分片数据的校验算法，以及上传完毕后的 Etag 校验
+    pub fn checksum_policy(mut self, checksum_policy: ChecksumPolicy) -> Self {
+        self.checksum_policy = checksum_policy;
         self
     }
 
@@ -158,6 +262,73 @@ impl<'b> ObjectUploader<'b> {
         self
     }
 
+    /// 指定分片上传协议版本
+    ///
+    /// 默认将采用客户端配置中的配置项
+    pub fn resumable_upload_version(mut self, version: ResumableUploadVersion) -> Self {
+        self.resumable_upload_version = Some(version);
+        self
+    }
+
+    /// 指定上传分片尺寸策略
+    ///
+    /// 仅对分片上传 V2 有效，默认将采用客户端配置中的配置项
+    pub fn upload_part_size_policy(mut self, part_size_policy: UploadPartSizePolicy) -> Self {
+        self.part_size_policy = Some(part_size_policy);
+        self
+    }
+
+    /// 指定上传凭证提供者
+    ///
+    /// 上传开始前，以及分片上传初始化上传，上传每个分片和完成上传前，都将从上传凭证提供者获取最新的上传凭证，
+    /// 以避免上传时间过长导致上传凭证过期。
+    /// 上传凭证提供者获取的上传凭证应当与创建对象上传器时使用的上传凭证属于同一存储空间
+    pub fn upload_token_provider(mut self, upload_token_provider: impl UploadTokenProvider + 'static) -> Self {
+        self.upload_token_provider = Some(Arc::new(upload_token_provider));
+        self
+    }
+
+    /// 指定上传凭证的过期预留时间
+    ///
+    /// 上传前将在本地校验上传凭证，如果上传凭证将在该时间内过期，则不会上传，直接返回 `UploadError::UploadTokenExpiredError`。
+    /// 默认为 30 秒
+    pub fn upload_token_expiry_margin(mut self, margin: Duration) -> Self {
+        self.upload_token_expiry_margin = Some(margin);
+        self
+    }
+
+    /// 禁用上传凭证本地校验
+    ///
+    /// 默认情况下，上传前将在本地校验上传凭证的有效期，对象名称约束，文件尺寸限制与 MIME 类型限制，
+    /// 以避免上传大量数据后才被七牛服务器拒绝。如果本地时钟不准确，可以禁用该校验
+    pub fn disable_upload_token_validation(mut self) -> Self {
+        self.upload_token_expiry_margin = None;
+        self
+    }
+
+    /// 如果存储空间中已经存在内容相同的对象，则跳过上传
+    ///
+    /// 上传前将计算本地数据的 Etag，并使用指定的认证信息查询存储空间中的同名对象，如果其 Hash 值与 Etag 一致，
+    /// 则不再上传，直接返回根据已有对象信息生成的上传响应，其中包含 `key`，`hash`，`fsize` 和 `mimeType` 字段。
+    /// 查询对象信息失败或对象不存在时，将照常上传。
+    ///
+    /// 仅对指定了对象名称的文件上传和内存数据上传有效，数据流由于无法重复读取，总是会被上传。
+    /// 如果上传策略中设置了 `returnBody` 或回调，由于生成的上传响应无法代替七牛服务器或业务服务器返回的数据，也总是会被上传。
+    /// 需要注意的是，使用分片上传 V2 且分片尺寸不为 4 MB 上传的对象，其 Hash 值并非标准的 Etag，这些对象总是会被重新上传
+    pub fn skip_if_same(mut self, credential: Credential) -> Self {
+        self.skip_if_same = Some(credential);
+        self
+    }
+
+    /// 指定操作超时时长
+    ///
+    /// 限制本次上传包含所有重试在内的总耗时，超时后将返回 `DeadlineExceeded` 错误。
+    /// 将覆盖客户端配置中的操作超时时长
+    pub fn operation_timeout(mut self, timeout: Duration) -> Self {
+        self.operation_timeout = Some(timeout);
+        self
+    }
+
     /// 上传进度回调
     ///
     /// 将在上传期间反复回调指定的闭包，以获取上传进度。
@@ -188,24 +359,28 @@ impl<'b> ObjectUploader<'b> {
     /// * `file_name` - 指定上传文件的文件名称，在下载文件时将会被使用
     /// * `mime` - 指定文件的 MIME 类型，参照[文档](https://docs.rs/mime/0.3.14/mime/) 传值，如果不填写，七牛服务器将根据上传策略决定 `Content-Type`
     pub fn upload_file(
-        self,
+        mut self,
         file_path: impl AsRef<Path>,
         file_name: impl Into<Cow<'b, str>>,
         mime: Option<Mime>,
-    ) -> UploadResult {
+    ) -> UploadResult<T> {
         let file_path = file_path.as_ref();
-        let file_name = file_name.into();
-        match self.resumable_policy {
-            ResumablePolicy::Threshold(threshold) => {
-                if file_path.metadata()?.len() > threshold.into() {
-                    self.upload_file_by_blocks(file_path, file_name, mime)
-                } else {
-                    self.upload_file_by_form(file_path, file_name, mime)
-                }
-            }
-            ResumablePolicy::Always => self.upload_file_by_blocks(file_path, file_name, mime),
-            ResumablePolicy::Never => self.upload_file_by_form(file_path, file_name, mime),
+        let (resumable, verify_etag) = self.prepare_uploading(file_path.metadata()?.len(), mime.as_ref())?;
+        let local_etag = if self.should_compute_local_etag(verify_etag) {
+            Some(etag::from_file(file_path)?)
+        } else {
+            None
+        };
+        if let Some(upload_response) = self.find_same_object(local_etag.as_deref()) {
+            return Self::convert_response(upload_response);
         }
+        let upload_response = if resumable {
+            self.file_resumable_uploader(file_path, file_name.into(), mime)?
+                .send()?
+        } else {
+            self.upload_file_by_form(file_path, file_name.into(), mime)?
+        };
+        Self::convert_response(Self::verify_etag(upload_response, local_etag.filter(|_| verify_etag))?)
     }
 
     /// 开始上传数据流
@@ -217,14 +392,16 @@ impl<'b> ObjectUploader<'b> {
     /// * `file_name` - 指定上传文件的文件名称，在下载文件时将会被使用
     /// * `mime` - 指定文件的 MIME 类型，参照[文档](https://docs.rs/mime/0.3.14/mime/) 传值，如果不填写，七牛服务器将根据上传策略决定 `Content-Type`
     pub fn upload_stream(
-        self,
+        mut self,
         stream: impl Read + Send,
         size: u64,
         file_name: impl Into<Cow<'b, str>>,
         mime: Option<Mime>,
-    ) -> UploadResult {
+    ) -> UploadResult<T> {
         let file_name = file_name.into();
-        match self.resumable_policy {
+        self.start_deadline();
+        self.prepare_upload_token(None, mime.as_ref())?;
+        let upload_response = match self.resumable_policy {
             ResumablePolicy::Threshold(threshold) => {
                 if size > 0 && size < threshold.into() {
                     self.upload_stream_by_form(stream, size, file_name, mime)
@@ -234,65 +411,401 @@ impl<'b> ObjectUploader<'b> {
             }
             ResumablePolicy::Always => self.upload_stream_by_blocks(stream, size, file_name, mime),
             ResumablePolicy::Never => self.upload_stream_by_form(stream, size, file_name, mime),
+        }?;
+        Self::convert_response(upload_response)
+    }
+
+    /// 开始上传内存数据
+    ///
+    /// 与上传数据流相比，该方法无需将数据包装为数据流，数据校验值也只会计算一次。适合上传大量的小文件。
+    ///
+    /// 分片上传时将直接截取内存中的数据作为请求体，不会复制数据。
+    /// 表单上传时，由于 multipart 请求体必须是一段连续的内存，数据仍将被复制一次到请求体中
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - 上传数据
+    /// * `file_name` - 指定上传文件的文件名称，在下载文件时将会被使用
+    /// * `mime` - 指定文件的 MIME 类型，参照[文档](https://docs.rs/mime/0.3.14/mime/) 传值，如果不填写，七牛服务器将根据上传策略决定 `Content-Type`
+    pub fn upload_bytes(
+        mut self,
+        bytes: impl Into<Cow<'b, [u8]>>,
+        file_name: impl Into<Cow<'b, str>>,
+        mime: Option<Mime>,
+    ) -> UploadResult<T> {
+        let bytes = bytes.into();
+        let (resumable, verify_etag) = self.prepare_uploading(bytes.len() as u64, mime.as_ref())?;
+        let local_etag = if self.should_compute_local_etag(verify_etag) {
+            Some(etag::from_bytes(&bytes))
+        } else {
+            None
+        };
+        if let Some(upload_response) = self.find_same_object(local_etag.as_deref()) {
+            return Self::convert_response(upload_response);
+        }
+        let upload_response = if resumable {
+            self.bytes_resumable_uploader(bytes, file_name.into(), mime)?.send()?
+        } else {
+            self.bytes_form_uploader(&bytes, file_name.into(), mime)?.send()?
+        };
+        Self::convert_response(Self::verify_etag(upload_response, local_etag.filter(|_| verify_etag))?)
+    }
+
+    /// 异步上传文件
+    ///
+    /// 需要开启 `async` 功能。
+    /// 与 `upload_file` 一样，根据文件尺寸选择表单上传或分片上传，分片上传时各个分片在当前任务中并发上传。
+    /// 计算文件 Etag 和读取表单上传的文件内容将在 Rayon 全局线程池中进行，
+    /// 但分片上传时读取分片数据，写入断点续传记录和上传限速依然是同步操作，可能会短暂阻塞当前线程
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - 上传文件路径
+    /// * `file_name` - 指定上传文件的文件名称，在下载文件时将会被使用
+    /// * `mime` - 指定文件的 MIME 类型，参照[文档](https://docs.rs/mime/0.3.14/mime/) 传值，如果不填写，七牛服务器将根据上传策略决定 `Content-Type`
+    #[cfg(feature = "async")]
+    pub async fn upload_file_async(
+        mut self,
+        file_path: impl AsRef<Path>,
+        file_name: impl Into<Cow<'b, str>>,
+        mime: Option<Mime>,
+    ) -> UploadResult<T> {
+        let file_path = file_path.as_ref();
+        let (resumable, verify_etag) = self.prepare_uploading(file_path.metadata()?.len(), mime.as_ref())?;
+        let local_etag = if self.should_compute_local_etag(verify_etag) {
+            let file_path = file_path.to_owned();
+            Some(run_blocking(move || etag::from_file(file_path)).await?)
+        } else {
+            None
+        };
+        if let Some(upload_response) = self.find_same_object_async(local_etag.as_deref()).await {
+            return Self::convert_response(upload_response);
+        }
+        let upload_response = if resumable {
+            self.file_resumable_uploader(file_path, file_name.into(), mime)?
+                .send_async()
+                .await?
+        } else {
+            self.upload_file_by_form_async(file_path, file_name.into(), mime)
+                .await?
+        };
+        Self::convert_response(Self::verify_etag(upload_response, local_etag.filter(|_| verify_etag))?)
+    }
+
+    /// 异步上传内存数据
+    ///
+    /// 需要开启 `async` 功能。
+    /// 与 `upload_bytes` 一样，根据数据尺寸选择表单上传或分片上传，分片上传时各个分片在当前任务中并发上传
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - 上传数据
+    /// * `file_name` - 指定上传文件的文件名称，在下载文件时将会被使用
+    /// * `mime` - 指定文件的 MIME 类型，参照[文档](https://docs.rs/mime/0.3.14/mime/) 传值，如果不填写，七牛服务器将根据上传策略决定 `Content-Type`
+    #[cfg(feature = "async")]
+    pub async fn upload_bytes_async(
+        mut self,
+        bytes: impl Into<Cow<'b, [u8]>>,
+        file_name: impl Into<Cow<'b, str>>,
+        mime: Option<Mime>,
+    ) -> UploadResult<T> {
+        let bytes = bytes.into();
+        let (resumable, verify_etag) = self.prepare_uploading(bytes.len() as u64, mime.as_ref())?;
+        let local_etag = if self.should_compute_local_etag(verify_etag) {
+            Some(etag::from_bytes(&bytes))
+        } else {
+            None
+        };
+        if let Some(upload_response) = self.find_same_object_async(local_etag.as_deref()).await {
+            return Self::convert_response(upload_response);
+        }
+        let upload_response = if resumable {
+            self.bytes_resumable_uploader(bytes, file_name.into(), mime)?
+                .send_async()
+                .await?
+        } else {
+            self.bytes_form_uploader(&bytes, file_name.into(), mime)?
+                .send_async()
+                .await?
+        };
+        Self::convert_response(Self::verify_etag(upload_response, local_etag.filter(|_| verify_etag))?)
+    }
+
+    // 返回是否使用分片上传，以及上传完毕后是否需要校验 Etag
+    fn prepare_uploading(&mut self, size: u64, mime: Option<&Mime>) -> UploadResult<(bool, bool)> {
+        self.start_deadline();
+        self.prepare_upload_token(Some(size), mime)?;
+        let resumable = self.is_resumable(size);
+        Ok((resumable, self.should_verify_etag(resumable, size)))
+    }
+
+    // 校验 Etag 和查找相同对象都需要计算本地 Etag
+    fn should_compute_local_etag(&self, verify_etag: bool) -> bool {
+        verify_etag || self.should_skip_if_same()
+    }
+
+    fn start_deadline(&mut self) {
+        self.deadline = self
+            .operation_timeout
+            .or_else(|| self.upload_manager.config().operation_timeout())
+            .map(|timeout| Instant::now() + timeout);
+    }
+
+    fn prepare_upload_token(&mut self, size: Option<u64>, mime: Option<&Mime>) -> UploadResult<()> {
+        if let Some(upload_token_provider) = &self.upload_token_provider {
+            self.upload_token =
+                Cow::Owned(upload_token_provider.upload_token().map_err(|err| {
+                    HTTPError::new_unretryable_error(HTTPErrorKind::UnknownError(err), None, None, None)
+                })?);
+        }
+        let expiry_margin = if let Some(expiry_margin) = self.upload_token_expiry_margin {
+            expiry_margin
+        } else {
+            return Ok(());
+        };
+        let policy = self.upload_token.policy()?;
+        if let Some(deadline) = policy.token_deadline() {
+            if deadline < SystemTime::now() + expiry_margin {
+                return Err(UploadError::UploadTokenExpiredError { deadline });
+            }
+        }
+        if let (Some(scope_key), Some(key)) = (policy.key(), self.key.as_deref()) {
+            let matched = if policy.use_prefixal_object_key() {
+                key.starts_with(scope_key)
+            } else {
+                key == scope_key
+            };
+            if !matched && !policy.is_save_key_forced() {
+                return Err(UploadError::KeyMismatchError {
+                    key: key.to_owned(),
+                    scope_key: scope_key.to_owned(),
+                });
+            }
         }
+        if let Some(size) = size {
+            let (min, max) = policy.file_size_limitation();
+            if min.map_or(false, |min| size < min as u64) || max.map_or(false, |max| size > max as u64) {
+                return Err(UploadError::FileSizeLimitationError { size, min, max });
+            }
+        }
+        if let (Some(mime), Some(mime_types)) = (mime, policy.mime_types()) {
+            let mime_types = mime_types.map(|mime_type| mime_type.trim()).collect::<Vec<_>>();
+            if !is_mime_type_allowed(mime, &mime_types) {
+                return Err(UploadError::MimeTypeLimitationError {
+                    mime: mime.to_owned(),
+                    mime_limit: mime_types.join(";"),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn should_skip_if_same(&self) -> bool {
+        if self.skip_if_same.is_none() || self.key.is_none() {
+            return false;
+        }
+        // 生成的上传响应无法还原自定义的 `returnBody`，也无法代替业务服务器的回调响应，因此这两种情况总是会上传
+        match self.upload_token.policy() {
+            Ok(policy) => policy.return_body().is_none() && policy.callback_urls().is_none(),
+            Err(_) => false,
+        }
+    }
+
+    fn find_same_object(&self, local_etag: Option<&str>) -> Option<UploadResponse> {
+        let (object, local_etag) = self.object_to_compare(local_etag)?;
+        let object_info = object.get_info().ok()?;
+        Self::same_object_response(&object, &object_info, local_etag)
+    }
+
+    #[cfg(feature = "async")]
+    async fn find_same_object_async(&self, local_etag: Option<&str>) -> Option<UploadResponse> {
+        let (object, local_etag) = self.object_to_compare(local_etag)?;
+        let object_info = object.get_info_async().await.ok()?;
+        Self::same_object_response(&object, &object_info, local_etag)
+    }
+
+    fn object_to_compare<'e>(&self, local_etag: Option<&'e str>) -> Option<(Object, &'e str)> {
+        let (credential, key, local_etag) = match (&self.skip_if_same, &self.key, local_etag) {
+            (Some(credential), Some(key), Some(local_etag)) => (credential, key, local_etag),
+            _ => return None,
+        };
+        let object = BucketBuilder::new(
+            self.bucket_name.to_string().into(),
+            credential.to_owned(),
+            self.upload_manager.to_owned(),
+        )
+        .build()
+        .object(key.to_string());
+        let object = match self.deadline {
+            Some(deadline) => object.operation_timeout(deadline.checked_duration_since(Instant::now())?),
+            None => object,
+        };
+        Some((object, local_etag))
+    }
+
+    fn same_object_response(object: &Object, object_info: &ObjectInfo, local_etag: &str) -> Option<UploadResponse> {
+        if object_info.hash() != local_etag {
+            return None;
+        }
+        Some(
+            json!({
+                "key": object.key(),
+                "hash": object_info.hash(),
+                "fsize": object_info.size(),
+                "mimeType": object_info.mime_type(),
+            })
+            .into(),
+        )
+    }
+
+    fn is_resumable(&self, size: u64) -> bool {
+        match self.resumable_policy {
+            ResumablePolicy::Threshold(threshold) => size > threshold.into(),
+            ResumablePolicy::Always => true,
+            ResumablePolicy::Never => false,
+        }
+    }
+
+    fn should_verify_etag(&self, resumable: bool, size: u64) -> bool {
+        if !self.checksum_policy.is_etag_verification_enabled() {
+            return false;
+        }
+        if !resumable {
+            return true;
+        }
+        // 只有数据按照 4 MB 分块上传时，七牛服务器返回的 Hash 值才是标准的 Etag
+        let config = self.upload_manager.config();
+        match self
+            .resumable_upload_version
+            .unwrap_or_else(|| config.resumable_upload_version())
+        {
+            ResumableUploadVersion::V1 => true,
+            ResumableUploadVersion::V2 => {
+                self.part_size_policy
+                    .unwrap_or_else(|| config.upload_part_size_policy())
+                    == UploadPartSizePolicy::Fixed
+                    && config.upload_block_size() == ETAG_BLOCK_SIZE
+                    && size <= u64::from(ETAG_BLOCK_SIZE) * MAX_PARTS_COUNT as u64
+            }
+        }
+    }
+
+    fn verify_etag(upload_response: UploadResponse, expected_etag: Option<String>) -> UploadResult {
+        if let (Some(expected_etag), Some(hash)) = (expected_etag, upload_response.hash()) {
+            if expected_etag != hash {
+                return Err(UploadError::EtagMismatchError {
+                    local_etag: expected_etag,
+                    remote_hash: hash.to_owned(),
+                });
+            }
+        }
+        Ok(upload_response)
+    }
+
+    fn convert_response(upload_response: UploadResponse) -> UploadResult<T> {
+        T::from_upload_response(upload_response).map_err(UploadError::DeserializeResponseError)
     }
 
-    fn upload_file_by_form(self, file_path: &Path, file_name: Cow<str>, mime: Option<Mime>) -> UploadResult {
+    fn form_uploader(&self) -> FormUploaderBuilder {
         let mut uploader = FormUploaderBuilder::new(self.upload_manager, &self.upload_token, &self.up_urls_list);
-        if let Some(key) = self.key {
-            uploader = uploader.key(key);
+        if let Some(key) = &self.key {
+            uploader = uploader.key(Cow::Borrowed(key.as_ref()));
         }
-        for (k, v) in self.vars.into_iter() {
-            uploader = uploader.var(&k, v);
+        for (k, v) in self.vars.iter() {
+            uploader = uploader.var(k, Cow::Borrowed(v.as_ref()));
         }
-        for (k, v) in self.metadata.into_iter() {
-            uploader = uploader.metadata(&k, v);
+        for (k, v) in self.metadata.iter() {
+            uploader = uploader.metadata(k, Cow::Borrowed(v.as_ref()));
         }
         if let Some(callback) = &self.on_uploading_progress {
             uploader = uploader.on_uploading_progress(callback.as_ref());
         }
-        Ok(uploader
-            .seekable_stream(
-                File::open(file_path)?,
-                Self::guess_filename(file_path, file_name),
-                Self::guess_mime_from_file_path(mime, file_path),
-                self.checksum_enabled,
-            )?
-            .send()?)
+        if let Some(rate_limiter) = &self.rate_limiter {
+            uploader = uploader.rate_limiter(rate_limiter);
+        }
+        if let Some(deadline) = self.deadline {
+            uploader = uploader.deadline(deadline);
+        }
+        uploader
     }
 
-    fn upload_file_by_blocks<'n>(self, file_path: &Path, file_name: Cow<'n, str>, mime: Option<Mime>) -> UploadResult {
-        let file = File::open(file_path)?;
-        let file_size = file.metadata()?.len();
-        if file_size == 0 {
-            return Err(UploadError::EmptyFileError);
-        }
+    fn resumable_uploader(&self) -> ResumableUploaderBuilder {
         let mut uploader = ResumableUploaderBuilder::new(
             self.upload_manager,
-            self.upload_token,
+            Cow::Borrowed(&self.upload_token),
             &self.bucket_name,
             &self.up_urls_list,
         )
         .max_concurrency(self.max_concurrency)
-        .vars(self.vars)
-        .metadata(self.metadata);
-
+        .vars(
+            self.vars
+                .iter()
+                .map(|(k, v)| (Cow::Borrowed(k.as_ref()), Cow::Borrowed(v.as_ref())))
+                .collect(),
+        )
+        .metadata(
+            self.metadata
+                .iter()
+                .map(|(k, v)| (Cow::Borrowed(k.as_ref()), Cow::Borrowed(v.as_ref())))
+                .collect(),
+        );
+        if let Some(upload_token_provider) = self.upload_token_provider.as_deref() {
+            uploader = uploader.upload_token_provider(upload_token_provider);
+        }
         if let Some(key) = &self.key {
-            uploader = uploader.key(key.to_owned());
+            uploader = uploader.key(Cow::Borrowed(key.as_ref()));
         }
         if let Some(callback) = &self.on_uploading_progress {
             uploader = uploader.on_uploading_progress(callback.as_ref());
         }
-        if let Some(thread_pool) = self.thread_pool {
-            uploader = uploader.thread_pool(thread_pool);
+        if let Some(rate_limiter) = &self.rate_limiter {
+            uploader = uploader.rate_limiter(rate_limiter);
+        }
+        if let Some(thread_pool) = &self.thread_pool {
+            uploader = uploader.thread_pool(Ron::Referenced(thread_pool.as_ref()));
+        }
+        if let Some(version) = self.resumable_upload_version {
+            uploader = uploader.version(version);
+        }
+        if let Some(part_size_policy) = self.part_size_policy {
+            uploader = uploader.part_size_policy(part_size_policy);
+        }
+        if let Some(deadline) = self.deadline {
+            uploader = uploader.deadline(deadline);
+        }
+        uploader
+    }
+
+    fn upload_file_by_form(&self, file_path: &Path, file_name: Cow<str>, mime: Option<Mime>) -> UploadResult {
+        Ok(self
+            .form_uploader()
+            .seekable_stream(
+                File::open(file_path)?,
+                Self::guess_filename(file_path, file_name),
+                Self::guess_mime_from_file_path(mime, file_path),
+                self.checksum_policy.is_form_crc32_enabled(),
+            )?
+            .send()?)
+    }
+
+    fn file_resumable_uploader<'a>(
+        &'a self,
+        file_path: &'a Path,
+        file_name: Cow<'a, str>,
+        mime: Option<Mime>,
+    ) -> UploadResult<ResumableUploader<'a, File>> {
+        let file = File::open(file_path)?;
+        let file_size = file.metadata()?.len();
+        if file_size == 0 {
+            return Err(UploadError::EmptyFileError);
         }
-        let mut uploader = uploader.file(
+        let mut uploader = self.resumable_uploader().file(
             file,
             file_path.into(),
             Self::guess_filename(file_path, file_name),
             file_size,
             Self::guess_mime_from_file_path(mime, file_path),
-            self.checksum_enabled,
+            self.checksum_policy.part_checksum_algorithm(),
         )?;
         Self::prepare_for_resuming(
             self.key.as_ref().map(|key| key.as_ref()),
@@ -300,7 +813,30 @@ impl<'b> ObjectUploader<'b> {
             &mut uploader,
             file_path,
         )?;
-        Ok(uploader.send()?)
+        Ok(uploader)
+    }
+
+    #[cfg(feature = "async")]
+    async fn upload_file_by_form_async(
+        &self,
+        file_path: &Path,
+        file_name: Cow<'_, str>,
+        mime: Option<Mime>,
+    ) -> UploadResult {
+        let content = {
+            let file_path = file_path.to_owned();
+            run_blocking(move || std::fs::read(file_path)).await?
+        };
+        Ok(self
+            .form_uploader()
+            .bytes(
+                &content,
+                Self::guess_filename(file_path, file_name),
+                Self::guess_mime_from_file_path(mime, file_path),
+                self.checksum_policy.is_form_crc32_enabled(),
+            )?
+            .send_async()
+            .await?)
     }
 
     fn prepare_for_resuming(
@@ -322,19 +858,7 @@ impl<'b> ObjectUploader<'b> {
         file_name: Cow<str>,
         mime: Option<Mime>,
     ) -> UploadResult {
-        let mut uploader = FormUploaderBuilder::new(self.upload_manager, &self.upload_token, &self.up_urls_list);
-        if let Some(key) = self.key {
-            uploader = uploader.key(key);
-        }
-        for (k, v) in self.vars.into_iter() {
-            uploader = uploader.var(&k, v);
-        }
-        for (k, v) in self.metadata.into_iter() {
-            uploader = uploader.metadata(&k, v);
-        }
-        if let Some(callback) = &self.on_uploading_progress {
-            uploader = uploader.on_uploading_progress(callback.as_ref());
-        }
+        let uploader = self.form_uploader();
         let mime = Self::guess_mime_from_file_name(mime, file_name.as_ref());
         let upload_response = if size > 0 {
             uploader.stream(stream.take(size), file_name, mime, None)?.send()?
@@ -351,35 +875,58 @@ impl<'b> ObjectUploader<'b> {
         file_name: Cow<str>,
         mime: Option<Mime>,
     ) -> UploadResult {
-        let mut uploader = ResumableUploaderBuilder::new(
-            &self.upload_manager,
-            self.upload_token,
-            &self.bucket_name,
-            &self.up_urls_list,
-        )
-        .max_concurrency(self.max_concurrency)
-        .vars(self.vars)
-        .metadata(self.metadata);
-        if let Some(key) = self.key {
-            uploader = uploader.key(key);
-        }
-        if let Some(callback) = &self.on_uploading_progress {
-            uploader = uploader.on_uploading_progress(callback.as_ref());
-        }
-        if let Some(thread_pool) = self.thread_pool {
-            uploader = uploader.thread_pool(thread_pool);
-        }
+        let uploader = self.resumable_uploader();
         let mime = Self::guess_mime_from_file_name(mime, file_name.as_ref());
         let upload_response = if size > 0 {
             uploader
-                .stream(stream.take(size), size, mime, file_name, true)?
+                .stream(
+                    stream.take(size),
+                    size,
+                    mime,
+                    file_name,
+                    self.checksum_policy.part_checksum_algorithm(),
+                )?
                 .send()?
         } else {
-            uploader.stream(stream, size, mime, file_name, true)?.send()?
+            uploader
+                .stream(
+                    stream,
+                    size,
+                    mime,
+                    file_name,
+                    self.checksum_policy.part_checksum_algorithm(),
+                )?
+                .send()?
         };
         Ok(upload_response)
     }
 
+    fn bytes_form_uploader<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        file_name: Cow<'a, str>,
+        mime: Option<Mime>,
+    ) -> UploadResult<FormUploader<'a>> {
+        let mime = Self::guess_mime_from_file_name(mime, file_name.as_ref());
+        self.form_uploader()
+            .bytes(bytes, file_name, mime, self.checksum_policy.is_form_crc32_enabled())
+    }
+
+    fn bytes_resumable_uploader<'a>(
+        &'a self,
+        bytes: Cow<'a, [u8]>,
+        file_name: Cow<'a, str>,
+        mime: Option<Mime>,
+    ) -> UploadResult<ResumableUploader<'a, SeekAdapter<Empty>>> {
+        if bytes.is_empty() {
+            return Err(UploadError::EmptyFileError);
+        }
+        let mime = Self::guess_mime_from_file_name(mime, file_name.as_ref());
+        Ok(self
+            .resumable_uploader()
+            .bytes(bytes, mime, file_name, self.checksum_policy.part_checksum_algorithm()))
+    }
+
     fn guess_filename<'n>(file_path: &Path, file_name: Cow<'n, str>) -> Cow<'n, str> {
         if file_name.is_empty() {
             file_path
@@ -405,6 +952,23 @@ impl<'b> ObjectUploader<'b> {
     }
 }
 
+// `mime_types` 如果以 `!` 开头，则表示禁止上传列表中的 MIME 类型
+fn is_mime_type_allowed(mime: &Mime, mime_types: &[&str]) -> bool {
+    let denied = mime_types.first().map_or(false, |mime_type| mime_type.starts_with('!'));
+    let matched = mime_types
+        .iter()
+        .map(|mime_type| mime_type.trim_start_matches('!'))
+        .filter(|mime_type| !mime_type.is_empty())
+        .any(|mime_type| {
+            if let Some(type_) = mime_type.strip_suffix("/*") {
+                mime.type_().as_str().eq_ignore_ascii_case(type_)
+            } else {
+                mime.essence_str().eq_ignore_ascii_case(mime_type)
+            }
+        });
+    matched != denied
+}
+
 /// 上传错误
 #[derive(Error, Debug)]
 pub enum UploadError {
@@ -417,28 +981,111 @@ pub enum UploadError {
     /// 调用七牛 API 上传时发送错误
     #[error("Qiniu API call error: {0}")]
     QiniuError(#[from] crate::http::Error),
+    /// 上传完毕后，本地计算的 Etag 与七牛服务器返回的文件 Hash 值不一致
+    #[error("Etag mismatch, local etag is {local_etag}, but remote hash is {remote_hash}")]
+    EtagMismatchError {
+        /// 本地计算的 Etag
+        local_etag: String,
+        /// 七牛服务器返回的文件 Hash 值
+        remote_hash: String,
+    },
+    /// 上传成功，但上传响应无法转换为指定的上传响应类型
+    #[error("Failed to deserialize upload response: {0}")]
+    DeserializeResponseError(serde_json::Error),
+    /// 上传凭证无法解析
+    #[error("Invalid upload token: {0}")]
+    InvalidUploadTokenError(#[from] UploadTokenParseError),
+    /// 上传凭证已经过期或即将过期
+    #[error("Upload token is expired or about to expire, deadline is {deadline:?}")]
+    UploadTokenExpiredError {
+        /// 上传凭证过期时间
+        deadline: SystemTime,
+    },
+    /// 上传对象的名称不符合上传凭证的对象名称约束
+    #[error("Key {key} does not match the scope key {scope_key} of upload token")]
+    KeyMismatchError {
+        /// 上传对象的名称
+        key: String,
+        /// 上传凭证中的对象名称约束或对象名称前缀约束
+        scope_key: String,
+    },
+    /// 上传文件尺寸超出上传凭证限定的范围
+    #[error("File size {size} is out of the limitation of upload token, min: {min:?}, max: {max:?}")]
+    FileSizeLimitationError {
+        /// 上传文件尺寸
+        size: u64,
+        /// 上传凭证限定的最小尺寸
+        min: Option<usize>,
+        /// 上传凭证限定的最大尺寸
+        max: Option<usize>,
+    },
+    /// 上传文件的 MIME 类型不符合上传凭证限定的文件类型
+    #[error("MIME type {mime} is not allowed by upload token, mime limit is {mime_limit}")]
+    MimeTypeLimitationError {
+        /// 上传文件的 MIME 类型
+        mime: Mime,
+        /// 上传凭证限定的文件类型
+        mime_limit: String,
+    },
+}
+
+impl UploadError {
+    /// 重新上传是否可能成功
+    ///
+    /// 七牛服务器明确拒绝的请求（例如上传凭证错误，或是在仅能新增模式下对象已经存在）、
+    /// 用户取消上传，空文件，无法打开或读取本地文件，以及上传凭证本地校验失败等错误，重新上传也不会成功，因此是不可重试的
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::IOError(err) => !matches!(
+                err.kind(),
+                IOErrorKind::NotFound
+                    | IOErrorKind::PermissionDenied
+                    | IOErrorKind::InvalidInput
+                    | IOErrorKind::InvalidData
+            ),
+            Self::EmptyFileError => false,
+            Self::QiniuError(err) => {
+                err.retry_kind() != HTTPRetryKind::UnretryableError
+                    && !matches!(err.error_kind(), HTTPErrorKind::UserCanceled)
+            }
+            Self::EtagMismatchError { .. } => true,
+            Self::DeserializeResponseError(_)
+            | Self::InvalidUploadTokenError(_)
+            | Self::UploadTokenExpiredError { .. }
+            | Self::KeyMismatchError { .. }
+            | Self::FileSizeLimitationError { .. }
+            | Self::MimeTypeLimitationError { .. } => false,
+        }
+    }
 }
+
 /// 上传结果
-pub type UploadResult = Result<UploadResponse, UploadError>;
+pub type UploadResult<T = UploadResponse> = Result<T, UploadError>;
 
 #[cfg(test)]
 mod tests {
     use super::{
-        super::{resumable_uploader::encode_key, UploadPolicyBuilder},
+        super::{resumable_uploader::encode_key, PartChecksumAlgorithm, UploadPolicyBuilder},
         *,
     };
     use crate::{
         http::{DomainsManagerBuilder, Error as HTTPError, ErrorKind as HTTPErrorKind, HeadersOwned, Method},
-        utils::mime,
+        utils::{crc32, mime},
         ConfigBuilder, Credential,
     };
     use qiniu_http::ResponseBuilder;
     use qiniu_test_utils::{
+        fault_injection::{Fault, FaultInjectionCaller, FaultRule},
         http_call_mock::{fake_req_id, CallHandlers},
         temp_file::create_temp_file,
     };
-    use serde_json::json;
-    use std::{error::Error, result::Result};
+    use serde::Deserialize;
+    use std::{
+        error::Error,
+        result::Result,
+        sync::atomic::{AtomicUsize, Ordering::Relaxed},
+        thread::sleep,
+    };
 
     #[test]
     fn test_storage_uploader_object_uploader_upload_file_with_recovering() -> Result<(), Box<dyn Error>> {
@@ -533,10 +1180,11 @@ mod tests {
                 "test_upload_id",
                 &["http://z1h1.com"],
                 1 << 22,
+                ResumableUploadVersion::V2,
             )?;
-            medium.append("etag_1", 1)?;
-            medium.append("etag_3", 3)?;
-            medium.append("etag_5", 5)?;
+            medium.append("etag_1", 1, 0, 1 << 22)?;
+            medium.append("etag_3", 3, 2 * (1 << 22), 1 << 22)?;
+            medium.append("etag_5", 5, 4 * (1 << 22), 1 << 22)?;
         }
         let result = ObjectUploader::new(
             &UploadManager::new(config),
@@ -551,7 +1199,109 @@ mod tests {
         Ok(())
     }
     #[test]
-    fn test_storage_uploader_object_uploader_upload_file_with_1_unretryable_failure() -> Result<(), Box<dyn Error>> {
+    fn test_storage_uploader_object_uploader_upload_file_by_v1_with_recovering() -> Result<(), Box<dyn Error>> {
+        let temp_path = create_temp_file(10 * (1 << 20))?.into_temp_path();
+        let config = ConfigBuilder::default()
+            .http_request_handler(
+                CallHandlers::new(|request| {
+                    panic!("Unexpected Request: {} {}", request.method(), request.url());
+                })
+                .install(
+                    Method::POST,
+                    "^".to_owned() + &regex::escape("http://z1h1.com/bput/ctx_2_1/2097152") + "$",
+                    |request, called| {
+                        if called > 1 {
+                            panic!("Unexpected call `POST {}` for {} times", request.url(), called);
+                        }
+                        assert_eq!(request.body().len(), 1 << 21);
+                        let mut headers = HeadersOwned::new();
+                        headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                        headers.insert("X-Reqid".into(), fake_req_id().into());
+                        Ok(ResponseBuilder::default()
+                            .status_code(200u16)
+                            .headers(headers)
+                            .bytes_as_body(
+                                json!({"ctx": "ctx_2_2", "crc32": crc32::from_bytes(&request.body().to_bytes()), "offset": 1 << 22})
+                                    .to_string(),
+                            )
+                            .build())
+                    },
+                )
+                .install(
+                    Method::POST,
+                    "^".to_owned() + &regex::escape("http://z1h1.com/mkblk/2097152") + "$",
+                    |request, called| {
+                        if called > 1 {
+                            panic!("Unexpected call `POST {}` for {} times", request.url(), called);
+                        }
+                        let mut headers = HeadersOwned::new();
+                        headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                        headers.insert("X-Reqid".into(), fake_req_id().into());
+                        Ok(ResponseBuilder::default()
+                            .status_code(200u16)
+                            .headers(headers)
+                            .bytes_as_body(
+                                json!({"ctx": "ctx_3_1", "crc32": crc32::from_bytes(&request.body().to_bytes()), "offset": 1 << 21})
+                                    .to_string(),
+                            )
+                            .build())
+                    },
+                )
+                .install(
+                    Method::POST,
+                    "^".to_owned()
+                        + &regex::escape(
+                            &("http://z1h1.com/mkfile/10485760/key/".to_owned()
+                                + &encode_key(Some("test-key"))
+                                + "/fname/"),
+                        ),
+                    |request, _| {
+                        assert_eq!(request.body().to_bytes().as_ref(), b"ctx_1_2,ctx_2_2,ctx_3_1");
+                        let mut headers = HeadersOwned::new();
+                        headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                        headers.insert("X-Reqid".into(), fake_req_id().into());
+                        Ok(ResponseBuilder::default()
+                            .status_code(200u16)
+                            .headers(headers)
+                            .bytes_as_body(json!({"hash": "abcdef", "key": "test-key"}).to_string())
+                            .build())
+                    },
+                ),
+            )
+            .resumable_upload_version(ResumableUploadVersion::V1)
+            .upload_logger(None)
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let policy = UploadPolicyBuilder::new_policy_for_bucket("test_bucket", &config).build();
+        {
+            // 第一个块已经上传完毕，第二个块只上传了第一个片
+            let medium = config.upload_recorder().open_and_write_metadata(
+                &temp_path,
+                Some("test-key"),
+                "",
+                &["http://z1h1.com"],
+                1 << 22,
+                ResumableUploadVersion::V1,
+            )?;
+            medium.append_ctx("ctx_1_1", 1, 0, 1 << 22, 1 << 21)?;
+            medium.append_ctx("ctx_1_2", 1, 0, 1 << 22, 1 << 22)?;
+            medium.append_ctx("ctx_2_1", 2, 1 << 22, 1 << 22, 1 << 21)?;
+        }
+        let result = ObjectUploader::new(
+            &UploadManager::new(config),
+            Cow::Owned(UploadToken::new(policy, get_credential())),
+            "test_bucket".into(),
+            vec![vec![Box::from("http://z1h1.com")].into()].into(),
+        )
+        .key("test-key")
+        .upload_file(temp_path, "", None)?;
+        assert_eq!(result.key(), Some("test-key"));
+        assert_eq!(result.hash(), Some("abcdef"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_storage_uploader_object_uploader_upload_file_with_1_unretryable_failure() -> Result<(), Box<dyn Error>> {
         let temp_path = create_temp_file(10 * (1 << 20))?.into_temp_path();
         let config = ConfigBuilder::default()
             .http_request_handler(
@@ -660,6 +1410,855 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_storage_uploader_object_uploader_upload_bytes() -> Result<(), Box<dyn Error>> {
+        let config = ConfigBuilder::default()
+            .http_request_handler(
+                CallHandlers::new(|request| {
+                    panic!("Unexpected Request: {} {}", request.method(), request.url());
+                })
+                .install(Method::POST, "^http://z1h1.com/?$", |request, called| {
+                    if called > 1 {
+                        panic!("Unexpected call `POST {}` for {} times", request.url(), called);
+                    }
+                    let mut headers = HeadersOwned::new();
+                    headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                    headers.insert("X-Reqid".into(), fake_req_id());
+                    Ok(ResponseBuilder::default()
+                        .status_code(200u16)
+                        .headers(headers)
+                        .bytes_as_body(json!({"hash": "form_hash", "key": "test-key"}).to_string())
+                        .build())
+                })
+                .install(
+                    Method::POST,
+                    "^".to_owned()
+                        + &regex::escape(
+                            &("http://z1h1.com/buckets/test_bucket/objects/".to_owned()
+                                + &encode_key(Some("test-key"))
+                                + "/uploads"),
+                        )
+                        + "$",
+                    |_, _| {
+                        let mut headers = HeadersOwned::new();
+                        headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                        headers.insert("X-Reqid".into(), fake_req_id());
+                        Ok(ResponseBuilder::default()
+                            .status_code(200u16)
+                            .headers(headers)
+                            .bytes_as_body(json!({"uploadId":"test_upload_id"}).to_string())
+                            .build())
+                    },
+                )
+                .install(
+                    Method::PUT,
+                    "^".to_owned()
+                        + &regex::escape(
+                            &("http://z1h1.com/buckets/test_bucket/objects/".to_owned()
+                                + &encode_key(Some("test-key"))
+                                + "/uploads/test_upload_id/"),
+                        )
+                        + "[12]$",
+                    |request, called| {
+                        if called > 2 {
+                            panic!("Unexpected call `PUT {}` for {} times", request.url(), called);
+                        }
+                        let mut headers = HeadersOwned::new();
+                        headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                        headers.insert("X-Reqid".into(), fake_req_id());
+                        Ok(ResponseBuilder::default()
+                            .status_code(200u16)
+                            .headers(headers)
+                            .bytes_as_body(json!({ "etag": format!("etag_{}", request.body().len()) }).to_string())
+                            .build())
+                    },
+                )
+                .install(
+                    Method::POST,
+                    "^".to_owned()
+                        + &regex::escape(
+                            &("http://z1h1.com/buckets/test_bucket/objects/".to_owned()
+                                + &encode_key(Some("test-key"))
+                                + "/uploads/test_upload_id"),
+                        )
+                        + "$",
+                    |_, _| {
+                        let mut headers = HeadersOwned::new();
+                        headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                        headers.insert("X-Reqid".into(), fake_req_id());
+                        Ok(ResponseBuilder::default()
+                            .status_code(200u16)
+                            .headers(headers)
+                            .bytes_as_body(json!({"hash": "resumable_hash", "key": "test-key"}).to_string())
+                            .build())
+                    },
+                ),
+            )
+            .upload_logger(None)
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let upload_manager = UploadManager::new(config);
+        let policy = UploadPolicyBuilder::new_policy_for_bucket("test_bucket", upload_manager.config()).build();
+        let token = UploadToken::new(policy, get_credential());
+        let data = vec![b'x'; 5 * (1 << 20)];
+
+        let result = ObjectUploader::new(
+            &upload_manager,
+            Cow::Borrowed(&token),
+            "test_bucket".into(),
+            vec![vec![Box::from("http://z1h1.com")].into()].into(),
+        )
+        .key("test-key")
+        .upload_bytes(&data[..1 << 10], "", None)?;
+        assert_eq!(result.hash(), Some("form_hash"));
+
+        let result = ObjectUploader::new(
+            &upload_manager,
+            Cow::Borrowed(&token),
+            "test_bucket".into(),
+            vec![vec![Box::from("http://z1h1.com")].into()].into(),
+        )
+        .key("test-key")
+        .upload_bytes(data, "", None)?;
+        assert_eq!(result.hash(), Some("resumable_hash"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_storage_uploader_object_uploader_operation_timeout() -> Result<(), Box<dyn Error>> {
+        let config = ConfigBuilder::default()
+            .http_request_handler(
+                FaultInjectionCaller::new(CallHandlers::new(|request| {
+                    panic!("Unexpected Request: {} {}", request.method(), request.url());
+                }))
+                .rule(FaultRule::new().always(Fault::Timeout)),
+            )
+            .http_request_retries(100)
+            .http_request_retry_delay(Duration::from_millis(100))
+            .operation_timeout(Duration::from_secs(60))
+            .upload_logger(None)
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let upload_manager = UploadManager::new(config);
+        let policy = UploadPolicyBuilder::new_policy_for_bucket("test_bucket", upload_manager.config()).build();
+        let token = UploadToken::new(policy, get_credential());
+
+        let timer = Instant::now();
+        let err = ObjectUploader::new(
+            &upload_manager,
+            Cow::Borrowed(&token),
+            "test_bucket".into(),
+            vec![vec![Box::from("http://z1h1.com")].into()].into(),
+        )
+        .key("test-key")
+        .operation_timeout(Duration::from_millis(300))
+        .upload_bytes(b"hello qiniu".as_ref(), "", None)
+        .unwrap_err();
+        assert!(timer.elapsed() < Duration::from_secs(2));
+        match err {
+            UploadError::QiniuError(err) => {
+                assert!(::matches::matches!(err.error_kind(), HTTPErrorKind::DeadlineExceeded))
+            }
+            err => panic!("Unexpected error: {}", err),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_storage_uploader_object_uploader_operation_timeout_between_parts() -> Result<(), Box<dyn Error>> {
+        const PARTS: usize = 8;
+        let parts_uploaded = Arc::new(AtomicUsize::new(0));
+        let config = ConfigBuilder::default()
+            .http_request_handler(
+                CallHandlers::new(|request| {
+                    panic!("Unexpected Request: {} {}", request.method(), request.url());
+                })
+                .install(
+                    Method::POST,
+                    "^".to_owned()
+                        + &regex::escape(
+                            &("http://z1h1.com/buckets/test_bucket/objects/".to_owned()
+                                + &encode_key(Some("test-key"))
+                                + "/uploads"),
+                        )
+                        + "$",
+                    |_, _| {
+                        let mut headers = HeadersOwned::new();
+                        headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                        headers.insert("X-Reqid".into(), fake_req_id());
+                        Ok(ResponseBuilder::default()
+                            .status_code(200u16)
+                            .headers(headers)
+                            .bytes_as_body(json!({"uploadId":"test_upload_id"}).to_string())
+                            .build())
+                    },
+                )
+                .install(
+                    Method::PUT,
+                    "^".to_owned()
+                        + &regex::escape(
+                            &("http://z1h1.com/buckets/test_bucket/objects/".to_owned()
+                                + &encode_key(Some("test-key"))
+                                + "/uploads/test_upload_id/"),
+                        )
+                        + "\\d+$",
+                    {
+                        let parts_uploaded = parts_uploaded.to_owned();
+                        move |request, _| {
+                            sleep(Duration::from_millis(150));
+                            parts_uploaded.fetch_add(1, Relaxed);
+                            let mut headers = HeadersOwned::new();
+                            headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                            headers.insert("X-Reqid".into(), fake_req_id());
+                            Ok(ResponseBuilder::default()
+                                .status_code(200u16)
+                                .headers(headers)
+                                .bytes_as_body(json!({ "etag": format!("etag_{}", request.body().len()) }).to_string())
+                                .build())
+                        }
+                    },
+                ),
+            )
+            .upload_logger(None)
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let upload_manager = UploadManager::new(config);
+        let policy = UploadPolicyBuilder::new_policy_for_bucket("test_bucket", upload_manager.config()).build();
+        let token = UploadToken::new(policy, get_credential());
+        let data = vec![b'x'; PARTS * (1 << 22)];
+
+        let err = ObjectUploader::new(
+            &upload_manager,
+            Cow::Borrowed(&token),
+            "test_bucket".into(),
+            vec![vec![Box::from("http://z1h1.com")].into()].into(),
+        )
+        .key("test-key")
+        .always_be_resumable()
+        .max_concurrency(1)
+        .operation_timeout(Duration::from_millis(400))
+        .upload_bytes(data, "", None)
+        .unwrap_err();
+        match err {
+            UploadError::QiniuError(err) => {
+                assert!(::matches::matches!(err.error_kind(), HTTPErrorKind::DeadlineExceeded))
+            }
+            err => panic!("Unexpected error: {}", err),
+        }
+        // 截止时间过后不再上传剩余的分片，也不会完成上传
+        let parts_uploaded = parts_uploaded.load(Relaxed);
+        assert!(parts_uploaded > 0);
+        assert!(parts_uploaded < PARTS);
+        Ok(())
+    }
+
+    #[test]
+    fn test_storage_uploader_object_uploader_upload_bytes_with_etag_verification() -> Result<(), Box<dyn Error>> {
+        let data = b"hello qiniu".to_vec();
+        let expected_etag = etag::from_bytes(&data);
+        let config = ConfigBuilder::default()
+            .http_request_handler(
+                CallHandlers::new(|request| {
+                    panic!("Unexpected Request: {} {}", request.method(), request.url());
+                })
+                .install(Method::POST, "^http://z1h1.com/?$", move |_, called| {
+                    let mut headers = HeadersOwned::new();
+                    headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                    headers.insert("X-Reqid".into(), fake_req_id());
+                    let hash = if called == 1 {
+                        "wrong_hash".to_owned()
+                    } else {
+                        expected_etag.to_owned()
+                    };
+                    Ok(ResponseBuilder::default()
+                        .status_code(200u16)
+                        .headers(headers)
+                        .bytes_as_body(json!({"hash": hash, "key": "test-key"}).to_string())
+                        .build())
+                }),
+            )
+            .upload_logger(None)
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let upload_manager = UploadManager::new(config);
+        let policy = UploadPolicyBuilder::new_policy_for_bucket("test_bucket", upload_manager.config()).build();
+        let token = UploadToken::new(policy, get_credential());
+
+        match ObjectUploader::new(
+            &upload_manager,
+            Cow::Borrowed(&token),
+            "test_bucket".into(),
+            vec![vec![Box::from("http://z1h1.com")].into()].into(),
+        )
+        .key("test-key")
+        .checksum_policy(ChecksumPolicy::default().etag_verification(true))
+        .upload_bytes(&data, "", None)
+        {
+            Err(UploadError::EtagMismatchError {
+                local_etag,
+                remote_hash,
+            }) => {
+                assert_eq!(local_etag, etag::from_bytes(&data));
+                assert_eq!(remote_hash, "wrong_hash");
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        let result = ObjectUploader::new(
+            &upload_manager,
+            Cow::Borrowed(&token),
+            "test_bucket".into(),
+            vec![vec![Box::from("http://z1h1.com")].into()].into(),
+        )
+        .key("test-key")
+        .checksum_policy(ChecksumPolicy::default().etag_verification(true))
+        .upload_bytes(&data, "", None)?;
+        assert_eq!(result.hash(), Some(etag::from_bytes(&data).as_str()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_storage_uploader_object_uploader_upload_bytes_with_part_checksum() -> Result<(), Box<dyn Error>> {
+        let md5_calls = Arc::new(AtomicUsize::new(0));
+        let config = ConfigBuilder::default()
+            .http_request_handler(
+                CallHandlers::new(|request| {
+                    panic!("Unexpected Request: {} {}", request.method(), request.url());
+                })
+                .install(
+                    Method::POST,
+                    "^".to_owned()
+                        + &regex::escape(
+                            &("http://z1h1.com/buckets/test_bucket/objects/".to_owned()
+                                + &encode_key(Some("test-key"))
+                                + "/uploads"),
+                        )
+                        + "$",
+                    |_, _| {
+                        let mut headers = HeadersOwned::new();
+                        headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                        headers.insert("X-Reqid".into(), fake_req_id());
+                        Ok(ResponseBuilder::default()
+                            .status_code(200u16)
+                            .headers(headers)
+                            .bytes_as_body(json!({"uploadId":"test_upload_id"}).to_string())
+                            .build())
+                    },
+                )
+                .install(
+                    Method::PUT,
+                    "^".to_owned()
+                        + &regex::escape(
+                            &("http://z1h1.com/buckets/test_bucket/objects/".to_owned()
+                                + &encode_key(Some("test-key"))
+                                + "/uploads/test_upload_id/"),
+                        )
+                        + "[12]$",
+                    {
+                        let md5_calls = md5_calls.to_owned();
+                        move |request, _| {
+                            if let Some(content_md5) = request.headers().get(&"Content-MD5".into()) {
+                                assert_eq!(
+                                    content_md5.as_ref(),
+                                    format!("{:x}", md5::compute(request.body().to_bytes()))
+                                );
+                                md5_calls.fetch_add(1, Relaxed);
+                            }
+                            let mut headers = HeadersOwned::new();
+                            headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                            headers.insert("X-Reqid".into(), fake_req_id());
+                            Ok(ResponseBuilder::default()
+                                .status_code(200u16)
+                                .headers(headers)
+                                .bytes_as_body(json!({ "etag": format!("etag_{}", request.body().len()) }).to_string())
+                                .build())
+                        }
+                    },
+                )
+                .install(
+                    Method::POST,
+                    "^".to_owned()
+                        + &regex::escape(
+                            &("http://z1h1.com/buckets/test_bucket/objects/".to_owned()
+                                + &encode_key(Some("test-key"))
+                                + "/uploads/test_upload_id"),
+                        )
+                        + "$",
+                    |_, _| {
+                        let mut headers = HeadersOwned::new();
+                        headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                        headers.insert("X-Reqid".into(), fake_req_id());
+                        Ok(ResponseBuilder::default()
+                            .status_code(200u16)
+                            .headers(headers)
+                            .bytes_as_body(json!({"hash": "resumable_hash", "key": "test-key"}).to_string())
+                            .build())
+                    },
+                ),
+            )
+            .upload_logger(None)
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let upload_manager = UploadManager::new(config);
+        let policy = UploadPolicyBuilder::new_policy_for_bucket("test_bucket", upload_manager.config()).build();
+        let token = UploadToken::new(policy, get_credential());
+        let data = vec![b'x'; 5 * (1 << 20)];
+
+        ObjectUploader::new(
+            &upload_manager,
+            Cow::Borrowed(&token),
+            "test_bucket".into(),
+            vec![vec![Box::from("http://z1h1.com")].into()].into(),
+        )
+        .key("test-key")
+        .upload_bytes(&data, "", None)?;
+        assert_eq!(md5_calls.load(Relaxed), 2);
+
+        ObjectUploader::new(
+            &upload_manager,
+            Cow::Borrowed(&token),
+            "test_bucket".into(),
+            vec![vec![Box::from("http://z1h1.com")].into()].into(),
+        )
+        .key("test-key")
+        .checksum_policy(ChecksumPolicy::default().part_checksum(PartChecksumAlgorithm::None))
+        .upload_bytes(&data, "", None)?;
+        assert_eq!(md5_calls.load(Relaxed), 2);
+
+        // 分片上传 V2 不支持 CRC32 校验，将改用 MD5 校验
+        ObjectUploader::new(
+            &upload_manager,
+            Cow::Borrowed(&token),
+            "test_bucket".into(),
+            vec![vec![Box::from("http://z1h1.com")].into()].into(),
+        )
+        .key("test-key")
+        .checksum_policy(ChecksumPolicy::default().part_checksum(PartChecksumAlgorithm::Crc32))
+        .upload_bytes(&data, "", None)?;
+        assert_eq!(md5_calls.load(Relaxed), 4);
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_storage_uploader_object_uploader_upload_bytes_async() -> Result<(), Box<dyn Error>> {
+        let data = b"hello qiniu".to_vec();
+        let expected_etag = etag::from_bytes(&data);
+        let config = ConfigBuilder::default()
+            .http_request_handler(
+                CallHandlers::new(|request| {
+                    panic!("Unexpected Request: {} {}", request.method(), request.url());
+                })
+                .install(Method::POST, "^http://z1h1.com/?$", move |_, _| {
+                    let mut headers = HeadersOwned::new();
+                    headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                    headers.insert("X-Reqid".into(), fake_req_id());
+                    Ok(ResponseBuilder::default()
+                        .status_code(200u16)
+                        .headers(headers)
+                        .bytes_as_body(json!({"hash": expected_etag, "key": "test-key"}).to_string())
+                        .build())
+                }),
+            )
+            .upload_logger(None)
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let upload_manager = UploadManager::new(config);
+        let policy = UploadPolicyBuilder::new_policy_for_bucket("test_bucket", upload_manager.config()).build();
+        let token = UploadToken::new(policy, get_credential());
+
+        let result = futures::executor::block_on(
+            ObjectUploader::new(
+                &upload_manager,
+                Cow::Borrowed(&token),
+                "test_bucket".into(),
+                vec![vec![Box::from("http://z1h1.com")].into()].into(),
+            )
+            .key("test-key")
+            .checksum_policy(ChecksumPolicy::default().etag_verification(true))
+            .upload_bytes_async(&data, "", None),
+        )?;
+        assert_eq!(result.key(), Some("test-key"));
+        assert_eq!(result.hash(), Some(etag::from_bytes(&data).as_str()));
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_storage_uploader_object_uploader_upload_bytes_and_file_by_blocks_async() -> Result<(), Box<dyn Error>> {
+        let config = ConfigBuilder::default()
+            .http_request_handler(
+                CallHandlers::new(|request| {
+                    panic!("Unexpected Request: {} {}", request.method(), request.url());
+                })
+                .install(Method::POST, "^http://z1h1.com/?$", |request, called| {
+                    if called > 2 {
+                        panic!("Unexpected call `POST {}` for {} times", request.url(), called);
+                    }
+                    let mut headers = HeadersOwned::new();
+                    headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                    headers.insert("X-Reqid".into(), fake_req_id());
+                    Ok(ResponseBuilder::default()
+                        .status_code(200u16)
+                        .headers(headers)
+                        .bytes_as_body(json!({"hash": "form_hash", "key": "test-key"}).to_string())
+                        .build())
+                })
+                .install(
+                    Method::POST,
+                    "^".to_owned()
+                        + &regex::escape(
+                            &("http://z1h1.com/buckets/test_bucket/objects/".to_owned()
+                                + &encode_key(Some("test-key"))
+                                + "/uploads"),
+                        )
+                        + "$",
+                    |_, _| {
+                        let mut headers = HeadersOwned::new();
+                        headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                        headers.insert("X-Reqid".into(), fake_req_id());
+                        Ok(ResponseBuilder::default()
+                            .status_code(200u16)
+                            .headers(headers)
+                            .bytes_as_body(json!({"uploadId":"test_upload_id"}).to_string())
+                            .build())
+                    },
+                )
+                .install(
+                    Method::PUT,
+                    "^".to_owned()
+                        + &regex::escape(
+                            &("http://z1h1.com/buckets/test_bucket/objects/".to_owned()
+                                + &encode_key(Some("test-key"))
+                                + "/uploads/test_upload_id/"),
+                        )
+                        + "[12]$",
+                    |request, called| {
+                        if called > 4 {
+                            panic!("Unexpected call `PUT {}` for {} times", request.url(), called);
+                        }
+                        let mut headers = HeadersOwned::new();
+                        headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                        headers.insert("X-Reqid".into(), fake_req_id());
+                        Ok(ResponseBuilder::default()
+                            .status_code(200u16)
+                            .headers(headers)
+                            .bytes_as_body(json!({ "etag": format!("etag_{}", request.body().len()) }).to_string())
+                            .build())
+                    },
+                )
+                .install(
+                    Method::POST,
+                    "^".to_owned()
+                        + &regex::escape(
+                            &("http://z1h1.com/buckets/test_bucket/objects/".to_owned()
+                                + &encode_key(Some("test-key"))
+                                + "/uploads/test_upload_id"),
+                        )
+                        + "$",
+                    |_, _| {
+                        let mut headers = HeadersOwned::new();
+                        headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                        headers.insert("X-Reqid".into(), fake_req_id());
+                        Ok(ResponseBuilder::default()
+                            .status_code(200u16)
+                            .headers(headers)
+                            .bytes_as_body(json!({"hash": "resumable_hash", "key": "test-key"}).to_string())
+                            .build())
+                    },
+                ),
+            )
+            .upload_logger(None)
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let upload_manager = UploadManager::new(config);
+        let policy = UploadPolicyBuilder::new_policy_for_bucket("test_bucket", upload_manager.config()).build();
+        let token = UploadToken::new(policy, get_credential());
+        let new_uploader = || {
+            ObjectUploader::new(
+                &upload_manager,
+                Cow::Borrowed(&token),
+                "test_bucket".into(),
+                vec![vec![Box::from("http://z1h1.com")].into()].into(),
+            )
+            .key("test-key")
+        };
+        let data = vec![b'x'; 5 * (1 << 20)];
+
+        let result = futures::executor::block_on(new_uploader().upload_bytes_async(&data[..1 << 10], "", None))?;
+        assert_eq!(result.hash(), Some("form_hash"));
+        let result = futures::executor::block_on(new_uploader().upload_bytes_async(&data, "", None))?;
+        assert_eq!(result.hash(), Some("resumable_hash"));
+
+        let temp_path = create_temp_file(1 << 10)?.into_temp_path();
+        let result = futures::executor::block_on(new_uploader().upload_file_async(&temp_path, "", None))?;
+        assert_eq!(result.hash(), Some("form_hash"));
+        let temp_path = create_temp_file(5 * (1 << 20))?.into_temp_path();
+        let result = futures::executor::block_on(new_uploader().upload_file_async(&temp_path, "", None))?;
+        assert_eq!(result.hash(), Some("resumable_hash"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_storage_uploader_object_uploader_upload_bytes_with_response_type() -> Result<(), Box<dyn Error>> {
+        #[derive(Deserialize, Debug)]
+        struct CustomResponse {
+            key: String,
+            fsize: u64,
+            user: String,
+        }
+
+        let config = ConfigBuilder::default()
+            .http_request_handler(
+                CallHandlers::new(|request| {
+                    panic!("Unexpected Request: {} {}", request.method(), request.url());
+                })
+                .install(Method::POST, "^http://z1h1.com/?$", |_, called| {
+                    let mut headers = HeadersOwned::new();
+                    headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                    headers.insert("X-Reqid".into(), fake_req_id());
+                    let body = if called == 1 {
+                        json!({"key": "test-key", "fsize": 11, "user": "test-user"})
+                    } else {
+                        json!({"key": "test-key"})
+                    };
+                    Ok(ResponseBuilder::default()
+                        .status_code(200u16)
+                        .headers(headers)
+                        .bytes_as_body(body.to_string())
+                        .build())
+                }),
+            )
+            .upload_logger(None)
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let upload_manager = UploadManager::new(config);
+        let policy = UploadPolicyBuilder::new_policy_for_bucket("test_bucket", upload_manager.config())
+            .return_body(r#"{"key":$(key),"fsize":$(fsize),"user":$(x:user)}"#)
+            .build();
+        let token = UploadToken::new(policy, get_credential());
+
+        let result = ObjectUploader::new(
+            &upload_manager,
+            Cow::Borrowed(&token),
+            "test_bucket".into(),
+            vec![vec![Box::from("http://z1h1.com")].into()].into(),
+        )
+        .key("test-key")
+        .var("user", "test-user")
+        .response_type::<CustomResponse>()
+        .upload_bytes(b"hello qiniu".as_ref(), "", None)?;
+        assert_eq!(result.key, "test-key");
+        assert_eq!(result.fsize, 11);
+        assert_eq!(result.user, "test-user");
+
+        match ObjectUploader::new(
+            &upload_manager,
+            Cow::Borrowed(&token),
+            "test_bucket".into(),
+            vec![vec![Box::from("http://z1h1.com")].into()].into(),
+        )
+        .key("test-key")
+        .response_type::<CustomResponse>()
+        .upload_bytes(b"hello qiniu".as_ref(), "", None)
+        {
+            Err(UploadError::DeserializeResponseError(_)) => {}
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_storage_uploader_object_uploader_validate_upload_token() -> Result<(), Box<dyn Error>> {
+        let config = ConfigBuilder::default()
+            .http_request_handler(CallHandlers::new(|request| {
+                panic!("Unexpected Request: {} {}", request.method(), request.url());
+            }))
+            .upload_logger(None)
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let upload_manager = UploadManager::new(config);
+        let new_uploader = |token: &UploadToken| {
+            ObjectUploader::new(
+                &upload_manager,
+                Cow::Owned(token.to_owned()),
+                "test_bucket".into(),
+                vec![vec![Box::from("http://z1h1.com")].into()].into(),
+            )
+        };
+
+        let token = UploadToken::new(
+            UploadPolicyBuilder::new_policy_for_bucket("test_bucket", upload_manager.config())
+                .token_lifetime(Duration::from_secs(10))
+                .build(),
+            get_credential(),
+        );
+        match new_uploader(&token).upload_bytes(b"hello qiniu".as_ref(), "", None) {
+            Err(err @ UploadError::UploadTokenExpiredError { .. }) => assert!(!err.is_retryable()),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        let token = UploadToken::new(
+            UploadPolicyBuilder::new_policy_for_objects_with_prefix("test_bucket", "photos/", upload_manager.config())
+                .build(),
+            get_credential(),
+        );
+        match new_uploader(&token)
+            .key("videos/test-key")
+            .upload_bytes(b"hello qiniu".as_ref(), "", None)
+        {
+            Err(UploadError::KeyMismatchError { key, scope_key }) => {
+                assert_eq!(key, "videos/test-key");
+                assert_eq!(scope_key, "photos/");
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        let token = UploadToken::new(
+            UploadPolicyBuilder::new_policy_for_bucket("test_bucket", upload_manager.config())
+                .file_size_limitation(..5)
+                .build(),
+            get_credential(),
+        );
+        match new_uploader(&token).upload_bytes(b"hello qiniu".as_ref(), "", None) {
+            Err(UploadError::FileSizeLimitationError { size, min, max }) => {
+                assert_eq!(size, 11);
+                assert_eq!(min, None);
+                assert_eq!(max, Some(4));
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        let token = UploadToken::new(
+            UploadPolicyBuilder::new_policy_for_bucket("test_bucket", upload_manager.config())
+                .mime_types(&["image/*", "video/mp4"])
+                .build(),
+            get_credential(),
+        );
+        match new_uploader(&token).upload_bytes(b"hello qiniu".as_ref(), "", Some(::mime::TEXT_PLAIN)) {
+            Err(UploadError::MimeTypeLimitationError { mime, mime_limit }) => {
+                assert_eq!(mime, ::mime::TEXT_PLAIN);
+                assert_eq!(mime_limit, "image/*;video/mp4");
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        assert!(is_mime_type_allowed(&::mime::IMAGE_PNG, &["image/*", "video/mp4"]));
+        assert!(!is_mime_type_allowed(&::mime::IMAGE_PNG, &["!image/png", "text/plain"]));
+        assert!(is_mime_type_allowed(&::mime::IMAGE_JPEG, &["!image/png", "text/plain"]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_storage_uploader_object_uploader_upload_bytes_with_skip_if_same() -> Result<(), Box<dyn Error>> {
+        let local_etag = etag::from_bytes(b"hello qiniu");
+        let stat_calls = Arc::new(AtomicUsize::new(0));
+        let config = ConfigBuilder::default()
+            .http_request_handler(
+                CallHandlers::new(|request| {
+                    panic!("Unexpected Request: {} {}", request.method(), request.url());
+                })
+                .install(Method::GET, "/v3/query", |_, _| {
+                    let mut headers = HeadersOwned::new();
+                    headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                    headers.insert("X-Reqid".into(), fake_req_id());
+                    Ok(ResponseBuilder::default()
+                        .status_code(631u16)
+                        .headers(headers)
+                        .bytes_as_body(json!({"error": "no such bucket"}).to_string())
+                        .build())
+                })
+                .install(Method::GET, "/stat/", {
+                    let local_etag = local_etag.to_owned();
+                    let stat_calls = stat_calls.to_owned();
+                    move |_, called| {
+                        stat_calls.fetch_add(1, Relaxed);
+                        let mut headers = HeadersOwned::new();
+                        headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                        headers.insert("X-Reqid".into(), fake_req_id());
+                        let hash = if called == 1 {
+                            local_etag.as_str()
+                        } else {
+                            "remote_hash"
+                        };
+                        Ok(ResponseBuilder::default()
+                            .status_code(200u16)
+                            .headers(headers)
+                            .bytes_as_body(
+                                json!({
+                                    "fsize": 11u64,
+                                    "hash": hash,
+                                    "mimeType": "text/plain",
+                                    "putTime": 15_000_000_000_000_000u64,
+                                })
+                                .to_string(),
+                            )
+                            .build())
+                    }
+                })
+                .install(Method::POST, "^http://z1h1.com/?$", |_, called| {
+                    if called > 2 {
+                        panic!("Unexpected upload for {} times", called);
+                    }
+                    let mut headers = HeadersOwned::new();
+                    headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                    headers.insert("X-Reqid".into(), fake_req_id());
+                    Ok(ResponseBuilder::default()
+                        .status_code(200u16)
+                        .headers(headers)
+                        .bytes_as_body(json!({"key": "test-key", "hash": etag::from_bytes(b"hello qiniu")}).to_string())
+                        .build())
+                }),
+            )
+            .upload_logger(None)
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let upload_manager = UploadManager::new(config);
+        let policy = UploadPolicyBuilder::new_policy_for_bucket("test_bucket", upload_manager.config()).build();
+        let token = UploadToken::new(policy, get_credential());
+        let new_uploader = || {
+            ObjectUploader::new(
+                &upload_manager,
+                Cow::Borrowed(&token),
+                "test_bucket".into(),
+                vec![vec![Box::from("http://z1h1.com")].into()].into(),
+            )
+            .key("test-key")
+            .skip_if_same(get_credential())
+        };
+
+        let result = new_uploader().upload_bytes(b"hello qiniu".as_ref(), "", None)?;
+        assert_eq!(result.key(), Some("test-key"));
+        assert_eq!(result.hash(), Some(local_etag.as_str()));
+        assert_eq!(result.get("fsize").and_then(|fsize| fsize.as_u64()), Some(11));
+        assert_eq!(
+            result.get("mimeType").and_then(|mime| mime.as_str()),
+            Some("text/plain")
+        );
+
+        let result = new_uploader().upload_bytes(b"hello qiniu".as_ref(), "", None)?;
+        assert_eq!(result.key(), Some("test-key"));
+        assert_eq!(result.get("fsize"), None);
+        assert_eq!(stat_calls.load(Relaxed), 2);
+
+        let policy = UploadPolicyBuilder::new_policy_for_bucket("test_bucket", upload_manager.config())
+            .return_body("{\"key\":$(key),\"hash\":$(etag)}")
+            .build();
+        let token = UploadToken::new(policy, get_credential());
+        let result = ObjectUploader::new(
+            &upload_manager,
+            Cow::Borrowed(&token),
+            "test_bucket".into(),
+            vec![vec![Box::from("http://z1h1.com")].into()].into(),
+        )
+        .key("test-key")
+        .skip_if_same(get_credential())
+        .upload_bytes(b"hello qiniu".as_ref(), "", None)?;
+        assert_eq!(result.key(), Some("test-key"));
+        assert_eq!(stat_calls.load(Relaxed), 2);
+        Ok(())
+    }
+
     fn get_credential() -> Credential {
         Credential::new("abcdefghklmnopq", "1234567890")
     }
@@ -0,0 +1,173 @@
+use std::{
+    cmp::{max, min},
+    time::Duration,
+};
+
+/// 分片上传 V2 允许的最小分片尺寸，最后一个分片除外
+pub(super) const MIN_PART_SIZE: u64 = 1 << 20;
+/// 分片上传 V2 允许的最大分片尺寸
+pub(super) const MAX_PART_SIZE: u64 = 1 << 30;
+/// 分片上传 V2 允许的最大分片数量
+pub(super) const MAX_PARTS_COUNT: usize = 10000;
+
+/// 自适应模式下，期望每个分片的上传耗时
+const TARGET_PART_UPLOAD_DURATION: Duration = Duration::from_secs(10);
+
+/// 分片尺寸策略
+///
+/// 仅对分片上传 V2 有效，分片上传 V1 的分块尺寸固定为 4 MB
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum UploadPartSizePolicy {
+    /// 固定分片尺寸
+    ///
+    /// 所有分片均采用客户端配置中的上传分块尺寸。
+    /// 但如果文件过大，导致分片数量超过 10000 个，则会自动增大分片尺寸
+    Fixed,
+
+    /// 自适应分片尺寸
+    ///
+    /// 首个分片采用客户端配置中的上传分块尺寸，之后将根据已经观测到的上传速度调整分片尺寸，
+    /// 使每个分片的上传耗时保持在 10 秒左右，同时确保分片数量不超过 10000 个。
+    ///
+    /// 需要注意的是，每个分片在上传前都会被完整地读入内存，因此最大分片尺寸与上传并发度的乘积应该小于可用内存
+    Adaptive {
+        /// 最小分片尺寸，单位为字节，不能小于 1 MB
+        min_part_size: u32,
+        /// 最大分片尺寸，单位为字节，不能大于 1 GB
+        max_part_size: u32,
+    },
+}
+
+impl Default for UploadPartSizePolicy {
+    fn default() -> Self {
+        UploadPartSizePolicy::Fixed
+    }
+}
+
+impl UploadPartSizePolicy {
+    /// 创建自适应分片尺寸策略，分片尺寸范围为 1 MB 到 1 GB
+    pub fn adaptive() -> Self {
+        UploadPartSizePolicy::Adaptive {
+            min_part_size: MIN_PART_SIZE as u32,
+            max_part_size: MAX_PART_SIZE as u32,
+        }
+    }
+}
+
+/// 分片尺寸选择器
+///
+/// 由 `IOStatusManager` 在读取每个分片前调用，以决定该分片的尺寸
+pub(super) struct PartSizer {
+    policy: UploadPartSizePolicy,
+    current_part_size: u64,
+    total_size: Option<u64>,
+    max_parts_count: Option<usize>,
+}
+
+impl PartSizer {
+    /// 创建固定尺寸且不限制分片数量的选择器，用于分片上传 V1
+    pub(super) fn fixed(part_size: u32) -> PartSizer {
+        PartSizer {
+            policy: UploadPartSizePolicy::Fixed,
+            current_part_size: part_size.into(),
+            total_size: None,
+            max_parts_count: None,
+        }
+    }
+
+    /// 创建用于分片上传 V2 的选择器
+    pub(super) fn new(policy: UploadPartSizePolicy, initial_part_size: u32, total_size: Option<u64>) -> PartSizer {
+        PartSizer {
+            current_part_size: clamp_part_size(initial_part_size.into(), &policy),
+            policy,
+            total_size,
+            max_parts_count: Some(MAX_PARTS_COUNT),
+        }
+    }
+
+    pub(super) fn next_part_size(&self, part_number: usize, offset: u64) -> u64 {
+        let mut part_size = self.current_part_size;
+        if let Some(max_parts_count) = self.max_parts_count {
+            let min_part_size = match self.total_size {
+                Some(total_size) => {
+                    let rest_parts_count = max(max_parts_count.saturating_sub(part_number - 1), 1) as u64;
+                    let rest_size = total_size.saturating_sub(offset);
+                    (rest_size + rest_parts_count - 1) / rest_parts_count
+                }
+                // 数据总量不可预知时，每用掉 2000 个分片就将分片尺寸下限翻倍，最多可以上传约 62 GB 的数据
+                None => MIN_PART_SIZE << min((part_number - 1) / 2000, 10),
+            };
+            part_size = min(max(part_size, align_to_mb(min_part_size)), MAX_PART_SIZE);
+        }
+        part_size
+    }
+
+    /// 根据分片上传耗时调整后续分片的尺寸
+    pub(super) fn feedback(&mut self, part_size: u64, elapsed: Duration) {
+        if let UploadPartSizePolicy::Adaptive { .. } = self.policy {
+            let elapsed = elapsed.as_secs_f64();
+            if elapsed <= 0f64 {
+                return;
+            }
+            let expected_part_size = part_size as f64 / elapsed * TARGET_PART_UPLOAD_DURATION.as_secs_f64();
+            // 取平均值以平滑网络抖动带来的影响，向下对齐以确保分片尺寸可以收敛到下限
+            let part_size = (self.current_part_size as f64 + expected_part_size) / 2f64;
+            self.current_part_size = clamp_part_size(part_size as u64 / MIN_PART_SIZE * MIN_PART_SIZE, &self.policy);
+        }
+    }
+}
+
+fn clamp_part_size(part_size: u64, policy: &UploadPartSizePolicy) -> u64 {
+    let (min_part_size, max_part_size) = match policy {
+        UploadPartSizePolicy::Fixed => (MIN_PART_SIZE, MAX_PART_SIZE),
+        UploadPartSizePolicy::Adaptive {
+            min_part_size,
+            max_part_size,
+        } => (
+            max(u64::from(*min_part_size), MIN_PART_SIZE),
+            min(u64::from(*max_part_size), MAX_PART_SIZE),
+        ),
+    };
+    min(max(part_size, min_part_size), max(max_part_size, min_part_size))
+}
+
+fn align_to_mb(size: u64) -> u64 {
+    (size + MIN_PART_SIZE - 1) / MIN_PART_SIZE * MIN_PART_SIZE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{error::Error, result::Result};
+
+    #[test]
+    fn test_storage_uploader_part_sizer_fixed() -> Result<(), Box<dyn Error>> {
+        let mut sizer = PartSizer::new(UploadPartSizePolicy::Fixed, 1 << 22, Some(1 << 30));
+        assert_eq!(sizer.next_part_size(1, 0), 1 << 22);
+        sizer.feedback(1 << 22, Duration::from_millis(1));
+        assert_eq!(sizer.next_part_size(2, 1 << 22), 1 << 22);
+
+        let sizer = PartSizer::new(UploadPartSizePolicy::Fixed, 1 << 22, Some(100 * (1 << 30)));
+        let part_size = sizer.next_part_size(1, 0);
+        assert!(part_size > 1 << 22);
+        assert_eq!(part_size % MIN_PART_SIZE, 0);
+        assert!(part_size * MAX_PARTS_COUNT as u64 >= 100 * (1 << 30));
+        Ok(())
+    }
+
+    #[test]
+    fn test_storage_uploader_part_sizer_adaptive() -> Result<(), Box<dyn Error>> {
+        let mut sizer = PartSizer::new(UploadPartSizePolicy::adaptive(), 1 << 22, None);
+        assert_eq!(sizer.next_part_size(1, 0), 1 << 22);
+        sizer.feedback(1 << 22, Duration::from_secs(1));
+        assert_eq!(sizer.next_part_size(2, 1 << 22), 22 * (1 << 20));
+        sizer.feedback(22 * (1 << 20), Duration::from_secs(100));
+        assert_eq!(sizer.next_part_size(3, 26 * (1 << 20)), 12 * (1 << 20));
+        for _ in 0..100 {
+            sizer.feedback(1 << 20, Duration::from_secs(100));
+        }
+        assert_eq!(sizer.next_part_size(4, 0), MIN_PART_SIZE);
+        assert_eq!(sizer.next_part_size(9001, 0), MIN_PART_SIZE << 4);
+        Ok(())
+    }
+}
@@ -1,35 +1,63 @@
 use super::{
-    io_status_manager::{IOStatusManager, Result as IOStatusResult},
+    io_status_manager::{CompletedPart, IOStatusManager, PartData, PartSource, Result as IOStatusResult},
+    part_sizer::{PartSizer, UploadPartSizePolicy},
     upload_manager::UploadManager,
     upload_recorder::{FileUploadRecordMedium, FileUploadRecordMediumBlockItem, FileUploadRecordMediumMetadata},
-    upload_response_callback, TokenizedUploadLogger, UpType, UploadError, UploadLoggerRecordBuilder, UploadResponse,
-    UploadToken,
+    upload_request::{parse_upload_response, UploadRequest, UploadingProgress},
+    upload_token_provider::UploadTokenProvider,
+    PartChecksumAlgorithm, TokenizedUploadLogger, UpType, UploadError, UploadLoggerRecordBuilder, UploadResponse,
+    UploadThrottle, UploadToken,
 };
 use crate::{
-    http::{Client, Error as HTTPError, ErrorKind as HTTPErrorKind, Result as HTTPResult, RetryKind},
-    utils::{base64, ron::Ron, seek_adapter},
+    http::{Client, Error as HTTPError, ErrorKind as HTTPErrorKind, Response, Result as HTTPResult, RetryKind},
+    utils::{base64, crc32, mime::JSON_MIME, rate_limiter::RateLimiter, ron::Ron, seek_adapter},
 };
+#[cfg(feature = "async")]
+use futures::future::join_all;
+use matches::matches;
 use mime::Mime;
 use rayon::{ThreadPool, ThreadPoolBuilder};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
 use std::{
     borrow::Cow,
     boxed::Box,
-    cell::Cell,
-    collections::HashMap,
+    cell::{Cell, RefCell},
+    collections::{BTreeMap, HashMap},
     convert::TryInto,
     fs::File,
-    io::{Read, Result as IOResult, Seek, SeekFrom},
+    io::{empty, Empty, Read, Result as IOResult, Seek, SeekFrom},
+    mem::take,
     path::Path,
     result::Result,
     sync::{
         atomic::{AtomicU64, Ordering::Relaxed},
-        Mutex,
+        Mutex, MutexGuard,
     },
     time::{Duration, Instant},
 };
-use tap::{TapOptionOps, TapResultOps};
+use tap::TapOptionOps;
+
+/// 分片上传 V1 的块尺寸，固定为 4 MB
+const V1_BLOCK_SIZE: u32 = 1 << 22;
+
+/// 分片上传协议版本
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResumableUploadVersion {
+    /// 分片上传 V1，即 `mkblk` / `mkfile` 接口
+    ///
+    /// 分块尺寸固定为 4 MB，适用于尚不支持分片上传 V2 的私有云部署
+    #[serde(rename = "v1")]
+    V1,
+    /// 分片上传 V2，即 `initParts` / `uploadPart` / `completeParts` 接口
+    #[serde(rename = "v2")]
+    V2,
+}
+
+impl Default for ResumableUploadVersion {
+    fn default() -> Self {
+        ResumableUploadVersion::V2
+    }
+}
 
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -46,8 +74,13 @@ struct UploadPartResult {
 #[derive(Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 struct Part {
+    // 对于分片上传 V1，该字段记录的是块的 ctx
     etag: Box<str>,
     part_number: usize,
+    #[serde(skip)]
+    offset: u64,
+    #[serde(skip)]
+    size: u64,
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -60,23 +93,26 @@ struct CompletedParts<'f> {
     custom_vars: HashMap<Cow<'f, str>, Cow<'f, str>>,
 }
 
+// 断点续传时尚未上传完毕的块
+struct PartialBlock {
+    ctx: Box<str>,
+    offset: u64,
+    size: u64,
+    ctx_offset: u64,
+}
+
 struct FromResuming {
     upload_id: Box<str>,
     up_urls: Box<[Box<str>]>,
     recorder: FileUploadRecordMedium,
 }
 
-struct UploadingProgressCallback<'u> {
-    callback: &'u (dyn Fn(u64, Option<u64>) + Send + Sync),
-    completed_size: AtomicU64,
-    total_size: Option<u64>,
-}
-
 pub(super) struct ResumableUploaderBuilder<'u> {
     upload_manager: &'u UploadManager,
     bucket_name: &'u str,
     up_urls_list: &'u [Box<[Box<str>]>],
     upload_token: Cow<'u, UploadToken>,
+    upload_token_provider: Option<&'u dyn UploadTokenProvider>,
     key: Option<Cow<'u, str>>,
     metadata: HashMap<Cow<'u, str>, Cow<'u, str>>,
     custom_vars: HashMap<Cow<'u, str>, Cow<'u, str>>,
@@ -84,6 +120,10 @@ pub(super) struct ResumableUploaderBuilder<'u> {
     thread_pool: Option<Ron<'u, ThreadPool>>,
     max_concurrency: usize,
     upload_logger: Option<TokenizedUploadLogger>,
+    throttle: UploadThrottle<'u>,
+    version: ResumableUploadVersion,
+    part_size_policy: UploadPartSizePolicy,
+    deadline: Option<Instant>,
 }
 
 #[must_use]
@@ -92,20 +132,28 @@ pub(super) struct ResumableUploader<'u, R: Read + Seek + Send + 'u> {
     bucket_name: &'u str,
     up_urls_list: &'u [Box<[Box<str>]>],
     upload_token: Cow<'u, UploadToken>,
+    upload_token_provider: Option<&'u dyn UploadTokenProvider>,
     key: Option<Cow<'u, str>>,
     completed_parts: Mutex<CompletedParts<'u>>,
-    checksum_enabled: bool,
+    part_checksum: PartChecksumAlgorithm,
     is_seekable: bool,
     block_size: u32,
+    chunk_size: u32,
     io_size: Option<u64>,
     io: R,
+    bytes: Option<Cow<'u, [u8]>>,
     uploaded_size: AtomicU64,
     file_path: Option<Cow<'u, Path>>,
     from_resuming: Option<FromResuming>,
-    uploading_progress_callback: Option<UploadingProgressCallback<'u>>,
-    thread_pool: Ron<'u, ThreadPool>,
+    partial_blocks: HashMap<usize, PartialBlock>,
+    uploading_progress: Option<UploadingProgress<'u>>,
+    thread_pool: Option<Ron<'u, ThreadPool>>,
     max_concurrency: usize,
     upload_logger: Option<TokenizedUploadLogger>,
+    throttle: UploadThrottle<'u>,
+    version: ResumableUploadVersion,
+    part_size_policy: UploadPartSizePolicy,
+    deadline: Option<Instant>,
 }
 
 impl<'u> ResumableUploaderBuilder<'u> {
@@ -124,10 +172,18 @@ impl<'u> ResumableUploaderBuilder<'u> {
             custom_vars: HashMap::new(),
             on_uploading_progress: None,
             thread_pool: None,
+            throttle: UploadThrottle::new(upload_manager),
+            version: upload_manager.config().resumable_upload_version(),
+            part_size_policy: upload_manager.config().upload_part_size_policy(),
+            deadline: upload_manager
+                .config()
+                .operation_timeout()
+                .map(|timeout| Instant::now() + timeout),
             upload_logger: upload_manager.config().upload_logger().as_ref().map(|upload_logger| {
                 upload_logger.tokenize(upload_token.to_string().into(), upload_manager.http_client().to_owned())
             }),
             upload_token,
+            upload_token_provider: None,
             max_concurrency: 0,
         }
     }
@@ -142,6 +198,34 @@ impl<'u> ResumableUploaderBuilder<'u> {
         self
     }
 
+    pub(super) fn version(mut self, version: ResumableUploadVersion) -> ResumableUploaderBuilder<'u> {
+        self.version = version;
+        self
+    }
+
+    pub(super) fn upload_token_provider(
+        mut self,
+        upload_token_provider: &'u dyn UploadTokenProvider,
+    ) -> ResumableUploaderBuilder<'u> {
+        self.upload_token_provider = Some(upload_token_provider);
+        self
+    }
+
+    pub(super) fn part_size_policy(mut self, part_size_policy: UploadPartSizePolicy) -> ResumableUploaderBuilder<'u> {
+        self.part_size_policy = part_size_policy;
+        self
+    }
+
+    pub(super) fn deadline(mut self, deadline: Instant) -> ResumableUploaderBuilder<'u> {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub(super) fn rate_limiter(mut self, rate_limiter: &'u RateLimiter) -> ResumableUploaderBuilder<'u> {
+        self.throttle = self.throttle.job_rate_limiter(rate_limiter);
+        self
+    }
+
     pub(super) fn key(mut self, key: Cow<'u, str>) -> ResumableUploaderBuilder<'u> {
         self.key = Some(key);
         self
@@ -176,23 +260,26 @@ impl<'u> ResumableUploaderBuilder<'u> {
         file_name: Cow<'u, str>,
         file_size: u64,
         mime_type: Option<Mime>,
-        checksum_enabled: bool,
+        part_checksum: PartChecksumAlgorithm,
     ) -> IOResult<ResumableUploader<'u, File>> {
         let upload_manager = self.upload_manager;
-        let block_size = upload_manager.config().upload_block_size();
+        let block_size = self.block_size();
         Ok(ResumableUploader {
             upload_manager,
             bucket_name: self.bucket_name,
             up_urls_list: self.up_urls_list,
             upload_token: self.upload_token,
+            upload_token_provider: self.upload_token_provider,
             key: self.key,
             file_path: Some(file_path),
             io: file,
+            bytes: None,
             io_size: Some(file_size),
             uploaded_size: AtomicU64::new(0),
-            checksum_enabled,
+            part_checksum,
             is_seekable: true,
             block_size,
+            chunk_size: upload_manager.config().upload_chunk_size(),
             completed_parts: Mutex::new(CompletedParts {
                 parts: Vec::with_capacity({
                     let block_size: u64 = block_size.into();
@@ -206,24 +293,19 @@ impl<'u> ResumableUploaderBuilder<'u> {
                 custom_vars: self.custom_vars,
             }),
             from_resuming: None,
-            uploading_progress_callback: self.on_uploading_progress.map(|callback| UploadingProgressCallback {
-                callback,
-                completed_size: AtomicU64::new(0),
-                total_size: Some(file_size),
-            }),
+            partial_blocks: HashMap::new(),
+            uploading_progress: self
+                .on_uploading_progress
+                .map(|callback| UploadingProgress::new(callback, Some(file_size))),
             thread_pool: self
                 .thread_pool
-                .or_else(|| upload_manager.thread_pool().map(|pool| Ron::Referenced(pool.as_ref())))
-                .unwrap_or_else(|| {
-                    Ron::Owned(
-                        ThreadPoolBuilder::new()
-                            .thread_name(|index| format!("resumable_uploader_thread_{}", index))
-                            .build()
-                            .unwrap(),
-                    )
-                }),
+                .or_else(|| upload_manager.thread_pool().map(|pool| Ron::Referenced(pool.as_ref()))),
             max_concurrency: self.max_concurrency,
             upload_logger: self.upload_logger,
+            throttle: self.throttle,
+            version: self.version,
+            part_size_policy: self.part_size_policy,
+            deadline: self.deadline,
         })
     }
 
@@ -233,22 +315,26 @@ impl<'u> ResumableUploaderBuilder<'u> {
         size: u64,
         mime_type: Option<Mime>,
         file_name: Cow<'u, str>,
-        checksum_enabled: bool,
+        part_checksum: PartChecksumAlgorithm,
     ) -> IOResult<ResumableUploader<'u, seek_adapter::SeekAdapter<R>>> {
+        let block_size = self.block_size();
         let upload_manager = self.upload_manager;
         Ok(ResumableUploader {
             upload_manager,
             bucket_name: self.bucket_name,
             up_urls_list: self.up_urls_list,
             upload_token: self.upload_token,
+            upload_token_provider: self.upload_token_provider,
             key: self.key,
             file_path: None,
             io: seek_adapter::SeekAdapter(stream),
+            bytes: None,
             io_size: if size > 0 { Some(size) } else { None },
             uploaded_size: AtomicU64::new(0),
-            checksum_enabled,
+            part_checksum,
             is_seekable: false,
-            block_size: upload_manager.config().upload_block_size(),
+            block_size,
+            chunk_size: upload_manager.config().upload_chunk_size(),
             completed_parts: Mutex::new(CompletedParts {
                 parts: Vec::new(),
                 fname: if file_name.is_empty() { None } else { Some(file_name) },
@@ -257,26 +343,83 @@ impl<'u> ResumableUploaderBuilder<'u> {
                 custom_vars: self.custom_vars,
             }),
             from_resuming: None,
-            uploading_progress_callback: self.on_uploading_progress.map(|callback| UploadingProgressCallback {
-                callback,
-                completed_size: AtomicU64::new(0),
-                total_size: if size > 0 { Some(size) } else { None },
-            }),
+            partial_blocks: HashMap::new(),
+            uploading_progress: self
+                .on_uploading_progress
+                .map(|callback| UploadingProgress::new(callback, if size > 0 { Some(size) } else { None })),
             thread_pool: self
                 .thread_pool
-                .or_else(|| upload_manager.thread_pool().map(|pool| Ron::Referenced(pool.as_ref())))
-                .unwrap_or_else(|| {
-                    Ron::Owned(
-                        ThreadPoolBuilder::new()
-                            .thread_name(|index| format!("resumable_uploader_thread_{}", index))
-                            .build()
-                            .unwrap(),
-                    )
-                }),
+                .or_else(|| upload_manager.thread_pool().map(|pool| Ron::Referenced(pool.as_ref()))),
             max_concurrency: self.max_concurrency,
             upload_logger: self.upload_logger,
+            throttle: self.throttle,
+            version: self.version,
+            part_size_policy: self.part_size_policy,
+            deadline: self.deadline,
         })
     }
+
+    pub(super) fn bytes(
+        self,
+        bytes: Cow<'u, [u8]>,
+        mime_type: Option<Mime>,
+        file_name: Cow<'u, str>,
+        part_checksum: PartChecksumAlgorithm,
+    ) -> ResumableUploader<'u, seek_adapter::SeekAdapter<Empty>> {
+        let upload_manager = self.upload_manager;
+        let block_size = self.block_size();
+        let size: u64 = bytes.len().try_into().unwrap_or(u64::max_value());
+        ResumableUploader {
+            upload_manager,
+            bucket_name: self.bucket_name,
+            up_urls_list: self.up_urls_list,
+            upload_token: self.upload_token,
+            upload_token_provider: self.upload_token_provider,
+            key: self.key,
+            file_path: None,
+            io: seek_adapter::SeekAdapter(empty()),
+            bytes: Some(bytes),
+            io_size: Some(size),
+            uploaded_size: AtomicU64::new(0),
+            part_checksum,
+            is_seekable: true,
+            block_size,
+            chunk_size: upload_manager.config().upload_chunk_size(),
+            completed_parts: Mutex::new(CompletedParts {
+                parts: Vec::with_capacity({
+                    let block_size: u64 = block_size.into();
+                    ((size + block_size - 1) / (block_size))
+                        .try_into()
+                        .unwrap_or(usize::max_value())
+                }),
+                fname: if file_name.is_empty() { None } else { Some(file_name) },
+                mime_type: mime_type.map(|m| m.as_ref().into()),
+                metadata: self.metadata,
+                custom_vars: self.custom_vars,
+            }),
+            from_resuming: None,
+            partial_blocks: HashMap::new(),
+            uploading_progress: self
+                .on_uploading_progress
+                .map(|callback| UploadingProgress::new(callback, Some(size))),
+            thread_pool: self
+                .thread_pool
+                .or_else(|| upload_manager.thread_pool().map(|pool| Ron::Referenced(pool.as_ref()))),
+            max_concurrency: self.max_concurrency,
+            upload_logger: self.upload_logger,
+            throttle: self.throttle,
+            version: self.version,
+            part_size_policy: self.part_size_policy,
+            deadline: self.deadline,
+        }
+    }
+
+    fn block_size(&self) -> u32 {
+        match self.version {
+            ResumableUploadVersion::V1 => V1_BLOCK_SIZE,
+            ResumableUploadVersion::V2 => self.upload_manager.config().upload_block_size(),
+        }
+    }
 }
 
 impl<'u, R: Read + Seek + Send> ResumableUploader<'u, R> {
@@ -296,19 +439,9 @@ impl<'u, R: Read + Seek + Send> ResumableUploader<'u, R> {
                 Ok(result) => {
                     return Ok(result);
                 }
-                Err(UploadError::QiniuError(err)) => match err.retry_kind() {
-                    RetryKind::RetryableError | RetryKind::HostUnretryableError | RetryKind::ZoneUnretryableError => {
-                        if self.is_seekable {
-                            prev_err = Some(err);
-                            continue;
-                        } else {
-                            return Err(UploadError::QiniuError(err));
-                        }
-                    }
-                    _ => {
-                        return Err(UploadError::QiniuError(err));
-                    }
-                },
+                Err(UploadError::QiniuError(err)) if self.should_try_next_zone(&err) => {
+                    prev_err = Some(err);
+                }
                 Err(err) => {
                     return Err(err);
                 }
@@ -320,51 +453,131 @@ impl<'u, R: Read + Seek + Send> ResumableUploader<'u, R> {
         ))
     }
 
+    /// 异步上传
+    ///
+    /// 分片在当前任务中并发上传，不占用线程池。
+    /// 读取分片数据，写入断点续传记录和上传限速依然是同步操作，可能会短暂阻塞当前线程
+    #[cfg(feature = "async")]
+    pub(super) async fn send_async(&mut self) -> Result<UploadResponse, UploadError> {
+        let base_path = self.make_base_path();
+        let authorization = self.make_authorization();
+        if let Ok(Some(result)) = self.try_to_resume_async(&base_path, &authorization).await {
+            return Ok(result);
+        }
+        let mut prev_err: Option<HTTPError> = None;
+        for up_urls in self.up_urls_list.iter() {
+            match self
+                .try_to_init_and_upload_with_log_async(
+                    &up_urls.iter().map(|url| url.as_ref()).collect::<Box<[&str]>>(),
+                    &base_path,
+                    &authorization,
+                )
+                .await
+            {
+                Ok(result) => {
+                    return Ok(result);
+                }
+                Err(UploadError::QiniuError(err)) if self.should_try_next_zone(&err) => {
+                    prev_err = Some(err);
+                }
+                Err(err) => {
+                    return Err(err);
+                }
+            }
+        }
+
+        Err(UploadError::QiniuError(prev_err.expect(
+            "ResumableUploader::send_async() should try at lease once, but not",
+        )))
+    }
+
+    // 无法倒回的数据流只能在当前区域内上传
+    fn should_try_next_zone(&self, err: &HTTPError) -> bool {
+        self.is_seekable
+            && matches!(
+                err.retry_kind(),
+                RetryKind::RetryableError | RetryKind::HostUnretryableError | RetryKind::ZoneUnretryableError
+            )
+    }
+
     fn try_to_init_and_upload_with_log(
         &mut self,
         up_urls: &[&str],
         base_path: &str,
         authorization: &str,
     ) -> Result<UploadResponse, UploadError> {
-        if self.is_seekable {
+        self.reset_uploading()?;
+        let timer = Instant::now();
+        let result = self.try_to_init_and_upload(up_urls, base_path, authorization);
+        self.log_uploading_result(&result, timer.elapsed(), 0);
+        result
+    }
+
+    #[cfg(feature = "async")]
+    async fn try_to_init_and_upload_with_log_async(
+        &mut self,
+        up_urls: &[&str],
+        base_path: &str,
+        authorization: &str,
+    ) -> Result<UploadResponse, UploadError> {
+        self.reset_uploading()?;
+        let timer = Instant::now();
+        let result = self
+            .try_to_init_and_upload_async(up_urls, base_path, authorization)
+            .await;
+        self.log_uploading_result(&result, timer.elapsed(), 0);
+        result
+    }
+
+    fn reset_uploading(&mut self) -> Result<(), UploadError> {
+        if self.is_seekable && self.bytes.is_none() {
             self.io
                 .seek(SeekFrom::Start(0))
                 .map_err(|err| HTTPError::new_unretryable_error(HTTPErrorKind::IOError(err), None, None, None))?;
         }
         self.uploaded_size.store(0, Relaxed);
-        if let Some(uploading_progress_callback) = &self.uploading_progress_callback {
-            uploading_progress_callback.completed_size.store(0, Relaxed);
+        if let Some(uploading_progress) = &self.uploading_progress {
+            uploading_progress.reset(0);
         }
         self.completed_parts.lock().unwrap().parts.clear();
-        let timer = Instant::now();
-        let result = self.try_to_init_and_upload(up_urls, base_path, authorization);
+        self.partial_blocks.clear();
+        Ok(())
+    }
+
+    // 断点续传时，`init_uploaded_size` 为此前已经上传的数据量，不计入本次上传
+    fn log_uploading_result(
+        &self,
+        result: &Result<UploadResponse, UploadError>,
+        duration: Duration,
+        init_uploaded_size: u64,
+    ) {
         if let Some(upload_logger) = &self.upload_logger {
             let uploaded_size = self.uploaded_size.load(Relaxed);
-            match &result {
+            match result {
                 Ok(_) => {
                     let _ = upload_logger.log(
                         UploadLoggerRecordBuilder::default()
-                            .duration(timer.elapsed())
-                            .up_type(UpType::Chunkedv2)
-                            .sent(uploaded_size)
-                            .total_size(uploaded_size)
+                            .duration(duration)
+                            .up_type(self.up_type())
+                            .sent(uploaded_size - init_uploaded_size)
+                            .total_size(uploaded_size - init_uploaded_size)
                             .build(),
                     );
                 }
                 Err(err) => {
                     let mut record_builder = UploadLoggerRecordBuilder::default()
-                        .duration(timer.elapsed())
-                        .up_type(UpType::Chunkedv2)
-                        .sent(uploaded_size)
+                        .duration(duration)
+                        .up_type(self.up_type())
+                        .sent(uploaded_size - init_uploaded_size)
                         .upload_error(err);
                     if let Some(total_size) = self.io_size {
-                        record_builder = record_builder.total_size(u64::max(uploaded_size, total_size));
+                        record_builder =
+                            record_builder.total_size(u64::max(uploaded_size, total_size) - init_uploaded_size);
                     }
                     let _ = upload_logger.log(record_builder.build());
                 }
             }
         }
-        result
     }
 
     fn try_to_init_and_upload(
@@ -373,131 +586,197 @@ impl<'u, R: Read + Seek + Send> ResumableUploader<'u, R> {
         base_path: &str,
         authorization: &str,
     ) -> Result<UploadResponse, UploadError> {
-        let upload_id = self.init_parts(&base_path, up_urls, &authorization)?;
-        let recorder = self.file_path.as_ref().and_then(|file_path| {
+        let upload_id = match self.version {
+            ResumableUploadVersion::V1 => Box::<str>::default(),
+            ResumableUploadVersion::V2 => self
+                .init_parts_request(base_path, up_urls, authorization)?
+                .send(parse_upload_id)?,
+        };
+        let recorder = self.open_upload_recorder(up_urls, &upload_id);
+        self.upload_blocks(
+            up_urls,
+            &(base_path.to_owned() + "/" + &upload_id),
+            authorization,
+            recorder,
+        )
+    }
+
+    #[cfg(feature = "async")]
+    async fn try_to_init_and_upload_async(
+        &mut self,
+        up_urls: &[&str],
+        base_path: &str,
+        authorization: &str,
+    ) -> Result<UploadResponse, UploadError> {
+        let upload_id = match self.version {
+            ResumableUploadVersion::V1 => Box::<str>::default(),
+            ResumableUploadVersion::V2 => {
+                self.init_parts_request(base_path, up_urls, authorization)?
+                    .send_async(parse_upload_id)
+                    .await?
+            }
+        };
+        let recorder = self.open_upload_recorder(up_urls, &upload_id);
+        self.upload_blocks_async(
+            up_urls,
+            &(base_path.to_owned() + "/" + &upload_id),
+            authorization,
+            recorder,
+        )
+        .await
+    }
+
+    fn open_upload_recorder(&self, up_urls: &[&str], upload_id: &str) -> Option<FileUploadRecordMedium> {
+        self.file_path.as_ref().and_then(|file_path| {
             self.upload_manager
                 .config()
                 .upload_recorder()
                 .open_and_write_metadata(
                     file_path,
                     self.key.as_ref().map(|key| key.as_ref()),
-                    &upload_id,
+                    upload_id,
                     up_urls,
                     self.block_size,
+                    self.version,
                 )
                 .ok()
+        })
+    }
+
+    fn upload_blocks(
+        &mut self,
+        up_urls: &[&str],
+        base_path: &str,
+        authorization: &str,
+        upload_recorder: Option<FileUploadRecordMedium>,
+    ) -> Result<UploadResponse, UploadError> {
+        // 异步上传不需要线程池，因此直到第一次同步上传时才创建
+        let thread_pool = self.thread_pool.take().unwrap_or_else(|| {
+            Ron::Owned(
+                ThreadPoolBuilder::new()
+                    .thread_name(|index| format!("resumable_uploader_thread_{}", index))
+                    .build()
+                    .unwrap(),
+            )
         });
-        self.start_uploading_blocks(
-            up_urls,
-            &(base_path.to_owned() + "/" + &upload_id),
-            authorization,
-            recorder,
-        )
+        let concurrency = self.concurrency(thread_pool.current_num_threads());
+        let result = {
+            let parts_uploader = self.parts_uploader(up_urls, base_path, authorization, upload_recorder.as_ref());
+            thread_pool.scope(|s| {
+                for _ in 0..concurrency {
+                    s.spawn(|_| parts_uploader.upload_parts());
+                }
+            });
+            parts_uploader.finish()
+        };
+        self.thread_pool = Some(thread_pool);
+        result?;
+        let response = self
+            .completion_request(up_urls, base_path, authorization)?
+            .send(parse_upload_response)?;
+        self.drop_upload_record();
+        Ok(response)
     }
 
-    fn start_uploading_blocks(
+    #[cfg(feature = "async")]
+    async fn upload_blocks_async(
         &mut self,
         up_urls: &[&str],
         base_path: &str,
         authorization: &str,
         upload_recorder: Option<FileUploadRecordMedium>,
     ) -> Result<UploadResponse, UploadError> {
-        let io_status_manager = IOStatusManager::new(
-            &mut self.io,
-            self.block_size,
-            &self
-                .completed_parts
-                .lock()
-                .unwrap()
-                .parts
-                .iter()
-                .map(|part| part.part_number)
-                .collect::<Vec<_>>(),
+        let concurrency = self.concurrency(
+            self.thread_pool
+                .as_ref()
+                .map_or_else(rayon::current_num_threads, |thread_pool| {
+                    thread_pool.current_num_threads()
+                }),
         );
-        let http_client = self.upload_manager.http_client();
-        let block_size = self.block_size;
-        let completed_parts = &self.completed_parts;
-        let uploaded_size = &self.uploaded_size;
-        let uploading_progress_callback = self.uploading_progress_callback.as_ref();
-        let checksum_enabled = self.checksum_enabled;
-        let upload_logger = self.upload_logger.as_ref();
-        let concurrency = if self.max_concurrency > 0 {
+        let result = {
+            let parts_uploader = self.parts_uploader(up_urls, base_path, authorization, upload_recorder.as_ref());
+            join_all((0..concurrency).map(|_| parts_uploader.upload_parts_async())).await;
+            parts_uploader.finish()
+        };
+        result?;
+        let response = self
+            .completion_request(up_urls, base_path, authorization)?
+            .send_async(parse_upload_response)
+            .await?;
+        self.drop_upload_record();
+        Ok(response)
+    }
+
+    fn concurrency(&self, default_concurrency: usize) -> usize {
+        if self.max_concurrency > 0 {
             self.max_concurrency
         } else {
-            self.thread_pool.current_num_threads()
+            default_concurrency
+        }
+    }
+
+    fn parts_uploader<'p>(
+        &'p mut self,
+        up_urls: &'p [&'p str],
+        base_path: &'p str,
+        authorization: &'p str,
+        upload_recorder: Option<&'p FileUploadRecordMedium>,
+    ) -> PartsUploader<'p, 'u, R> {
+        let part_sizer = self.part_sizer();
+        let completed_part_records = self.completed_part_records();
+        let source = match &self.bytes {
+            Some(bytes) => PartSource::Bytes(bytes.as_ref()),
+            None => PartSource::Reader(&mut self.io),
         };
+        PartsUploader {
+            io_status_manager: IOStatusManager::new(source, part_sizer, &completed_part_records),
+            http_client: self.upload_manager.http_client(),
+            up_urls,
+            base_path,
+            authorization,
+            upload_token_provider: self.upload_token_provider,
+            completed_parts: &self.completed_parts,
+            uploaded_size: &self.uploaded_size,
+            uploading_progress: self.uploading_progress.as_ref(),
+            part_checksum: self.part_checksum,
+            chunk_size: self.chunk_size,
+            partial_blocks: &self.partial_blocks,
+            upload_logger: self.upload_logger.as_ref(),
+            upload_recorder,
+            throttle: self.throttle,
+            version: self.version,
+            deadline: self.deadline,
+        }
+    }
+    fn part_sizer(&self) -> PartSizer {
+        match self.version {
+            ResumableUploadVersion::V1 => PartSizer::fixed(self.block_size),
+            ResumableUploadVersion::V2 => PartSizer::new(self.part_size_policy, self.block_size, self.io_size),
+        }
+    }
 
-        self.thread_pool.scope(|s| {
-            for _ in 0..concurrency {
-                s.spawn(|_| {
-                    let mut md5 = OptionalMd5::new(checksum_enabled);
-                    loop {
-                        match io_status_manager.read() {
-                            Some(part_data) => {
-                                let last_block_uploaded = Cell::new(0);
-                                match Self::upload_part(
-                                    http_client,
-                                    &(base_path.to_owned() + "/" + &part_data.part_number.to_string()),
-                                    up_urls,
-                                    authorization,
-                                    &part_data.data,
-                                    part_data.part_number,
-                                    &mut md5,
-                                    |block_uploaded, _| {
-                                        if let Some(progress) = uploading_progress_callback {
-                                            let added_size =
-                                                block_uploaded - last_block_uploaded.replace(block_uploaded);
-                                            (progress.callback)(
-                                                progress.completed_size.fetch_add(added_size, Relaxed) + added_size,
-                                                progress.total_size,
-                                            );
-                                        }
-                                    },
-                                    |_, _, _| {
-                                        if let Some(progress) = uploading_progress_callback {
-                                            progress
-                                                .completed_size
-                                                .fetch_sub(last_block_uploaded.replace(0), Relaxed);
-                                        }
-                                    },
-                                    upload_logger,
-                                    upload_recorder.as_ref(),
-                                ) {
-                                    Ok(etag) => {
-                                        completed_parts.lock().unwrap().parts.push(Part {
-                                            etag,
-                                            part_number: part_data.part_number,
-                                        });
-                                        uploaded_size.fetch_add(block_size.into(), Relaxed);
-                                    }
-                                    Err(err) => {
-                                        io_status_manager.error(err);
-                                        return;
-                                    }
-                                };
-                            }
-                            None => {
-                                return;
-                            }
-                        }
-                    }
-                });
-            }
-        });
+    fn completed_part_records(&self) -> Vec<CompletedPart> {
+        self.completed_parts
+            .lock()
+            .unwrap()
+            .parts
+            .iter()
+            .map(|part| CompletedPart {
+                part_number: part.part_number,
+                offset: part.offset,
+                size: part.size,
+            })
+            .collect()
+    }
 
-        match io_status_manager.result() {
-            IOStatusResult::Success => self.complete_parts(base_path, up_urls, authorization).tap_ok(|_| {
-                self.file_path.as_ref().tap_some(|file_path| {
-                    let _ = self
-                        .upload_manager
-                        .config()
-                        .upload_recorder()
-                        .drop(file_path, self.key.as_ref().map(|key| key.as_ref()));
-                })
-            }),
-            IOStatusResult::IOError(err) => Err(UploadError::IOError(err)),
-            IOStatusResult::HTTPError(err) => Err(UploadError::QiniuError(err)),
-        }
+    fn drop_upload_record(&self) {
+        self.file_path.as_ref().tap_some(|file_path| {
+            let _ = self
+                .upload_manager
+                .config()
+                .upload_recorder()
+                .drop(file_path, self.key.as_ref().map(|key| key.as_ref()));
+        });
     }
 
     pub(super) fn prepare_for_resuming(
@@ -506,16 +785,55 @@ impl<'u, R: Read + Seek + Send> ResumableUploader<'u, R> {
         block_records: Box<[FileUploadRecordMediumBlockItem]>,
         recorder: FileUploadRecordMedium,
     ) {
+        if file_record.version != self.version {
+            return;
+        }
         let mut io_offset = 0u64;
         {
-            let block_records: Vec<FileUploadRecordMediumBlockItem> = block_records.into();
+            // 分片上传 V1 中同一个块可能有多条记录，以最后一条为准
+            let mut block_records: BTreeMap<usize, FileUploadRecordMediumBlockItem> = block_records
+                .into_vec()
+                .into_iter()
+                .map(|block_record| (block_record.part_number, block_record))
+                .collect();
+            let block_size = u64::from(file_record.block_size);
             let mut completed_parts = self.completed_parts.lock().unwrap();
-            for block_record in block_records {
-                completed_parts.parts.push(Part {
-                    etag: block_record.etag,
-                    part_number: block_record.part_number,
-                });
-                io_offset += u64::from(file_record.block_size);
+            for (part_number, block_record) in block_records.iter_mut() {
+                // 早期版本的记录中没有分片偏移量和尺寸，此时所有分片尺寸均相同
+                let offset = block_record
+                    .offset
+                    .unwrap_or_else(|| block_size * (*part_number as u64 - 1));
+                let size = block_record
+                    .size
+                    .unwrap_or_else(|| u64::min(block_size, file_record.file_size.saturating_sub(offset)));
+                match block_record.ctx_offset {
+                    Some(ctx_offset) if ctx_offset < size => {
+                        if ctx_offset > 0 {
+                            self.partial_blocks.insert(
+                                *part_number,
+                                PartialBlock {
+                                    ctx: take(&mut block_record.ctx),
+                                    offset,
+                                    size,
+                                    ctx_offset,
+                                },
+                            );
+                            io_offset += ctx_offset;
+                        }
+                    }
+                    _ => {
+                        completed_parts.parts.push(Part {
+                            etag: match self.version {
+                                ResumableUploadVersion::V1 => take(&mut block_record.ctx),
+                                ResumableUploadVersion::V2 => take(&mut block_record.etag),
+                            },
+                            part_number: *part_number,
+                            offset,
+                            size,
+                        });
+                        io_offset += size;
+                    }
+                }
             }
         }
         self.from_resuming = Some(FromResuming {
@@ -527,179 +845,104 @@ impl<'u, R: Read + Seek + Send> ResumableUploader<'u, R> {
         self.uploaded_size = AtomicU64::new(io_offset);
     }
 
-    fn init_parts(&self, base_path: &str, up_urls: &[&str], authorization: &str) -> HTTPResult<Box<str>> {
-        let result: InitPartsResult = self
-            .upload_manager
-            .http_client()
-            .post(base_path, up_urls)
-            .header("Authorization".into(), authorization.into())
-            .idempotent()
-            .on_response(&|response, duration| {
-                let result = upload_response_callback(response);
-                if result.is_ok() {
-                    if let Some(upload_logger) = &self.upload_logger {
-                        let _ = upload_logger.log(
-                            UploadLoggerRecordBuilder::default()
-                                .response(response)
-                                .duration(duration)
-                                .up_type(UpType::InitParts)
-                                .build(),
-                        );
-                    }
-                }
-                result
-            })
-            .on_error(&|host_url, err, duration| {
-                if let Some(upload_logger) = &self.upload_logger {
-                    let _ = upload_logger.log({
-                        let mut builder = UploadLoggerRecordBuilder::default()
-                            .duration(duration)
-                            .up_type(UpType::InitParts)
-                            .http_error(err);
-                        if let Some(host_url) = host_url {
-                            builder = builder.host(host_url);
-                        }
-                        builder.build()
-                    });
-                }
-            })
-            .accept_json()
-            .no_body()
-            .send()?
-            .parse_json()?;
-        Ok(result.upload_id)
+    fn init_parts_request<'r>(
+        &'r self,
+        base_path: &'r str,
+        up_urls: &'r [&'r str],
+        authorization: &'r str,
+    ) -> HTTPResult<UploadRequest<'r>> {
+        Ok(
+            UploadRequest::post(self.upload_manager.http_client(), base_path, up_urls, UpType::InitParts)
+                .authorization(refresh_authorization(self.upload_token_provider, authorization)?)
+                .deadline(self.deadline)
+                .upload_logger(self.upload_logger.as_ref()),
+        )
     }
 
-    #[allow(clippy::too_many_arguments)]
-    fn upload_part(
-        http_client: &Client,
-        path: &str,
-        up_urls: &[&str],
-        authorization: &str,
-        part: &[u8],
-        part_number: usize,
-        md5_hasher: &mut OptionalMd5,
-        on_progress: impl Fn(u64, u64),
-        on_error: impl Fn(Option<&str>, &HTTPError, Duration),
-        upload_logger: Option<&TokenizedUploadLogger>,
-        upload_recorder: Option<&FileUploadRecordMedium>,
-    ) -> HTTPResult<Box<str>> {
-        let mut builder = http_client
-            .put(path, up_urls)
-            .header("Authorization".into(), authorization.into())
-            .on_uploading_progress(&on_progress);
-        if let Some(md5) = md5_hasher.hash(part) {
-            builder = builder.header("Content-MD5".into(), md5.into());
+    // 所有分片上传完毕后，分片上传 V1 调用 `mkfile` 接口，分片上传 V2 调用 `completeParts` 接口创建文件
+    fn completion_request<'r>(
+        &'r self,
+        up_urls: &'r [&'r str],
+        base_path: &'r str,
+        authorization: &'r str,
+    ) -> Result<UploadRequest<'r>, UploadError> {
+        let authorization = refresh_authorization(self.upload_token_provider, authorization)?;
+        let http_client = self.upload_manager.http_client();
+        let request = match self.version {
+            ResumableUploadVersion::V1 => {
+                let (path, ctxs) = self.make_file_request()?;
+                UploadRequest::post(http_client, path, up_urls, UpType::MakeFile).body("text/plain", ctxs.into_bytes())
+            }
+            ResumableUploadVersion::V2 => {
+                // 序列化后立即释放锁，避免在等待响应期间持有锁
+                let body = serde_json::to_vec(&*self.sorted_completed_parts()?).unwrap();
+                UploadRequest::post(http_client, base_path, up_urls, UpType::CompleteParts).body(JSON_MIME, body)
+            }
+        };
+        Ok(request
+            .authorization(authorization)
+            .deadline(self.deadline)
+            .upload_logger(self.upload_logger.as_ref()))
+    }
+
+    // 返回 `mkfile` 的请求路径和请求体
+    fn make_file_request(&self) -> Result<(String, String), UploadError> {
+        let completed_parts = self.sorted_completed_parts()?;
+        let path = self.make_file_path(&completed_parts);
+        let ctxs = completed_parts
+            .parts
+            .iter()
+            .map(|part| part.etag.as_ref())
+            .collect::<Vec<_>>()
+            .join(",");
+        Ok((path, ctxs))
+    }
+
+    fn make_file_path(&self, completed_parts: &CompletedParts) -> String {
+        let file_size: u64 = completed_parts.parts.iter().map(|part| part.size).sum();
+        let mut path = "/mkfile/".to_owned() + &file_size.to_string();
+        if let Some(key) = &self.key {
+            path.push_str("/key/");
+            path.push_str(&base64::urlsafe(key.as_bytes()));
         }
-        let result: UploadPartResult = builder
-            .idempotent()
-            .on_response(&|response, duration| {
-                let result = upload_response_callback(response);
-                if result.is_ok() {
-                    if let Some(upload_logger) = upload_logger {
-                        let _ = upload_logger.log(
-                            UploadLoggerRecordBuilder::default()
-                                .response(response)
-                                .duration(duration)
-                                .up_type(UpType::UploadPart)
-                                .sent(part.len().try_into().unwrap_or(u64::max_value()))
-                                .total_size(part.len().try_into().unwrap_or(u64::max_value()))
-                                .build(),
-                        );
-                    }
-                }
-                result
-            })
-            .on_error(&|host_url, err, duration| {
-                (on_error)(host_url, err, duration);
-                if let Some(upload_logger) = upload_logger {
-                    let _ = upload_logger.log({
-                        let mut builder = UploadLoggerRecordBuilder::default()
-                            .duration(duration)
-                            .up_type(UpType::UploadPart)
-                            .http_error(err)
-                            .total_size(part.len().try_into().unwrap_or(u64::max_value()));
-                        if let Some(host_url) = host_url {
-                            builder = builder.host(host_url);
-                        }
-                        builder.build()
-                    });
-                }
-            })
-            .accept_json()
-            .raw_body("application/octet-stream".into(), part.as_ref().into())
-            .send()?
-            .parse_json()?;
-        if let Some(upload_recorder) = upload_recorder {
-            upload_recorder
-                .append(&result.etag, part_number)
-                .map_err(|err| HTTPError::new_unretryable_error(HTTPErrorKind::IOError(err), None, None, None))?;
+        if let Some(fname) = &completed_parts.fname {
+            path.push_str("/fname/");
+            path.push_str(&base64::urlsafe(fname.as_bytes()));
+        }
+        if let Some(mime_type) = &completed_parts.mime_type {
+            path.push_str("/mimeType/");
+            path.push_str(&base64::urlsafe(mime_type.as_bytes()));
+        }
+        for (k, v) in completed_parts.custom_vars.iter() {
+            path.push_str("/");
+            path.push_str(k);
+            path.push_str("/");
+            path.push_str(&base64::urlsafe(v.as_bytes()));
         }
-        Ok(result.etag)
+        for (k, v) in completed_parts.metadata.iter() {
+            path.push_str("/x-qn-meta-");
+            path.push_str(k);
+            path.push_str("/");
+            path.push_str(&base64::urlsafe(v.as_bytes()));
+        }
+        path
     }
 
-    fn complete_parts(&self, path: &str, up_urls: &[&str], authorization: &str) -> Result<UploadResponse, UploadError> {
+    // 按分片编号排序后返回已上传的分片
+    fn sorted_completed_parts(&self) -> Result<MutexGuard<'_, CompletedParts<'u>>, UploadError> {
         let mut completed_parts = self.completed_parts.lock().unwrap();
         if completed_parts.parts.is_empty() {
             return Err(UploadError::EmptyFileError);
         }
         completed_parts.parts.sort_unstable_by_key(|part| part.part_number);
-        let upload_result = self
-            .upload_manager
-            .http_client()
-            .post(path, up_urls)
-            .header("Authorization".into(), authorization.into())
-            .idempotent()
-            .on_response(&|response, duration| {
-                let result = upload_response_callback(response);
-                if result.is_ok() {
-                    if let Some(upload_logger) = &self.upload_logger {
-                        let _ = upload_logger.log(
-                            UploadLoggerRecordBuilder::default()
-                                .response(response)
-                                .duration(duration)
-                                .up_type(UpType::CompleteParts)
-                                .build(),
-                        );
-                    }
-                }
-                result
-            })
-            .on_error(&|host_url, err, duration| {
-                if let Some(upload_logger) = &self.upload_logger {
-                    let _ = upload_logger.log({
-                        let mut builder = UploadLoggerRecordBuilder::default()
-                            .duration(duration)
-                            .up_type(UpType::CompleteParts)
-                            .http_error(err);
-                        if let Some(host_url) = host_url {
-                            builder = builder.host(host_url);
-                        }
-                        builder.build()
-                    });
-                }
-            })
-            .accept_json()
-            .json_body(&*completed_parts)
-            .unwrap()
-            .send()?
-            .try_parse_json::<Value>()?;
-        match upload_result {
-            Ok(value) => Ok(value.into()),
-            Err(bytes) => Ok(bytes.into()),
-        }
+        Ok(completed_parts)
     }
 
     fn try_to_resume(&mut self, base_path: &str, authorization: &str) -> Result<Option<UploadResponse>, UploadError> {
         if let Some(from_resuming) = self.from_resuming.take() {
-            let init_uploaded_size = self.uploaded_size.load(Relaxed);
-            if let Some(uploading_progress_callback) = &self.uploading_progress_callback {
-                uploading_progress_callback
-                    .completed_size
-                    .store(init_uploaded_size, Relaxed);
-            }
+            let init_uploaded_size = self.start_resuming();
             let timer = Instant::now();
-            self.start_uploading_blocks(
+            let result = self.upload_blocks(
                 &from_resuming
                     .up_urls
                     .iter()
@@ -708,43 +951,57 @@ impl<'u, R: Read + Seek + Send> ResumableUploader<'u, R> {
                 &(base_path.to_owned() + "/" + &from_resuming.upload_id),
                 authorization,
                 Some(from_resuming.recorder),
-            )
-            .map(|response| {
-                if let Some(upload_logger) = &self.upload_logger {
-                    let uploaded_size = self.uploaded_size.load(Relaxed);
-                    let _ = upload_logger.log(
-                        UploadLoggerRecordBuilder::default()
-                            .duration(timer.elapsed())
-                            .up_type(UpType::Chunkedv2)
-                            .sent(uploaded_size - init_uploaded_size)
-                            .total_size(uploaded_size - init_uploaded_size)
-                            .build(),
-                    );
-                }
-                response
-            })
-            .map_err(|err| {
-                if let Some(upload_logger) = &self.upload_logger {
-                    let uploaded_size = self.uploaded_size.load(Relaxed);
-                    let mut record_builder = UploadLoggerRecordBuilder::default()
-                        .duration(timer.elapsed())
-                        .up_type(UpType::Chunkedv2)
-                        .sent(uploaded_size - init_uploaded_size)
-                        .upload_error(&err);
-                    if let Some(total_size) = self.io_size {
-                        record_builder =
-                            record_builder.total_size(u64::max(uploaded_size, total_size) - init_uploaded_size);
-                    }
-                    let _ = upload_logger.log(record_builder.build());
-                }
-                err
-            })
-            .map(Some)
+            );
+            self.log_uploading_result(&result, timer.elapsed(), init_uploaded_size);
+            result.map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    #[cfg(feature = "async")]
+    async fn try_to_resume_async(
+        &mut self,
+        base_path: &str,
+        authorization: &str,
+    ) -> Result<Option<UploadResponse>, UploadError> {
+        if let Some(from_resuming) = self.from_resuming.take() {
+            let init_uploaded_size = self.start_resuming();
+            let timer = Instant::now();
+            let result = self
+                .upload_blocks_async(
+                    &from_resuming
+                        .up_urls
+                        .iter()
+                        .map(|url| url.as_ref())
+                        .collect::<Box<[_]>>(),
+                    &(base_path.to_owned() + "/" + &from_resuming.upload_id),
+                    authorization,
+                    Some(from_resuming.recorder),
+                )
+                .await;
+            self.log_uploading_result(&result, timer.elapsed(), init_uploaded_size);
+            result.map(Some)
         } else {
             Ok(None)
         }
     }
 
+    // 从断点续传记录中已经上传的数据量开始计算上传进度，返回该数据量
+    fn start_resuming(&self) -> u64 {
+        let init_uploaded_size = self.uploaded_size.load(Relaxed);
+        if let Some(uploading_progress) = &self.uploading_progress {
+            uploading_progress.reset(init_uploaded_size);
+        }
+        init_uploaded_size
+    }
+    fn up_type(&self) -> UpType {
+        match self.version {
+            ResumableUploadVersion::V1 => UpType::Chunkedv1,
+            ResumableUploadVersion::V2 => UpType::Chunkedv2,
+        }
+    }
+
     fn make_base_path(&self) -> String {
         "/buckets/".to_owned()
             + self.bucket_name
@@ -759,6 +1016,295 @@ impl<'u, R: Read + Seek + Send> ResumableUploader<'u, R> {
     }
 }
 
+// 并发上传分片时共享的状态
+//
+// 读取分片，构建请求，处理响应，记录断点续传和汇总上传结果均在此完成，
+// 同步上传和异步上传仅在驱动请求的方式上有所不同
+struct PartsUploader<'p, 'u, R: Read + Seek + Send> {
+    io_status_manager: IOStatusManager<'p, &'p mut R>,
+    http_client: &'p Client,
+    up_urls: &'p [&'p str],
+    base_path: &'p str,
+    authorization: &'p str,
+    upload_token_provider: Option<&'p dyn UploadTokenProvider>,
+    completed_parts: &'p Mutex<CompletedParts<'u>>,
+    uploaded_size: &'p AtomicU64,
+    uploading_progress: Option<&'p UploadingProgress<'p>>,
+    part_checksum: PartChecksumAlgorithm,
+    chunk_size: u32,
+    partial_blocks: &'p HashMap<usize, PartialBlock>,
+    upload_logger: Option<&'p TokenizedUploadLogger>,
+    upload_recorder: Option<&'p FileUploadRecordMedium>,
+    throttle: UploadThrottle<'p>,
+    version: ResumableUploadVersion,
+    deadline: Option<Instant>,
+}
+
+// 正在上传的分片
+struct PartUpload<'p> {
+    data: PartData<'p>,
+    authorization: Cow<'p, str>,
+    // 对于分片上传 V1，该字段记录的是块内最后一个已上传的片的 ctx
+    etag: RefCell<Option<Box<str>>>,
+    // 分片内已经上传的数据量，对于分片上传 V1，即为下一个片在块内的偏移量
+    uploaded: Cell<u64>,
+    // 断点续传时此前已经上传的数据量
+    resumed: u64,
+    timer: Instant,
+}
+
+enum PartStep<'r> {
+    Request(Box<UploadRequest<'r>>),
+    Completed(Box<str>),
+}
+
+impl<'p, 'u, R: Read + Seek + Send> PartsUploader<'p, 'u, R> {
+    fn upload_parts(&self) {
+        while let Some(part) = self.next_part() {
+            let result = loop {
+                match self.next_step(&part) {
+                    PartStep::Request(request) => {
+                        if let Err(err) =
+                            request.send(|request, response| self.on_part_response(&part, request, response))
+                        {
+                            break Err(err);
+                        }
+                    }
+                    PartStep::Completed(etag) => break Ok(etag),
+                }
+            };
+            if !self.complete_part(part, result) {
+                return;
+            }
+        }
+    }
+
+    #[cfg(feature = "async")]
+    async fn upload_parts_async(&self) {
+        while let Some(part) = self.next_part() {
+            let result = loop {
+                match self.next_step(&part) {
+                    PartStep::Request(request) => {
+                        if let Err(err) = request
+                            .send_async(|request, response| self.on_part_response(&part, request, response))
+                            .await
+                        {
+                            break Err(err);
+                        }
+                    }
+                    PartStep::Completed(etag) => break Ok(etag),
+                }
+            };
+            if !self.complete_part(part, result) {
+                return;
+            }
+        }
+    }
+
+    fn next_part(&self) -> Option<PartUpload<'p>> {
+        if self.deadline.map_or(false, |deadline| Instant::now() >= deadline) {
+            self.io_status_manager.error(HTTPError::new_unretryable_error(
+                HTTPErrorKind::DeadlineExceeded,
+                None,
+                None,
+                None,
+            ));
+            return None;
+        }
+        let data = self.io_status_manager.read()?;
+        let partial_block = self.partial_blocks.get(&data.part_number).filter(|block| {
+            block.offset == data.offset && block.size == data.data.len().try_into().unwrap_or(u64::max_value())
+        });
+        let resumed = partial_block.map_or(0, |block| block.ctx_offset);
+        match refresh_authorization(self.upload_token_provider, self.authorization) {
+            Ok(authorization) => Some(PartUpload {
+                data,
+                authorization,
+                etag: RefCell::new(partial_block.map(|block| block.ctx.to_owned())),
+                uploaded: Cell::new(resumed),
+                resumed,
+                timer: Instant::now(),
+            }),
+            Err(err) => {
+                self.io_status_manager.error(err);
+                None
+            }
+        }
+    }
+
+    fn next_step<'r>(&'r self, part: &'r PartUpload<'p>) -> PartStep<'r> {
+        let chunk = self.next_chunk(part);
+        if chunk.is_empty() {
+            if let Some(etag) = part.etag.borrow_mut().take() {
+                return PartStep::Completed(etag);
+            }
+        }
+        let request = match self.version {
+            ResumableUploadVersion::V1 => {
+                let (path, up_type) = match part.etag.borrow().as_ref() {
+                    Some(ctx) => (
+                        "/bput/".to_owned() + ctx + "/" + &part.uploaded.get().to_string(),
+                        UpType::Bput,
+                    ),
+                    None => (
+                        "/mkblk/".to_owned() + &part.data.data.len().to_string(),
+                        UpType::MakeBlock,
+                    ),
+                };
+                UploadRequest::post(self.http_client, path, self.up_urls, up_type)
+                    .make_block(expected_crc32(chunk, self.part_checksum))
+            }
+            ResumableUploadVersion::V2 => UploadRequest::put(
+                self.http_client,
+                self.base_path.to_owned() + "/" + &part.data.part_number.to_string(),
+                self.up_urls,
+                UpType::UploadPart,
+            )
+            .content_md5(content_md5(chunk, self.part_checksum)),
+        };
+        PartStep::Request(Box::new(
+            request
+                .authorization(part.authorization.as_ref())
+                .body("application/octet-stream", chunk)
+                .deadline(self.deadline)
+                .upload_logger(self.upload_logger)
+                .transfer(self.throttle, self.uploading_progress),
+        ))
+    }
+
+    // 分片上传 V1 每次请求上传块中的一个片，分片上传 V2 每次请求上传整个分片
+    fn next_chunk<'a>(&self, part: &'a PartUpload) -> &'a [u8] {
+        let rest = part
+            .data
+            .data
+            .get(part.uploaded.get().try_into().unwrap_or(usize::max_value())..)
+            .unwrap_or_default();
+        let chunk_size = self.chunk_size.try_into().unwrap_or(usize::max_value());
+        match self.version {
+            ResumableUploadVersion::V1 if chunk_size > 0 && chunk_size < rest.len() => &rest[..chunk_size],
+            _ => rest,
+        }
+    }
+
+    fn on_part_response(&self, part: &PartUpload, request: &UploadRequest, response: &mut Response) -> HTTPResult<()> {
+        let size = part.size();
+        let uploaded = part.uploaded.get() + self.next_chunk(part).len().try_into().unwrap_or(u64::max_value());
+        let etag = match self.version {
+            ResumableUploadVersion::V1 => {
+                let ctx = request.made_block(response)?.ctx;
+                record_made_block(
+                    self.upload_recorder,
+                    &ctx,
+                    part.data.part_number,
+                    part.data.offset,
+                    size,
+                    uploaded,
+                )?;
+                ctx
+            }
+            ResumableUploadVersion::V2 => {
+                let etag = response.parse_json::<UploadPartResult>()?.etag;
+                record_uploaded_part(
+                    self.upload_recorder,
+                    &etag,
+                    part.data.part_number,
+                    part.data.offset,
+                    size,
+                )?;
+                etag
+            }
+        };
+        part.uploaded.set(uploaded);
+        *part.etag.borrow_mut() = Some(etag);
+        Ok(())
+    }
+
+    // 返回是否继续上传后续分片
+    fn complete_part(&self, part: PartUpload, result: HTTPResult<Box<str>>) -> bool {
+        match result {
+            Ok(etag) => {
+                let size = part.size();
+                self.io_status_manager.feedback(size, part.timer.elapsed());
+                self.completed_parts.lock().unwrap().parts.push(Part {
+                    etag,
+                    part_number: part.data.part_number,
+                    offset: part.data.offset,
+                    size,
+                });
+                self.uploaded_size.fetch_add(size - part.resumed, Relaxed);
+                true
+            }
+            Err(err) => {
+                self.io_status_manager.error(err);
+                false
+            }
+        }
+    }
+
+    fn finish(self) -> Result<(), UploadError> {
+        match self.io_status_manager.result() {
+            IOStatusResult::Success => Ok(()),
+            IOStatusResult::IOError(err) => Err(UploadError::IOError(err)),
+            IOStatusResult::HTTPError(err) => Err(UploadError::QiniuError(err)),
+        }
+    }
+}
+
+impl PartUpload<'_> {
+    fn size(&self) -> u64 {
+        self.data.data.len().try_into().unwrap_or(u64::max_value())
+    }
+}
+
+fn parse_upload_id(_: &UploadRequest, response: &mut Response) -> HTTPResult<Box<str>> {
+    Ok(response.parse_json::<InitPartsResult>()?.upload_id)
+}
+
+// 如果指定了上传凭证提供者，则从中获取最新的上传凭证，否则沿用初始的上传凭证
+fn refresh_authorization<'a>(
+    upload_token_provider: Option<&dyn UploadTokenProvider>,
+    authorization: &'a str,
+) -> HTTPResult<Cow<'a, str>> {
+    match upload_token_provider {
+        Some(upload_token_provider) => upload_token_provider
+            .upload_token()
+            .map(|upload_token| ("UpToken ".to_owned() + &upload_token.to_string()).into())
+            .map_err(|err| HTTPError::new_unretryable_error(HTTPErrorKind::UnknownError(err), None, None, None)),
+        None => Ok(authorization.into()),
+    }
+}
+
+fn record_uploaded_part(
+    upload_recorder: Option<&FileUploadRecordMedium>,
+    etag: &str,
+    part_number: usize,
+    offset: u64,
+    size: u64,
+) -> HTTPResult<()> {
+    if let Some(upload_recorder) = upload_recorder {
+        upload_recorder
+            .append(etag, part_number, offset, size)
+            .map_err(|err| HTTPError::new_unretryable_error(HTTPErrorKind::IOError(err), None, None, None))?;
+    }
+    Ok(())
+}
+
+fn record_made_block(
+    upload_recorder: Option<&FileUploadRecordMedium>,
+    ctx: &str,
+    part_number: usize,
+    offset: u64,
+    size: u64,
+    ctx_offset: u64,
+) -> HTTPResult<()> {
+    if let Some(upload_recorder) = upload_recorder {
+        upload_recorder
+            .append_ctx(ctx, part_number, offset, size, ctx_offset)
+            .map_err(|err| HTTPError::new_unretryable_error(HTTPErrorKind::IOError(err), None, None, None))?;
+    }
+    Ok(())
+}
+
 pub(super) fn encode_key(key: Option<&str>) -> Cow<'static, str> {
     if let Some(key) = key {
         base64::urlsafe(key.as_bytes()).into()
@@ -767,30 +1313,26 @@ pub(super) fn encode_key(key: Option<&str>) -> Cow<'static, str> {
     }
 }
 
-struct OptionalMd5 {
-    enabled: bool,
-}
-
-impl OptionalMd5 {
-    fn new(checksum_enabled: bool) -> OptionalMd5 {
-        OptionalMd5 {
-            enabled: checksum_enabled,
-        }
+// 分片上传 V2 仅支持通过 Content-MD5 校验分片数据，指定 CRC32 时也将改用 MD5 校验
+fn content_md5(data: &[u8], part_checksum: PartChecksumAlgorithm) -> Option<String> {
+    match part_checksum {
+        PartChecksumAlgorithm::None => None,
+        PartChecksumAlgorithm::Md5 | PartChecksumAlgorithm::Crc32 => Some(format!("{:x}", md5::compute(data))),
     }
+}
 
-    fn hash(&mut self, buf: &[u8]) -> Option<String> {
-        if self.enabled {
-            Some(format!("{:x}", md5::compute(buf)))
-        } else {
-            None
-        }
+// 分片上传 V1 仅支持比对七牛服务器返回的 CRC32 校验片数据，指定 MD5 时也将改用 CRC32 校验
+fn expected_crc32(data: &[u8], part_checksum: PartChecksumAlgorithm) -> Option<u32> {
+    match part_checksum {
+        PartChecksumAlgorithm::None => None,
+        PartChecksumAlgorithm::Crc32 | PartChecksumAlgorithm::Md5 => Some(crc32::from_bytes(data)),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
-        super::{UploadPolicyBuilder, UploadToken},
+        super::{upload_token_provider::UploadTokenProviderResult, UploadPolicyBuilder, UploadToken},
         *,
     };
     use crate::{
@@ -805,7 +1347,11 @@ mod tests {
         temp_file::create_temp_file,
     };
     use serde_json::json;
-    use std::{error::Error, result::Result};
+    use std::{
+        error::Error,
+        result::Result,
+        sync::{atomic::AtomicUsize, Arc},
+    };
 
     #[test]
     fn test_storage_uploader_resumable_uploader_upload_empty_file() -> Result<(), Box<dyn Error>> {
@@ -847,7 +1393,7 @@ mod tests {
             &[vec![Box::from("http://z1h1.com")].into()],
         )
         .key("test-key".into())
-        .stream(temp_file, 0, None, "".into(), true)?
+        .stream(temp_file, 0, None, "".into(), PartChecksumAlgorithm::Md5)?
         .send()
         .unwrap_err();
         assert!(matches!(err, UploadError::EmptyFileError));
@@ -944,7 +1490,233 @@ mod tests {
             "".into(),
             10 * (1 << 20),
             None,
-            true,
+            PartChecksumAlgorithm::Md5,
+        )?
+        .send()?;
+        assert_eq!(result.key(), Some("test-key"));
+        assert_eq!(result.hash(), Some("abcdef"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_storage_uploader_resumable_uploader_upload_file_with_upload_token_provider() -> Result<(), Box<dyn Error>> {
+        const PROVIDED_TOKEN: &str = "provided-access-key:provided-sign:provided-policy";
+        fn assert_authorization(request: &qiniu_http::Request) {
+            assert_eq!(
+                request
+                    .headers()
+                    .get(&"Authorization".into())
+                    .map(|authorization| authorization.as_ref()),
+                Some(("UpToken ".to_owned() + PROVIDED_TOKEN).as_str())
+            );
+        }
+        let (temp_file, temp_path) = create_temp_file(10 * (1 << 20))?.into_parts();
+        let put_calls = Arc::new(AtomicUsize::new(0));
+        let config = ConfigBuilder::default()
+            .http_request_handler(
+                CallHandlers::new(|request| {
+                    panic!("Unexpected Request: {} {}", request.method(), request.url());
+                })
+                .install(
+                    Method::POST,
+                    "^".to_owned()
+                        + &regex::escape(
+                            &("http://z1h1.com/buckets/test_bucket/objects/".to_owned()
+                                + &encode_key(Some("test-key"))
+                                + "/uploads"),
+                        )
+                        + "$",
+                    |request, _| {
+                        assert_authorization(request);
+                        let mut headers = HeadersOwned::new();
+                        headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                        headers.insert("X-Reqid".into(), fake_req_id());
+                        Ok(ResponseBuilder::default()
+                            .status_code(200u16)
+                            .headers(headers)
+                            .bytes_as_body(json!({"uploadId":"test_upload_id"}).to_string())
+                            .build())
+                    },
+                )
+                .install(
+                    Method::PUT,
+                    "^".to_owned()
+                        + &regex::escape(
+                            &("http://z1h1.com/buckets/test_bucket/objects/".to_owned()
+                                + &encode_key(Some("test-key"))
+                                + "/uploads/test_upload_id/"),
+                        )
+                        + "\\d"
+                        + "$",
+                    {
+                        let put_calls = put_calls.to_owned();
+                        move |request, called| {
+                            assert_authorization(request);
+                            put_calls.fetch_add(1, Relaxed);
+                            let mut headers = HeadersOwned::new();
+                            headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                            headers.insert("X-Reqid".into(), fake_req_id());
+                            Ok(ResponseBuilder::default()
+                                .status_code(200u16)
+                                .headers(headers)
+                                .bytes_as_body(json!({ "etag": format!("etag_{}", called) }).to_string())
+                                .build())
+                        }
+                    },
+                )
+                .install(
+                    Method::POST,
+                    "^".to_owned()
+                        + &regex::escape(
+                            &("http://z1h1.com/buckets/test_bucket/objects/".to_owned()
+                                + &encode_key(Some("test-key"))
+                                + "/uploads/test_upload_id"),
+                        )
+                        + "$",
+                    |request, _| {
+                        assert_authorization(request);
+                        let mut headers = HeadersOwned::new();
+                        headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                        headers.insert("X-Reqid".into(), fake_req_id());
+                        Ok(ResponseBuilder::default()
+                            .status_code(200u16)
+                            .headers(headers)
+                            .bytes_as_body(json!({"hash": "abcdef", "key": "test-key"}).to_string())
+                            .build())
+                    },
+                ),
+            )
+            .upload_logger(None)
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let provider_calls = AtomicUsize::new(0);
+        let upload_token_provider = || -> UploadTokenProviderResult<UploadToken> {
+            provider_calls.fetch_add(1, Relaxed);
+            Ok(UploadToken::from(PROVIDED_TOKEN.to_owned()))
+        };
+        let policy = UploadPolicyBuilder::new_policy_for_bucket("test_bucket", &config).build();
+        let result = ResumableUploaderBuilder::new(
+            &UploadManager::new(config),
+            Cow::Owned(UploadToken::new(policy, get_credential())),
+            "test_bucket",
+            &[vec![Box::from("http://z1h1.com")].into()],
+        )
+        .key("test-key".into())
+        .upload_token_provider(&upload_token_provider)
+        .file(
+            temp_file,
+            Cow::Borrowed(temp_path.as_ref()),
+            "".into(),
+            10 * (1 << 20),
+            None,
+            PartChecksumAlgorithm::Md5,
+        )?
+        .send()?;
+        assert_eq!(result.key(), Some("test-key"));
+        assert_eq!(result.hash(), Some("abcdef"));
+        assert!(put_calls.load(Relaxed) > 0);
+        assert_eq!(provider_calls.load(Relaxed), put_calls.load(Relaxed) + 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_storage_uploader_resumable_uploader_upload_file_by_v1() -> Result<(), Box<dyn Error>> {
+        let (temp_file, temp_path) = create_temp_file(10 * (1 << 20))?.into_parts();
+        let config = ConfigBuilder::default()
+            .http_request_handler(
+                CallHandlers::new(|request| {
+                    panic!("Unexpected Request: {} {}", request.method(), request.url());
+                })
+                .install(
+                    Method::POST,
+                    "^".to_owned() + &regex::escape("http://z1h1.com/mkblk/") + "(4194304|2097152)$",
+                    |request, called| {
+                        if called > 3 {
+                            panic!("Unexpected call `POST {}` for {} times", request.url(), called);
+                        }
+                        // 每个块的第一个片通过 `mkblk` 上传
+                        assert_eq!(request.body().len(), 1 << 21);
+                        let crc32 = crc32::from_bytes(&request.body().to_bytes());
+                        let mut headers = HeadersOwned::new();
+                        headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                        headers.insert("X-Reqid".into(), fake_req_id());
+                        Ok(ResponseBuilder::default()
+                            .status_code(200u16)
+                            .headers(headers)
+                            .bytes_as_body(
+                                json!({"ctx": format!("ctx_{}", crc32), "crc32": crc32, "offset": request.body().len()})
+                                    .to_string(),
+                            )
+                            .build())
+                    },
+                )
+                .install(
+                    Method::POST,
+                    "^".to_owned() + &regex::escape("http://z1h1.com/bput/ctx_") + "\\d+/2097152$",
+                    |request, called| {
+                        if called > 2 {
+                            panic!("Unexpected call `POST {}` for {} times", request.url(), called);
+                        }
+                        assert_eq!(request.body().len(), 1 << 21);
+                        let crc32 = crc32::from_bytes(&request.body().to_bytes());
+                        let mut headers = HeadersOwned::new();
+                        headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                        headers.insert("X-Reqid".into(), fake_req_id());
+                        Ok(ResponseBuilder::default()
+                            .status_code(200u16)
+                            .headers(headers)
+                            .bytes_as_body(
+                                json!({"ctx": format!("bput_{}", crc32), "crc32": crc32, "offset": 1 << 22})
+                                    .to_string(),
+                            )
+                            .build())
+                    },
+                )
+                .install(
+                    Method::POST,
+                    "^".to_owned()
+                        + &regex::escape(
+                            &("http://z1h1.com/mkfile/10485760/key/".to_owned() + &encode_key(Some("test-key"))),
+                        )
+                        + "$",
+                    |request, _| {
+                        let ctxs = String::from_utf8(request.body().to_bytes().to_vec()).unwrap();
+                        let ctxs: Vec<&str> = ctxs.split(',').collect();
+                        assert_eq!(ctxs.len(), 3);
+                        // 前两个块的最后一个片通过 `bput` 上传，最后一个块只有一个片
+                        assert!(ctxs[0].starts_with("bput_"));
+                        assert!(ctxs[1].starts_with("bput_"));
+                        assert!(ctxs[2].starts_with("ctx_"));
+                        let mut headers = HeadersOwned::new();
+                        headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                        headers.insert("X-Reqid".into(), fake_req_id());
+                        Ok(ResponseBuilder::default()
+                            .status_code(200u16)
+                            .headers(headers)
+                            .bytes_as_body(json!({"hash": "abcdef", "key": "test-key"}).to_string())
+                            .build())
+                    },
+                ),
+            )
+            .resumable_upload_version(ResumableUploadVersion::V1)
+            .upload_logger(None)
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let policy = UploadPolicyBuilder::new_policy_for_bucket("test_bucket", &config).build();
+        let result = ResumableUploaderBuilder::new(
+            &UploadManager::new(config),
+            Cow::Owned(UploadToken::new(policy, get_credential())),
+            "test_bucket",
+            &[vec![Box::from("http://z1h1.com")].into()],
+        )
+        .key("test-key".into())
+        .file(
+            temp_file,
+            Cow::Borrowed(temp_path.as_ref()),
+            "".into(),
+            10 * (1 << 20),
+            None,
+            PartChecksumAlgorithm::Crc32,
         )?
         .send()?;
         assert_eq!(result.key(), Some("test-key"));
@@ -952,6 +1724,231 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_storage_uploader_resumable_uploader_upload_file_by_v1_async() -> Result<(), Box<dyn Error>> {
+        let (temp_file, temp_path) = create_temp_file(10 * (1 << 20))?.into_parts();
+        let config = ConfigBuilder::default()
+            .http_request_handler(
+                CallHandlers::new(|request| {
+                    panic!("Unexpected Request: {} {}", request.method(), request.url());
+                })
+                .install(
+                    Method::POST,
+                    "^".to_owned() + &regex::escape("http://z1h1.com/mkblk/") + "(4194304|2097152)$",
+                    |request, called| {
+                        if called > 3 {
+                            panic!("Unexpected call `POST {}` for {} times", request.url(), called);
+                        }
+                        // 每个块的第一个片通过 `mkblk` 上传
+                        assert_eq!(request.body().len(), 1 << 21);
+                        let crc32 = crc32::from_bytes(&request.body().to_bytes());
+                        let mut headers = HeadersOwned::new();
+                        headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                        headers.insert("X-Reqid".into(), fake_req_id());
+                        Ok(ResponseBuilder::default()
+                            .status_code(200u16)
+                            .headers(headers)
+                            .bytes_as_body(
+                                json!({"ctx": format!("ctx_{}", crc32), "crc32": crc32, "offset": request.body().len()})
+                                    .to_string(),
+                            )
+                            .build())
+                    },
+                )
+                .install(
+                    Method::POST,
+                    "^".to_owned() + &regex::escape("http://z1h1.com/bput/ctx_") + "\\d+/2097152$",
+                    |request, called| {
+                        if called > 2 {
+                            panic!("Unexpected call `POST {}` for {} times", request.url(), called);
+                        }
+                        assert_eq!(request.body().len(), 1 << 21);
+                        let crc32 = crc32::from_bytes(&request.body().to_bytes());
+                        let mut headers = HeadersOwned::new();
+                        headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                        headers.insert("X-Reqid".into(), fake_req_id());
+                        Ok(ResponseBuilder::default()
+                            .status_code(200u16)
+                            .headers(headers)
+                            .bytes_as_body(
+                                json!({"ctx": format!("bput_{}", crc32), "crc32": crc32, "offset": 1 << 22})
+                                    .to_string(),
+                            )
+                            .build())
+                    },
+                )
+                .install(
+                    Method::POST,
+                    "^".to_owned()
+                        + &regex::escape(
+                            &("http://z1h1.com/mkfile/10485760/key/".to_owned() + &encode_key(Some("test-key"))),
+                        )
+                        + "$",
+                    |request, _| {
+                        let ctxs = String::from_utf8(request.body().to_bytes().to_vec()).unwrap();
+                        let ctxs: Vec<&str> = ctxs.split(',').collect();
+                        assert_eq!(ctxs.len(), 3);
+                        // 前两个块的最后一个片通过 `bput` 上传，最后一个块只有一个片
+                        assert!(ctxs[0].starts_with("bput_"));
+                        assert!(ctxs[1].starts_with("bput_"));
+                        assert!(ctxs[2].starts_with("ctx_"));
+                        let mut headers = HeadersOwned::new();
+                        headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                        headers.insert("X-Reqid".into(), fake_req_id());
+                        Ok(ResponseBuilder::default()
+                            .status_code(200u16)
+                            .headers(headers)
+                            .bytes_as_body(json!({"hash": "abcdef", "key": "test-key"}).to_string())
+                            .build())
+                    },
+                ),
+            )
+            .resumable_upload_version(ResumableUploadVersion::V1)
+            .upload_logger(None)
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let policy = UploadPolicyBuilder::new_policy_for_bucket("test_bucket", &config).build();
+        let last_completed = AtomicU64::new(0);
+        let on_progress = |completed: u64, total: Option<u64>| {
+            assert_eq!(total, Some(10 * (1 << 20)));
+            last_completed.fetch_max(completed, Relaxed);
+        };
+        let result = futures::executor::block_on(
+            ResumableUploaderBuilder::new(
+                &UploadManager::new(config),
+                Cow::Owned(UploadToken::new(policy, get_credential())),
+                "test_bucket",
+                &[vec![Box::from("http://z1h1.com")].into()],
+            )
+            .key("test-key".into())
+            .max_concurrency(2)
+            .on_uploading_progress(&on_progress)
+            .file(
+                temp_file,
+                Cow::Borrowed(temp_path.as_ref()),
+                "".into(),
+                10 * (1 << 20),
+                None,
+                PartChecksumAlgorithm::Crc32,
+            )?
+            .send_async(),
+        )?;
+        assert_eq!(result.key(), Some("test-key"));
+        assert_eq!(result.hash(), Some("abcdef"));
+        assert_eq!(last_completed.load(Relaxed), 10 * (1 << 20));
+        Ok(())
+    }
+
+    #[test]
+    fn test_storage_uploader_resumable_uploader_upload_file_by_v1_with_crc32_mismatch() -> Result<(), Box<dyn Error>> {
+        let (temp_file, temp_path) = create_temp_file(10 * (1 << 20))?.into_parts();
+        let mkblk_calls = Arc::new(AtomicUsize::new(0));
+        let config = ConfigBuilder::default()
+            .http_request_handler(
+                CallHandlers::new(|request| {
+                    panic!("Unexpected Request: {} {}", request.method(), request.url());
+                })
+                .install(
+                    Method::POST,
+                    "^".to_owned() + &regex::escape("http://z1h1.com/mkblk/") + "(4194304|2097152)$",
+                    {
+                        let mkblk_calls = mkblk_calls.to_owned();
+                        move |request, called| {
+                            mkblk_calls.fetch_add(1, Relaxed);
+                            let crc32 = crc32::from_bytes(&request.body().to_bytes());
+                            // 第一次请求返回错误的 CRC32，模拟数据在传输过程中被损坏
+                            let returned_crc32 = if called == 1 { crc32 ^ 1 } else { crc32 };
+                            let mut headers = HeadersOwned::new();
+                            headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                            headers.insert("X-Reqid".into(), fake_req_id());
+                            Ok(ResponseBuilder::default()
+                                .status_code(200u16)
+                                .headers(headers)
+                                .bytes_as_body(
+                                    json!({"ctx": format!("ctx_{}", crc32), "crc32": returned_crc32, "offset": request.body().len()})
+                                        .to_string(),
+                                )
+                                .build())
+                        }
+                    },
+                )
+                .install(
+                    Method::POST,
+                    "^".to_owned() + &regex::escape("http://z1h1.com/bput/ctx_") + "\\d+/2097152$",
+                    |request, called| {
+                        if called > 2 {
+                            panic!("Unexpected call `POST {}` for {} times", request.url(), called);
+                        }
+                        assert_eq!(request.body().len(), 1 << 21);
+                        let crc32 = crc32::from_bytes(&request.body().to_bytes());
+                        let mut headers = HeadersOwned::new();
+                        headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                        headers.insert("X-Reqid".into(), fake_req_id());
+                        Ok(ResponseBuilder::default()
+                            .status_code(200u16)
+                            .headers(headers)
+                            .bytes_as_body(
+                                json!({"ctx": format!("bput_{}", crc32), "crc32": crc32, "offset": 1 << 22})
+                                    .to_string(),
+                            )
+                            .build())
+                    },
+                )
+                .install(
+                    Method::POST,
+                    "^".to_owned()
+                        + &regex::escape(
+                            &("http://z1h1.com/mkfile/10485760/key/".to_owned() + &encode_key(Some("test-key"))),
+                        )
+                        + "$",
+                    |request, _| {
+                        let ctxs = String::from_utf8(request.body().to_bytes().to_vec()).unwrap();
+                        let ctxs: Vec<&str> = ctxs.split(',').collect();
+                        assert_eq!(ctxs.len(), 3);
+                        // 前两个块的最后一个片通过 `bput` 上传，最后一个块只有一个片
+                        assert!(ctxs[0].starts_with("bput_"));
+                        assert!(ctxs[1].starts_with("bput_"));
+                        assert!(ctxs[2].starts_with("ctx_"));
+                        let mut headers = HeadersOwned::new();
+                        headers.insert("Content-Type".into(), mime::JSON_MIME.into());
+                        headers.insert("X-Reqid".into(), fake_req_id());
+                        Ok(ResponseBuilder::default()
+                            .status_code(200u16)
+                            .headers(headers)
+                            .bytes_as_body(json!({"hash": "abcdef", "key": "test-key"}).to_string())
+                            .build())
+                    },
+                ),
+            )
+            .resumable_upload_version(ResumableUploadVersion::V1)
+            .http_request_retry_delay(Duration::from_millis(1))
+            .upload_logger(None)
+            .domains_manager(DomainsManagerBuilder::default().disable_url_resolution().build())
+            .build();
+        let policy = UploadPolicyBuilder::new_policy_for_bucket("test_bucket", &config).build();
+        let result = ResumableUploaderBuilder::new(
+            &UploadManager::new(config),
+            Cow::Owned(UploadToken::new(policy, get_credential())),
+            "test_bucket",
+            &[vec![Box::from("http://z1h1.com")].into()],
+        )
+        .key("test-key".into())
+        .file(
+            temp_file,
+            Cow::Borrowed(temp_path.as_ref()),
+            "".into(),
+            10 * (1 << 20),
+            None,
+            // 分片上传 V1 不支持 MD5 校验，将改用 CRC32 校验
+            PartChecksumAlgorithm::Md5,
+        )?
+        .send()?;
+        assert_eq!(result.hash(), Some("abcdef"));
+        assert_eq!(mkblk_calls.load(Relaxed), 4);
+        Ok(())
+    }
+
     #[test]
     fn test_storage_uploader_resumable_uploader_upload_file_with_many_retryable_errors() -> Result<(), Box<dyn Error>> {
         let (temp_file, temp_path) = create_temp_file(10 * (1 << 20))?.into_parts();
@@ -1045,7 +2042,7 @@ mod tests {
             "".into(),
             10 * (1 << 20),
             None,
-            true,
+            PartChecksumAlgorithm::Md5,
         )?
         .send()?;
         assert_eq!(result.key(), Some("test-key"));
@@ -1173,7 +2170,7 @@ mod tests {
             "".into(),
             10 * (1 << 20),
             None,
-            true,
+            PartChecksumAlgorithm::Md5,
         )?
         .send()?;
         assert_eq!(result.key(), Some("test-key"));
@@ -1293,7 +2290,7 @@ mod tests {
             "".into(),
             10 * (1 << 20),
             None,
-            true,
+            PartChecksumAlgorithm::Md5,
         )?
         .send()?;
         assert_eq!(result.key(), Some("test-key"));
@@ -1445,7 +2442,7 @@ mod tests {
             "".into(),
             10 * (1 << 20),
             None,
-            true,
+            PartChecksumAlgorithm::Md5,
         )?
         .send()?;
         assert_eq!(result.key(), Some("test-key"));
@@ -1576,7 +2573,7 @@ mod tests {
             "".into(),
             10 * (1 << 20),
             None,
-            true,
+            PartChecksumAlgorithm::Md5,
         )?
         .send()
         .is_err());
@@ -1593,7 +2590,7 @@ mod tests {
             "".into(),
             10 * (1 << 20),
             None,
-            true,
+            PartChecksumAlgorithm::Md5,
         )?
         .send()?;
 
@@ -0,0 +1,523 @@
+//! 自定义对象名称模板模块
+//!
+//! 提供类型安全的[自定义对象名称](https://developer.qiniu.com/kodo/manual/1206/put-policy#put-policy-save-key)模板，
+//! 并能在本地预测七牛服务器最终生成的对象名称
+
+use super::magic_variable::is_valid_name;
+use std::{
+    collections::HashMap,
+    fmt,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use thiserror::Error;
+
+const MAX_KEY_SIZE: usize = 750;
+const DEFAULT_UTC_OFFSET: i64 = 8 * 60 * 60;
+
+/// 自定义对象名称模板片段
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SaveKeyPart {
+    /// 常量字符串
+    Literal(String),
+    /// 对象内容的 Etag，即 `$(etag)`
+    Etag,
+    /// 上传的原始文件名，即 `$(fname)`
+    FileName,
+    /// 上传的原始文件名去除扩展名后的部分，即 `$(fprefix)`
+    FilePrefix,
+    /// 上传的原始文件扩展名，包含 `.`，即 `$(ext)`
+    Ext,
+    /// 上传时的年份，即 `$(year)`
+    Year,
+    /// 上传时的月份，即 `$(mon)`
+    Month,
+    /// 上传时的日期，即 `$(day)`
+    Day,
+    /// 上传时的小时，即 `$(hour)`
+    Hour,
+    /// 上传时的分钟，即 `$(min)`
+    Minute,
+    /// 上传时的秒，即 `$(sec)`
+    Second,
+    /// 自定义变量，即 `$(x:<name>)`，变量名称无需包含 `x:` 前缀
+    Custom(String),
+}
+
+impl SaveKeyPart {
+    fn parse_variable(name: &str) -> SaveKeyResult<Self> {
+        let part = match name {
+            "etag" => Self::Etag,
+            "fname" => Self::FileName,
+            "fprefix" => Self::FilePrefix,
+            "ext" => Self::Ext,
+            "year" => Self::Year,
+            "mon" => Self::Month,
+            "day" => Self::Day,
+            "hour" => Self::Hour,
+            "min" => Self::Minute,
+            "sec" => Self::Second,
+            "key" | "bucket" | "fsize" | "mimeType" | "endUser" | "persistentId" | "exif" | "imageInfo" => {
+                return Err(SaveKeyError::UnsupportedVariable(name.to_owned()));
+            }
+            _ => {
+                if let Some(custom) = name.strip_prefix("x:") {
+                    Self::Custom(custom.to_owned())
+                } else {
+                    return Err(SaveKeyError::UnknownVariable(name.to_owned()));
+                }
+            }
+        };
+        Ok(part)
+    }
+
+    fn validate(&self) -> SaveKeyResult<()> {
+        match self {
+            Self::Custom(name) if !is_valid_name(name) => Err(SaveKeyError::InvalidCustomVariableName(name.to_owned())),
+            Self::Literal(literal) if literal.contains("$(") => {
+                Err(SaveKeyError::UnescapedVariable(literal.to_owned()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for SaveKeyPart {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Literal(literal) => f.write_str(literal),
+            Self::Etag => f.write_str("$(etag)"),
+            Self::FileName => f.write_str("$(fname)"),
+            Self::FilePrefix => f.write_str("$(fprefix)"),
+            Self::Ext => f.write_str("$(ext)"),
+            Self::Year => f.write_str("$(year)"),
+            Self::Month => f.write_str("$(mon)"),
+            Self::Day => f.write_str("$(day)"),
+            Self::Hour => f.write_str("$(hour)"),
+            Self::Minute => f.write_str("$(min)"),
+            Self::Second => f.write_str("$(sec)"),
+            Self::Custom(name) => write!(f, "$(x:{})", name),
+        }
+    }
+}
+
+/// 自定义对象名称模板
+///
+/// 可以通过 `SaveKeyTemplateBuilder` 构建，也可以从模板字符串解析得到，
+/// 调用 `to_string()` 即可得到用于 `UploadPolicyBuilder::save_as` 的模板字符串
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SaveKeyTemplate {
+    parts: Vec<SaveKeyPart>,
+}
+
+impl SaveKeyTemplate {
+    /// 模板片段列表
+    pub fn parts(&self) -> &[SaveKeyPart] {
+        &self.parts
+    }
+
+    /// 模板中引用的自定义变量名称列表
+    pub fn custom_variables(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().filter_map(|part| match part {
+            SaveKeyPart::Custom(name) => Some(name.as_str()),
+            _ => None,
+        })
+    }
+
+    /// 在本地预测七牛服务器将生成的对象名称
+    ///
+    /// 如果模板引用的变量在上下文中不存在，或生成的对象名称为空或超出长度限制，将返回错误
+    pub fn predict(&self, context: &SaveKeyContext) -> SaveKeyResult<String> {
+        let (year, month, day, hour, minute, second) = context.local_time();
+        let mut key = String::new();
+        for part in self.parts.iter() {
+            match part {
+                SaveKeyPart::Literal(literal) => key.push_str(literal),
+                SaveKeyPart::Etag => key.push_str(context.etag.as_ref().ok_or(SaveKeyError::MissingEtag)?),
+                SaveKeyPart::FileName => key.push_str(context.require_file_name()?),
+                SaveKeyPart::FilePrefix => key.push_str(split_file_name(context.require_file_name()?).0),
+                SaveKeyPart::Ext => key.push_str(split_file_name(context.require_file_name()?).1),
+                SaveKeyPart::Year => key.push_str(&format!("{:04}", year)),
+                SaveKeyPart::Month => key.push_str(&format!("{:02}", month)),
+                SaveKeyPart::Day => key.push_str(&format!("{:02}", day)),
+                SaveKeyPart::Hour => key.push_str(&format!("{:02}", hour)),
+                SaveKeyPart::Minute => key.push_str(&format!("{:02}", minute)),
+                SaveKeyPart::Second => key.push_str(&format!("{:02}", second)),
+                SaveKeyPart::Custom(name) => key.push_str(
+                    context
+                        .vars
+                        .get(name)
+                        .ok_or_else(|| SaveKeyError::MissingCustomVariable(name.to_owned()))?,
+                ),
+            }
+        }
+        if key.is_empty() {
+            return Err(SaveKeyError::EmptyKey);
+        } else if key.len() > MAX_KEY_SIZE {
+            return Err(SaveKeyError::KeyTooLong(key.len()));
+        }
+        Ok(key)
+    }
+}
+
+fn split_file_name(file_name: &str) -> (&str, &str) {
+    match file_name.rfind('.') {
+        Some(index) if index > 0 => file_name.split_at(index),
+        _ => (file_name, ""),
+    }
+}
+
+impl fmt::Display for SaveKeyTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.parts.iter().try_for_each(|part| part.fmt(f))
+    }
+}
+
+impl FromStr for SaveKeyTemplate {
+    type Err = SaveKeyError;
+
+    /// 解析模板字符串，并检查七牛服务器将会拒绝的模板
+    fn from_str(s: &str) -> SaveKeyResult<Self> {
+        let mut builder = SaveKeyTemplateBuilder::default();
+        let mut rest = s;
+        while let Some(begin) = rest.find("$(") {
+            if begin > 0 {
+                builder.literal(&rest[..begin]);
+            }
+            let end = rest[begin..]
+                .find(')')
+                .ok_or_else(|| SaveKeyError::UnclosedVariable(rest[begin..].to_owned()))?;
+            builder.part(SaveKeyPart::parse_variable(&rest[begin + 2..begin + end])?);
+            rest = &rest[begin + end + 1..];
+        }
+        if !rest.is_empty() {
+            builder.literal(rest);
+        }
+        builder.build()
+    }
+}
+
+/// 自定义对象名称模板生成器
+#[derive(Default, Debug, Clone)]
+pub struct SaveKeyTemplateBuilder {
+    parts: Vec<SaveKeyPart>,
+}
+
+impl SaveKeyTemplateBuilder {
+    /// 追加模板片段
+    pub fn part(&mut self, part: SaveKeyPart) -> &mut Self {
+        self.parts.push(part);
+        self
+    }
+
+    /// 追加常量字符串
+    pub fn literal(&mut self, literal: impl Into<String>) -> &mut Self {
+        self.part(SaveKeyPart::Literal(literal.into()))
+    }
+
+    /// 追加 `$(etag)`
+    pub fn etag(&mut self) -> &mut Self {
+        self.part(SaveKeyPart::Etag)
+    }
+
+    /// 追加 `$(fname)`
+    pub fn file_name(&mut self) -> &mut Self {
+        self.part(SaveKeyPart::FileName)
+    }
+
+    /// 追加 `$(fprefix)`
+    pub fn file_prefix(&mut self) -> &mut Self {
+        self.part(SaveKeyPart::FilePrefix)
+    }
+
+    /// 追加 `$(ext)`
+    pub fn ext(&mut self) -> &mut Self {
+        self.part(SaveKeyPart::Ext)
+    }
+
+    /// 追加 `$(year)`
+    pub fn year(&mut self) -> &mut Self {
+        self.part(SaveKeyPart::Year)
+    }
+
+    /// 追加 `$(mon)`
+    pub fn month(&mut self) -> &mut Self {
+        self.part(SaveKeyPart::Month)
+    }
+
+    /// 追加 `$(day)`
+    pub fn day(&mut self) -> &mut Self {
+        self.part(SaveKeyPart::Day)
+    }
+
+    /// 追加 `$(hour)`
+    pub fn hour(&mut self) -> &mut Self {
+        self.part(SaveKeyPart::Hour)
+    }
+
+    /// 追加 `$(min)`
+    pub fn minute(&mut self) -> &mut Self {
+        self.part(SaveKeyPart::Minute)
+    }
+
+    /// 追加 `$(sec)`
+    pub fn second(&mut self) -> &mut Self {
+        self.part(SaveKeyPart::Second)
+    }
+
+    /// 追加自定义变量 `$(x:<name>)`，变量名称无需包含 `x:` 前缀
+    pub fn custom_variable(&mut self, name: impl Into<String>) -> &mut Self {
+        self.part(SaveKeyPart::Custom(name.into()))
+    }
+
+    /// 生成自定义对象名称模板
+    ///
+    /// 模板必须引用至少一个变量或包含非空的常量字符串，且常量字符串中不能包含 `$(`
+    pub fn build(&self) -> SaveKeyResult<SaveKeyTemplate> {
+        let mut parts: Vec<SaveKeyPart> = Vec::with_capacity(self.parts.len());
+        for part in self.parts.iter() {
+            match (parts.last_mut(), part) {
+                (_, SaveKeyPart::Literal(literal)) if literal.is_empty() => {}
+                (Some(SaveKeyPart::Literal(last)), SaveKeyPart::Literal(literal)) => last.push_str(literal),
+                _ => parts.push(part.to_owned()),
+            }
+        }
+        if parts.is_empty() {
+            return Err(SaveKeyError::EmptyTemplate);
+        }
+        parts.iter().try_for_each(SaveKeyPart::validate)?;
+        Ok(SaveKeyTemplate { parts })
+    }
+}
+
+/// 预测对象名称的上下文
+///
+/// 时间变量默认按照北京时间（UTC+8）计算
+#[derive(Debug, Clone)]
+pub struct SaveKeyContext {
+    file_name: Option<String>,
+    etag: Option<String>,
+    vars: HashMap<String, String>,
+    upload_time: SystemTime,
+    utc_offset: i64,
+}
+
+impl Default for SaveKeyContext {
+    fn default() -> Self {
+        Self {
+            file_name: None,
+            etag: None,
+            vars: HashMap::new(),
+            upload_time: SystemTime::now(),
+            utc_offset: DEFAULT_UTC_OFFSET,
+        }
+    }
+}
+
+impl SaveKeyContext {
+    /// 设置上传的原始文件名
+    pub fn file_name(mut self, file_name: impl Into<String>) -> Self {
+        self.file_name = Some(file_name.into());
+        self
+    }
+
+    /// 设置对象内容的 Etag
+    ///
+    /// 可以通过 `qiniu_ng::utils::etag` 在本地计算
+    pub fn etag(mut self, etag: impl Into<String>) -> Self {
+        self.etag = Some(etag.into());
+        self
+    }
+
+    /// 设置自定义变量，变量名称无需包含 `x:` 前缀
+    pub fn var(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.vars.insert(key.into(), value.into());
+        self
+    }
+
+    /// 设置上传时间
+    ///
+    /// 默认为当前时间
+    pub fn upload_time(mut self, upload_time: SystemTime) -> Self {
+        self.upload_time = upload_time;
+        self
+    }
+
+    /// 设置计算时间变量时使用的时区偏移
+    ///
+    /// 默认为北京时间（UTC+8）
+    pub fn utc_offset(mut self, utc_offset: Duration, east: bool) -> Self {
+        let offset = utc_offset.as_secs() as i64;
+        self.utc_offset = if east { offset } else { -offset };
+        self
+    }
+
+    fn require_file_name(&self) -> SaveKeyResult<&str> {
+        self.file_name.as_deref().ok_or(SaveKeyError::MissingFileName)
+    }
+
+    fn local_time(&self) -> (i64, u32, u32, u32, u32, u32) {
+        let timestamp = match self.upload_time.duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_secs() as i64,
+            Err(err) => -(err.duration().as_secs() as i64),
+        } + self.utc_offset;
+        let (days, seconds) = (timestamp.div_euclid(86400), timestamp.rem_euclid(86400) as u32);
+        let (year, month, day) = civil_from_days(days);
+        (year, month, day, seconds / 3600, seconds % 3600 / 60, seconds % 60)
+    }
+}
+
+// 将 1970-01-01 以来的天数转换为公历日期
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// 自定义对象名称错误
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum SaveKeyError {
+    /// 模板为空
+    #[error("Save key template must not be empty")]
+    EmptyTemplate,
+    /// 未知的变量
+    #[error("Unknown variable in save key: {0}")]
+    UnknownVariable(String),
+    /// 自定义对象名称中不支持使用的魔法变量
+    #[error("Variable is not supported in save key: {0}")]
+    UnsupportedVariable(String),
+    /// 非法的自定义变量名称
+    #[error("Invalid custom variable name: {0}")]
+    InvalidCustomVariableName(String),
+    /// 变量未闭合
+    #[error("Unclosed variable in save key: {0}")]
+    UnclosedVariable(String),
+    /// 常量字符串中包含变量
+    #[error("Literal must not contain variable: {0}")]
+    UnescapedVariable(String),
+    /// 预测时缺少原始文件名
+    #[error("File name is required to predict the key")]
+    MissingFileName,
+    /// 预测时缺少 Etag
+    #[error("Etag is required to predict the key")]
+    MissingEtag,
+    /// 预测时缺少自定义变量
+    #[error("Custom variable is required to predict the key: {0}")]
+    MissingCustomVariable(String),
+    /// 生成的对象名称为空
+    #[error("Predicted key is empty")]
+    EmptyKey,
+    /// 生成的对象名称过长
+    #[error("Predicted key is too long: {0} bytes")]
+    KeyTooLong(usize),
+}
+
+/// 自定义对象名称结果
+pub type SaveKeyResult<T> = Result<T, SaveKeyError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{boxed::Box, error::Error, result::Result};
+
+    #[test]
+    fn test_storage_uploader_save_key_template_parse() -> Result<(), Box<dyn Error>> {
+        let template = "uploads/$(year)/$(mon)/$(x:user)-$(etag)$(ext)".parse::<SaveKeyTemplate>()?;
+        assert_eq!(
+            template,
+            SaveKeyTemplateBuilder::default()
+                .literal("uploads/")
+                .year()
+                .literal("/")
+                .month()
+                .literal("/")
+                .custom_variable("user")
+                .literal("-")
+                .etag()
+                .ext()
+                .build()?
+        );
+        assert_eq!(template.to_string(), "uploads/$(year)/$(mon)/$(x:user)-$(etag)$(ext)");
+        assert_eq!(template.custom_variables().collect::<Vec<_>>(), vec!["user"]);
+
+        assert_eq!("".parse::<SaveKeyTemplate>(), Err(SaveKeyError::EmptyTemplate));
+        assert_eq!(
+            "a/$(key)".parse::<SaveKeyTemplate>(),
+            Err(SaveKeyError::UnsupportedVariable("key".into()))
+        );
+        assert_eq!(
+            "a/$(fnmae)".parse::<SaveKeyTemplate>(),
+            Err(SaveKeyError::UnknownVariable("fnmae".into()))
+        );
+        assert_eq!(
+            "a/$(etag".parse::<SaveKeyTemplate>(),
+            Err(SaveKeyError::UnclosedVariable("$(etag".into()))
+        );
+        assert_eq!(
+            "$(x:a b)".parse::<SaveKeyTemplate>(),
+            Err(SaveKeyError::InvalidCustomVariableName("a b".into()))
+        );
+        assert_eq!(
+            SaveKeyTemplateBuilder::default().literal("$(etag)").build(),
+            Err(SaveKeyError::UnescapedVariable("$(etag)".into()))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_storage_uploader_save_key_template_predict() -> Result<(), Box<dyn Error>> {
+        let template = "uploads/$(year)$(mon)$(day)-$(hour)$(min)$(sec)/$(x:user)/$(fprefix)-$(etag)$(ext)"
+            .parse::<SaveKeyTemplate>()?;
+        // 2019-12-31T16:30:05Z，即北京时间 2020-01-01 00:30:05
+        let upload_time = UNIX_EPOCH + Duration::from_secs(1_577_809_805);
+        let context = SaveKeyContext::default()
+            .file_name("photo.tar.gz")
+            .etag("FmDZwqadA4-ib_15hYfQpb7UXUYR")
+            .var("user", "alice")
+            .upload_time(upload_time);
+        assert_eq!(
+            template.predict(&context)?,
+            "uploads/20200101-003005/alice/photo.tar-FmDZwqadA4-ib_15hYfQpb7UXUYR.gz"
+        );
+        assert_eq!(
+            template.predict(&context.to_owned().utc_offset(Duration::from_secs(0), true))?,
+            "uploads/20191231-163005/alice/photo.tar-FmDZwqadA4-ib_15hYfQpb7UXUYR.gz"
+        );
+
+        let template = "$(fname)$(ext)".parse::<SaveKeyTemplate>()?;
+        assert_eq!(
+            template.predict(&SaveKeyContext::default().file_name(".bashrc"))?,
+            ".bashrc"
+        );
+        assert_eq!(
+            template.predict(&SaveKeyContext::default()),
+            Err(SaveKeyError::MissingFileName)
+        );
+        assert_eq!(
+            "$(x:user)"
+                .parse::<SaveKeyTemplate>()?
+                .predict(&SaveKeyContext::default()),
+            Err(SaveKeyError::MissingCustomVariable("user".into()))
+        );
+        assert_eq!(
+            "$(x:user)"
+                .parse::<SaveKeyTemplate>()?
+                .predict(&SaveKeyContext::default().var("user", "")),
+            Err(SaveKeyError::EmptyKey)
+        );
+        assert_eq!(
+            "$(x:user)"
+                .parse::<SaveKeyTemplate>()?
+                .predict(&SaveKeyContext::default().var("user", "a".repeat(751))),
+            Err(SaveKeyError::KeyTooLong(751))
+        );
+        Ok(())
+    }
+}
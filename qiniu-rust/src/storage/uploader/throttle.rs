@@ -0,0 +1,77 @@
+use super::UploadManager;
+use crate::utils::rate_limiter::RateLimiter;
+use std::cell::Cell;
+
+/// 上传限速，同时遵守客户端配置中的全局限速器和上传任务的专用限速器
+#[derive(Clone, Copy)]
+pub(super) struct UploadThrottle<'u> {
+    global: Option<&'u RateLimiter>,
+    job: Option<&'u RateLimiter>,
+}
+
+impl<'u> UploadThrottle<'u> {
+    pub(super) fn new(upload_manager: &'u UploadManager) -> UploadThrottle<'u> {
+        UploadThrottle {
+            global: upload_manager.config().upload_rate_limiter().as_ref(),
+            job: None,
+        }
+    }
+
+    pub(super) fn job_rate_limiter(mut self, rate_limiter: &'u RateLimiter) -> UploadThrottle<'u> {
+        self.job = Some(rate_limiter);
+        self
+    }
+
+    pub(super) fn is_enabled(&self) -> bool {
+        self.global.is_some() || self.job.is_some()
+    }
+
+    pub(super) fn acquire(&self, size: u64) {
+        if let Some(rate_limiter) = self.global {
+            rate_limiter.acquire(size);
+        }
+        if let Some(rate_limiter) = self.job {
+            rate_limiter.acquire(size);
+        }
+    }
+
+    /// 为一次请求创建限速跟踪器
+    pub(super) fn transfer(&self) -> ThrottledTransfer<'u> {
+        ThrottledTransfer {
+            throttle: *self,
+            throttled: Cell::new(0),
+        }
+    }
+}
+
+/// 跟踪单次请求中已经申请过带宽的字节数
+///
+/// 在上传进度回调中阻塞即可减缓 HTTP 请求体的发送速度，
+/// 对于不支持上传进度回调的 `HTTPCaller` 实现，在请求结束后补足尚未申请的字节数，确保平均速度依然受到限制
+pub(super) struct ThrottledTransfer<'u> {
+    throttle: UploadThrottle<'u>,
+    throttled: Cell<u64>,
+}
+
+impl ThrottledTransfer<'_> {
+    pub(super) fn on_progress(&self, uploaded: u64) {
+        if !self.throttle.is_enabled() {
+            return;
+        }
+        // 重试时上传进度将从头开始计算，重新发送的数据依然需要申请带宽
+        let prev_uploaded = self.throttled.replace(uploaded);
+        if uploaded > prev_uploaded {
+            self.throttle.acquire(uploaded - prev_uploaded);
+        }
+    }
+
+    pub(super) fn on_completed(&self, total: u64) {
+        if !self.throttle.is_enabled() {
+            return;
+        }
+        let prev_uploaded = self.throttled.replace(total);
+        if total > prev_uploaded {
+            self.throttle.acquire(total - prev_uploaded);
+        }
+    }
+}
@@ -498,6 +498,10 @@ impl Drop for TokenizedUploadLogger {
 #[derive(Copy, Clone, Debug)]
 pub(crate) enum UpType {
     Form,
+    Chunkedv1,
+    MakeBlock,
+    Bput,
+    MakeFile,
     Chunkedv2,
     InitParts,
     UploadPart,
@@ -508,6 +512,10 @@ impl UpType {
     fn as_str(self) -> &'static str {
         match self {
             UpType::Form => "form",
+            UpType::Chunkedv1 => "chunked_v1",
+            UpType::MakeBlock => "mkblk",
+            UpType::Bput => "bput",
+            UpType::MakeFile => "mkfile",
             UpType::Chunkedv2 => "chunked_v2",
             UpType::InitParts => "init_parts",
             UpType::UploadPart => "upload_part",
@@ -558,12 +566,28 @@ impl<'a> UploadLoggerRecordBuilder<'a> {
 
     pub(crate) fn upload_error(self, err: &'a UploadFileError) -> UploadLoggerRecordBuilder<'a> {
         const ZERO_SIZE_FILE: i32 = -6;
+        const INVALID_TOKEN: i32 = -5;
+        const INVALID_ARGUMENT: i32 = -4;
         const INVALID_FILE: i32 = -3;
+        // 与七牛服务器校验数据失败时返回的状态码保持一致
+        const CHECKSUM_MISMATCH: i32 = 406;
         #[allow(deprecated)]
         match err {
             UploadFileError::QiniuError(ref err) => self.http_error(err),
             UploadFileError::IOError(ref err) => self.status_code(INVALID_FILE).error_message(err.description()),
             UploadFileError::EmptyFileError => self.status_code(ZERO_SIZE_FILE).error_message(err.description()),
+            UploadFileError::EtagMismatchError { .. } => {
+                self.status_code(CHECKSUM_MISMATCH).error_message(err.description())
+            }
+            UploadFileError::DeserializeResponseError(_) => self.error_message(err.description()),
+            UploadFileError::InvalidUploadTokenError(_) | UploadFileError::UploadTokenExpiredError { .. } => {
+                self.status_code(INVALID_TOKEN).error_message(err.description())
+            }
+            UploadFileError::KeyMismatchError { .. }
+            | UploadFileError::FileSizeLimitationError { .. }
+            | UploadFileError::MimeTypeLimitationError { .. } => {
+                self.status_code(INVALID_ARGUMENT).error_message(err.description())
+            }
         }
     }
 
@@ -583,7 +607,9 @@ impl<'a> UploadLoggerRecordBuilder<'a> {
                         self.status_code(UNKNOWN_HOST).error_message(err.description())
                     }
                     HTTPCallerErrorKind::ProxyError => self.status_code(PROXY_ERROR).error_message(err.description()),
-                    HTTPCallerErrorKind::SSLError => self.status_code(SSL_ERROR).error_message(err.description()),
+                    HTTPCallerErrorKind::SSLError | HTTPCallerErrorKind::SSLHandshakeError => {
+                        self.status_code(SSL_ERROR).error_message(err.description())
+                    }
                     HTTPCallerErrorKind::ConnectionError => self
                         .status_code(CANNOT_CONNECT_TO_HOST)
                         .error_message(err.description()),
@@ -603,6 +629,7 @@ impl<'a> UploadLoggerRecordBuilder<'a> {
             HTTPErrorKind::MaliciousResponse => self.error_message(err.description()),
             HTTPErrorKind::UnexpectedRedirect => self.error_message(err.description()),
             HTTPErrorKind::UserCanceled => self.error_message(err.description()),
+            HTTPErrorKind::DeadlineExceeded => self.error_message(err.description()),
             HTTPErrorKind::IOError(err) => self.error_message(err.description()),
             HTTPErrorKind::UnknownError(err) => self.error_message(err.description()),
             HTTPErrorKind::ResponseStatusCodeError(status_code, error_message) => {
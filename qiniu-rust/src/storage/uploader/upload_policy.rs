@@ -2,6 +2,10 @@
 //!
 //! 负责解析和生成上传策略
 
+use super::{
+    magic_variable::{MagicVariableBody, MagicVariableResult},
+    save_key::SaveKeyTemplate,
+};
 use crate::{utils::bool as bool_utils, Config};
 use assert_impl::assert_impl;
 use serde::{Deserialize, Serialize};
@@ -399,6 +403,13 @@ impl UploadPolicyBuilder {
         self
     }
 
+    /// 使用魔法变量请求体作为 `return_body`
+    ///
+    /// 魔法变量请求体将被渲染为 JSON 格式
+    pub fn return_body_with_variables(&mut self, body: &MagicVariableBody) -> &mut Self {
+        self.return_body(body.to_json())
+    }
+
     /// 上传成功后，七牛云向业务服务器发送 POST 请求的 URL 列表，`Host`，回调请求的内容以及其 `Content-Type`
     ///
     /// 七牛服务器会在上传成功后逐一回调 URL 直到有一个成功为止
@@ -436,6 +447,23 @@ impl UploadPolicyBuilder {
         self
     }
 
+    /// 使用魔法变量请求体设置回调
+    ///
+    /// 与 `callback` 相同，但回调请求体将按照 `body_type` 渲染魔法变量请求体得到。
+    /// `body_type` 为空时即为默认的 `application/x-www-form-urlencoded`，
+    /// 如果 `body_type` 既不是 `application/x-www-form-urlencoded` 也不是 `application/json`，则返回错误，且不会修改回调设置
+    pub fn callback_with_variables<'a>(
+        &mut self,
+        urls: impl AsRef<[&'a str]>,
+        host: impl Into<Box<str>>,
+        body: &MagicVariableBody,
+        body_type: impl Into<Box<str>>,
+    ) -> MagicVariableResult<&mut Self> {
+        let body_type = body_type.into();
+        let body = body.render_for_body_type(&body_type)?;
+        Ok(self.callback(urls, host, body, body_type))
+    }
+
     /// 自定义对象名称
     ///
     /// 支持[魔法变量](https://developer.qiniu.com/kodo/manual/1235/vars#magicvar)和[自定义变量](https://developer.qiniu.com/kodo/manual/1235/vars#xvar)。
@@ -449,6 +477,13 @@ impl UploadPolicyBuilder {
         self
     }
 
+    /// 使用自定义对象名称模板命名对象
+    ///
+    /// 与 `save_as` 相同，但模板已经过本地校验
+    pub fn save_as_template(&mut self, template: &SaveKeyTemplate, force: bool) -> &mut Self {
+        self.save_as(template.to_string(), force)
+    }
+
     /// 限定上传文件尺寸的范围
     ///
     /// 单位为字节
@@ -555,7 +590,13 @@ pub type UploadPolicyResult<T> = Result<T, UploadPolicyError>;
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use super::{
+        super::{
+            magic_variable::{MagicVariable, MagicVariableBodyBuilder},
+            save_key::SaveKeyTemplateBuilder,
+        },
+        *,
+    };
     use crate::utils::mime;
     use serde_json::{json, Value};
     use std::{boxed::Box, error::Error, result::Result};
@@ -856,6 +897,40 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_build_upload_policy_with_magic_variables() -> Result<(), Box<dyn Error>> {
+        let body = MagicVariableBodyBuilder::default()
+            .variable("key", MagicVariable::Key)
+            .variable("hash", MagicVariable::Etag)
+            .custom_variable("user", "user")
+            .build()?;
+        let policy = UploadPolicyBuilder::new_policy_for_bucket("test_bucket", &Config::default())
+            .return_body_with_variables(&body)
+            .callback_with_variables(&["https://1.1.1.1"], "", &body, mime::JSON_MIME)?
+            .build();
+        assert_eq!(
+            policy.return_body(),
+            Some(r#"{"key":$(key),"hash":$(etag),"user":$(x:user)}"#)
+        );
+        assert_eq!(policy.callback_body(), policy.return_body());
+        assert_eq!(policy.callback_body_type(), Some(mime::JSON_MIME));
+
+        let mut builder = UploadPolicyBuilder::new_policy_for_bucket("test_bucket", &Config::default());
+        builder.callback_with_variables(&["https://1.1.1.1"], "", &body, "")?;
+        assert_eq!(
+            builder.build().callback_body(),
+            Some("key=$(key)&hash=$(etag)&user=$(x:user)")
+        );
+        assert!(builder
+            .callback_with_variables(&["https://2.2.2.2"], "", &body, "text/plain")
+            .is_err());
+        assert_eq!(
+            builder.build().callback_urls().map(|urls| urls.collect::<Vec<&str>>()),
+            Some(vec!["https://1.1.1.1"])
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_build_upload_policy_with_save_key() -> Result<(), Box<dyn Error>> {
         let policy = UploadPolicyBuilder::new_policy_for_bucket("test_bucket", &Config::default())
@@ -869,6 +944,30 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_build_upload_policy_with_save_key_template() -> Result<(), Box<dyn Error>> {
+        let template = SaveKeyTemplateBuilder::default()
+            .literal("uploads/")
+            .custom_variable("user")
+            .literal("/")
+            .etag()
+            .ext()
+            .build()?;
+        let policy = UploadPolicyBuilder::new_policy_for_bucket("test_bucket", &Config::default())
+            .save_as_template(&template, true)
+            .build();
+        assert_eq!(policy.save_key(), Some("uploads/$(x:user)/$(etag)$(ext)"));
+        assert_eq!(policy.is_save_key_forced(), true);
+        assert_eq!(
+            policy
+                .save_key()
+                .map(|key| key.parse::<SaveKeyTemplate>())
+                .transpose()?,
+            Some(template)
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_build_upload_policy_with_save_key_by_force() -> Result<(), Box<dyn Error>> {
         let policy = UploadPolicyBuilder::new_policy_for_bucket("test_bucket", &Config::default())
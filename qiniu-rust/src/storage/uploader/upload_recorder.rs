@@ -1,4 +1,7 @@
-use super::super::recorder::{FileSystemRecorder, RecordMedium, Recorder};
+use super::{
+    super::recorder::{FileSystemRecorder, RecordMedium, Recorder},
+    ResumableUploadVersion,
+};
 use assert_impl::assert_impl;
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
@@ -54,6 +57,8 @@ pub(super) struct FileUploadRecordMediumMetadata {
     pub(super) upload_id: Box<str>,
     pub(super) up_urls: Box<[Box<str>]>,
     pub(super) block_size: u32,
+    #[serde(default)]
+    pub(super) version: ResumableUploadVersion,
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -63,19 +68,35 @@ struct SerializableFileUploadRecordMediumMetadata<'a> {
     upload_id: &'a str,
     up_urls: &'a [&'a str],
     block_size: u32,
+    version: ResumableUploadVersion,
 }
 
 #[derive(Deserialize, Debug, Clone)]
 pub(super) struct FileUploadRecordMediumBlockItem {
+    #[serde(default)]
     pub(super) etag: Box<str>,
+    #[serde(default)]
+    pub(super) ctx: Box<str>,
     pub(super) part_number: usize,
+    pub(super) offset: Option<u64>,
+    pub(super) size: Option<u64>,
+    // 分片上传 V1 中块内已经上传的数据量，没有该字段表示整个块均已上传
+    #[serde(default)]
+    pub(super) ctx_offset: Option<u64>,
     pub(super) created_timestamp: u64,
 }
 
 #[derive(Serialize, Debug, Clone)]
 struct SerializableFileUploadRecordMediumBlockItem<'a> {
+    #[serde(skip_serializing_if = "str::is_empty")]
     etag: &'a str,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    ctx: &'a str,
     part_number: usize,
+    offset: u64,
+    size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ctx_offset: Option<u64>,
     created_timestamp: u64,
 }
 
@@ -94,6 +115,7 @@ impl UploadRecorder {
         upload_id: &str,
         up_urls: &[&str],
         block_size: u32,
+        version: ResumableUploadVersion,
     ) -> Result<FileUploadRecordMedium> {
         let metadata = path.metadata()?;
         let metadata = SerializableFileUploadRecordMediumMetadata {
@@ -106,6 +128,7 @@ impl UploadRecorder {
             upload_id,
             up_urls,
             block_size,
+            version,
         };
         let medium = self.recorder.open(&self.generate_key(path, key), true)?;
         {
@@ -256,10 +279,38 @@ mod default {
 }
 
 impl FileUploadRecordMedium {
-    pub(super) fn append(&self, etag: &str, part_number: usize) -> Result<()> {
+    pub(super) fn append(&self, etag: &str, part_number: usize, offset: u64, size: u64) -> Result<()> {
+        self.append_item(etag, "", part_number, offset, size, None)
+    }
+
+    /// 记录分片上传 V1 中块内最后一个已上传的片，`ctx_offset` 为块内已经上传的数据量
+    pub(super) fn append_ctx(
+        &self,
+        ctx: &str,
+        part_number: usize,
+        offset: u64,
+        size: u64,
+        ctx_offset: u64,
+    ) -> Result<()> {
+        self.append_item("", ctx, part_number, offset, size, Some(ctx_offset))
+    }
+
+    fn append_item(
+        &self,
+        etag: &str,
+        ctx: &str,
+        part_number: usize,
+        offset: u64,
+        size: u64,
+        ctx_offset: Option<u64>,
+    ) -> Result<()> {
         let mut item = serde_json::to_string(&SerializableFileUploadRecordMediumBlockItem {
             etag,
+            ctx,
             part_number,
+            offset,
+            size,
+            ctx_offset,
             created_timestamp: SystemTime::now()
                 .duration_since(SystemTime::UNIX_EPOCH)
                 .expect("Now is earlier than UNIX EPOCH")
@@ -0,0 +1,356 @@
+use super::{
+    throttle::ThrottledTransfer, upload_response_callback, TokenizedUploadLogger, UpType, UploadLoggerRecordBuilder,
+    UploadResponse, UploadThrottle,
+};
+use crate::http::{
+    request::{Builder as RequestBuilder, Request},
+    Client, Error as HTTPError, ErrorKind as HTTPErrorKind, Method, Response, Result as HTTPResult,
+};
+use qiniu_http::RequestBody;
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    borrow::Cow,
+    cell::Cell,
+    convert::TryInto,
+    sync::atomic::{AtomicU64, Ordering::Relaxed},
+    time::{Duration, Instant},
+};
+
+/// 上传进度
+///
+/// 由同一个上传任务的所有请求共享，汇总各个请求已经发送的数据量
+pub(super) struct UploadingProgress<'u> {
+    callback: &'u (dyn Fn(u64, Option<u64>) + Send + Sync),
+    completed_size: AtomicU64,
+    total_size: Option<u64>,
+}
+
+impl<'u> UploadingProgress<'u> {
+    pub(super) fn new(
+        callback: &'u (dyn Fn(u64, Option<u64>) + Send + Sync),
+        total_size: Option<u64>,
+    ) -> UploadingProgress<'u> {
+        UploadingProgress {
+            callback,
+            completed_size: AtomicU64::new(0),
+            total_size,
+        }
+    }
+
+    /// 重新设置已经完成的数据量，断点续传时将从已经上传的数据量开始计算上传进度
+    pub(super) fn reset(&self, completed_size: u64) {
+        self.completed_size.store(completed_size, Relaxed);
+    }
+
+    fn add(&self, size: u64) {
+        (self.callback)(self.completed_size.fetch_add(size, Relaxed) + size, self.total_size);
+    }
+
+    fn sub(&self, size: u64) {
+        self.completed_size.fetch_sub(size, Relaxed);
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub(super) struct MakeBlockResult {
+    pub(super) ctx: Box<str>,
+    pub(super) crc32: u32,
+}
+
+/// 上传请求
+///
+/// 负责构建请求，校验响应，记录上传日志，汇报上传进度和限制上传带宽。
+/// 同步上传和异步上传使用相同的上传请求，仅发送请求的方式不同
+#[must_use]
+pub(super) struct UploadRequest<'r> {
+    http_client: &'r Client,
+    method: Method,
+    path: Cow<'r, str>,
+    up_urls: &'r [&'r str],
+    up_type: UpType,
+    authorization: Option<Cow<'r, str>>,
+    content_md5: Option<String>,
+    body: Option<(Cow<'r, str>, RequestBody<'r>)>,
+    deadline: Option<Instant>,
+    upload_logger: Option<&'r TokenizedUploadLogger>,
+    transfer: Option<Transfer<'r>>,
+    made_block: Option<MadeBlock>,
+}
+
+// 发送上传数据的请求需要汇报上传进度，并限制上传带宽
+struct Transfer<'r> {
+    throttled: ThrottledTransfer<'r>,
+    progress: Option<&'r UploadingProgress<'r>>,
+    reported: Cell<u64>,
+}
+
+// 分片上传 V1 的响应需要在响应回调中校验 CRC32，确保校验失败的请求可以被重试
+struct MadeBlock {
+    expected_crc32: Option<u32>,
+    result: Cell<Option<MakeBlockResult>>,
+}
+
+impl<'r> UploadRequest<'r> {
+    pub(super) fn post(
+        http_client: &'r Client,
+        path: impl Into<Cow<'r, str>>,
+        up_urls: &'r [&'r str],
+        up_type: UpType,
+    ) -> UploadRequest<'r> {
+        Self::new(http_client, Method::POST, path.into(), up_urls, up_type)
+    }
+
+    pub(super) fn put(
+        http_client: &'r Client,
+        path: impl Into<Cow<'r, str>>,
+        up_urls: &'r [&'r str],
+        up_type: UpType,
+    ) -> UploadRequest<'r> {
+        Self::new(http_client, Method::PUT, path.into(), up_urls, up_type)
+    }
+
+    fn new(
+        http_client: &'r Client,
+        method: Method,
+        path: Cow<'r, str>,
+        up_urls: &'r [&'r str],
+        up_type: UpType,
+    ) -> UploadRequest<'r> {
+        UploadRequest {
+            http_client,
+            method,
+            path,
+            up_urls,
+            up_type,
+            authorization: None,
+            content_md5: None,
+            body: None,
+            deadline: None,
+            upload_logger: None,
+            transfer: None,
+            made_block: None,
+        }
+    }
+
+    pub(super) fn authorization(mut self, authorization: impl Into<Cow<'r, str>>) -> UploadRequest<'r> {
+        self.authorization = Some(authorization.into());
+        self
+    }
+
+    pub(super) fn content_md5(mut self, content_md5: Option<String>) -> UploadRequest<'r> {
+        self.content_md5 = content_md5;
+        self
+    }
+
+    pub(super) fn body(
+        mut self,
+        content_type: impl Into<Cow<'r, str>>,
+        body: impl Into<RequestBody<'r>>,
+    ) -> UploadRequest<'r> {
+        self.body = Some((content_type.into(), body.into()));
+        self
+    }
+
+    pub(super) fn deadline(mut self, deadline: Option<Instant>) -> UploadRequest<'r> {
+        self.deadline = deadline;
+        self
+    }
+
+    pub(super) fn upload_logger(mut self, upload_logger: Option<&'r TokenizedUploadLogger>) -> UploadRequest<'r> {
+        self.upload_logger = upload_logger;
+        self
+    }
+
+    /// 将请求体作为上传数据，汇报上传进度并限制上传带宽
+    pub(super) fn transfer(
+        mut self,
+        throttle: UploadThrottle<'r>,
+        progress: Option<&'r UploadingProgress<'r>>,
+    ) -> UploadRequest<'r> {
+        self.transfer = Some(Transfer {
+            throttled: throttle.transfer(),
+            progress,
+            reported: Cell::new(0),
+        });
+        self
+    }
+
+    /// 将响应体解析为分片上传 V1 的块信息，如果指定了 `expected_crc32`，则校验七牛服务器返回的 CRC32
+    pub(super) fn make_block(mut self, expected_crc32: Option<u32>) -> UploadRequest<'r> {
+        self.made_block = Some(MadeBlock {
+            expected_crc32,
+            result: Cell::new(None),
+        });
+        self
+    }
+
+    /// 发送请求，由 `parse` 解析响应
+    pub(super) fn send<T>(&self, parse: impl FnOnce(&UploadRequest, &mut Response) -> HTTPResult<T>) -> HTTPResult<T> {
+        let result = parse(
+            self,
+            &mut self
+                .with_body(
+                    self.builder()
+                        .on_uploading_progress(&|uploaded, _| self.on_uploading_progress(uploaded))
+                        .on_response(&|response, duration| self.on_response(response, duration))
+                        .on_error(&|host_url, err, duration| self.on_error(host_url, err, duration)),
+                )
+                .send()?,
+        )?;
+        self.on_completed();
+        Ok(result)
+    }
+
+    /// 异步发送请求，由 `parse` 解析响应
+    #[cfg(feature = "async")]
+    pub(super) async fn send_async<T>(
+        &self,
+        parse: impl FnOnce(&UploadRequest, &mut Response) -> HTTPResult<T>,
+    ) -> HTTPResult<T> {
+        let result = parse(
+            self,
+            &mut self
+                .with_body(
+                    self.builder()
+                        .on_uploading_progress(&|uploaded, _| self.on_uploading_progress(uploaded))
+                        .on_response(&|response, duration| self.on_response(response, duration))
+                        .on_error(&|host_url, err, duration| self.on_error(host_url, err, duration)),
+                )
+                .send_async()
+                .await?,
+        )?;
+        self.on_completed();
+        Ok(result)
+    }
+
+    /// 获取在响应回调中解析并校验过的块信息
+    pub(super) fn made_block(&self, response: &mut Response) -> HTTPResult<MakeBlockResult> {
+        match self.made_block.as_ref().and_then(|made_block| made_block.result.take()) {
+            Some(result) => Ok(result),
+            None => response.parse_json(),
+        }
+    }
+
+    fn builder(&self) -> RequestBuilder {
+        let mut builder = match self.method {
+            Method::PUT => self.http_client.put(&self.path, self.up_urls),
+            _ => self.http_client.post(&self.path, self.up_urls),
+        };
+        if let Some(authorization) = &self.authorization {
+            builder = builder.header("Authorization".into(), authorization.as_ref().into());
+        }
+        if let Some(content_md5) = &self.content_md5 {
+            builder = builder.header("Content-MD5".into(), content_md5.as_str().into());
+        }
+        builder.idempotent().deadline(self.deadline).accept_json()
+    }
+
+    fn with_body<'b>(&'b self, builder: RequestBuilder<'b>) -> Request<'b> {
+        match &self.body {
+            Some((content_type, body)) => builder.raw_body(content_type.as_ref().into(), body.as_borrowed()),
+            None => builder.no_body(),
+        }
+    }
+
+    fn on_uploading_progress(&self, uploaded: u64) {
+        if let Some(transfer) = &self.transfer {
+            transfer.throttled.on_progress(uploaded);
+            transfer.report(uploaded);
+        }
+    }
+
+    fn on_response(&self, response: &mut Response, duration: Duration) -> HTTPResult<()> {
+        upload_response_callback(response)?;
+        if let Some(made_block) = &self.made_block {
+            let result: MakeBlockResult = response.parse_json()?;
+            // 七牛服务器返回的 CRC32 与本地计算的不一致，说明数据在传输过程中被损坏，需要重新上传
+            if made_block
+                .expected_crc32
+                .map_or(false, |expected_crc32| expected_crc32 != result.crc32)
+            {
+                return Err(HTTPError::new_retryable_error(
+                    HTTPErrorKind::MaliciousResponse,
+                    true,
+                    Some(response.method()),
+                    Some((response.base_url().to_owned() + response.path()).into()),
+                    response.request_id().map(|request_id| request_id.into()),
+                ));
+            }
+            made_block.result.set(Some(result));
+        }
+        if let Some(upload_logger) = self.upload_logger {
+            let mut builder = UploadLoggerRecordBuilder::default()
+                .response(response)
+                .duration(duration)
+                .up_type(self.up_type);
+            if let Some(size) = self.transferred_size() {
+                builder = builder.sent(size).total_size(size);
+            }
+            let _ = upload_logger.log(builder.build());
+        }
+        Ok(())
+    }
+
+    // 对于可以安全重试的错误，每次请求失败都会回调，此前汇报的上传进度将被撤销
+    fn on_error(&self, host_url: Option<&str>, err: &HTTPError, duration: Duration) {
+        if let Some(transfer) = &self.transfer {
+            transfer.revoke();
+        }
+        if let Some(upload_logger) = self.upload_logger {
+            let mut builder = UploadLoggerRecordBuilder::default()
+                .duration(duration)
+                .up_type(self.up_type)
+                .http_error(err);
+            if let Some(size) = self.transferred_size() {
+                builder = builder.total_size(size);
+            }
+            if let Some(host_url) = host_url {
+                builder = builder.host(host_url);
+            }
+            let _ = upload_logger.log(builder.build());
+        }
+    }
+
+    // 异步 HTTP 请求处理函数可能不会回调上传进度，因此在请求完成后补足尚未汇报的上传进度和尚未申请的带宽
+    fn on_completed(&self) {
+        if let (Some(transfer), Some(size)) = (&self.transfer, self.transferred_size()) {
+            transfer.throttled.on_completed(size);
+            transfer.report(size);
+        }
+    }
+
+    fn transferred_size(&self) -> Option<u64> {
+        self.transfer.as_ref().map(|_| {
+            self.body
+                .as_ref()
+                .map_or(0, |(_, body)| body.len().try_into().unwrap_or(u64::max_value()))
+        })
+    }
+}
+
+impl Transfer<'_> {
+    fn report(&self, uploaded: u64) {
+        if let Some(progress) = self.progress {
+            let reported = self.reported.get();
+            if uploaded > reported {
+                self.reported.set(uploaded);
+                progress.add(uploaded - reported);
+            }
+        }
+    }
+
+    fn revoke(&self) {
+        if let Some(progress) = self.progress {
+            progress.sub(self.reported.replace(0));
+        }
+    }
+}
+
+/// 将上传响应体解析为 `UploadResponse`，响应体可以不是 JSON
+pub(super) fn parse_upload_response(_: &UploadRequest, response: &mut Response) -> HTTPResult<UploadResponse> {
+    Ok(match response.try_parse_json::<Value>()? {
+        Ok(value) => value.into(),
+        Err(bytes) => bytes.into(),
+    })
+}
@@ -1,5 +1,6 @@
 use matches::matches;
-use serde_json::{map::Map, value::Index, Value};
+use serde::de::DeserializeOwned;
+use serde_json::{map::Map, value::Index, Result as JSONResult, Value};
 use std::fmt;
 
 /// 上传响应实例
@@ -196,6 +197,18 @@ impl UploadResponse {
         }
     }
 
+    /// 将响应体反序列化为指定的类型
+    ///
+    /// 适用于在上传策略中自定义了 `returnBody` 或回调请求体的情况，
+    /// 例如将 `$(fsize)`，`$(imageInfo)` 或 `$(x:user)` 等魔法变量与自定义变量反序列化为自定义的结构体。
+    /// 即使响应体不是 JSON，也将尝试按照 JSON 格式进行反序列化
+    pub fn deserialize<T: DeserializeOwned>(&self) -> JSONResult<T> {
+        match &self.0 {
+            UploadResponseInner::JSON(value) => T::deserialize(value),
+            UploadResponseInner::Bytes(bytes) => serde_json::from_slice(bytes),
+        }
+    }
+
     /// 将响应体转换为二进制数据
     pub fn to_bytes(&self) -> Vec<u8> {
         match &self.0 {
@@ -213,6 +226,32 @@ impl UploadResponse {
     }
 }
 
+/// 上传响应类型
+///
+/// 对象上传器在上传完毕后，将通过该 Trait 把上传响应转换为指定的上传响应类型。
+/// `UploadResponse` 自身，以及所有实现了 `serde::de::DeserializeOwned` 的类型均已实现该 Trait
+pub trait FromUploadResponse: Sized {
+    /// 将上传响应转换为当前类型
+    fn from_upload_response(upload_response: UploadResponse) -> JSONResult<Self>;
+}
+
+impl FromUploadResponse for UploadResponse {
+    #[inline]
+    fn from_upload_response(upload_response: UploadResponse) -> JSONResult<Self> {
+        Ok(upload_response)
+    }
+}
+
+impl<T: DeserializeOwned> FromUploadResponse for T {
+    #[inline]
+    fn from_upload_response(upload_response: UploadResponse) -> JSONResult<Self> {
+        match upload_response.0 {
+            UploadResponseInner::JSON(value) => serde_json::from_value(value),
+            UploadResponseInner::Bytes(bytes) => serde_json::from_slice(&bytes),
+        }
+    }
+}
+
 impl From<Value> for UploadResponse {
     fn from(v: Value) -> Self {
         UploadResponse(UploadResponseInner::JSON(v))
@@ -233,3 +272,42 @@ impl fmt::Display for UploadResponse {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+    use std::{error::Error, result::Result};
+
+    #[derive(Deserialize, Debug, PartialEq, Eq)]
+    struct CustomResponse {
+        key: String,
+        fsize: u64,
+        user: String,
+    }
+
+    #[test]
+    fn test_storage_uploader_upload_response_deserialize() -> Result<(), Box<dyn Error>> {
+        let expected = CustomResponse {
+            key: "test-key".into(),
+            fsize: 1024,
+            user: "test-user".into(),
+        };
+
+        let response = UploadResponse::from(json!({"key": "test-key", "fsize": 1024, "user": "test-user"}));
+        assert_eq!(response.deserialize::<CustomResponse>()?, expected);
+        assert_eq!(CustomResponse::from_upload_response(response)?, expected);
+
+        let response = UploadResponse::from(br#"{"key":"test-key","fsize":1024,"user":"test-user"}"#.to_vec());
+        assert_eq!(response.deserialize::<CustomResponse>()?, expected);
+        assert_eq!(CustomResponse::from_upload_response(response)?, expected);
+
+        let response = UploadResponse::from(b"plain text".to_vec());
+        assert!(response.deserialize::<CustomResponse>().is_err());
+        assert!(UploadResponse::from_upload_response(response)?
+            .as_json_value()
+            .is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+//! 上传凭证提供者模块
+//!
+//! 用于在长时间的分片上传过程中获取最新的上传凭证
+
+use super::{
+    upload_policy::{UploadPolicy, UploadPolicyBuilder},
+    upload_token::UploadToken,
+};
+use crate::Credential;
+use std::{error::Error, fmt, result::Result, time::Duration};
+
+/// 上传凭证提供者
+///
+/// 分片上传将在初始化上传，上传每个分片以及完成上传前调用 `upload_token` 获取上传凭证，
+/// 因此即使上传时间超出上传凭证的有效期，上传也能继续进行。
+/// 如果上传凭证由远程签名服务提供，实现者应当自行缓存上传凭证，仅在其即将过期时才重新获取
+pub trait UploadTokenProvider: Send + Sync {
+    /// 获取上传凭证
+    fn upload_token(&self) -> UploadTokenProviderResult<UploadToken>;
+}
+
+impl UploadTokenProvider for UploadToken {
+    #[inline]
+    fn upload_token(&self) -> UploadTokenProviderResult<UploadToken> {
+        Ok(self.to_owned())
+    }
+}
+
+impl<F: Fn() -> UploadTokenProviderResult<UploadToken> + Send + Sync> UploadTokenProvider for F {
+    #[inline]
+    fn upload_token(&self) -> UploadTokenProviderResult<UploadToken> {
+        self()
+    }
+}
+
+/// 基于认证信息的上传凭证提供者
+///
+/// 每次获取上传凭证时，都将使用认证信息为上传策略重新签名，并将上传凭证有效期重置为指定的时长
+#[derive(Clone)]
+pub struct CredentialUploadTokenProvider {
+    policy: UploadPolicy,
+    credential: Credential,
+    lifetime: Duration,
+}
+
+impl CredentialUploadTokenProvider {
+    /// 创建基于认证信息的上传凭证提供者
+    pub fn new(policy: UploadPolicy, credential: Credential, lifetime: Duration) -> Self {
+        Self {
+            policy,
+            credential,
+            lifetime,
+        }
+    }
+}
+
+impl UploadTokenProvider for CredentialUploadTokenProvider {
+    fn upload_token(&self) -> UploadTokenProviderResult<UploadToken> {
+        Ok(UploadToken::new(
+            UploadPolicyBuilder::from(self.policy.to_owned())
+                .token_lifetime(self.lifetime)
+                .build(),
+            self.credential.to_owned(),
+        ))
+    }
+}
+
+impl fmt::Debug for CredentialUploadTokenProvider {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CredentialUploadTokenProvider")
+            .field("policy", &self.policy)
+            .field("access_key", &self.credential.access_key())
+            .field("lifetime", &self.lifetime)
+            .finish()
+    }
+}
+
+/// 上传凭证提供者获取结果
+pub type UploadTokenProviderResult<T> = Result<T, Box<dyn Error + Send>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use std::{
+        boxed::Box,
+        error::Error,
+        io::{Error as IOError, ErrorKind as IOErrorKind},
+        result::Result,
+        time::SystemTime,
+    };
+
+    #[test]
+    fn test_storage_uploader_upload_token_provider() -> Result<(), Box<dyn Error>> {
+        let policy = UploadPolicyBuilder::new_policy_for_bucket("test_bucket", &Config::default())
+            .token_deadline(SystemTime::now())
+            .build();
+        let provider = CredentialUploadTokenProvider::new(
+            policy,
+            Credential::new("abcdefghklmnopq", "1234567890"),
+            Duration::from_secs(3600),
+        );
+        let token = provider.upload_token().unwrap();
+        let lifetime = token.policy()?.token_lifetime().unwrap();
+        assert!(lifetime > Duration::from_secs(3500));
+        assert_eq!(token.policy()?.bucket(), Some("test_bucket"));
+
+        assert_eq!(token.upload_token().unwrap().to_string(), token.to_string());
+
+        let failed_provider =
+            || -> UploadTokenProviderResult<UploadToken> { Err(Box::new(IOError::new(IOErrorKind::Other, "failed"))) };
+        assert_eq!(failed_provider.upload_token().unwrap_err().to_string(), "failed");
+        Ok(())
+    }
+}
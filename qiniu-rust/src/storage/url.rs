@@ -2,12 +2,18 @@
 
 use crate::{
     http::{
-        Client as HTTPClient, HeaderNameOwned as HTTPHeaderNameOwned, HeadersOwned as HTTPHeadersOwned,
-        Result as HTTPResult,
+        Client as HTTPClient, Error as HTTPError, ErrorKind as HTTPErrorKind, HeaderNameOwned as HTTPHeaderNameOwned,
+        HeadersOwned as HTTPHeadersOwned, Result as HTTPResult,
     },
     Credential,
 };
-use std::{borrow::Cow, fmt, time::Duration};
+use qiniu_http::ResponseBody as HTTPResponseBody;
+use std::{
+    borrow::Cow,
+    fmt,
+    io::{copy as io_copy, Write},
+    time::Duration,
+};
 use url::Url;
 
 /// URL
@@ -140,6 +146,44 @@ impl PublicURL {
             metadata,
         })
     }
+
+    fn download_to(&self, client: &HTTPClient, writer: &mut dyn Write) -> HTTPResult<u64> {
+        self._download_to(client, &|_| {}, writer)
+    }
+
+    fn _download_to(
+        &self,
+        client: &HTTPClient,
+        callback: &dyn Fn(&mut Url),
+        writer: &mut dyn Write,
+    ) -> HTTPResult<u64> {
+        let base_urls = self.generate_base_urls();
+        let base_urls = base_urls.iter().map(|url| url.as_str()).collect::<Vec<_>>();
+        let mut request_builder = client
+            .get(&self.key, &base_urls)
+            .fop(Cow::Borrowed(&self.fop))
+            .idempotent()
+            .follow_redirection();
+        for (query_name, query_value) in self.query.iter() {
+            request_builder = request_builder.query(query_name.to_owned(), query_value.to_owned());
+        }
+        let request = request_builder.on_url_constructed(callback).no_body();
+        let mut response = request.send()?;
+        match response.take_body() {
+            Some(HTTPResponseBody::Reader(mut reader)) => io_copy(&mut reader, writer),
+            Some(HTTPResponseBody::File(mut file)) => io_copy(&mut file, writer),
+            Some(HTTPResponseBody::Bytes(bytes)) => writer.write_all(&bytes).map(|_| bytes.len() as u64),
+            None => Ok(0),
+        }
+        .map_err(|err| {
+            HTTPError::new_unretryable_error(
+                HTTPErrorKind::IOError(err),
+                Some(response.method()),
+                Some((response.base_url().to_owned() + response.path()).into()),
+                response.request_id().map(|request_id| request_id.into()),
+            )
+        })
+    }
 }
 
 impl fmt::Display for PublicURL {
@@ -179,6 +223,14 @@ impl PrivateURL {
             self.credential.sign_download_url(url, self.deadline, false)
         })
     }
+
+    fn download_to(&self, client: &HTTPClient, writer: &mut dyn Write) -> HTTPResult<u64> {
+        self.base_url._download_to(
+            client,
+            &|url| self.credential.sign_download_url(url, self.deadline, false),
+            writer,
+        )
+    }
 }
 
 impl fmt::Display for PrivateURL {
@@ -234,6 +286,13 @@ impl URL {
             URLInner::PrivateURL(private_url) => private_url.head(client),
         }
     }
+
+    pub(super) fn download_to(&self, client: &HTTPClient, writer: &mut dyn Write) -> HTTPResult<u64> {
+        match &self.0 {
+            URLInner::PublicURL(public_url) => public_url.download_to(client, writer),
+            URLInner::PrivateURL(private_url) => private_url.download_to(client, writer),
+        }
+    }
 }
 
 impl fmt::Display for URL {
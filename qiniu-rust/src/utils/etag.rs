@@ -193,6 +193,17 @@ pub fn from_file<P: AsRef<Path>>(path: P) -> Result<String> {
     from(File::open(path)?)
 }
 
+/// 判断对象的 Hash 值是否是标准的 Etag
+///
+/// 标准 Etag 由前缀字节 `0x16` 或 `0x96` 和 SHA1 值经过 URL 安全的 Base64 编码组成。
+/// 使用分片上传 V2 等方式上传的对象，其 Hash 值可能并非标准的 Etag，无法与本地计算的 Etag 比较
+pub fn is_etag(hash: &str) -> bool {
+    hash.len() == ETAG_SIZE
+        && base64::decode(hash.as_bytes()).map_or(false, |decoded| {
+            decoded.len() == 21 && (decoded[0] == 0x16 || decoded[0] == 0x96)
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -204,6 +215,16 @@ mod tests {
         result::Result,
     };
 
+    #[test]
+    fn test_etag_is_etag() -> Result<(), Box<dyn Error>> {
+        assert!(is_etag("Fto5o-5ea0sNMlW_75VgGJCv2AcJ"));
+        assert!(is_etag(&from_bytes(vec![0u8; (1 << 22) + 1])));
+        assert!(!is_etag("Ato5o-5ea0sNMlW_75VgGJCv2AcJ"));
+        assert!(!is_etag("Fto5o-5ea0sNMlW_75VgGJCv2Ac"));
+        assert!(!is_etag("d41d8cd98f00b204e9800998ecf8427e"));
+        Ok(())
+    }
+
     #[test]
     fn test_etag_from_data() -> Result<(), Box<dyn Error>> {
         assert_eq!(from(&mut empty())?, "Fto5o-5ea0sNMlW_75VgGJCv2AcJ",);
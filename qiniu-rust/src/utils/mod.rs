@@ -6,6 +6,7 @@ pub(crate) mod cache_map;
 pub(crate) mod crc32;
 pub mod etag;
 pub(crate) mod mime;
+pub mod rate_limiter;
 pub(crate) mod rob;
 pub(crate) mod ron;
 pub(crate) mod seek_adapter;
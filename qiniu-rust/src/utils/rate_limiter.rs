@@ -0,0 +1,138 @@
+//! 上传带宽限速器
+//!
+//! 基于令牌桶算法实现，可以在客户端配置中指定全局限速器，也可以为每个上传任务单独指定限速器
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+/// 带宽限速器
+///
+/// 限速器可以被克隆，克隆后的限速器与原限速器共享同一个令牌桶，因此可以在多个上传任务之间共享带宽
+#[derive(Clone)]
+pub struct RateLimiter(Arc<RateLimiterInner>);
+
+struct RateLimiterInner {
+    bytes_per_second: u64,
+    burst_size: u64,
+    bucket: Mutex<TokenBucket>,
+}
+
+struct TokenBucket {
+    tokens: f64,
+    last_refilled_at: Instant,
+}
+
+impl RateLimiter {
+    /// 创建带宽限速器
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes_per_second` - 每秒允许发送的字节数，为 `0` 表示不限速
+    ///
+    /// 令牌桶容量默认等于每秒允许发送的字节数
+    pub fn new(bytes_per_second: u64) -> Self {
+        Self::new_with_burst_size(bytes_per_second, bytes_per_second)
+    }
+
+    /// 创建带宽限速器，并指定令牌桶容量
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes_per_second` - 每秒允许发送的字节数，为 `0` 表示不限速
+    /// * `burst_size` - 令牌桶容量，即空闲一段时间后允许瞬间发送的最大字节数
+    pub fn new_with_burst_size(bytes_per_second: u64, burst_size: u64) -> Self {
+        Self(Arc::new(RateLimiterInner {
+            bytes_per_second,
+            burst_size,
+            bucket: Mutex::new(TokenBucket {
+                tokens: burst_size as f64,
+                last_refilled_at: Instant::now(),
+            }),
+        }))
+    }
+
+    /// 每秒允许发送的字节数
+    pub fn bytes_per_second(&self) -> u64 {
+        self.0.bytes_per_second
+    }
+
+    /// 令牌桶容量
+    pub fn burst_size(&self) -> u64 {
+        self.0.burst_size
+    }
+
+    /// 申请发送指定字节数
+    ///
+    /// 如果令牌桶中的令牌不足，该方法将阻塞当前线程，直到允许发送为止
+    pub fn acquire(&self, size: u64) {
+        let wait = self.reserve(size);
+        if wait > Duration::from_secs(0) {
+            sleep(wait);
+        }
+    }
+
+    fn reserve(&self, size: u64) -> Duration {
+        if size == 0 || self.0.bytes_per_second == 0 {
+            return Duration::from_secs(0);
+        }
+        let mut bucket = self.0.bucket.lock().unwrap();
+        let now = Instant::now();
+        let refilled = now.duration_since(bucket.last_refilled_at).as_secs_f64() * self.0.bytes_per_second as f64;
+        bucket.tokens = (bucket.tokens + refilled).min(self.0.burst_size as f64);
+        bucket.last_refilled_at = now;
+        // 允许令牌为负数，表示预支了之后的带宽，后续的申请者需要等待更长时间
+        bucket.tokens -= size as f64;
+        if bucket.tokens >= 0f64 {
+            Duration::from_secs(0)
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / self.0.bytes_per_second as f64)
+        }
+    }
+}
+
+impl fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("bytes_per_second", &self.0.bytes_per_second)
+            .field("burst_size", &self.0.burst_size)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{error::Error, result::Result};
+
+    #[test]
+    fn test_rate_limiter_acquire() -> Result<(), Box<dyn Error>> {
+        let limiter = RateLimiter::new(1 << 20);
+        assert_eq!(limiter.reserve(1 << 20), Duration::from_secs(0));
+        let wait = limiter.reserve(1 << 19);
+        assert!(wait > Duration::from_millis(400) && wait <= Duration::from_millis(500));
+        let wait = limiter.clone().reserve(1 << 19);
+        assert!(wait > Duration::from_millis(900) && wait <= Duration::from_millis(1000));
+
+        let begin_at = Instant::now();
+        let limiter = RateLimiter::new_with_burst_size(1 << 10, 0);
+        limiter.acquire(1 << 8);
+        assert!(begin_at.elapsed() >= Duration::from_millis(240));
+        Ok(())
+    }
+
+    #[test]
+    fn test_rate_limiter_unlimited() -> Result<(), Box<dyn Error>> {
+        let limiter = RateLimiter::new(0);
+        assert_eq!(limiter.reserve(1 << 30), Duration::from_secs(0));
+        assert_eq!(limiter.reserve(1 << 30), Duration::from_secs(0));
+
+        let begin_at = Instant::now();
+        RateLimiter::new_with_burst_size(0, 0).acquire(1 << 30);
+        assert!(begin_at.elapsed() < Duration::from_secs(1));
+        Ok(())
+    }
+}
@@ -35,3 +35,13 @@ fn create_thread_pool(num_threads: usize) -> ThreadPool {
         .build()
         .unwrap()
 }
+
+/// 在 Rayon 全局线程池中执行阻塞操作，并异步等待其结果，避免阻塞异步执行器
+#[cfg(feature = "async")]
+pub(crate) async fn run_blocking<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+    let (sender, receiver) = futures::channel::oneshot::channel();
+    rayon::spawn(move || {
+        let _ = sender.send(f());
+    });
+    receiver.await.expect("Blocking task should not be canceled")
+}